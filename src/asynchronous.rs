@@ -0,0 +1,238 @@
+//! An async wrapper around [`Metriful`] for tokio applications.
+//!
+//! The MS430 is driven via blocking I2C and GPIO calls, so each operation on
+//! [`AsyncMetriful`] runs on tokio's blocking thread pool via
+//! [`tokio::task::spawn_blocking`]. Operations are serialized; only one may
+//! access the device at a time.
+//!
+//! # Example
+//! ```no_run
+//! use metriful::{CyclePeriod, MetrifulOptions, metric::*};
+//! use metriful::asynchronous::AsyncMetriful;
+//!
+//! # async fn run() -> metriful::error::Result<()> {
+//! let metriful = AsyncMetriful::open(MetrifulOptions::from_env()?).await?;
+//! metriful.reset().await?;
+//!
+//! let temperature = metriful.read(METRIC_TEMPERATURE).await?;
+//! println!("temperature: {}", temperature);
+//!
+//! let mut reader = metriful.cycle_read(
+//!   METRIC_COMBINED_ALL, CyclePeriod::Period0, None, 4
+//! );
+//!
+//! while let Some(reading) = reader.next().await {
+//!   println!("{}", reading?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use i2cdev::linux::LinuxI2CDevice;
+use log::trace;
+use tokio::sync::mpsc;
+use tokio::task;
+
+use crate::{Metriful, MetrifulOptions};
+use crate::error::*;
+use crate::metric::Metric;
+use crate::status::*;
+use crate::transport::MetrifulTransport;
+use crate::unit::*;
+
+fn lock<D>(inner: &Mutex<Metriful<D>>) -> MutexGuard<'_, Metriful<D>>
+where
+  D: MetrifulTransport
+{
+  inner.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Async handle to a Metriful device.
+#[derive(Debug)]
+pub struct AsyncMetriful<D = LinuxI2CDevice>
+where
+  D: MetrifulTransport
+{
+  inner: Arc<Mutex<Metriful<D>>>,
+}
+
+impl AsyncMetriful {
+  /// Opens a device using the given options; see [`MetrifulOptions::open()`].
+  pub async fn open(options: MetrifulOptions) -> Result<AsyncMetriful> {
+    let metriful = run_blocking(move || options.open()).await?;
+
+    Ok(AsyncMetriful::new(metriful))
+  }
+}
+
+async fn run_blocking<F, T>(f: F) -> Result<T>
+where
+  F: FnOnce() -> Result<T> + Send + 'static,
+  T: Send + 'static,
+{
+  match task::spawn_blocking(f).await {
+    Ok(result) => result,
+    Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+    Err(e) => Err(MetrifulError::AsyncTaskError(e.to_string())),
+  }
+}
+
+impl<D> AsyncMetriful<D>
+where
+  D: MetrifulTransport + Send + 'static
+{
+  /// Wraps an already-initialized device.
+  pub fn new(metriful: Metriful<D>) -> AsyncMetriful<D> {
+    AsyncMetriful {
+      inner: Arc::new(Mutex::new(metriful)),
+    }
+  }
+
+  /// Returns the wrapped device, or `self` if a [`CycleReader`] or operation
+  /// is still using it.
+  pub fn into_inner(self) -> std::result::Result<Metriful<D>, AsyncMetriful<D>> {
+    match Arc::try_unwrap(self.inner) {
+      Ok(inner) => Ok(inner.into_inner().unwrap_or_else(|e| e.into_inner())),
+      Err(inner) => Err(AsyncMetriful { inner }),
+    }
+  }
+
+  /// Runs an arbitrary blocking operation against the device on the blocking
+  /// thread pool.
+  pub async fn with<F, T>(&self, f: F) -> Result<T>
+  where
+    F: FnOnce(&mut Metriful<D>) -> Result<T> + Send + 'static,
+    T: Send + 'static,
+  {
+    let inner = Arc::clone(&self.inner);
+
+    run_blocking(move || f(&mut lock(&inner))).await
+  }
+
+  /// Async version of [`Metriful::read()`].
+  pub async fn read<U>(&self, metric: Metric<U>) -> Result<UnitValue<U>>
+  where
+    U: MetrifulUnit + 'static
+  {
+    self.with(move |m| m.read(metric)).await
+  }
+
+  /// Async version of [`Metriful::set_mode_timeout()`].
+  pub async fn set_mode_timeout(
+    &self,
+    mode: OperationalMode,
+    timeout: Option<Duration>,
+  ) -> Result<DeviceStatus> {
+    self.with(move |m| m.set_mode_timeout(mode, timeout)).await
+  }
+
+  /// Async version of [`Metriful::set_mode_timeout()`] with no timeout.
+  pub async fn set_mode(&self, mode: OperationalMode) -> Result<DeviceStatus> {
+    self.set_mode_timeout(mode, None).await
+  }
+
+  /// Async version of [`Metriful::reset()`].
+  pub async fn reset(&self) -> Result<DeviceStatus> {
+    self.with(|m| m.reset()).await
+  }
+
+  /// Async version of [`Metriful::read_status()`].
+  pub async fn read_status(&self) -> Result<DeviceStatus> {
+    self.with(|m| m.read_status()).await
+  }
+
+  /// Async version of [`Metriful::wait_for_ready_timeout()`].
+  pub async fn wait_for_ready_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+    self.with(move |m| m.wait_for_ready_timeout(timeout)).await
+  }
+
+  /// Async version of [`Metriful::execute_measurement()`].
+  pub async fn execute_measurement(&self) -> Result<()> {
+    self.with(|m| m.execute_measurement()).await
+  }
+
+  /// Starts reading continuously in cycle mode, as with
+  /// [`Metriful::cycle_read_iter_timeout()`].
+  ///
+  /// At most `capacity` readings are buffered; the background reader waits
+  /// while the buffer is full. The device is held exclusively until the
+  /// returned [`CycleReader`] is stopped or dropped, so other operations on
+  /// this `AsyncMetriful` will wait until then. The reader ends after the first
+  /// error.
+  pub fn cycle_read<U>(
+    &self,
+    metric: Metric<U>,
+    cycle_period: CyclePeriod,
+    timeout: Option<Duration>,
+    capacity: usize,
+  ) -> CycleReader<U>
+  where
+    U: MetrifulUnit + 'static
+  {
+    let (tx, rx) = mpsc::channel(capacity.max(1));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let inner = Arc::clone(&self.inner);
+    let thread_stop = Arc::clone(&stop);
+    task::spawn_blocking(move || {
+      let mut metriful = lock(&inner);
+      let iter = metriful.cycle_read_iter_timeout(metric, cycle_period, timeout);
+
+      for result in iter {
+        if thread_stop.load(Ordering::Relaxed) {
+          trace!("AsyncMetriful::cycle_read(): stop");
+          break;
+        }
+
+        let is_err = result.is_err();
+        if tx.blocking_send(result).is_err() || is_err {
+          break;
+        }
+      }
+    });
+
+    CycleReader { rx, stop }
+  }
+}
+
+/// A stream of cycle readings returned by [`AsyncMetriful::cycle_read()`].
+///
+/// Dropping the reader stops the background read once the current cycle
+/// completes.
+#[derive(Debug)]
+pub struct CycleReader<U>
+where
+  U: MetrifulUnit
+{
+  rx: mpsc::Receiver<Result<UnitValue<U>>>,
+  stop: Arc<AtomicBool>,
+}
+
+impl<U> CycleReader<U>
+where
+  U: MetrifulUnit
+{
+  /// Waits for the next reading. Returns None once the reader has stopped.
+  pub async fn next(&mut self) -> Option<Result<UnitValue<U>>> {
+    self.rx.recv().await
+  }
+
+  /// Asks the background reader to stop after the current cycle. Readings
+  /// already buffered may still be returned by [`CycleReader::next()`].
+  pub fn stop(&self) {
+    self.stop.store(true, Ordering::Relaxed);
+  }
+}
+
+impl<U> Drop for CycleReader<U>
+where
+  U: MetrifulUnit
+{
+  fn drop(&mut self) {
+    self.stop();
+  }
+}
@@ -0,0 +1,269 @@
+//! Periodic UDP broadcast of sensor readings.
+//!
+//! A [`Beacon`] repeatedly sends the most recent [`CombinedData`] reading to a
+//! (usually broadcast) address so that displays and discovery tools on the
+//! local network can pick up sensors without any configuration.
+//!
+//! Two payload formats are supported:
+//!  * [`BeaconFormat::Json`]: a JSON object, `{"name": ..., "reading": ...}`,
+//!    where `reading` is the serialized `UnitValue<UnitCombinedData>`
+//!  * [`BeaconFormat::Binary`]: a compact little-endian encoding:
+//!
+//!    | field                  | type          |
+//!    |------------------------|---------------|
+//!    | magic, `b"MTFL"`       | 4 bytes       |
+//!    | format version (`1`)   | u8            |
+//!    | name length            | u8            |
+//!    | name (UTF-8)           | `len` bytes   |
+//!    | unix timestamp (secs)  | i64           |
+//!    | temperature (℃)        | f32           |
+//!    | pressure (Pa)          | u32           |
+//!    | humidity (% RH)        | f32           |
+//!    | gas resistance (Ω)     | u32           |
+//!    | AQI                    | f32           |
+//!    | estimated CO2 (ppm)    | f32           |
+//!    | estimated VOC (ppm)    | f32           |
+//!    | AQI accuracy           | u8            |
+//!    | illuminance (lx)       | f32           |
+//!    | white level            | u16           |
+//!    | A-weighted SPL (dBa)   | f32           |
+//!    | SPL bands (dB)         | 6 × f32       |
+//!    | peak amplitude (mPa)   | f32           |
+//!    | sound stability        | u8            |
+//!    | particle duty cycle    | f32           |
+//!    | particle concentration | f32           |
+//!    | particle validity      | u8            |
+//!
+//!    If no particle sensor is enabled, both particle values are NaN and the
+//!    validity is [`PARTICLE_NOT_AVAILABLE`].
+//!
+//! # Example
+//! ```no_run
+//! use std::time::Duration;
+//! use metriful::{Metriful, CyclePeriod, metric::*, beacon::*};
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+//! let (_tx, rx, _handle) = metriful.async_cycle_read_timeout(
+//!   METRIC_COMBINED_ALL,
+//!   CyclePeriod::Period0,
+//!   Some(Duration::from_secs(5)),
+//! );
+//!
+//! let beacon = Beacon::bind(
+//!   ("255.255.255.255", DEFAULT_BEACON_PORT),
+//!   "office",
+//!   BeaconFormat::Json,
+//! )?;
+//! beacon.run(rx, Duration::from_secs(10))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use bytes::{BufMut, BytesMut};
+use log::{trace, warn};
+use serde_json::json;
+
+use crate::error::*;
+use crate::unit::*;
+
+/// Default UDP port for beacon broadcasts.
+pub const DEFAULT_BEACON_PORT: u16 = 47430;
+
+/// Magic bytes prefixed to all binary beacon payloads.
+pub const BEACON_MAGIC: &[u8; 4] = b"MTFL";
+
+/// Current binary beacon format version.
+pub const BEACON_VERSION: u8 = 1;
+
+/// Particle validity sent in binary payloads when no particle sensor is
+/// enabled.
+pub const PARTICLE_NOT_AVAILABLE: u8 = 0xFF;
+
+/// Beacon payload encoding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BeaconFormat {
+  Binary,
+  Json,
+}
+
+/// Encodes a reading in the compact binary format documented in the
+/// [module docs](self).
+pub fn encode_binary(name: &str, reading: &UnitValue<UnitCombinedData>) -> Vec<u8> {
+  let mut buf = BytesMut::with_capacity(128);
+
+  // truncate the name to 255 bytes without splitting a character
+  let mut name_len = name.len().min(u8::MAX as usize);
+  while !name.is_char_boundary(name_len) {
+    name_len -= 1;
+  }
+
+  buf.put_slice(BEACON_MAGIC);
+  buf.put_u8(BEACON_VERSION);
+  buf.put_u8(name_len as u8);
+  buf.put_slice(&name.as_bytes()[..name_len]);
+  buf.put_i64_le(reading.time.timestamp());
+
+  let data = &reading.value;
+
+  let air = &data.air.value;
+  buf.put_f32_le(air.temperature.value);
+  buf.put_u32_le(air.pressure.value);
+  buf.put_f32_le(air.humidity.value);
+  buf.put_u32_le(air.gas_sensor_resistance.value);
+
+  let air_quality = &data.air_quality.value;
+  buf.put_f32_le(air_quality.aqi.value);
+  buf.put_f32_le(air_quality.estimated_co2.value);
+  buf.put_f32_le(air_quality.estimated_voc.value);
+  buf.put_u8(air_quality.aqi_accuracy.value.to_uint());
+
+  let light = &data.light.value;
+  buf.put_f32_le(light.illuminance.value);
+  buf.put_u16_le(light.white_level.value);
+
+  let sound = &data.sound.value;
+  buf.put_f32_le(sound.weighted_spl.value);
+  for band in sound.spl_bands.value.0.iter() {
+    buf.put_f32_le(*band);
+  }
+  buf.put_f32_le(sound.peak_amplitude.value);
+  buf.put_u8(sound.measurement_stability.value.to_uint());
+
+  match &data.particle {
+    Some(particle) => {
+      let particle = &particle.value;
+      buf.put_f32_le(particle.duty_cycle.value);
+      buf.put_f32_le(particle.concentration.value.sds011_value);
+      buf.put_u8(match particle.validity.value {
+        ParticleDataValidity::Initializing => 0,
+        ParticleDataValidity::Settled => 1,
+      });
+    },
+    None => {
+      buf.put_f32_le(f32::NAN);
+      buf.put_f32_le(f32::NAN);
+      buf.put_u8(PARTICLE_NOT_AVAILABLE);
+    },
+  }
+
+  buf.to_vec()
+}
+
+/// Encodes a reading as a JSON beacon payload.
+pub fn encode_json(name: &str, reading: &UnitValue<UnitCombinedData>) -> Result<Vec<u8>> {
+  let payload = json!({
+    "name": name,
+    "reading": reading,
+  });
+
+  Ok(serde_json::to_vec(&payload).map_err(std::io::Error::from)?)
+}
+
+/// A UDP sink that broadcasts sensor readings.
+#[derive(Debug)]
+pub struct Beacon {
+  socket: UdpSocket,
+  target: SocketAddr,
+  name: String,
+  format: BeaconFormat,
+}
+
+impl Beacon {
+  /// Creates a new beacon sending to the given target address, e.g.
+  /// `("255.255.255.255", DEFAULT_BEACON_PORT)`. The `name` is included in
+  /// every payload to identify this sensor.
+  pub fn bind(
+    target: impl ToSocketAddrs,
+    name: impl Into<String>,
+    format: BeaconFormat,
+  ) -> Result<Beacon> {
+    let target = target.to_socket_addrs()?
+      .next()
+      .ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        "beacon target resolved to no addresses"
+      ))?;
+
+    let socket = match target {
+      SocketAddr::V4(_) => UdpSocket::bind(("0.0.0.0", 0))?,
+      SocketAddr::V6(_) => UdpSocket::bind(("::", 0))?,
+    };
+    socket.set_broadcast(true)?;
+
+    Ok(Beacon {
+      socket,
+      target,
+      name: name.into(),
+      format,
+    })
+  }
+
+  /// Returns the address beacons are sent to.
+  pub fn target(&self) -> SocketAddr {
+    self.target
+  }
+
+  /// Immediately sends a single reading.
+  pub fn send(&self, reading: &UnitValue<UnitCombinedData>) -> Result<()> {
+    let payload = match self.format {
+      BeaconFormat::Binary => encode_binary(&self.name, reading),
+      BeaconFormat::Json => encode_json(&self.name, reading)?,
+    };
+
+    self.socket.send_to(&payload, self.target)?;
+    trace!("Beacon::send(): sent {} bytes to {}", payload.len(), self.target);
+
+    Ok(())
+  }
+
+  /// Consumes readings from `rx` (e.g. from
+  /// [`Metriful::async_cycle_read_timeout()`](crate::Metriful::async_cycle_read_timeout))
+  /// and re-broadcasts the latest one every `interval`, and additionally
+  /// whenever a new reading arrives.
+  ///
+  /// Read errors are logged and otherwise ignored. Returns once the sending
+  /// side of the channel is dropped, or if a send fails.
+  pub fn run(
+    &self,
+    rx: Receiver<Result<UnitValue<UnitCombinedData>>>,
+    interval: Duration,
+  ) -> Result<()> {
+    let mut latest = None;
+    let mut last_sent = Instant::now();
+
+    loop {
+      let wait = interval.checked_sub(last_sent.elapsed()).unwrap_or_default();
+
+      match rx.recv_timeout(wait) {
+        Ok(Ok(reading)) => {
+          self.send(&reading)?;
+          last_sent = Instant::now();
+          latest = Some(reading);
+        },
+        Ok(Err(e)) => warn!("beacon: ignoring read error: {}", e),
+        Err(RecvTimeoutError::Timeout) => {
+          if let Some(reading) = &latest {
+            self.send(reading)?;
+          }
+          last_sent = Instant::now();
+        },
+        Err(RecvTimeoutError::Disconnected) => return Ok(()),
+      }
+    }
+  }
+
+  /// Spawns a thread running [`Beacon::run()`].
+  pub fn spawn(
+    self,
+    rx: Receiver<Result<UnitValue<UnitCombinedData>>>,
+    interval: Duration,
+  ) -> JoinHandle<Result<()>> {
+    thread::spawn(move || self.run(rx, interval))
+  }
+}
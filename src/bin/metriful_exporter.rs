@@ -1,240 +1,477 @@
-use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use color_eyre::eyre::{Result, Context};
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{Result, Context, eyre};
+use futures_util::{SinkExt, StreamExt};
 use log::*;
-use metriful::unit::{MetrifulUnit, UnitCombinedData};
-use metriful::{Metriful, CyclePeriod, metric::METRIC_COMBINED_ALL, unit::UnitValue};
-use serde::Serialize;
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use metriful::asynchronous::AsyncMetriful;
+use metriful::cancel::CancelToken;
+use metriful::counters::Counters;
+use metriful::options::{
+  ENV_GPIO_CHIP, ENV_GPIO_READY, ENV_I2C_ADDRESS, ENV_I2C_DEVICE, ENV_READY_POLARITY, ENV_TIMEOUT,
+  parse_i2c_address, parse_timeout_secs,
+};
+use metriful::prometheus::PrometheusEncoder;
+use metriful::unit::UnitPartialCombinedData;
+use metriful::{LatestReading, MetrifulOptions, ReaderCommand, ReadyPolarity, CyclePeriod, metric::METRIC_COMBINED_ALL_PARTIAL, unit::UnitValue};
+use serde::{Deserialize, Serialize};
 use serde_json::{self, json};
-use simple_prometheus_exporter::{Exporter, export};
 use structopt::StructOpt;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::broadcast;
 use tokio::task;
 use warp::Filter;
-
-fn try_from_hex_arg(s: &str) -> Result<u16> {
-  if s.starts_with("0x") {
-    u16::from_str_radix(&s[2..], 16).with_context(|| format!("invalid hex: {}", s))
-  } else {
-    s.parse().with_context(|| format!("invalid int: {}", s))
-  }
-}
-
-fn parse_duration_secs(s: &str) -> Result<Duration> {
-  Ok(Duration::from_secs(
-    s.parse().wrap_err_with(|| format!("invalid seconds value: {}", s))?
-  ))
-}
+use warp::http::StatusCode;
+use warp::ws::{Message, WebSocket, Ws};
 
 #[derive(Debug, Clone, StructOpt, Serialize)]
 #[structopt(name = "metriful-exporter")]
 struct Options {
-  /// system i2c device, e.g. /dev/i2c-1
-  #[structopt(
-    long, short,
-    parse(from_os_str),
-    default_value = "/dev/i2c-1",
-    global = true,
-    env = "METRIFUL_I2C_DEVICE"
-  )]
-  device: PathBuf,
-
-  /// Metriful device i2c address; usually 0x71, or 0x71 if the solder bridge is
+  /// TOML file with default settings, keyed by long flag name, e.g.
+  /// `gpio-ready = 17`. Flags and environment variables take precedence.
+  #[structopt(long, parse(from_os_str), env = "METRIFUL_CONFIG")]
+  #[serde(skip)]
+  config: Option<PathBuf>,
+
+  /// system i2c device, e.g. /dev/i2c-1 [env: METRIFUL_I2C_DEVICE, default:
+  /// /dev/i2c-1]
+  #[structopt(long, short, parse(from_os_str), global = true)]
+  #[serde(skip)]
+  device: Option<PathBuf>,
+
+  /// Metriful device i2c address; usually 0x71, or 0x70 if the solder bridge is
   /// closed. Can specify a plain base-10 int or hex with a `0x` prefix.
-  #[structopt(
-    long,
-    parse(try_from_str = try_from_hex_arg),
-    default_value = "0x71",
-    global = true,
-    env = "METRIFUL_I2C_ADDRESS"
-  )]
-  i2c_address: u16,
+  /// [env: METRIFUL_I2C_ADDRESS, default: 0x71]
+  #[structopt(long, parse(try_from_str = parse_i2c_address), global = true)]
+  #[serde(skip)]
+  i2c_address: Option<u16>,
 
   /// GPIO number for the ready signal. Note that this is a GPIO number, not a
   /// physical pin number - the mapping between the two numbers varies by
-  /// device.
-  #[structopt(
-    long,
-    default_value = "11",
-    env = "METRIFUL_GPIO_READY",
-    global = true
-  )]
-  gpio_ready: u64,
+  /// device. [env: METRIFUL_GPIO_READY, default: 11]
+  #[structopt(long, global = true)]
+  #[serde(skip)]
+  gpio_ready: Option<u64>,
 
   /// Global timeout for any individual sensor command in seconds.
-  #[structopt(
-    long,
-    parse(try_from_str = parse_duration_secs),
-    global = true,
-    env = "METRIFUL_TIMEOUT"
-  )]
+  /// [env: METRIFUL_TIMEOUT]
+  #[structopt(long, parse(try_from_str = parse_timeout_secs), global = true)]
+  #[serde(skip)]
   timeout: Option<Duration>,
 
-  /// Cycle period, one of: 0 (3s), 1 (100s), 2 (300s)
-  #[structopt(long, short, default_value = "3s", env = "METRIFUL_INTERVAL")]
+  /// Logic level at which the READY signal is asserted, either `active-low`
+  /// or `active-high` (e.g. with an inverting level shifter).
+  /// [env: METRIFUL_READY_POLARITY, default: active-low]
+  #[structopt(long, global = true)]
+  #[serde(skip)]
+  ready_polarity: Option<ReadyPolarity>,
+
+  /// GPIO chip (e.g. `gpiochip0`) for the ready signal. If set, `gpio-ready`
+  /// is a line offset on this chip and the GPIO character device interface is
+  /// used instead of sysfs. [env: METRIFUL_GPIO_CHIP]
+  #[structopt(long, parse(from_os_str), global = true)]
+  #[serde(skip)]
+  gpio_chip: Option<PathBuf>,
+
+  /// Sensor options resolved from the flags above and the `METRIFUL_*`
+  /// environment variables
+  #[structopt(skip)]
+  #[serde(flatten)]
+  sensor: MetrifulOptions,
+
+  /// Cycle period, one of: 3s, 100s, 5m (or 0, 1, 2) [default: 3s]
+  #[structopt(long = "interval", short = "i", value_name = "interval", env = "METRIFUL_INTERVAL")]
+  #[serde(skip)]
+  interval_arg: Option<CyclePeriod>,
+
+  /// Cycle period resolved from the flag above and the config file
+  #[structopt(skip = DEFAULT_INTERVAL)]
   interval: CyclePeriod,
 
-  /// HTTP server port
-  #[structopt(long, short, default_value = "8083", env = "METRIFUL_PORT")]
+  /// HTTP server port [default: 8083]
+  #[structopt(long = "port", short = "p", value_name = "port", env = "METRIFUL_PORT")]
+  #[serde(skip)]
+  port_arg: Option<u16>,
+
+  /// HTTP server port resolved from the flag above and the config file
+  #[structopt(skip = DEFAULT_PORT)]
   port: u16,
+
+  /// If set, advertises the exporter via mDNS as `_metriful._tcp` and
+  /// `_prometheus-http._tcp`. [env: METRIFUL_MDNS=true|false]
+  #[structopt(long)]
+  mdns: bool,
+
+  /// Sensor name to publish in mDNS TXT records; defaults to the system
+  /// hostname.
+  #[structopt(long, env = "METRIFUL_NAME")]
+  name: Option<String>,
+
+  /// Number of cycle periods without a new reading after which `/health`
+  /// reports the exporter as unhealthy [default: 3]
+  #[structopt(long = "stale-after", value_name = "cycles", env = "METRIFUL_STALE_AFTER")]
+  #[serde(skip)]
+  stale_after_arg: Option<u32>,
+
+  /// Staleness threshold resolved from the flag above and the config file
+  #[structopt(skip = DEFAULT_STALE_AFTER)]
+  stale_after: u32,
+
+  /// PEM certificate chain; if set with `tls-key`, endpoints are served over
+  /// HTTPS. Requires the `exporter-tls` feature.
+  #[structopt(long, parse(from_os_str), env = "METRIFUL_TLS_CERT")]
+  tls_cert: Option<PathBuf>,
+
+  /// PEM private key for `tls-cert`
+  #[structopt(long, parse(from_os_str), env = "METRIFUL_TLS_KEY")]
+  tls_key: Option<PathBuf>,
 }
 
-type Reading = Option<UnitValue<UnitCombinedData>>;
-
-fn export_reading(
-  exporter: &Exporter,
-  reading: &Reading,
-  read_count: &Arc<AtomicUsize>,
-  error_count: &Arc<AtomicUsize>,
-) -> String {
-  let mut s = exporter.session();
-
-  match reading {
-    Some(r) => {
-      export!(s, "metriful_ready", 1);
-
-      let air = &r.value.air.value;
-      export!(
-        s, "metriful_air_gas_sensor_resistance", air.gas_sensor_resistance.value,
-        unit = air.gas_sensor_resistance.unit.get_name()
-      );
-      export!(
-        s, "metriful_air_humidity", air.humidity.value,
-        unit = air.humidity.unit.get_name()
-      );
-      export!(
-        s, "metriful_air_pressure", air.pressure.value,
-        unit = air.pressure.unit.get_name()
-      );
-      export!(
-        s, "metriful_air_temperature", air.temperature.value,
-        unit = air.temperature.unit.get_name()
-      );
-
-      let air_quality = &r.value.air_quality.value;
-      export!(
-        s, "metriful_air_quality_aqi", air_quality.aqi.value,
-        unit = air_quality.aqi.unit.get_name()
-      );
-      export!(
-        s, "metriful_air_quality_aqi_accuracy", air_quality.aqi_accuracy.value.to_uint(),
-        unit = air_quality.aqi_accuracy.unit.get_name()
-      );
-      export!(
-        s, "metriful_air_quality_estimated_co2", air_quality.estimated_co2.value,
-        unit = air_quality.estimated_co2.unit.get_name()
-      );
-      export!(
-        s, "metriful_air_quality_estimated_voc", air_quality.estimated_voc.value,
-        unit = air_quality.estimated_voc.unit.get_name()
-      );
-
-      let light = &r.value.light.value;
-      export!(
-        s, "metriful_light_illuminance", light.illuminance.value,
-        unit = light.illuminance.unit.get_name()
-      );
-      export!(
-        s, "metriful_light_white_level", light.white_level.value,
-        unit = light.white_level.unit.get_name()
-      );
-
-      // TODO: particle sensors not currently supported as we'd need to
-      // configure the sensor at startup
-      // TODO: we could _technically_ still expose the values here if users
-      // somehow configured it, since we know the initial_status, but this seems
-      // unlikely.
-      // let particle = &r.value.particle.value;
-      // export!(
-      //   s, "metriful_particle_concentration", particle.concentration.value,
-      //   unit = particle.concentration.unit.get_name()
-      // );
-      // TODO: duty_cycle, validity
-
-      let sound = &r.value.sound.value;
-      export!(
-        s, "metriful_sound_measurement_stable",
-        sound.measurement_stability.value.to_uint(),
-        unit = sound.measurement_stability.unit.get_name()
-      );
-      export!(
-        s, "metriful_sound_peak_amplitude",
-        sound.peak_amplitude.value,
-        unit = sound.peak_amplitude.unit.get_name()
-      );
-      export!(
-        s, "metriful_sound_weighted_spl",
-        sound.weighted_spl.value,
-        unit = sound.weighted_spl.unit.get_name()
-      );
-
-      let [b1, b2, b3, b4, b5, b6] = sound.spl_bands.value.0;
-      export!(
-        s, "metriful_sound_spl_band",
-        b1,
-        unit = "decibels",
-        band = "1",
-        band_midpoint_hz = "125",
-        band_lower_hz = "88",
-        band_upper_hz = "177"
-      );
-      export!(
-        s, "metriful_sound_spl_band",
-        b2,
-        unit = "decibels",
-        band = "2",
-        band_midpoint_hz = "250",
-        band_lower_hz = "177",
-        band_upper_hz = "354"
-      );
-      export!(
-        s, "metriful_sound_spl_band",
-        b3,
-        unit = "decibels",
-        band = "3",
-        band_midpoint_hz = "500",
-        band_lower_hz = "354",
-        band_upper_hz = "707"
-      );
-      export!(
-        s, "metriful_sound_spl_band",
-        b4,
-        unit = "decibels",
-        band = "4",
-        band_midpoint_hz = "1000",
-        band_lower_hz = "707",
-        band_upper_hz = "1414"
-      );
-      export!(
-        s, "metriful_sound_spl_band",
-        b5,
-        unit = "decibels",
-        band = "5",
-        band_midpoint_hz = "2000",
-        band_lower_hz = "1414",
-        band_upper_hz = "2828"
-      );
-      export!(
-        s, "metriful_sound_spl_band",
-        b6,
-        unit = "decibels",
-        band = "6",
-        band_midpoint_hz = "4000",
-        band_lower_hz = "2828",
-        band_upper_hz = "5657"
-      );
-    },
-    None => {
-      export!(s, "metriful_ready", 0);
+const DEFAULT_INTERVAL: CyclePeriod = CyclePeriod::Period0;
+const DEFAULT_PORT: u16 = 8083;
+const DEFAULT_STALE_AFTER: u32 = 3;
+
+/// Enables mDNS when set to `true`. Read by hand rather than via structopt's
+/// `env`, which would treat any non-empty value (even `false`) as set.
+const ENV_MDNS: &str = "METRIFUL_MDNS";
+
+/// Number of readings buffered per WebSocket client; clients that fall further
+/// behind skip readings rather than holding up others.
+const WS_BUFFER: usize = 16;
+
+/// Settings loaded from `--config`. Keys match the long flag names.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct ConfigFile {
+  device: Option<PathBuf>,
+  i2c_address: Option<u16>,
+  gpio_ready: Option<u64>,
+
+  /// Timeout in seconds
+  timeout: Option<u64>,
+
+  ready_polarity: Option<String>,
+  gpio_chip: Option<PathBuf>,
+  interval: Option<String>,
+  port: Option<u16>,
+  mdns: Option<bool>,
+  name: Option<String>,
+  stale_after: Option<u32>,
+  tls_cert: Option<PathBuf>,
+  tls_key: Option<PathBuf>,
+}
+
+impl ConfigFile {
+  fn load(path: &Path) -> Result<ConfigFile> {
+    let contents = fs::read_to_string(path)
+      .wrap_err_with(|| format!("could not read config file {:?}", path))?;
+
+    toml::from_str(&contents)
+      .wrap_err_with(|| format!("invalid config file {:?}", path))
+  }
+
+  /// Returns the value standing in for the given sensor environment variable,
+  /// if set, so it is parsed and validated like the variable would be.
+  fn var(&self, name: &str) -> Option<String> {
+    let path = |p: &Option<PathBuf>| p.as_ref().map(|p| p.to_string_lossy().into_owned());
+
+    match name {
+      ENV_I2C_DEVICE => path(&self.device),
+      ENV_I2C_ADDRESS => self.i2c_address.map(|a| a.to_string()),
+      ENV_GPIO_READY => self.gpio_ready.map(|g| g.to_string()),
+      ENV_TIMEOUT => self.timeout.map(|t| t.to_string()),
+      ENV_READY_POLARITY => self.ready_polarity.clone(),
+      ENV_GPIO_CHIP => path(&self.gpio_chip),
+      _ => None,
+    }
+  }
+}
+
+impl Options {
+  /// Resolves options from the command line, environment, and config file,
+  /// in that order of precedence.
+  fn resolve(&mut self) -> Result<()> {
+    let config = match &self.config {
+      Some(path) => ConfigFile::load(path)?,
+      None => ConfigFile::default(),
+    };
+
+    self.resolve_sensor_options(&config)?;
+
+    self.interval = match (self.interval_arg, &config.interval) {
+      (Some(interval), _) => interval,
+      (None, Some(interval)) => interval.parse()
+        .wrap_err_with(|| format!("invalid interval in config file: {:?}", interval))?,
+      (None, None) => DEFAULT_INTERVAL,
+    };
+
+    self.port = self.port_arg.or(config.port).unwrap_or(DEFAULT_PORT);
+    let env_mdns = env::var(ENV_MDNS).ok().filter(|v| !v.is_empty());
+    self.mdns = resolve_mdns(self.mdns, env_mdns.as_deref(), config.mdns)?;
+    self.name = self.name.take().or(config.name);
+    self.stale_after = self.stale_after_arg.or(config.stale_after).unwrap_or(DEFAULT_STALE_AFTER);
+    if self.stale_after == 0 {
+      return Err(eyre!("stale-after must be at least 1"));
+    }
+
+    self.tls_cert = self.tls_cert.take().or(config.tls_cert);
+    self.tls_key = self.tls_key.take().or(config.tls_key);
+
+    match (&self.tls_cert, &self.tls_key) {
+      (Some(_), None) => return Err(eyre!("tls-cert requires tls-key")),
+      (None, Some(_)) => return Err(eyre!("tls-key requires tls-cert")),
+      (Some(_), Some(_)) if cfg!(not(feature = "exporter-tls")) => return Err(eyre!(
+        "TLS is not supported by this build, rebuild with the exporter-tls feature"
+      )),
+      _ => (),
+    }
+
+    Ok(())
+  }
+
+  fn scheme(&self) -> &'static str {
+    match self.tls_cert {
+      Some(_) => "https",
+      None => "http",
+    }
+  }
+
+  /// Resolves sensor options from the environment and config file, with any
+  /// flags given on the command line taking precedence.
+  fn resolve_sensor_options(&mut self, config: &ConfigFile) -> Result<()> {
+    let mut sensor = MetrifulOptions::from_lookup(|name| {
+      env::var(name).ok().filter(|v| !v.is_empty()).or_else(|| config.var(name))
+    }).wrap_err("invalid sensor options in environment or config file")?;
+
+    if let Some(device) = &self.device {
+      sensor.i2c_device = device.clone();
+    }
+
+    if let Some(i2c_address) = self.i2c_address {
+      sensor.i2c_address = i2c_address;
+    }
+
+    if let Some(gpio_ready) = self.gpio_ready {
+      sensor.gpio_ready = gpio_ready;
+    }
+
+    if self.timeout.is_some() {
+      sensor.timeout = self.timeout;
+    }
+
+    if let Some(ready_polarity) = self.ready_polarity {
+      sensor.ready_polarity = ready_polarity;
     }
+
+    if self.gpio_chip.is_some() {
+      sensor.gpio_chip = self.gpio_chip.clone();
+    }
+
+    self.sensor = sensor;
+    Ok(())
+  }
+}
+
+/// Resolves whether to enable mDNS from the `--mdns` flag, the `METRIFUL_MDNS`
+/// value and the config file, in that order of precedence.
+fn resolve_mdns(flag: bool, env: Option<&str>, config: Option<bool>) -> Result<bool> {
+  if flag {
+    return Ok(true);
+  }
+
+  match env.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+    Some("true") | Some("1") | Some("yes") => Ok(true),
+    Some("false") | Some("0") | Some("no") => Ok(false),
+    Some(_) => Err(eyre!("invalid {}: {:?}, expected true or false", ENV_MDNS, env.unwrap())),
+    None => Ok(config.unwrap_or(false)),
+  }
+}
+
+/// mDNS service types advertised by the exporter.
+const MDNS_SERVICE_TYPES: &[&str] = &[
+  "_metriful._tcp.local.",
+  "_prometheus-http._tcp.local.",
+];
+
+/// Registers the exporter's HTTP endpoints via mDNS. The returned daemon must
+/// be kept alive for as long as the services should be advertised.
+fn register_mdns(opts: &Options) -> Result<ServiceDaemon> {
+  let hostname = hostname::get()
+    .wrap_err("could not determine hostname")?
+    .to_string_lossy()
+    .into_owned();
+
+  let name = opts.name.clone().unwrap_or_else(|| hostname.clone());
+  let host = format!("{}.local.", hostname);
+  let properties = [
+    ("name", name.clone()),
+    ("path", "/metrics".to_string()),
+    ("json_path", "/json".to_string()),
+    ("health_path", "/health".to_string()),
+    ("ws_path", "/ws".to_string()),
+    ("i2c_address", format!("0x{:x}", opts.sensor.i2c_address)),
+    ("interval", format!("{}s", opts.interval.to_duration().as_secs())),
+    ("scheme", opts.scheme().to_string()),
+  ];
+
+  let daemon = ServiceDaemon::new().wrap_err("could not start mDNS daemon")?;
+  for service_type in MDNS_SERVICE_TYPES {
+    let info = ServiceInfo::new(
+      service_type, &name, &host, (), opts.port, &properties[..]
+    ).wrap_err_with(|| format!("invalid mDNS service info for {}", service_type))?;
+
+    daemon.register(info.enable_addr_auto())
+      .wrap_err_with(|| format!("could not register mDNS service {}", service_type))?;
+
+    info!("advertising {} as {:?} via mDNS", service_type, name);
+  }
+
+  Ok(daemon)
+}
+
+type Reading = LatestReading<UnitValue<UnitPartialCombinedData>>;
+
+fn export_reading(latest: &Reading, counters: &Counters) -> String {
+  let mut encoder = PrometheusEncoder::new();
+  encoder.partial_reading(latest.snapshot().as_deref());
+  encoder.gauge("metriful_read_count", latest.version() as f64, &[]);
+  encoder.gauge("metriful_error_count", latest.error_count() as f64, &[]);
+  encoder.device_stats(&counters.snapshot());
+
+  encoder.finish()
+}
+
+/// Formats a reading as a WebSocket message.
+fn reading_message(version: u64, reading: &UnitValue<UnitPartialCombinedData>) -> Message {
+  Message::text(json!({
+    "read_count": version,
+    "reading": reading,
+  }).to_string())
+}
+
+/// Sends the latest reading to a WebSocket client, then each new one as it
+/// arrives, until the client disconnects or the reader stops.
+async fn stream_readings(
+  socket: WebSocket,
+  initial: Option<Message>,
+  mut readings: broadcast::Receiver<Message>,
+) {
+  let (mut tx, mut rx) = socket.split();
+
+  if let Some(message) = initial {
+    if tx.send(message).await.is_err() {
+      return;
+    }
+  }
+
+  loop {
+    tokio::select! {
+      reading = readings.recv() => match reading {
+        Ok(message) => {
+          if let Err(e) = tx.send(message).await {
+            debug!("websocket client disconnected: {}", e);
+            break;
+          }
+        },
+        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+          debug!("websocket client fell behind, skipped {} readings", skipped);
+        },
+        Err(broadcast::error::RecvError::Closed) => break,
+      },
+
+      // messages from the client are ignored, but must be read to notice it
+      // disconnecting
+      message = rx.next() => match message {
+        Some(Ok(_)) => (),
+        _ => break,
+      },
+    }
+  }
+
+  let _ = tx.close().await;
+}
+
+/// Checks that the reader is still running and has produced a reading within
+/// `max_age`, counting from `started` until the first one. Returns the status
+/// to report, and whether it is healthy.
+fn check_health(latest: &Reading, started: DateTime<Utc>, max_age: Duration) -> (serde_json::Value, bool) {
+  let reading = latest.snapshot();
+  let last = reading.as_ref().map(|r| r.time).unwrap_or(started);
+  let age = Utc::now().signed_duration_since(last).to_std().unwrap_or_default();
+
+  let error = if latest.is_closed() {
+    match latest.last_error() {
+      Some(e) => Some(format!("sensor reader stopped: {}", e)),
+      None => Some("sensor reader stopped".to_string()),
+    }
+  } else if age > max_age {
+    match reading {
+      Some(_) => Some(format!("last reading is stale ({}s old)", age.as_secs())),
+      None => Some(format!("no reading after {}s", age.as_secs())),
+    }
+  } else {
+    None
+  };
+
+  let status = json!({
+    "status": if error.is_some() { "error" } else { "ok" },
+    "error": error,
+    "age_s": age.as_secs_f64(),
+    "max_age_s": max_age.as_secs_f64(),
+    "read_count": latest.version(),
+    "error_count": latest.error_count(),
+  });
+
+  (status, error.is_none())
+}
+
+/// Sends a state update (e.g. `READY=1`) to systemd if running as a
+/// `Type=notify` service. Failures are logged, not returned, as the service
+/// works regardless.
+fn sd_notify(state: &str) {
+  let path = match env::var_os("NOTIFY_SOCKET") {
+    Some(path) => path,
+    None => return,
   };
 
-  export!(s, "metriful_read_count", read_count.load(Ordering::Relaxed) as f64);
-  export!(s, "metriful_error_count", error_count.load(Ordering::Relaxed) as f64);
+  trace!("sd_notify({:?})", state);
+  if let Err(e) = send_notify(&path, state) {
+    warn!("could not notify systemd via {:?}: {}", path, e);
+  }
+}
+
+fn send_notify(path: &OsStr, state: &str) -> io::Result<()> {
+  let socket = UnixDatagram::unbound()?;
 
-  s.to_string()
+  // a leading @ denotes an abstract socket
+  match path.as_bytes().strip_prefix(b"@") {
+    Some(name) => socket.send_to_addr(state.as_bytes(), &SocketAddr::from_abstract_name(name)?)?,
+    None => socket.send_to(state.as_bytes(), path)?,
+  };
+
+  Ok(())
+}
+
+/// Returns the interval within which systemd expects `WATCHDOG=1`, if the
+/// watchdog is enabled for this process.
+fn watchdog_timeout() -> Option<Duration> {
+  if let Ok(pid) = env::var("WATCHDOG_PID") {
+    if pid.parse() != Ok(std::process::id()) {
+      return None;
+    }
+  }
+
+  let usec = env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+  Some(Duration::from_micros(usec))
 }
 
 #[tokio::main]
@@ -249,108 +486,226 @@ async fn main() -> Result<()> {
     .target(env_logger::Target::Stderr)
     .init();
 
-  let opts = Options::from_args();
+  let mut opts = Options::from_args();
+  opts.resolve()?;
   let port = opts.port;
-
-  let latest_reading_lock = Arc::new(RwLock::new(None));
-  let read_count = Arc::new(AtomicUsize::new(0));
-  let error_count = Arc::new(AtomicUsize::new(0));
+  let started = Utc::now();
 
   // initialize the sensor and start the async read thread
-  let sensor_opts = opts.clone();
-  let res: Result<_> = task::spawn_blocking(move || {
-    let mut metriful = Metriful::try_new(
-      sensor_opts.gpio_ready,
-      sensor_opts.device,
-      sensor_opts.i2c_address
-    ).wrap_err("could not initialize sensor")?;
-
-    metriful.wait_for_ready_timeout(sensor_opts.timeout)
-      .wrap_err("sensor did not become ready in time")?;
-
-    metriful.reset().wrap_err("sensor reset failed")?;
-
-    // fetch the initial status while we're here - we need it to determine the
-    // particle sensor type, if any
-    let status = metriful.read_status()
-      .wrap_err("could not read sensor status")?;
-
-    info!("sensor is ready, status: {:?}", &status);
-
-    let handles = metriful.async_cycle_read_timeout(
-      *METRIC_COMBINED_ALL,
-      sensor_opts.interval,
-      sensor_opts.timeout
-    );
-
-    Ok((status, handles))
-  }).await?;
-
-  // unpack the channel + handle (separate for type inference reasons)
-  let (initial_status, (_tx, rx, _handle)) = res?;
-
-  // spawn a task to continuously move the latest reading into latest_reading_lock
-  let data_lock = Arc::clone(&latest_reading_lock);
-  let data_read_count = Arc::clone(&read_count);
-  let data_error_count = Arc::clone(&error_count);
+  let metriful = AsyncMetriful::open(opts.sensor.clone()).await
+    .wrap_err("could not initialize sensor")?;
+
+  metriful.reset().await.wrap_err("sensor reset failed")?;
+
+  // fetch the initial status while we're here - we need it to determine the
+  // particle sensor type, if any
+  let initial_status = metriful.read_status().await
+    .wrap_err("could not read sensor status")?;
+
+  info!("sensor is ready, status: {:?}", &initial_status);
+
+  let mut metriful = metriful.into_inner()
+    .map_err(|_| eyre!("sensor is still in use"))?;
+
+  // lets shutdown interrupt the reader's wait for the next cycle
+  let cancel = CancelToken::new();
+  metriful.set_cancel_token(Some(cancel.clone()));
+
+  let counters = metriful.counters();
+  let (tx, latest, handle) = metriful
+    .async_cycle_read_latest(METRIC_COMBINED_ALL_PARTIAL, opts.interval, opts.sensor.timeout);
+
+  let max_age = opts.interval.to_duration() * opts.stale_after;
+
+  // the first reading takes up to a cycle period, which may exceed systemd's
+  // default start timeout
+  sd_notify(&format!("EXTEND_TIMEOUT_USEC={}", max_age.as_micros()));
+
+  let ready_latest = latest.clone();
   task::spawn_blocking(move || {
-    for reading in rx.iter() {
-      match reading {
-        Ok(reading) => match data_lock.try_write() {
-          Ok(mut r) => {
-            *r = Some(reading);
-            data_read_count.fetch_add(1, Ordering::Relaxed);
-          },
-          Err(e) => {
-            error!("could not acquire write lock, reading will be dropped: {}", e);
-            data_error_count.fetch_add(1, Ordering::Relaxed);
-          }
-        },
-        Err(e) => {
-          error!("error in sensor read: {}", e);
-          data_error_count.fetch_add(1, Ordering::Relaxed);
+    if ready_latest.wait_for_update(0).is_some() {
+      sd_notify("READY=1");
+    }
+  });
+
+  // only pet the watchdog while readings keep arriving, so systemd restarts
+  // the service if the reader stops or the bus wedges
+  if let Some(timeout) = watchdog_timeout() {
+    let watchdog_latest = latest.clone();
+    tokio::spawn(async move {
+      let mut interval = tokio::time::interval(timeout / 2);
+      loop {
+        interval.tick().await;
+
+        match check_health(&watchdog_latest, started, max_age) {
+          (_, true) => sd_notify("WATCHDOG=1"),
+          (status, false) => warn!("not notifying systemd watchdog: {}", status["error"]),
         }
       }
+    });
+  }
+
+  // log read errors as they occur; the reader stops after the first one
+  let error_latest = latest.clone();
+  task::spawn_blocking(move || {
+    while error_latest.wait_for_update(error_latest.version()).is_some() {}
+
+    if let Some(e) = error_latest.last_error() {
+      error!("error in sensor read: {}", e);
     }
   });
 
   // json endpoint
-  let json_lock = Arc::clone(&latest_reading_lock);
-  let json_read_count = Arc::clone(&read_count);
-  let json_error_count = Arc::clone(&error_count);
+  let json_latest = latest.clone();
   let json_opts = opts.clone();
+  let json_counters = counters.clone();
   let r_json = warp::path("json").map(move || {
     trace!("exporter: /json");
-    match *json_lock.read().unwrap() {
-      Some(ref r) => warp::reply::json(&json!({
-        "initial_status": &initial_status,
-        "reading": r,
-        "options": json_opts,
-        "error_count": json_error_count.load(Ordering::Relaxed),
-        "read_count": json_read_count.load(Ordering::Relaxed),
-      })),
-      None => warp::reply::json(&json!(null))
+    let reading = json_latest.snapshot();
+    let last_error = json_latest.last_error();
+    if reading.is_none() && last_error.is_none() {
+      return warp::reply::json(&json!(null));
     }
+
+    warp::reply::json(&json!({
+      "initial_status": &initial_status,
+      "reading": reading.as_deref(),
+      "options": json_opts,
+      "error_count": json_latest.error_count(),
+      "read_count": json_latest.version(),
+      "last_error": last_error.as_deref(),
+      "device_stats": json_counters.snapshot(),
+    }))
   });
 
-  let exporter = Arc::new(Exporter::new());
-  let metrics_lock = Arc::clone(&latest_reading_lock);
-  let metrics_read_count = Arc::clone(&read_count);
-  let metrics_error_count = Arc::clone(&error_count);
+  let metrics_latest = latest.clone();
+  let metrics_counters = counters.clone();
   let r_metrics = warp::path("metrics").map(move || {
     trace!("exporter: /metrics");
-    export_reading(
-      &exporter,
-      &*metrics_lock.read().unwrap(),
-      &metrics_read_count,
-      &metrics_error_count,
-    )
+    export_reading(&metrics_latest, &metrics_counters)
+  });
+
+  let health_latest = latest.clone();
+  let r_health = warp::path("health").map(move || {
+    trace!("exporter: /health");
+    let (status, healthy) = check_health(&health_latest, started, max_age);
+    let code = if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    warp::reply::with_status(warp::reply::json(&status), code)
+  });
+
+  // serialize each reading once for all WebSocket clients
+  let (ws_tx, _) = broadcast::channel(WS_BUFFER);
+  let ws_publisher = ws_tx.clone();
+  let ws_publisher_latest = latest.clone();
+  task::spawn_blocking(move || {
+    let mut seen = 0;
+    while let Some((version, reading)) = ws_publisher_latest.wait_for_update(seen) {
+      seen = version;
+
+      // this only fails if no clients are connected
+      let _ = ws_publisher.send(reading_message(version, &reading));
+    }
   });
 
-  info!("starting exporter on port {}", port);
+  let ws_latest = latest.clone();
+  let r_ws = warp::path("ws").and(warp::ws()).map(move |ws: Ws| {
+    trace!("exporter: /ws");
+    let initial = ws_latest.snapshot_versioned()
+      .map(|(version, reading)| reading_message(version, &reading));
+
+    let readings = ws_tx.subscribe();
+    ws.on_upgrade(move |socket| stream_readings(socket, initial, readings))
+  });
 
-  let routes = warp::get().and(r_json).or(r_metrics);
-  warp::serve(routes).run(([0, 0, 0, 0], port)).await;
+  let _mdns = if opts.mdns {
+    Some(register_mdns(&opts)?)
+  } else {
+    None
+  };
+
+  let mut sigterm = signal(SignalKind::terminate())
+    .wrap_err("could not install SIGTERM handler")?;
+
+  let shutdown = async move {
+    tokio::select! {
+      _ = sigterm.recv() => info!("received SIGTERM, shutting down"),
+      _ = tokio::signal::ctrl_c() => info!("received SIGINT, shutting down"),
+    }
+  };
+
+  info!("starting exporter on port {} ({})", port, opts.scheme());
+
+  let routes = warp::get().and(r_json).or(r_metrics).or(r_health).or(r_ws);
+  let server = warp::serve(routes);
+  let addr = ([0, 0, 0, 0], port);
+  match (&opts.tls_cert, &opts.tls_key) {
+    #[cfg(feature = "exporter-tls")]
+    (Some(cert), Some(key)) => {
+      // read these up front so a missing file is reported by name
+      let cert = fs::read(cert).wrap_err_with(|| format!("could not read TLS certificate {:?}", cert))?;
+      let key = fs::read(key).wrap_err_with(|| format!("could not read TLS key {:?}", key))?;
+
+      let (_, server) = server.tls().cert(cert).key(key)
+        .try_bind_with_graceful_shutdown(addr, shutdown)
+        .wrap_err_with(|| format!("could not listen on port {}", port))?;
+
+      server.await;
+    },
+    _ => {
+      let (_, server) = server.try_bind_with_graceful_shutdown(addr, shutdown)
+        .wrap_err_with(|| format!("could not listen on port {}", port))?;
+
+      server.await;
+    },
+  }
+
+  sd_notify("STOPPING=1");
+
+  // stop the reader, then return the sensor to standby and release the READY
+  // line so it isn't left cycling after we exit
+  let _ = tx.send(ReaderCommand::Stop);
+  cancel.cancel();
+
+  let metriful = task::spawn_blocking(move || handle.join()).await?
+    .map_err(|_| eyre!("sensor read thread panicked"))?;
+
+  metriful.close().wrap_err("could not return sensor to standby")?;
+  info!("sensor returned to standby");
 
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn mdns_env_false_leaves_mdns_off() {
+    assert!(!resolve_mdns(false, Some("false"), None).unwrap());
+    assert!(!resolve_mdns(false, Some("0"), None).unwrap());
+    assert!(!resolve_mdns(false, None, None).unwrap());
+  }
+
+  #[test]
+  fn mdns_precedence() {
+    assert!(resolve_mdns(false, Some("true"), None).unwrap());
+    assert!(resolve_mdns(true, Some("false"), Some(false)).unwrap());
+    assert!(!resolve_mdns(false, Some("false"), Some(true)).unwrap());
+    assert!(resolve_mdns(false, None, Some(true)).unwrap());
+  }
+
+  #[test]
+  fn mdns_env_invalid() {
+    assert!(resolve_mdns(false, Some("maybe"), None).is_err());
+  }
+
+  #[test]
+  fn mdns_flag_takes_no_value() {
+    let opts = Options::from_iter_safe(&["metriful-exporter", "--mdns"]).unwrap();
+    assert!(opts.mdns);
+
+    let opts = Options::from_iter_safe(&["metriful-exporter"]).unwrap();
+    assert!(!opts.mdns);
+  }
+}
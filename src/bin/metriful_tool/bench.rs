@@ -0,0 +1,156 @@
+//! The `bench` subcommand.
+
+use super::*;
+use super::csv::csv_record;
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct BenchAction {
+  /// Number of measurements to time; measurements are at least 2s apart
+  #[structopt(long, short = "n", default_value = "20")]
+  iterations: usize,
+
+  /// Data output format, one of: plain, json, csv
+  #[structopt(long, short, default_value = "plain")]
+  output: OutputMode,
+}
+
+/// Durations recorded by `bench`, in milliseconds, in the order each was
+/// first recorded.
+struct Timings {
+  iterations: usize,
+  series: Vec<(String, Accumulator)>,
+}
+
+impl Timings {
+  fn new(iterations: usize) -> Timings {
+    Timings { iterations, series: Vec::new() }
+  }
+
+  fn record(&mut self, name: &str, elapsed: Duration) {
+    let index = match self.series.iter().position(|(n, _)| n == name) {
+      Some(index) => index,
+      None => {
+        self.series.push((name.to_string(), Accumulator::new(Window::Count(self.iterations))));
+        self.series.len() - 1
+      },
+    };
+
+    self.series[index].1.push(Utc::now(), elapsed.as_secs_f64() * 1000.0);
+  }
+
+  /// Returns each series' name, summary, and 50th, 90th, and 99th percentiles.
+  fn summaries(&self) -> Vec<(&str, Summary, [f64; 3])> {
+    self.series.iter()
+      .filter_map(|(name, acc)| {
+        let summary = acc.summary()?;
+        let percentile = |q| acc.percentile(q).unwrap_or_default();
+
+        Some((name.as_str(), summary, [percentile(0.5), percentile(0.9), percentile(0.99)]))
+      })
+      .collect()
+  }
+}
+
+pub(crate) fn bench(opts: &Options, action: &BenchAction, mut metriful: Metriful) -> Result<()> {
+  if let OutputMode::Influx = action.output {
+    return Err(influx_unsupported());
+  }
+
+  metriful.set_mode_timeout(OperationalMode::Standby, opts.sensor.timeout)
+    .map_err(|e| report_error(action.output, e))?;
+
+  // only registers readable after an on-demand measurement
+  let registers: Vec<(String, Register)> = metrics().iter()
+    .filter(|info| !info.validity.cycle_mode && !info.validity.particle_sensor)
+    .filter_map(|info| Some((format!("read_{}", info.id), Register::from_address(info.register)?)))
+    .collect();
+
+  let mut timings = Timings::new(action.iterations);
+  for i in 0..action.iterations {
+    // only the accepted attempt is timed, excluding waits for the rate limit
+    let start = loop {
+      let attempt = Instant::now();
+      match metriful.execute_measurement() {
+        Err(MetrifulError::RateLimited { retry_after }) => thread::sleep(retry_after),
+        result => {
+          result.map_err(|e| report_error(action.output, e))?;
+          break attempt;
+        },
+      }
+    };
+
+    timings.record("command", start.elapsed());
+
+    let wait = Instant::now();
+    metriful.wait_for_ready().map_err(|e| report_error(action.output, e))?;
+    timings.record("ready_wait", wait.elapsed());
+    timings.record("measurement", start.elapsed());
+
+    for (name, register) in &registers {
+      let read = Instant::now();
+      metriful.read_register(*register).map_err(|e| report_error(action.output, e))?;
+      timings.record(name, read.elapsed());
+    }
+
+    debug!("bench: finished iteration {} of {}", i + 1, action.iterations);
+  }
+
+  let summaries = timings.summaries();
+  match action.output {
+    OutputMode::Plain => {
+      println!("ready line: {}", ready_line_description(opts));
+      println!(
+        "{:<40} {:>5} {:>9} {:>9} {:>9} {:>9} {:>9}",
+        "timing (ms)", "n", "min", "p50", "p90", "p99", "max"
+      );
+
+      for (name, summary, [p50, p90, p99]) in &summaries {
+        println!(
+          "{:<40} {:>5} {:>9.3} {:>9.3} {:>9.3} {:>9.3} {:>9.3}",
+          name, summary.count, summary.min, p50, p90, p99, summary.max
+        );
+      }
+    },
+    OutputMode::JSON => {
+      let timings: serde_json::Map<String, serde_json::Value> = summaries.iter()
+        .map(|(name, summary, [p50, p90, p99])| (name.to_string(), json!({
+          "count": summary.count,
+          "min_ms": summary.min,
+          "p50_ms": p50,
+          "p90_ms": p90,
+          "p99_ms": p99,
+          "max_ms": summary.max,
+          "mean_ms": summary.mean,
+          "stddev_ms": summary.stddev,
+        })))
+        .collect();
+
+      println!("{}", json!({
+        "ready_line": ready_line_description(opts),
+        "timings": timings,
+      }));
+    },
+    OutputMode::CSV => {
+      println!("{}", csv_record(&[
+        "name", "count", "min_ms", "p50_ms", "p90_ms", "p99_ms", "max_ms", "mean_ms", "stddev_ms",
+      ]));
+
+      for (name, summary, [p50, p90, p99]) in &summaries {
+        println!("{}", csv_record(&[
+          name.to_string(),
+          summary.count.to_string(),
+          summary.min.to_string(),
+          p50.to_string(),
+          p90.to_string(),
+          p99.to_string(),
+          summary.max.to_string(),
+          summary.mean.to_string(),
+          summary.stddev.to_string(),
+        ]));
+      }
+    },
+    OutputMode::Influx => unreachable!("rejected above"),
+  }
+
+  Ok(())
+}
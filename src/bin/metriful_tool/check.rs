@@ -0,0 +1,260 @@
+//! The `check` subcommand.
+
+use super::*;
+use super::units::set_in_units;
+
+/// A comparison in a `check` threshold.
+#[derive(Debug, Copy, Clone)]
+enum ThresholdOp {
+  Above,
+  AtLeast,
+  Below,
+  AtMost,
+}
+
+impl ThresholdOp {
+  fn symbol(&self) -> &'static str {
+    match self {
+      ThresholdOp::Above => ">",
+      ThresholdOp::AtLeast => ">=",
+      ThresholdOp::Below => "<",
+      ThresholdOp::AtMost => "<=",
+    }
+  }
+}
+
+/// A `check` threshold, e.g. `co2>1000`.
+#[derive(Debug, Clone)]
+struct Threshold {
+  metric: DynamicMetric,
+  op: ThresholdOp,
+  value: f64,
+}
+
+impl Threshold {
+  /// Returns true if `value` breaches this threshold.
+  fn breached(&self, value: f64) -> bool {
+    match self.op {
+      ThresholdOp::Above => value > self.value,
+      ThresholdOp::AtLeast => value >= self.value,
+      ThresholdOp::Below => value < self.value,
+      ThresholdOp::AtMost => value <= self.value,
+    }
+  }
+
+  /// Returns this threshold as a monitoring plugin range, for perfdata.
+  fn range(&self) -> String {
+    match self.op {
+      ThresholdOp::Above | ThresholdOp::AtLeast => format!("{}", self.value),
+      ThresholdOp::Below | ThresholdOp::AtMost => format!("{}:", self.value),
+    }
+  }
+}
+
+impl FromStr for Threshold {
+  type Err = Error;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let index = s.find(&['<', '>'][..])
+      .ok_or_else(|| eyre!("invalid threshold '{}', expected e.g. co2>1000 or temperature<=10", s))?;
+
+    let (name, rest) = s.split_at(index);
+    let (op, value) = match (rest.starts_with('>'), rest[1..].strip_prefix('=')) {
+      (true, Some(value)) => (ThresholdOp::AtLeast, value),
+      (true, None) => (ThresholdOp::Above, &rest[1..]),
+      (false, Some(value)) => (ThresholdOp::AtMost, value),
+      (false, None) => (ThresholdOp::Below, &rest[1..]),
+    };
+
+    Ok(Threshold {
+      metric: name.trim().parse()?,
+      op,
+      value: value.trim().parse()
+        .map_err(|_| eyre!("invalid threshold value '{}' in '{}'", value, s))?,
+    })
+  }
+}
+
+/// Warning and critical thresholds checked against readings.
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct Thresholds {
+  /// Reports WARNING if a metric crosses this threshold, e.g. co2>1000 or
+  /// temperature<=10; may be given several times. Values are in the units
+  /// selected with `--units`.
+  #[structopt(long, number_of_values = 1)]
+  warn: Vec<Threshold>,
+
+  /// Reports CRITICAL if a metric crosses this threshold; may be given
+  /// several times
+  #[structopt(long, number_of_values = 1)]
+  crit: Vec<Threshold>,
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct CheckAction {
+  #[structopt(flatten)]
+  thresholds: Thresholds,
+
+  /// Starts cycle mode with this period if the sensor isn't already cycling,
+  /// and leaves it cycling for later checks; needed to check metrics such as
+  /// aqi and co2. One of: 3s, 100s, 5m (or 0, 1, 2)
+  #[structopt(long)]
+  cycle: Option<CyclePeriod>,
+}
+
+/// Sensor timeout for `check` when none is configured, so a missing sensor is
+/// reported as UNKNOWN rather than hanging the monitoring system.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The result of a `check`, per monitoring plugin conventions; the
+/// discriminant is the exit status.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum CheckState {
+  Ok = 0,
+  Warning = 1,
+  Critical = 2,
+  Unknown = 3,
+}
+
+impl std::fmt::Display for CheckState {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      CheckState::Ok => "OK",
+      CheckState::Warning => "WARNING",
+      CheckState::Critical => "CRITICAL",
+      CheckState::Unknown => "UNKNOWN",
+    })
+  }
+}
+
+/// The result of checking a reading against [`Thresholds`].
+pub(crate) struct CheckResult {
+  pub(crate) state: CheckState,
+
+  /// Values breaching a threshold, or all values checked if none do
+  summary: String,
+
+  /// Monitoring plugin perfdata, e.g. `'temperature'=21.3;28;`
+  perfdata: String,
+}
+
+impl CheckResult {
+  fn unknown(summary: impl ToString) -> CheckResult {
+    CheckResult { state: CheckState::Unknown, summary: summary.to_string(), perfdata: String::new() }
+  }
+}
+
+impl std::fmt::Display for CheckResult {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "METRIFUL {} - {}", self.state, self.summary)?;
+
+    if !self.perfdata.is_empty() {
+      write!(f, " | {}", self.perfdata)?;
+    }
+
+    Ok(())
+  }
+}
+
+impl Thresholds {
+  pub(crate) fn is_empty(&self) -> bool {
+    self.warn.is_empty() && self.crit.is_empty()
+  }
+
+  /// Returns each metric with a threshold once, in the order first given.
+  fn metrics(&self) -> Vec<DynamicMetric> {
+    let mut metrics: Vec<DynamicMetric> = Vec::new();
+    for threshold in self.warn.iter().chain(&self.crit) {
+      if !metrics.contains(&threshold.metric) {
+        metrics.push(threshold.metric);
+      }
+    }
+
+    metrics
+  }
+
+  /// Checks a reading against these thresholds. Metrics missing from the
+  /// reading, or without a numeric value, are reported as UNKNOWN.
+  pub(crate) fn check(&self, reading: &MetricSetReading) -> CheckResult {
+    let mut state = CheckState::Ok;
+    let mut values = Vec::new();
+    let mut problems = Vec::new();
+    let mut perfdata = Vec::new();
+
+    for metric in self.metrics() {
+      let id = metric.metadata().id;
+      let (r, value) = match reading.get(id).and_then(|r| r.value.as_f64().map(|v| (r, v))) {
+        Some(found) => found,
+        None => return CheckResult::unknown(format!("{} has no numeric value to check", id)),
+      };
+
+      let mut warn = self.warn.iter().filter(|t| t.metric == metric);
+      let mut crit = self.crit.iter().filter(|t| t.metric == metric);
+
+      let breach = crit.clone().find(|t| t.breached(value)).map(|t| (CheckState::Critical, t))
+        .or_else(|| warn.clone().find(|t| t.breached(value)).map(|t| (CheckState::Warning, t)));
+
+      if let Some((breach_state, threshold)) = breach {
+        state = state.max(breach_state);
+        problems.push(format!("{} {} ({} {})", id, r, threshold.op.symbol(), threshold.value));
+      }
+
+      values.push(format!("{} {}", id, r));
+      perfdata.push(format!(
+        "'{}'={};{};{}",
+        id, value as f32,
+        warn.next().map(Threshold::range).unwrap_or_default(),
+        crit.next().map(Threshold::range).unwrap_or_default(),
+      ));
+    }
+
+    let summary = if problems.is_empty() { values } else { problems };
+    CheckResult { state, summary: summary.join(", "), perfdata: perfdata.join(" ") }
+  }
+}
+
+/// Opens the sensor and reads the metrics named by `action`'s thresholds,
+/// taking a single measurement unless the sensor is already cycling.
+fn check_reading(opts: &Options, action: &CheckAction) -> Result<MetricSetReading> {
+  let sensor = opts.sensor.clone().timeout(opts.sensor.timeout.or(Some(CHECK_TIMEOUT)));
+  let mut metriful = sensor.open()?;
+
+  let set = action.thresholds.metrics()
+    .into_iter()
+    .fold(MetricSet::new(), |set, metric| set.with(metric));
+
+  // a sensor that is already cycling (e.g. from an earlier `--cycle` check)
+  // is read as-is rather than interrupted
+  metriful.read_status()?;
+  match action.cycle {
+    _ if metriful.is_mode_cycle() => metriful.wait_for_ready()?,
+    Some(period) => {
+      metriful.set_mode_timeout(OperationalMode::Cycle(period), sensor.timeout)?;
+
+      // the first cycle completes a full period later
+      metriful.wait_for_ready_timeout(sensor.timeout.map(|t| t + period.to_duration()))?;
+    },
+    None => {
+      metriful.execute_measurement()?;
+      metriful.wait_for_ready()?;
+    },
+  }
+
+  Ok(set_in_units(&metriful.read_set(&set)?, opts.units))
+}
+
+/// Checks a reading against `action`'s thresholds and prints a status line
+/// with perfdata, e.g. `METRIFUL WARNING - estimated_co2 1200 ppm (> 1000) |
+/// 'estimated_co2'=1200;1000;2000`. Any error is reported as UNKNOWN.
+pub(crate) fn check(opts: &Options, action: &CheckAction) -> CheckState {
+  let result = if action.thresholds.is_empty() {
+    CheckResult::unknown("no thresholds given; see --warn and --crit")
+  } else {
+    match check_reading(opts, action) {
+      Ok(reading) => action.thresholds.check(&reading),
+      Err(e) => CheckResult::unknown(e),
+    }
+  };
+
+  println!("{}", result);
+  result.state
+}
@@ -0,0 +1,224 @@
+//! The `compare` subcommand.
+
+use super::*;
+use super::csv::csv_record;
+use super::units::set_in_units;
+use super::watch::WatchLimits;
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct CompareAction {
+  /// i2c device of the second sensor; defaults to that of the first
+  #[structopt(long, parse(from_os_str))]
+  second_device: Option<PathBuf>,
+
+  /// i2c address of the second sensor; defaults to that of the first
+  #[structopt(long, parse(try_from_str = parse_i2c_address))]
+  second_address: Option<u16>,
+
+  /// GPIO number (or line offset, with `--gpio-chip`) for the second
+  /// sensor's ready signal; defaults to that of the first
+  #[structopt(long)]
+  second_gpio_ready: Option<u64>,
+
+  /// Data output format, one of: plain, json, csv
+  #[structopt(long, short, default_value = "plain")]
+  output: OutputMode,
+
+  /// Time interval between measurements in seconds
+  #[structopt(
+    long, short,
+    default_value = "2",
+    parse(try_from_str = try_watch_interval_from_str)
+  )]
+  interval: Duration,
+
+  #[structopt(flatten)]
+  limits: WatchLimits,
+}
+
+/// A value read from both sensors by `compare`.
+#[derive(Debug, Serialize)]
+struct Comparison {
+  name: String,
+  first: serde_json::Value,
+  second: serde_json::Value,
+
+  /// The second sensor's value minus the first's, if both are numeric
+  delta: Option<f64>,
+}
+
+impl Comparison {
+  /// Pairs up the values of two readings of the same metrics.
+  fn all(first: &MetricSetReading, second: &MetricSetReading) -> Vec<Comparison> {
+    let flatten = |reading: &MetricSetReading| {
+      let mut columns = Vec::new();
+      for r in &reading.readings {
+        flatten_reading(r, &mut columns);
+      }
+
+      columns
+    };
+
+    let value = |s: &str| match s.parse::<f64>() {
+      Ok(n) => json!(n),
+      Err(_) => json!(s),
+    };
+
+    flatten(first).into_iter()
+      .zip(flatten(second))
+      .map(|((name, a), (_, b))| {
+        let (first, second) = (value(&a), value(&b));
+        let delta = match (first.as_f64(), second.as_f64()) {
+          (Some(a), Some(b)) => Some(b - a),
+          _ => None,
+        };
+
+        Comparison { name, first, second, delta }
+      })
+      .collect()
+  }
+}
+
+/// Formats a compared value as text, without quoting strings.
+fn comparison_text(value: &serde_json::Value) -> String {
+  match value {
+    serde_json::Value::String(s) => s.clone(),
+    value => value.to_string(),
+  }
+}
+
+/// Returns a short description of a sensor's bus and address, e.g.
+/// `/dev/i2c-1@0x71`.
+fn sensor_label(sensor: &MetrifulOptions) -> String {
+  format!("{}@0x{:02x}", sensor.i2c_device.display(), sensor.i2c_address)
+}
+
+pub(crate) fn compare(opts: &Options, action: &CompareAction, first: Metriful) -> Result<()> {
+  if let OutputMode::Influx = action.output {
+    return Err(influx_unsupported());
+  }
+
+  let mut second_opts = opts.sensor.clone();
+  if let Some(device) = &action.second_device {
+    second_opts = second_opts.i2c_device(device);
+  }
+
+  if let Some(address) = action.second_address {
+    second_opts = second_opts.i2c_address(address);
+  }
+
+  if let Some(gpio) = action.second_gpio_ready {
+    second_opts = second_opts.gpio_ready(gpio);
+  }
+
+  if second_opts.i2c_device == opts.sensor.i2c_device && second_opts.i2c_address == opts.sensor.i2c_address {
+    return Err(eyre!("the second sensor needs a different --second-device or --second-address"));
+  }
+
+  info!("waiting for second sensor to become ready...");
+  let mut second = second_opts.open()?;
+  second.set_shutdown_options(ShutdownOptions {
+    on_drop: true,
+    ..ShutdownOptions::default()
+  });
+
+  let labels = [sensor_label(&opts.sensor), sensor_label(&second_opts)];
+  let mut sensors = [first, second];
+
+  let mut statuses = Vec::new();
+  for sensor in &mut sensors {
+    let status = sensor.set_mode_timeout(OperationalMode::Standby, opts.sensor.timeout)
+      .map_err(|e| report_error(action.output, e))?;
+
+    statuses.push(status);
+  }
+
+  // air quality data is only valid in cycle mode, so isn't compared; particle
+  // data is compared only if both sensors have a particle sensor
+  let mut set = MetricSet::new()
+    .with(METRIC_COMBINED_AIR_DATA)
+    .with(METRIC_COMBINED_LIGHT_DATA)
+    .with(METRIC_COMBINED_SOUND_DATA);
+
+  if statuses.iter().all(DeviceStatus::particle_sensor_enabled) {
+    set.add(METRIC_COMBINED_PARTICLE_DATA);
+  }
+
+  let precision = opts.precision.unwrap_or(2);
+  let start = Instant::now();
+  let mut readings = 0;
+
+  while !action.limits.reached(readings, start) {
+    // start both measurements before waiting on either, so they overlap
+    for sensor in &mut sensors {
+      sensor.execute_measurement().map_err(|e| report_error(action.output, e))?;
+    }
+
+    let mut results = Vec::new();
+    for sensor in &mut sensors {
+      let reading = sensor.wait_for_ready()
+        .and_then(|()| sensor.read_set(&set))
+        .map_err(|e| report_error(action.output, e))?;
+
+      results.push(reading);
+    }
+
+    let time = results[0].time.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let comparisons = match action.output {
+      OutputMode::JSON => Comparison::all(&results[0], &results[1]),
+      _ => Comparison::all(&set_in_units(&results[0], opts.units), &set_in_units(&results[1], opts.units)),
+    };
+
+    match action.output {
+      OutputMode::Plain => {
+        println!("{:<28} {:>20} {:>20} {:>10}", time, labels[0], labels[1], "delta");
+        for c in &comparisons {
+          let delta = c.delta.map(|d| format!("{:+.*}", precision, d)).unwrap_or_default();
+          println!(
+            "{:<28} {:>20} {:>20} {:>10}",
+            c.name, comparison_text(&c.first), comparison_text(&c.second), delta
+          );
+        }
+
+        println!("---");
+      },
+      OutputMode::JSON => println!("{}", json!({
+        "timestamp": time,
+        "first": labels[0],
+        "second": labels[1],
+        "values": comparisons,
+      })),
+      OutputMode::CSV => {
+        if readings == 0 {
+          let mut header = vec!["timestamp".to_string()];
+          for c in &comparisons {
+            header.push(format!("{}_first", c.name));
+            header.push(format!("{}_second", c.name));
+            header.push(format!("{}_delta", c.name));
+          }
+
+          println!("{}", csv_record(&header));
+        }
+
+        let mut record = vec![time];
+        for c in &comparisons {
+          record.push(comparison_text(&c.first));
+          record.push(comparison_text(&c.second));
+          record.push(c.delta.map(|d| d.to_string()).unwrap_or_default());
+        }
+
+        println!("{}", csv_record(&record));
+      },
+      OutputMode::Influx => unreachable!("rejected above"),
+    }
+
+    readings += 1;
+    if action.limits.reached(readings, start) {
+      break;
+    }
+
+    thread::sleep(action.limits.sleep(action.interval, start));
+  }
+
+  Ok(())
+}
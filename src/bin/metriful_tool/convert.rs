@@ -0,0 +1,220 @@
+//! The `convert` subcommand.
+
+use super::*;
+use super::csv::{combined_csv_header, combined_csv_record, csv_record};
+#[cfg(feature = "parquet")] use super::csv::{TEXT_COLUMNS, combined_fields};
+#[cfg(feature = "sqlite")] use super::record::{create_schema, insert_reading, insert_session, prepare_insert};
+use super::replay::Recording;
+
+/// A format `convert` can write.
+#[derive(Debug, Copy, Clone)]
+enum ConvertFormat {
+  Csv,
+  Parquet,
+  Sqlite,
+}
+
+impl FromStr for ConvertFormat {
+  type Err = Error;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_ascii_lowercase().as_str() {
+      "csv" => Ok(ConvertFormat::Csv),
+      "parquet" => Ok(ConvertFormat::Parquet),
+      "sqlite" => Ok(ConvertFormat::Sqlite),
+      s => Err(eyre!("invalid format '{}', expected one of: csv, parquet, sqlite", s))
+    }
+  }
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct ConvertAction {
+  /// NDJSON file of combined readings, as with `replay`; `-` reads from stdin
+  #[structopt(parse(from_os_str))]
+  input: PathBuf,
+
+  /// File to write; required for parquet and sqlite. CSV is written to
+  /// stdout by default.
+  #[structopt(parse(from_os_str))]
+  output: Option<PathBuf>,
+
+  /// Format to convert to, one of: csv, parquet (with the `parquet` feature),
+  /// sqlite (with the `sqlite` feature)
+  #[structopt(long)]
+  to: ConvertFormat,
+}
+
+/// Readings buffered per Parquet row group.
+#[cfg(feature = "parquet")]
+const PARQUET_ROW_GROUP_SIZE: usize = 10_000;
+
+/// Writes combined readings to a Parquet file, with the same columns as CSV
+/// output. Timestamps are stored as milliseconds since the epoch, text columns
+/// as UTF-8 strings, and others as doubles; unavailable values are null.
+#[cfg(feature = "parquet")]
+struct ParquetWriter {
+  writer: parquet::file::writer::SerializedFileWriter<File>,
+  header: Vec<String>,
+  times: Vec<i64>,
+  rows: Vec<Vec<String>>,
+}
+
+#[cfg(feature = "parquet")]
+impl ParquetWriter {
+  fn create(path: &Path) -> Result<ParquetWriter> {
+    use std::sync::Arc;
+    use parquet::file::{properties::WriterProperties, writer::SerializedFileWriter};
+    use parquet::schema::parser::parse_message_type;
+
+    let header = combined_csv_header(UnitSystem::Metric);
+    let fields: Vec<String> = header.iter()
+      .map(|name| match name.as_str() {
+        "timestamp" => "REQUIRED INT64 timestamp (TIMESTAMP_MILLIS);".to_string(),
+        name if TEXT_COLUMNS.contains(&name) => format!("OPTIONAL BYTE_ARRAY {} (UTF8);", name),
+        name => format!("OPTIONAL DOUBLE {};", name),
+      })
+      .collect();
+
+    let schema = parse_message_type(&format!("message metriful {{ {} }}", fields.join(" ")))?;
+    let file = File::create(path)
+      .with_context(|| format!("could not create {:?}", path))?;
+
+    Ok(ParquetWriter {
+      writer: SerializedFileWriter::new(file, Arc::new(schema), Arc::new(WriterProperties::builder().build()))?,
+      header,
+      times: Vec::new(),
+      rows: Vec::new(),
+    })
+  }
+
+  fn write(&mut self, reading: &UnitValue<UnitCombinedData>) -> Result<()> {
+    self.times.push(reading.time.timestamp_millis());
+    self.rows.push(combined_fields(reading, UnitSystem::Metric));
+
+    if self.rows.len() >= PARQUET_ROW_GROUP_SIZE {
+      self.flush()?;
+    }
+
+    Ok(())
+  }
+
+  /// Writes buffered readings as a row group.
+  fn flush(&mut self) -> Result<()> {
+    use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+
+    if self.rows.is_empty() {
+      return Ok(());
+    }
+
+    let (header, rows) = (&self.header, &self.rows);
+    let mut group = self.writer.next_row_group()?;
+    let mut index = 0;
+
+    while let Some(mut column) = group.next_column()? {
+      let name = header[index].as_str();
+      let fields = rows.iter().map(|row| row[index].as_str());
+
+      // nulls are written as a definition level of 0, with no value
+      if name == "timestamp" {
+        column.typed::<Int64Type>().write_batch(&self.times, None, None)?;
+      } else if TEXT_COLUMNS.contains(&name) {
+        let levels: Vec<i16> = fields.clone().map(|f| i16::from(!f.is_empty())).collect();
+        let values: Vec<ByteArray> = fields.filter(|f| !f.is_empty()).map(ByteArray::from).collect();
+        column.typed::<ByteArrayType>().write_batch(&values, Some(&levels), None)?;
+      } else {
+        let parsed: Vec<Option<f64>> = fields.map(|f| f.parse().ok()).collect();
+        let levels: Vec<i16> = parsed.iter().map(|v| i16::from(v.is_some())).collect();
+        let values: Vec<f64> = parsed.into_iter().flatten().collect();
+        column.typed::<DoubleType>().write_batch(&values, Some(&levels), None)?;
+      }
+
+      column.close()?;
+      index += 1;
+    }
+
+    group.close()?;
+    self.times.clear();
+    self.rows.clear();
+
+    Ok(())
+  }
+
+  fn close(mut self) -> Result<()> {
+    self.flush()?;
+    self.writer.close()?;
+
+    Ok(())
+  }
+}
+
+pub(crate) fn convert(opts: &Options, action: &ConvertAction) -> Result<()> {
+  let recording = Recording::open(&action.input)?;
+
+  let mut readings = 0;
+  match action.to {
+    ConvertFormat::Csv => {
+      let mut out: Box<dyn Write> = match &action.output {
+        Some(path) => Box::new(BufWriter::new(
+          File::create(path).with_context(|| format!("could not create {:?}", path))?
+        )),
+        None => Box::new(BufWriter::new(io::stdout())),
+      };
+
+      writeln!(out, "{}", csv_record(&combined_csv_header(opts.units)))?;
+      for reading in recording {
+        writeln!(out, "{}", combined_csv_record(&reading?, opts.units))?;
+        readings += 1;
+      }
+
+      out.flush()?;
+    },
+
+    #[cfg(feature = "parquet")]
+    ConvertFormat::Parquet => {
+      let path = action.output.as_ref()
+        .ok_or_else(|| eyre!("an output file is required for parquet output"))?;
+
+      let mut writer = ParquetWriter::create(path)?;
+      for reading in recording {
+        writer.write(&reading?)?;
+        readings += 1;
+      }
+
+      writer.close()?;
+    },
+
+    #[cfg(feature = "sqlite")]
+    ConvertFormat::Sqlite => {
+      let path = action.output.as_ref()
+        .ok_or_else(|| eyre!("an output file is required for sqlite output"))?;
+
+      let db = rusqlite::Connection::open(path)
+        .with_context(|| format!("could not open database {:?}", path))?;
+
+      create_schema(&db)?;
+
+      // the cycle period and device status of a recording aren't known
+      let session_id = insert_session(&db, 0, "null", &json!({ "converted_from": &action.input }).to_string())?;
+
+      // a single transaction is much faster, and leaves no partial session
+      // if the recording is invalid
+      db.execute_batch("BEGIN")?;
+      let mut insert = prepare_insert(&db)?;
+      for reading in recording {
+        insert_reading(&mut insert, session_id, &reading?)?;
+        readings += 1;
+      }
+
+      db.execute_batch("COMMIT")?;
+      info!("converted to session {} of {:?}", session_id, path);
+    },
+
+    #[cfg(not(feature = "parquet"))]
+    ConvertFormat::Parquet => return Err(eyre!("parquet output requires the `parquet` feature")),
+
+    #[cfg(not(feature = "sqlite"))]
+    ConvertFormat::Sqlite => return Err(eyre!("sqlite output requires the `sqlite` feature")),
+  }
+
+  info!("converted {} readings", readings);
+  Ok(())
+}
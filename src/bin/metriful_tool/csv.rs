@@ -0,0 +1,161 @@
+//! CSV output shared by the subcommands.
+
+use super::*;
+
+/// Joins fields into a CSV record per RFC 4180, quoting fields as needed.
+pub(crate) fn csv_record<S: AsRef<str>>(fields: &[S]) -> String {
+  fields.iter()
+    .map(|field| {
+      let field = field.as_ref();
+      if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+      } else {
+        field.to_string()
+      }
+    })
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+/// Returns a value's name as serialized to JSON, e.g. `sds011` for
+/// `ParticleSensorMode::EnabledSDS011`, so CSV and JSON output agree.
+pub(crate) fn csv_name(value: &impl Serialize) -> String {
+  match serde_json::to_value(value) {
+    Ok(serde_json::Value::String(s)) => s,
+    _ => String::new(),
+  }
+}
+
+/// CSV header for combined readings. Columns are named with their units and
+/// only ever appended to, so scripts may rely on their order.
+pub(crate) fn combined_csv_header(units: UnitSystem) -> Vec<String> {
+  let (temperature, pressure) = match units {
+    UnitSystem::Metric => ("temperature_c", "pressure_pa"),
+    UnitSystem::Imperial => ("temperature_f", "pressure_inhg"),
+  };
+
+  let mut header: Vec<String> = vec![
+    "timestamp", temperature, pressure, "humidity_pct_rh",
+    "gas_resistance_ohm", "aqi", "estimated_co2_ppm", "estimated_voc_ppm",
+    "aqi_accuracy", "illuminance_lux", "white_level", "weighted_spl_dba",
+  ].into_iter().map(String::from).collect();
+
+  header.extend(SPL_BANDS.iter().map(|band| format!("spl_band_{}hz_db", band.midpoint_hz)));
+
+  header.extend(vec![
+    "peak_amplitude_mpa", "sound_stability", "particle_duty_cycle_pct",
+    "particle_concentration_ugm3", "particle_concentration_ppl", "particle_validity",
+  ].into_iter().map(String::from));
+
+  header
+}
+
+/// Formats a combined reading as a CSV record matching
+/// [`combined_csv_header()`].
+pub(crate) fn combined_csv_record(reading: &UnitValue<UnitCombinedData>, units: UnitSystem) -> String {
+  csv_record(&combined_fields(reading, units))
+}
+
+/// Returns a combined reading's values as text, in the order of
+/// [`combined_csv_header()`]. Particle values are empty if no particle sensor
+/// is enabled.
+pub(crate) fn combined_fields(reading: &UnitValue<UnitCombinedData>, units: UnitSystem) -> Vec<String> {
+  let data = &reading.value;
+  let (air, air_quality) = (&data.air.value, &data.air_quality.value);
+  let (light, sound) = (&data.light.value, &data.sound.value);
+
+  let (temperature, pressure) = match units {
+    UnitSystem::Metric => (air.temperature.value.to_string(), air.pressure.value.to_string()),
+    UnitSystem::Imperial => (
+      air.temperature.clone().convert::<UnitDegreesFahrenheit>().value.to_string(),
+      air.pressure.clone().convert::<UnitInchesOfMercury>().value.to_string(),
+    ),
+  };
+
+  let mut fields = vec![
+    reading.time.to_rfc3339_opts(SecondsFormat::Secs, true),
+    temperature,
+    pressure,
+    air.humidity.value.to_string(),
+    air.gas_sensor_resistance.value.to_string(),
+    air_quality.aqi.value.to_string(),
+    air_quality.estimated_co2.value.to_string(),
+    air_quality.estimated_voc.value.to_string(),
+    air_quality.aqi_accuracy.value.to_string(),
+    light.illuminance.value.to_string(),
+    light.white_level.value.to_string(),
+    sound.weighted_spl.value.to_string(),
+  ];
+
+  fields.extend(sound.spl_bands.value.0.iter().map(f32::to_string));
+  fields.push(sound.peak_amplitude.value.to_string());
+  fields.push(sound.measurement_stability.value.to_string());
+
+  match &data.particle {
+    Some(particle) => fields.extend(vec![
+      particle.value.duty_cycle.value.to_string(),
+      particle.value.concentration.value.sds011_value.to_string(),
+      particle.value.concentration.value.ppd42_value.to_string(),
+      particle.value.validity.value.to_string(),
+    ]),
+    None => fields.extend(vec![String::new(); 4]),
+  }
+
+  fields
+}
+
+/// Columns of device status CSV output.
+pub(crate) const STATUS_COLUMNS: &[&str] = &[
+  "mode", "cycle_period_s", "particle_sensor",
+  "light_interrupt", "light_interrupt_mode", "light_interrupt_polarity",
+  "light_interrupt_threshold_lux",
+  "sound_interrupt", "sound_interrupt_mode", "sound_interrupt_threshold_mpa",
+];
+
+/// Formats the device status as a CSV header and record.
+pub(crate) fn status_csv(status: &DeviceStatus) -> (String, String) {
+  (csv_record(STATUS_COLUMNS), csv_record(&status_fields(status)))
+}
+
+/// Formats the device status as fields matching [`STATUS_COLUMNS`]; fields
+/// that don't apply (e.g. settings of a disabled interrupt) are empty.
+pub(crate) fn status_fields(status: &DeviceStatus) -> Vec<String> {
+  let (mode, period) = match status.mode {
+    OperationalMode::Standby => ("standby", String::new()),
+    OperationalMode::Cycle(period) => ("cycle", period.to_duration().as_secs().to_string()),
+  };
+
+  let mut fields = vec![mode.to_string(), period, csv_name(&status.particle_sensor)];
+
+  match &status.light_int {
+    InterruptStatus::Enabled(light) => fields.extend(vec![
+      "enabled".to_string(),
+      csv_name(&light.mode),
+      csv_name(&light.polarity),
+      light.threshold.to_string(),
+    ]),
+    InterruptStatus::Disabled => {
+      fields.push("disabled".to_string());
+      fields.extend(vec![String::new(); 3]);
+    },
+  }
+
+  match &status.sound_int {
+    InterruptStatus::Enabled(sound) => fields.extend(vec![
+      "enabled".to_string(),
+      csv_name(&sound.mode),
+      sound.threshold.to_string(),
+    ]),
+    InterruptStatus::Disabled => {
+      fields.push("disabled".to_string());
+      fields.extend(vec![String::new(); 2]);
+    },
+  }
+
+  fields
+}
+
+/// Columns of combined CSV output (and so of the `readings` table) that hold
+/// text rather than numbers.
+#[cfg(any(feature = "sqlite", feature = "parquet"))]
+pub(crate) const TEXT_COLUMNS: &[&str] = &["timestamp", "aqi_accuracy", "sound_stability", "particle_validity"];
@@ -0,0 +1,281 @@
+//! Interactive terminal dashboard for the `dashboard` subcommand.
+
+use std::collections::VecDeque;
+use std::io::{self, Stdout};
+use std::sync::mpsc::Receiver;
+use std::thread::JoinHandle;
+
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use tui::Terminal;
+use tui::backend::CrosstermBackend;
+use tui::layout::{Constraint, Direction, Layout};
+use tui::style::{Color, Style};
+use tui::widgets::{Block, Borders, Gauge, Paragraph, Sparkline};
+
+use metriful::error::Result as MetrifulResult;
+use metriful::unit::CombinedData;
+
+use super::*;
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct DashboardAction {
+  /// Initial cycle period, one of: 3s, 100s, 5m (or 0, 1, 2)
+  #[structopt(long, short, default_value = "3s", env = "METRIFUL_INTERVAL")]
+  interval: CyclePeriod
+}
+
+/// Number of readings kept for sparklines.
+const HISTORY: usize = 512;
+
+/// How often the dashboard is redrawn while waiting for input.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+type Backend = CrosstermBackend<Stdout>;
+
+/// Recent values of one reading, and the range its gauge spans.
+struct Series {
+  name: &'static str,
+  color: Color,
+  min: f64,
+  max: f64,
+  extract: fn(&CombinedData) -> (f64, String),
+  values: VecDeque<f64>,
+  label: String,
+}
+
+impl Series {
+  fn new(
+    name: &'static str,
+    color: Color,
+    min: f64,
+    max: f64,
+    extract: fn(&CombinedData) -> (f64, String),
+  ) -> Series {
+    Series { name, color, min, max, extract, values: VecDeque::new(), label: "-".to_string() }
+  }
+
+  fn push(&mut self, data: &CombinedData) {
+    let (value, label) = (self.extract)(data);
+    if self.values.len() >= HISTORY {
+      self.values.pop_front();
+    }
+
+    self.values.push_back(value);
+    self.label = label;
+  }
+
+  /// Returns a value's position within the gauge's range, from 0 to 1.
+  fn ratio(&self, value: f64) -> f64 {
+    ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
+  }
+}
+
+fn all_series() -> Vec<Series> {
+  vec![
+    Series::new("temperature", Color::Red, -10.0, 40.0, |d| {
+      let v = &d.air.value.temperature;
+      (f64::from(v.value), v.to_string())
+    }),
+    Series::new("humidity", Color::Blue, 0.0, 100.0, |d| {
+      let v = &d.air.value.humidity;
+      (f64::from(v.value), v.to_string())
+    }),
+    Series::new("estimated CO₂", Color::Green, 400.0, 5000.0, |d| {
+      let v = &d.air_quality.value.estimated_co2;
+      (f64::from(v.value), v.to_string())
+    }),
+    Series::new("air quality index", Color::Magenta, 0.0, 500.0, |d| {
+      let aq = &d.air_quality.value;
+      (f64::from(aq.aqi.value), format!("{} ({})", aq.aqi, aq.aqi_accuracy))
+    }),
+    Series::new("illuminance", Color::Yellow, 0.0, 1000.0, |d| {
+      let v = &d.light.value.illuminance;
+      (f64::from(v.value), v.to_string())
+    }),
+    Series::new("sound level", Color::Cyan, 30.0, 100.0, |d| {
+      let v = &d.sound.value.weighted_spl;
+      (f64::from(v.value), v.to_string())
+    }),
+  ]
+}
+
+/// A background cycle reader that can be stopped promptly to regain the
+/// device.
+struct Reader {
+  cancel: CancelToken,
+  rx: Receiver<MetrifulResult<UnitValue<UnitCombinedData>>>,
+  handle: JoinHandle<Metriful>,
+}
+
+impl Reader {
+  fn start(mut metriful: Metriful, period: CyclePeriod, timeout: Option<Duration>) -> Reader {
+    let cancel = CancelToken::new();
+    metriful.set_cancel_token(Some(cancel.clone()));
+
+    let (_cmd_tx, rx, handle) = metriful.async_cycle_read_timeout(METRIC_COMBINED_ALL, period, timeout);
+    Reader { cancel, rx, handle }
+  }
+
+  fn stop(self) -> Result<Metriful> {
+    self.cancel.cancel();
+    self.handle.join().map_err(|_| eyre!("reader thread panicked"))
+  }
+}
+
+struct Dashboard {
+  series: Vec<Series>,
+  period: CyclePeriod,
+  last_reading: Option<DateTime<Utc>>,
+  message: String,
+}
+
+impl Dashboard {
+  fn draw(&self, terminal: &mut Terminal<Backend>) -> Result<()> {
+    terminal.draw(|f| {
+      let mut constraints = vec![Constraint::Ratio(1, self.series.len() as u32); self.series.len()];
+      constraints.push(Constraint::Length(2));
+
+      let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(f.size());
+
+      for (series, row) in self.series.iter().zip(&rows) {
+        let columns = Layout::default()
+          .direction(Direction::Horizontal)
+          .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+          .split(*row);
+
+        let ratio = series.values.back().map(|v| series.ratio(*v)).unwrap_or(0.0);
+        let gauge = Gauge::default()
+          .block(Block::default().borders(Borders::ALL).title(series.name))
+          .gauge_style(Style::default().fg(series.color))
+          .ratio(ratio)
+          .label(series.label.clone());
+
+        // the most recent readings that fit, scaled to the gauge's range
+        let width = columns[1].width.saturating_sub(2) as usize;
+        let data: Vec<u64> = series.values.iter()
+          .skip(series.values.len().saturating_sub(width))
+          .map(|v| (series.ratio(*v) * 100.0).round() as u64)
+          .collect();
+
+        let sparkline = Sparkline::default()
+          .block(Block::default().borders(Borders::ALL))
+          .style(Style::default().fg(series.color))
+          .data(&data)
+          .max(100);
+
+        f.render_widget(gauge, columns[0]);
+        f.render_widget(sparkline, columns[1]);
+      }
+
+      let last = self.last_reading
+        .map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_else(|| "waiting for first reading".to_string());
+
+      let footer = Paragraph::new(format!(
+        "cycle: {:?}, last reading: {}  {}\n[1] 3s  [2] 100s  [3] 300s  [r] reset  [q] quit",
+        self.period.to_duration(), last, self.message
+      ));
+
+      f.render_widget(footer, rows[rows.len() - 1]);
+    })?;
+
+    Ok(())
+  }
+}
+
+/// Runs the dashboard until the user quits, restoring the terminal
+/// afterward even if it fails.
+pub(crate) fn run(opts: &Options, action: &DashboardAction, metriful: Metriful) -> Result<()> {
+  let mut stdout = io::stdout();
+  terminal::enable_raw_mode()?;
+  execute!(stdout, EnterAlternateScreen)?;
+
+  let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+  terminal.hide_cursor()?;
+
+  let result = run_dashboard(opts, action, metriful, &mut terminal);
+
+  terminal::disable_raw_mode()?;
+  execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+  terminal.show_cursor()?;
+
+  result
+}
+
+fn run_dashboard(
+  opts: &Options,
+  action: &DashboardAction,
+  metriful: Metriful,
+  terminal: &mut Terminal<Backend>,
+) -> Result<()> {
+  let timeout = opts.sensor.timeout;
+  let mut dashboard = Dashboard {
+    series: all_series(),
+    period: action.interval,
+    last_reading: None,
+    message: String::new(),
+  };
+
+  let mut reader = Reader::start(metriful, dashboard.period, timeout);
+
+  loop {
+    while let Ok(result) = reader.rx.try_recv() {
+      match result {
+        Ok(reading) => {
+          for series in &mut dashboard.series {
+            series.push(&reading.value);
+          }
+
+          dashboard.last_reading = Some(reading.time);
+          dashboard.message.clear();
+        },
+        Err(e) => dashboard.message = format!("read failed: {} (press r to reset)", e),
+      }
+    }
+
+    dashboard.draw(terminal)?;
+
+    if !event::poll(REFRESH_INTERVAL)? {
+      continue;
+    }
+
+    let key = match event::read()? {
+      Event::Key(key) => key,
+      _ => continue,
+    };
+
+    let (period, reset) = match key.code {
+      KeyCode::Char('q') | KeyCode::Esc => break,
+      KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+      KeyCode::Char('1') => (CyclePeriod::Period0, false),
+      KeyCode::Char('2') => (CyclePeriod::Period1, false),
+      KeyCode::Char('3') => (CyclePeriod::Period2, false),
+      KeyCode::Char('r') => (dashboard.period, true),
+      _ => continue,
+    };
+
+    // the reader is restarted rather than sent a command, which would only
+    // take effect after the current cycle
+    let mut metriful = reader.stop()?;
+    if reset {
+      dashboard.message = "resetting...".to_string();
+      dashboard.draw(terminal)?;
+
+      metriful.reset()?;
+      metriful.wait_for_ready_timeout(timeout)?;
+      dashboard.message = "reset finished".to_string();
+    }
+
+    dashboard.period = period;
+    reader = Reader::start(metriful, period, timeout);
+  }
+
+  // dropping the device returns it to standby
+  reader.stop()?;
+  Ok(())
+}
@@ -0,0 +1,292 @@
+//! The `doctor` subcommand.
+
+use super::*;
+use super::scan::{Probe, SCAN_ADDRESSES, i2c_buses};
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct DoctorAction {
+  /// Data output format, one of: plain, json
+  #[structopt(long, short, default_value = "plain")]
+  output: OutputMode,
+}
+
+/// The outcome of a `doctor` check.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Diagnosis {
+  Ok,
+  Warn,
+  Fail,
+}
+
+/// A single `doctor` check, with a suggested fix if it didn't pass.
+#[derive(Debug, Serialize)]
+struct DoctorCheck {
+  name: &'static str,
+  result: Diagnosis,
+  detail: String,
+  fix: Option<String>,
+}
+
+/// Checks recorded by `doctor`, in the order run.
+#[derive(Default)]
+struct DoctorReport {
+  checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+  fn ok(&mut self, name: &'static str, detail: impl Into<String>) {
+    self.push(name, Diagnosis::Ok, detail, None::<String>);
+  }
+
+  fn push(&mut self, name: &'static str, result: Diagnosis, detail: impl Into<String>, fix: Option<impl Into<String>>) {
+    self.checks.push(DoctorCheck { name, result, detail: detail.into(), fix: fix.map(Into::into) });
+  }
+}
+
+/// The current process's user and group ids, per /proc/self/status.
+struct ProcessIds {
+  uid: u32,
+  gids: Vec<u32>,
+}
+
+impl ProcessIds {
+  fn current() -> Result<ProcessIds> {
+    let status = fs::read_to_string("/proc/self/status")?;
+    let field = |name: &str| -> Vec<u32> {
+      status.lines()
+        .find_map(|line| line.strip_prefix(name))
+        .map(|ids| ids.split_whitespace().filter_map(|id| id.parse().ok()).collect())
+        .unwrap_or_default()
+    };
+
+    // effective ids are the second of real, effective, saved, and filesystem
+    let uid = field("Uid:").get(1).copied().unwrap_or(u32::MAX);
+    let mut gids = field("Groups:");
+    gids.extend(field("Gid:").get(1));
+
+    Ok(ProcessIds { uid, gids })
+  }
+
+  /// Returns a description of `path`'s group ownership, and a fix if the
+  /// current user may lack access through it.
+  fn group_access(&self, path: &Path) -> (String, Option<String>) {
+    let gid = match fs::metadata(path) {
+      Ok(metadata) => metadata.gid(),
+      Err(e) => return (format!("could not inspect {}: {}", path.display(), e), None),
+    };
+
+    let group = group_name(gid).unwrap_or_else(|| gid.to_string());
+    if self.uid == 0 {
+      (format!("{} is owned by group {}; running as root", path.display(), group), None)
+    } else if self.gids.contains(&gid) {
+      (format!("{} is owned by group {}, which you are a member of", path.display(), group), None)
+    } else {
+      (
+        format!("{} is owned by group {}, which you are not a member of", path.display(), group),
+        Some(format!("sudo usermod -aG {} $USER, then log out and back in", group)),
+      )
+    }
+  }
+}
+
+/// Looks up a group's name in /etc/group.
+fn group_name(gid: u32) -> Option<String> {
+  fs::read_to_string("/etc/group").ok()?
+    .lines()
+    .find_map(|line| {
+      let mut fields = line.split(':');
+      let name = fields.next()?;
+      let id: u32 = fields.nth(1)?.parse().ok()?;
+
+      if id == gid { Some(name.to_string()) } else { None }
+    })
+}
+
+/// Checks the i2c bus, its permissions, and that the sensor responds.
+fn doctor_i2c(opts: &Options, ids: Option<&ProcessIds>, report: &mut DoctorReport) {
+  let device = &opts.sensor.i2c_device;
+
+  // present whether i2c-dev is a module or built in
+  if Path::new("/sys/class/i2c-dev").exists() {
+    report.ok("i2c-dev module", "loaded");
+  } else {
+    report.push(
+      "i2c-dev module", Diagnosis::Fail, "not loaded",
+      Some("sudo modprobe i2c-dev, and add i2c-dev to /etc/modules to load it at boot"),
+    );
+  }
+
+  let buses = i2c_buses().unwrap_or_default();
+  if buses.is_empty() {
+    report.push(
+      "i2c buses", Diagnosis::Fail, "no /dev/i2c-* devices",
+      Some("enable i2c, e.g. with `sudo raspi-config nonint do_i2c 0` or `dtparam=i2c_arm=on` in /boot/config.txt, then reboot"),
+    );
+  } else {
+    let names: Vec<String> = buses.iter().map(|bus| bus.display().to_string()).collect();
+    report.ok("i2c buses", names.join(", "));
+  }
+
+  if !device.exists() {
+    let fix = match buses.first() {
+      Some(bus) => format!("pass --device {} (or another bus above)", bus.display()),
+      None => "enable i2c as above".to_string(),
+    };
+
+    report.push("i2c device", Diagnosis::Fail, format!("{} does not exist", device.display()), Some(fix));
+    return;
+  }
+
+  if let Some(ids) = ids {
+    let (detail, fix) = ids.group_access(device);
+    let result = if fix.is_some() { Diagnosis::Warn } else { Diagnosis::Ok };
+    report.push("i2c group", result, detail, fix);
+  }
+
+  match OpenOptions::new().read(true).write(true).open(device) {
+    Ok(_) => report.ok("i2c permissions", format!("{} is readable and writable", device.display())),
+    Err(e) => {
+      let fix = match e.kind() {
+        io::ErrorKind::PermissionDenied => "join the device's group as above, or add a udev rule granting access",
+        _ => "check that the device is an i2c bus",
+      };
+
+      report.push("i2c permissions", Diagnosis::Fail, format!("could not open {}: {}", device.display(), e), Some(fix));
+      return;
+    },
+  }
+
+  let address = opts.sensor.i2c_address;
+  match Probe::run(device, address) {
+    Probe::Found { status } => report.ok(
+      "sensor", format!("MS430 found at 0x{:02x} ({:?})", address, status.mode),
+    ),
+    Probe::Unrecognized { error } => report.push(
+      "sensor", Diagnosis::Fail,
+      format!("a device at 0x{:02x} responded, but isn't an MS430: {}", address, error),
+      Some("check --i2c-address; `metriful-tool scan --all-buses` lists responding sensors"),
+    ),
+    Probe::NoResponse { error } => {
+      let elsewhere = SCAN_ADDRESSES.iter()
+        .copied()
+        .filter(|&other| other != address)
+        .find(|&other| matches!(Probe::run(device, other), Probe::Found { .. }));
+
+      let fix = match elsewhere {
+        Some(other) => format!("the sensor responds at 0x{:02x}; pass --i2c-address 0x{:02x}", other, other),
+        None => "check the SDA, SCL, VDD and GND wiring, or try `metriful-tool scan --all-buses`".to_string(),
+      };
+
+      report.push(
+        "sensor", Diagnosis::Fail,
+        format!("no response at 0x{:02x}: {}", address, error),
+        Some(fix),
+      );
+    },
+  }
+}
+
+/// Checks the GPIO backend, its permissions, and the READY line.
+fn doctor_gpio(opts: &Options, ids: Option<&ProcessIds>, report: &mut DoctorReport) {
+  let path = match &opts.sensor.gpio_chip {
+    Some(chip) => {
+      let path = gpiochip_path(chip);
+      if !path.exists() {
+        report.push(
+          "gpio backend", Diagnosis::Fail, format!("cdev: {} does not exist", path.display()),
+          Some("pass a chip from `ls /dev/gpiochip*` to --gpio-chip"),
+        );
+        return;
+      }
+
+      report.ok("gpio backend", format!("cdev: {}", path.display()));
+      path
+    },
+    None => {
+      let path = PathBuf::from("/sys/class/gpio/export");
+      if !path.exists() {
+        let detail = "sysfs: /sys/class/gpio is unavailable, as on newer kernels";
+        let fix = match Path::new("/dev/gpiochip0").exists() {
+          true => "use the GPIO character device instead, e.g. --gpio-chip gpiochip0",
+          false => "enable sysfs GPIO (CONFIG_GPIO_SYSFS) or use --gpio-chip",
+        };
+
+        report.push("gpio backend", Diagnosis::Fail, detail, Some(fix));
+        return;
+      }
+
+      report.ok("gpio backend", "sysfs");
+      path
+    },
+  };
+
+  if let Some(ids) = ids {
+    let (detail, fix) = ids.group_access(&path);
+    let result = if fix.is_some() { Diagnosis::Warn } else { Diagnosis::Ok };
+    report.push("gpio group", result, detail, fix);
+  }
+
+  match opts.sensor.open_ready_line() {
+    Ok(line) => match line.is_ready() {
+      Ok(ready) => report.ok("ready line", format!(
+        "{} is {}", ready_line_description(opts), if ready { "asserted" } else { "deasserted" }
+      )),
+      Err(e) => report.push(
+        "ready line", Diagnosis::Fail, format!("could not read {}: {}", ready_line_description(opts), e),
+        Some("check --gpio-ready"),
+      ),
+    },
+    Err(e) => {
+      let fix = match e.raw_os_error() {
+        Some(libc::EBUSY) => "the line is in use, e.g. by a running metriful-exporter",
+        Some(errno) if io::Error::from_raw_os_error(errno).kind() == io::ErrorKind::PermissionDenied => {
+          "join the GPIO group as above, or run as root"
+        },
+        _ => "check --gpio-ready (a GPIO number, not a pin number)",
+      };
+
+      report.push(
+        "ready line", Diagnosis::Fail, format!("could not open {}: {}", ready_line_description(opts), e),
+        Some(fix),
+      );
+    },
+  }
+}
+
+pub(crate) fn doctor(opts: &Options, action: &DoctorAction) -> Result<()> {
+  if let OutputMode::CSV | OutputMode::Influx = action.output {
+    return Err(eyre!("doctor only supports plain and json output"));
+  }
+
+  let ids = ProcessIds::current()
+    .map_err(|e| warn!("could not read process groups, skipping group checks: {}", e))
+    .ok();
+
+  let mut report = DoctorReport::default();
+  doctor_i2c(opts, ids.as_ref(), &mut report);
+  doctor_gpio(opts, ids.as_ref(), &mut report);
+
+  if let OutputMode::JSON = action.output {
+    println!("{}", json!({ "checks": &report.checks }));
+  } else {
+    for check in &report.checks {
+      let result = match check.result {
+        Diagnosis::Ok => "OK",
+        Diagnosis::Warn => "WARN",
+        Diagnosis::Fail => "FAIL",
+      };
+
+      println!("{:<4} {:<16} {}", result, check.name, check.detail);
+      if let Some(fix) = &check.fix {
+        println!("{:<21} fix: {}", "", fix);
+      }
+    }
+  }
+
+  let failures = report.checks.iter().filter(|c| c.result == Diagnosis::Fail).count();
+  match failures {
+    0 => Ok(()),
+    n => Err(eyre!("{} problem(s) found", n)),
+  }
+}
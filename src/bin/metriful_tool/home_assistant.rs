@@ -0,0 +1,249 @@
+//! The `gen-homeassistant` subcommand.
+
+use super::*;
+
+/// Kinds of Home Assistant configuration `gen-homeassistant` can generate.
+#[derive(Debug, Copy, Clone)]
+enum HomeAssistantFlavor {
+  Rest,
+  Mqtt,
+}
+
+impl FromStr for HomeAssistantFlavor {
+  type Err = Error;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_ascii_lowercase().as_str() {
+      "rest" => Ok(HomeAssistantFlavor::Rest),
+      "mqtt" => Ok(HomeAssistantFlavor::Mqtt),
+      s => Err(eyre!("invalid flavor '{}', expected one of: rest, mqtt", s))
+    }
+  }
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct GenHomeAssistantAction {
+  /// Configuration to generate, one of: rest (YAML for Home Assistant's
+  /// `rest` integration, polling metriful-exporter), mqtt (MQTT discovery
+  /// messages, one `topic payload` pair per line)
+  #[structopt(long, default_value = "rest")]
+  flavor: HomeAssistantFlavor,
+
+  /// Device name, prefixed to entity names and used in unique ids
+  #[structopt(long, default_value = "Metriful")]
+  name: String,
+
+  /// Base URL of metriful-exporter, for the rest flavor
+  #[structopt(long, default_value = "http://localhost:8083")]
+  url: String,
+
+  /// Seconds between polls of metriful-exporter, for the rest flavor
+  #[structopt(long, default_value = "3")]
+  scan_interval: u64,
+
+  /// Topic that combined readings are published to as JSON (e.g. from
+  /// `cycle-watch -o json`), for the mqtt flavor
+  #[structopt(long, default_value = "metriful/state")]
+  state_topic: String,
+
+  /// Home Assistant's MQTT discovery prefix, for the mqtt flavor
+  #[structopt(long, default_value = "homeassistant")]
+  discovery_prefix: String,
+
+  /// Also generates entities for particle sensor metrics; only use with a
+  /// particle sensor enabled, as their values are otherwise missing
+  #[structopt(long)]
+  particles: bool,
+}
+
+/// A Home Assistant sensor entity for one value of a combined reading.
+struct HomeAssistantSensor {
+  /// Unique within a device, e.g. `temperature` or `sound_level_125hz`
+  key: String,
+
+  name: String,
+
+  /// Path to the value within a serialized combined reading, e.g.
+  /// `air.value.temperature.value`
+  path: String,
+
+  device_class: Option<&'static str>,
+  unit: Option<String>,
+
+  /// False for text values, e.g. AQI accuracy
+  numeric: bool,
+}
+
+/// Returns the location of a metric within a serialized combined reading, as
+/// its group and field names.
+fn combined_field(id: &str) -> Option<(&'static str, &'static str)> {
+  Some(match id {
+    "temperature" => ("air", "temperature"),
+    "pressure" => ("air", "pressure"),
+    "relative_humidity" => ("air", "humidity"),
+    "gas_resistance" => ("air", "gas_sensor_resistance"),
+    "aqi" => ("air_quality", "aqi"),
+    "estimated_co2" => ("air_quality", "estimated_co2"),
+    "estimated_voc" => ("air_quality", "estimated_voc"),
+    "aqi_accuracy" => ("air_quality", "aqi_accuracy"),
+    "illuminance" => ("light", "illuminance"),
+    "white_light_level" => ("light", "white_level"),
+    "weighted_sound_level" => ("sound", "weighted_spl"),
+    "sound_level" => ("sound", "spl_bands"),
+    "peak_sound_amplitude" => ("sound", "peak_amplitude"),
+    "sound_measurement_stability" => ("sound", "measurement_stability"),
+    "particle_sensor_duty_cycle" => ("particle", "duty_cycle"),
+    "particle_concentration" => ("particle", "concentration"),
+    "particle_data_valid" => ("particle", "validity"),
+    _ => return None,
+  })
+}
+
+/// Builds Home Assistant sensors from the metric metadata. Units are spelled
+/// as Home Assistant expects them for each device class.
+fn home_assistant_sensors(particles: bool) -> Vec<HomeAssistantSensor> {
+  let mut sensors = Vec::new();
+
+  for info in metrics() {
+    if info.combined || (info.validity.particle_sensor && !particles) {
+      continue;
+    }
+
+    let (group, field) = match combined_field(info.id) {
+      Some(location) => location,
+      None => continue,
+    };
+
+    let path = format!("{}.value.{}.value", group, field);
+    let sensor = |key: String, name: String, path: String, device_class, unit: Option<&str>| HomeAssistantSensor {
+      key, name, path, device_class,
+      unit: unit.map(str::to_string),
+      numeric: true,
+    };
+
+    match info.id {
+      "sound_level" => {
+        for (i, band) in SPL_BANDS.iter().enumerate() {
+          sensors.push(sensor(
+            format!("sound_level_{}hz", band.midpoint_hz),
+            format!("Sound pressure level {} Hz", band.midpoint_hz),
+            format!("{}[{}]", path, i),
+            Some("sound_pressure"),
+            Some("dB"),
+          ));
+        }
+      },
+      "particle_concentration" => {
+        sensors.push(sensor(
+          info.id.to_string(), format!("{} (SDS011)", info.description),
+          format!("{}.sds011_value", path), Some("pm25"), Some("µg/m³"),
+        ));
+        sensors.push(sensor(
+          format!("{}_ppl", info.id), format!("{} (PPD42)", info.description),
+          format!("{}.ppd42_value", path), None, Some("ppl"),
+        ));
+      },
+      id => {
+        let device_class = match id {
+          "temperature" => Some("temperature"),
+          "pressure" => Some("pressure"),
+          "relative_humidity" => Some("humidity"),
+          "aqi" => Some("aqi"),
+          "estimated_co2" => Some("carbon_dioxide"),
+          "estimated_voc" => Some("volatile_organic_compounds_parts"),
+          "illuminance" => Some("illuminance"),
+          "weighted_sound_level" => Some("sound_pressure"),
+          _ => None,
+        };
+
+        let unit = info.unit_symbol.map(|symbol| match symbol {
+          "\u{2103}" => "°C",
+          "% RH" => "%",
+          "dBa" => "dBA",
+          symbol => symbol,
+        });
+
+        let mut sensor = sensor(id.to_string(), info.description.to_string(), path, device_class, unit);
+        sensor.numeric = !matches!(id, "aqi_accuracy" | "sound_measurement_stability" | "particle_data_valid");
+        sensors.push(sensor);
+      },
+    }
+  }
+
+  sensors
+}
+
+/// Converts a name to a form usable in ids and MQTT topics.
+fn slug(name: &str) -> String {
+  name.chars()
+    .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+    .collect()
+}
+
+pub(crate) fn gen_home_assistant(action: &GenHomeAssistantAction) -> Result<()> {
+  let device = slug(&action.name);
+  let sensors = home_assistant_sensors(action.particles);
+
+  // JSON strings are valid YAML scalars, and escape anything YAML would trip
+  // over
+  let quote = |s: &str| serde_json::to_string(s).unwrap_or_default();
+
+  match action.flavor {
+    HomeAssistantFlavor::Rest => {
+      println!("# generated by metriful-tool gen-homeassistant");
+      println!("rest:");
+      println!("  - resource: {}", quote(&format!("{}/json", action.url.trim_end_matches('/'))));
+      println!("    scan_interval: {}", action.scan_interval);
+      println!("    sensor:");
+
+      for sensor in &sensors {
+        println!("      - name: {}", quote(&format!("{} {}", action.name, sensor.name)));
+        println!("        unique_id: {}", quote(&format!("{}_{}", device, sensor.key)));
+        println!("        value_template: {}", quote(&format!("{{{{ value_json.reading.value.{} }}}}", sensor.path)));
+
+        if let Some(device_class) = sensor.device_class {
+          println!("        device_class: {}", device_class);
+        }
+
+        if let Some(unit) = &sensor.unit {
+          println!("        unit_of_measurement: {}", quote(unit));
+        }
+
+        if sensor.numeric {
+          println!("        state_class: measurement");
+        }
+      }
+    },
+    HomeAssistantFlavor::Mqtt => {
+      for sensor in &sensors {
+        let mut config = json!({
+          "name": sensor.name,
+          "unique_id": format!("{}_{}", device, sensor.key),
+          "state_topic": action.state_topic,
+          "value_template": format!("{{{{ value_json.value.{} }}}}", sensor.path),
+          "device": {
+            "identifiers": [device],
+            "name": action.name,
+            "manufacturer": "Metriful",
+            "model": "MS430",
+          },
+        });
+
+        if let Some(device_class) = sensor.device_class {
+          config["device_class"] = json!(device_class);
+        }
+
+        if let Some(unit) = &sensor.unit {
+          config["unit_of_measurement"] = json!(unit);
+        }
+
+        if sensor.numeric {
+          config["state_class"] = json!("measurement");
+        }
+
+        println!("{}/sensor/{}/{}/config {}", action.discovery_prefix, device, sensor.key, config);
+      }
+    },
+  }
+
+  Ok(())
+}
@@ -0,0 +1,205 @@
+//! InfluxDB line protocol output and the `influx-push` subcommand.
+
+use super::*;
+
+#[cfg(feature = "influx")]
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct InfluxPushAction {
+  /// InfluxDB base URL, e.g. http://localhost:8086
+  #[structopt(long)]
+  url: String,
+
+  /// Bucket to write readings to
+  #[structopt(long)]
+  bucket: String,
+
+  /// Organization owning the bucket
+  #[structopt(long, env = "INFLUX_ORG")]
+  org: Option<String>,
+
+  /// API token with write access to the bucket
+  #[structopt(long, env = "INFLUX_TOKEN", hide_env_values = true)]
+  token: Option<String>,
+
+  /// Maximum number of readings held while InfluxDB is unreachable; the
+  /// oldest are dropped first
+  #[structopt(long, default_value = "1000")]
+  buffer: usize,
+
+  /// Cycle period, one of: 3s, 100s, 5m (or 0, 1, 2)
+  #[structopt(long, short, default_value = "3s", env = "METRIFUL_INTERVAL")]
+  interval: CyclePeriod
+}
+
+/// InfluxDB measurement that readings are written to.
+const INFLUX_MEASUREMENT: &str = "metriful";
+
+/// Escapes a tag key, tag value, or field key for InfluxDB line protocol.
+fn influx_escape(s: &str) -> String {
+  let mut escaped = String::with_capacity(s.len());
+  for c in s.chars() {
+    if matches!(c, ',' | '=' | ' ') {
+      escaped.push('\\');
+    }
+
+    escaped.push(c);
+  }
+
+  escaped
+}
+
+/// Appends a reading's values as InfluxDB fields, named as with
+/// `flatten_reading()`. Text values become string fields, and non-finite
+/// numbers (which line protocol can't represent) are skipped.
+fn influx_fields(reading: &DynReading, fields: &mut Vec<String>) {
+  let mut number = |name: &str, n: f64| if n.is_finite() {
+    fields.push(format!("{}={}", influx_escape(name), n));
+  };
+
+  match &reading.value {
+    ReadingValue::Number(n) => number(reading.name, *n),
+    ReadingValue::Numbers(values) => {
+      for (i, n) in values.iter().enumerate() {
+        number(&format!("{}_{}", reading.name, i), *n);
+      }
+    },
+    ReadingValue::Text(s) => fields.push(format!(
+      "{}=\"{}\"",
+      influx_escape(reading.name),
+      s.replace('\\', "\\\\").replace('"', "\\\"")
+    )),
+    ReadingValue::Group(components) => {
+      for component in components {
+        influx_fields(component, fields);
+      }
+    },
+  }
+}
+
+/// Formats readings as InfluxDB line protocol, tagged with the sensor's i2c
+/// address and device and, if set, its location.
+pub(crate) struct InfluxFormatter {
+  tags: String,
+}
+
+impl InfluxFormatter {
+  pub(crate) fn new(opts: &Options) -> InfluxFormatter {
+    // tags are sorted by key, as InfluxDB recommends
+    let mut tags = format!(
+      ",address=0x{:02x},device={}",
+      opts.sensor.i2c_address,
+      influx_escape(&opts.sensor.i2c_device.display().to_string())
+    );
+
+    if let Some(location) = &opts.location {
+      tags.push_str(&format!(",location={}", influx_escape(location)));
+    }
+
+    InfluxFormatter { tags }
+  }
+
+  /// Formats readings taken together as a single line, or returns None if
+  /// they have no values to write.
+  pub(crate) fn line(&self, time: DateTime<Utc>, readings: &[DynReading]) -> Option<String> {
+    let mut fields = Vec::new();
+    for reading in readings {
+      influx_fields(reading, &mut fields);
+    }
+
+    if fields.is_empty() {
+      return None;
+    }
+
+    let nanos = time.timestamp() * 1_000_000_000 + i64::from(time.timestamp_subsec_nanos());
+    Some(format!("{}{} {} {}", INFLUX_MEASUREMENT, self.tags, fields.join(","), nanos))
+  }
+
+  /// Formats a combined reading, which always has values to write.
+  pub(crate) fn combined_line(&self, reading: &UnitValue<UnitCombinedData>) -> String {
+    let reading = DynReading::from_value("combined_all", reading);
+    self.line(reading.time, &[reading]).unwrap_or_default()
+  }
+}
+
+/// Writes lines to InfluxDB's v2 write API, returning the response status.
+#[cfg(feature = "influx")]
+fn influx_write(action: &InfluxPushAction, lines: &VecDeque<String>) -> Result<attohttpc::StatusCode> {
+  let url = format!("{}/api/v2/write", action.url.trim_end_matches('/'));
+  let body = lines.iter().map(String::as_str).collect::<Vec<_>>().join("\n");
+
+  let mut request = attohttpc::post(url)
+    .param("bucket", &action.bucket)
+    .param("precision", "ns")
+    .timeout(Duration::from_secs(10));
+
+  if let Some(org) = &action.org {
+    request = request.param("org", org);
+  }
+
+  if let Some(token) = &action.token {
+    request = request.header("Authorization", format!("Token {}", token));
+  }
+
+  let response = request.text(body).send()?;
+  let status = response.status();
+  if !status.is_success() {
+    let message = response.text().unwrap_or_default();
+    warn!("InfluxDB responded {}: {}", status, message.trim());
+  }
+
+  Ok(status)
+}
+
+#[cfg(feature = "influx")]
+pub(crate) fn influx_push(opts: &Options, action: &InfluxPushAction, mut metriful: Metriful) -> Result<()> {
+  let influx = InfluxFormatter::new(opts);
+  let mut pending = VecDeque::new();
+
+  // transient errors shouldn't end a long-running push
+  let policy = ReadPolicy {
+    max_retries: 3,
+    on_error: OnError::Continue,
+    ..ReadPolicy::default()
+  };
+
+  let iter = metriful
+    .cycle_read_iter_timeout(METRIC_COMBINED_ALL, action.interval, opts.sensor.timeout)
+    .with_policy(policy)
+    .with_cancel(shutdown_token());
+
+  for reading in iter {
+    let reading = match reading {
+      Ok(reading) => reading,
+      Err(e) => {
+        warn!("read failed, continuing: {}", e);
+        continue;
+      },
+    };
+
+    if pending.len() >= action.buffer.max(1) {
+      warn!("InfluxDB write buffer full, dropping oldest reading");
+      pending.pop_front();
+    }
+
+    pending.push_back(influx.combined_line(&reading));
+
+    match influx_write(action, &pending) {
+      Ok(status) if status.is_success() => pending.clear(),
+
+      // rejected lines won't be accepted later either, but throttling and
+      // server errors may pass
+      Ok(status) if status.is_client_error() && status.as_u16() != 429 => {
+        error!("InfluxDB rejected {} readings, dropping them", pending.len());
+        pending.clear();
+      },
+      Ok(_) => warn!("InfluxDB write failed, will retry {} readings", pending.len()),
+      Err(e) => warn!("InfluxDB write failed, will retry {} readings: {}", pending.len(), e),
+    }
+  }
+
+  if !pending.is_empty() {
+    warn!("shutting down with {} readings unsent", pending.len());
+  }
+
+  Ok(())
+}
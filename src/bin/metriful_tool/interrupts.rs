@@ -0,0 +1,136 @@
+//! The `light-int` and `sound-int` subcommands.
+
+use super::*;
+
+/// Parses a light interrupt threshold in lux, e.g. `300` or `300lx`.
+fn try_lux_from_str(s: &str) -> Result<f32> {
+  let lux: f32 = s.trim()
+    .trim_end_matches("lux")
+    .trim_end_matches("lx")
+    .trim_end()
+    .parse()
+    .with_context(|| format!("invalid illuminance in lux: {:?}", s))?;
+
+  // the device stores a u16 integer part and a single decimal digit
+  if !(0.0..=f32::from(u16::MAX)).contains(&lux) {
+    return Err(eyre!("threshold must be between 0 and {} lx", u16::MAX));
+  }
+
+  Ok(lux)
+}
+
+/// Parses a sound interrupt threshold in millipascals, e.g. `5000` or
+/// `5000mPa`.
+fn try_mpa_from_str(s: &str) -> Result<u16> {
+  s.trim()
+    .trim_end_matches("mPa")
+    .trim_end()
+    .parse()
+    .with_context(|| format!("invalid amplitude in mPa (0 to {}): {:?}", u16::MAX, s))
+}
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub(crate) enum LightIntAction {
+  /// Enables the light interrupt, or updates its settings if already enabled
+  Enable {
+    /// Illuminance threshold in lux, e.g. 300lx
+    #[structopt(long, short, parse(try_from_str = try_lux_from_str))]
+    threshold: f32,
+
+    /// Trigger above (positive) or below (negative) the threshold
+    #[structopt(long, short, default_value = "positive")]
+    polarity: InterruptPolarity,
+
+    /// Interrupt mode, one of: latch (held until cleared), comparator
+    #[structopt(long, short, default_value = "latch")]
+    mode: InterruptMode,
+  },
+
+  /// Disables the light interrupt
+  Disable,
+
+  /// Clears a latched light interrupt
+  Clear,
+}
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub(crate) enum SoundIntAction {
+  /// Enables the sound interrupt, or updates its settings if already enabled
+  Enable {
+    /// Peak sound amplitude threshold in millipascals, e.g. 5000mPa
+    #[structopt(long, short, parse(try_from_str = try_mpa_from_str))]
+    threshold: u16,
+
+    /// Interrupt mode, one of: latch (held until cleared), comparator
+    #[structopt(long, short, default_value = "latch")]
+    mode: InterruptMode,
+  },
+
+  /// Disables the sound interrupt
+  Disable,
+
+  /// Clears a latched sound interrupt
+  Clear,
+}
+
+pub(crate) fn light_int(opts: &Options, action: &LightIntAction, mut metriful: Metriful) -> Result<()> {
+  let config = match action {
+    LightIntAction::Enable { threshold, polarity, mode } => InterruptStatus::Enabled(LightInterrupt {
+      mode: *mode,
+      polarity: *polarity,
+      threshold: *threshold,
+    }),
+    LightIntAction::Disable => InterruptStatus::Disabled,
+    LightIntAction::Clear => {
+      metriful.wait_for_ready_timeout(opts.sensor.timeout)?;
+      metriful.clear_light_interrupt()?;
+      info!("light interrupt cleared");
+
+      return Ok(());
+    },
+  };
+
+  let status = update_config(opts, &mut metriful, |c| c.light_interrupt = config)?;
+  println!("light interrupt: {:#?}", status.light_int);
+
+  Ok(())
+}
+
+pub(crate) fn sound_int(opts: &Options, action: &SoundIntAction, mut metriful: Metriful) -> Result<()> {
+  let config = match action {
+    SoundIntAction::Enable { threshold, mode } => InterruptStatus::Enabled(SoundInterrupt {
+      mode: *mode,
+      threshold: *threshold,
+    }),
+    SoundIntAction::Disable => InterruptStatus::Disabled,
+    SoundIntAction::Clear => {
+      metriful.wait_for_ready_timeout(opts.sensor.timeout)?;
+      metriful.clear_sound_interrupt()?;
+      info!("sound interrupt cleared");
+
+      return Ok(());
+    },
+  };
+
+  let status = update_config(opts, &mut metriful, |c| c.sound_interrupt = config)?;
+  println!("sound interrupt: {:#?}", status.sound_int);
+
+  Ok(())
+}
+
+/// Changes the device's current configuration and returns the status read
+/// back afterward.
+fn update_config(
+  opts: &Options,
+  metriful: &mut Metriful,
+  f: impl FnOnce(&mut DeviceConfig),
+) -> Result<DeviceStatus> {
+  // settings can only be changed in standby; applying a config takes care of
+  // this and restores the current mode afterward
+  let mut config = DeviceConfig::from_status(&metriful.read_status()?);
+  f(&mut config);
+
+  Ok(metriful.apply_config_timeout(&config, opts.sensor.timeout)?)
+}
@@ -0,0 +1,212 @@
+//! The `log` subcommand.
+
+use super::*;
+use super::csv::{combined_csv_header, combined_csv_record, csv_record};
+
+#[derive(Debug, Copy, Clone)]
+enum LogFormat {
+  Ndjson,
+  Csv,
+}
+
+impl FromStr for LogFormat {
+  type Err = Error;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_ascii_lowercase().as_str() {
+      "ndjson" | "json" => Ok(LogFormat::Ndjson),
+      "csv" => Ok(LogFormat::Csv),
+      s => Err(eyre!("invalid log format '{}', expected one of: ndjson, csv", s))
+    }
+  }
+}
+
+impl LogFormat {
+  fn extension(&self) -> &'static str {
+    match self {
+      LogFormat::Ndjson => "ndjson",
+      LogFormat::Csv => "csv",
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Rotation {
+  Hourly,
+  Daily,
+  Never,
+}
+
+impl FromStr for Rotation {
+  type Err = Error;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_ascii_lowercase().as_str() {
+      "hourly" => Ok(Rotation::Hourly),
+      "daily" => Ok(Rotation::Daily),
+      "never" | "none" => Ok(Rotation::Never),
+      s => Err(eyre!("invalid rotation '{}', expected one of: hourly, daily, never", s))
+    }
+  }
+}
+
+impl Rotation {
+  /// Returns the name (without extension) of the file a reading taken at the
+  /// given time belongs in. Times are UTC, so files never overlap across
+  /// daylight saving changes.
+  fn file_stem(&self, time: &DateTime<Utc>) -> String {
+    match self {
+      Rotation::Hourly => time.format("metriful-%Y-%m-%dT%H").to_string(),
+      Rotation::Daily => time.format("metriful-%Y-%m-%d").to_string(),
+      Rotation::Never => "metriful".to_string(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct LogAction {
+  /// Directory to write log files to; created if it doesn't exist
+  #[structopt(long, parse(from_os_str))]
+  output_dir: PathBuf,
+
+  /// Log file format, one of: ndjson, csv
+  #[structopt(long, short, default_value = "ndjson")]
+  format: LogFormat,
+
+  /// How often to start a new file, one of: hourly, daily, never
+  #[structopt(long, default_value = "daily")]
+  rotate: Rotation,
+
+  /// Number of readings written between syncs to disk; readings since the
+  /// last sync may be lost on power failure
+  #[structopt(long, default_value = "10")]
+  sync_every: usize,
+
+  /// Cycle period, one of: 3s, 100s, 5m (or 0, 1, 2)
+  #[structopt(long, short, default_value = "3s", env = "METRIFUL_INTERVAL")]
+  interval: CyclePeriod
+}
+
+/// Appends readings to rotating log files.
+struct Logger {
+  dir: PathBuf,
+  format: LogFormat,
+  rotate: Rotation,
+  sync_every: usize,
+
+  /// The current file's stem and writer
+  current: Option<(String, BufWriter<File>)>,
+
+  /// Readings written since the last sync
+  unsynced: usize,
+}
+
+impl Logger {
+  fn new(action: &LogAction) -> Result<Logger> {
+    fs::create_dir_all(&action.output_dir)
+      .with_context(|| format!("could not create output directory {:?}", action.output_dir))?;
+
+    Ok(Logger {
+      dir: action.output_dir.clone(),
+      format: action.format,
+      rotate: action.rotate,
+      sync_every: action.sync_every.max(1),
+      current: None,
+      unsynced: 0,
+    })
+  }
+
+  /// Opens a log file for appending. Files left by a previous run are
+  /// continued: a CSV header is only written to new files, and a line cut
+  /// short by a crash is terminated so it doesn't corrupt the next one.
+  fn open(&self, stem: &str) -> Result<BufWriter<File>> {
+    let path = self.dir.join(format!("{}.{}", stem, self.format.extension()));
+    let mut file = OpenOptions::new()
+      .read(true)
+      .append(true)
+      .create(true)
+      .open(&path)
+      .with_context(|| format!("could not open log file {:?}", path))?;
+
+    let len = file.metadata()?.len();
+    if len > 0 {
+      let mut last = [0u8];
+      file.seek(SeekFrom::Start(len - 1))?;
+      file.read_exact(&mut last)?;
+
+      if last[0] != b'\n' {
+        warn!("log file {:?} ends with a partial line, starting a new line", path);
+        file.write_all(b"\n")?;
+      }
+    }
+
+    info!("logging to {:?}", path);
+    let mut writer = BufWriter::new(file);
+    if len == 0 {
+      if let LogFormat::Csv = self.format {
+        writeln!(writer, "{}", csv_record(&combined_csv_header(UnitSystem::Metric)))?;
+      }
+    }
+
+    Ok(writer)
+  }
+
+  fn write(&mut self, reading: &UnitValue<UnitCombinedData>) -> Result<()> {
+    let stem = self.rotate.file_stem(&reading.time);
+    if self.current.as_ref().map(|(current, _)| current != &stem).unwrap_or(true) {
+      self.sync()?;
+      self.current = Some((stem.clone(), self.open(&stem)?));
+    }
+
+    let line = match self.format {
+      LogFormat::Ndjson => serde_json::to_string(reading)?,
+      LogFormat::Csv => combined_csv_record(reading, UnitSystem::Metric),
+    };
+
+    if let Some((_, writer)) = &mut self.current {
+      writeln!(writer, "{}", line)?;
+    }
+
+    self.unsynced += 1;
+    if self.unsynced >= self.sync_every {
+      self.sync()?;
+    }
+
+    Ok(())
+  }
+
+  /// Flushes buffered readings and syncs them to disk.
+  fn sync(&mut self) -> Result<()> {
+    if let Some((_, writer)) = &mut self.current {
+      writer.flush()?;
+      writer.get_ref().sync_data()?;
+    }
+
+    self.unsynced = 0;
+    Ok(())
+  }
+}
+
+pub(crate) fn log(opts: &Options, action: &LogAction, mut metriful: Metriful) -> Result<()> {
+  let mut logger = Logger::new(action)?;
+
+  // transient errors shouldn't end a long-running log
+  let policy = ReadPolicy {
+    max_retries: 3,
+    on_error: OnError::Continue,
+    ..ReadPolicy::default()
+  };
+
+  let iter = metriful
+    .cycle_read_iter_timeout(METRIC_COMBINED_ALL, action.interval, opts.sensor.timeout)
+    .with_policy(policy)
+    .with_cancel(shutdown_token());
+
+  for reading in iter {
+    match reading {
+      Ok(reading) => logger.write(&reading)?,
+      Err(e) => warn!("read failed, continuing: {}", e),
+    }
+  }
+
+  info!("shutting down, syncing log file");
+  logger.sync()
+}
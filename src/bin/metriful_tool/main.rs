@@ -0,0 +1,627 @@
+use std::collections::BTreeMap;
+#[cfg(feature = "influx")] use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use std::thread;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use color_eyre::eyre::{Result, Error, Context, eyre};
+use log::*;
+use serde::Serialize;
+use serde_json::json;
+use structopt::StructOpt;
+
+use metriful::{CyclePeriod, DeviceConfig, Metriful, MetricSet, MetrifulOptions, ReadyPolarity, OperationalMode, ShutdownOptions};
+use metriful::{InterruptMode, InterruptPolarity, LightInterrupt, SoundInterrupt};
+use metriful::cancel::{CancelToken, CANCEL_CHECK_INTERVAL};
+use metriful::dyn_metric::{DynReading, ReadingValue};
+use metriful::error::{ErrorKind, MetrifulError};
+use metriful::events::{EventConfig, InterruptSource, MetrifulEvent};
+use metriful::format::{FormatOptions, set_default_format_options};
+use metriful::guard::MODE_CHANGE_SETTLE;
+use metriful::options::{parse_i2c_address, parse_timeout_secs};
+use metriful::ready::gpiochip_path;
+use metriful::metric::*;
+use metriful::metric_set::MetricSetReading;
+use metriful::registers::Register;
+use metriful::retry::{OnError, ReadPolicy};
+use metriful::stats::{Accumulator, Summary, Window};
+use metriful::status::{DeviceStatus, InterruptStatus};
+use metriful::transport::{LinuxTransport, TransferMode};
+use metriful::unit::{SPL_BANDS, UnitCombinedData, UnitDegreesFahrenheit, UnitInchesOfMercury, UnitSystem, UnitValue};
+
+mod bench;
+mod check;
+mod compare;
+mod convert;
+mod csv;
+#[cfg(feature = "dashboard")]
+mod dashboard;
+mod doctor;
+mod home_assistant;
+mod influx;
+mod interrupts;
+mod logger;
+mod monitor;
+mod read;
+#[cfg(feature = "sqlite")]
+mod record;
+mod replay;
+mod scan;
+mod self_test;
+mod status_watch;
+mod stress;
+mod units;
+mod watch;
+
+use bench::{BenchAction, bench};
+use check::{CheckAction, check};
+use compare::{CompareAction, compare};
+use convert::{ConvertAction, convert};
+use csv::{csv_name, csv_record, status_csv};
+#[cfg(feature = "dashboard")] use dashboard::DashboardAction;
+use doctor::{DoctorAction, doctor};
+use home_assistant::{GenHomeAssistantAction, gen_home_assistant};
+#[cfg(feature = "influx")] use influx::{InfluxPushAction, influx_push};
+use interrupts::{LightIntAction, SoundIntAction, light_int, sound_int};
+use logger::{LogAction, log};
+use monitor::{MonitorInterruptsAction, monitor_interrupts};
+use read::{ReadAction, read_metric};
+#[cfg(feature = "sqlite")] use record::{RecordAction, record};
+use replay::{ReplayAction, replay};
+use scan::{ScanAction, scan};
+use self_test::{SelfTestAction, self_test};
+use status_watch::{StatusWatchAction, status_watch};
+use stress::{StressAction, stress};
+use watch::{CycleWatchAction, WatchAction, cycle_watch, cycle_watch_async, watch};
+
+fn try_watch_interval_from_str(s: &str) -> Result<Duration> {
+  let seconds: u64 = s.strip_suffix("s")
+    .unwrap_or(s)
+    .parse()
+    .with_context(|| format!("invalid duration in seconds: {:?}", s))?;
+
+  if seconds < 2 {
+    return Err(eyre!("interval must be at least 2 seconds"));
+  }
+
+  Ok(Duration::from_secs(seconds))
+}
+
+/// Parses a duration in seconds, minutes or hours, e.g. `90`, `90s`, `10m` or
+/// `1h`.
+fn try_duration_from_str(s: &str) -> Result<Duration> {
+  let s = s.trim();
+  let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+  let (number, suffix) = s.split_at(split);
+
+  let multiplier = match suffix {
+    "" | "s" => 1,
+    "m" => 60,
+    "h" => 60 * 60,
+    _ => return Err(eyre!("invalid duration {:?}, expected e.g. 90s, 10m or 1h", s)),
+  };
+
+  number.parse::<u64>()
+    .ok()
+    .and_then(|number| number.checked_mul(multiplier))
+    .map(Duration::from_secs)
+    .ok_or_else(|| eyre!("invalid duration {:?}, expected e.g. 90s, 10m or 1h", s))
+}
+
+#[derive(Debug, Copy, Clone)]
+enum OutputMode {
+  Plain,
+  JSON,
+  CSV,
+
+  /// InfluxDB line protocol; only supported by actions that output readings
+  Influx,
+}
+
+impl FromStr for OutputMode {
+  type Err = Error;
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_ascii_lowercase().as_str() {
+      "plain" => Ok(OutputMode::Plain),
+      "json" => Ok(OutputMode::JSON),
+      "csv" => Ok(OutputMode::CSV),
+      "influx" => Ok(OutputMode::Influx),
+      s => Err(eyre!("invalid output mode '{}', expected one of: plain, json, csv, influx", s))
+    }
+  }
+}
+
+/// Error for actions that don't output readings, and so have no InfluxDB
+/// representation.
+fn influx_unsupported() -> Error {
+  eyre!("influx output is only supported by read, watch, and cycle-watch")
+}
+
+#[derive(Debug, Clone, StructOpt)]
+struct InfoAction {
+  /// Data output format, one of: plain, json, csv
+  #[structopt(long, short, default_value = "plain")]
+  output: OutputMode,
+}
+
+#[derive(Debug, Clone, StructOpt)]
+struct MetricsAction {
+  /// Data output format, one of: plain, json, csv
+  #[structopt(long, short, default_value = "plain")]
+  output: OutputMode,
+}
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+enum Action {
+  /// Fetches sensor information
+  Info(InfoAction),
+
+  /// Polls the device status and prints changes to the mode, cycle period,
+  /// interrupts, or particle sensor, e.g. when other processes share the
+  /// sensor; does not change its configuration
+  StatusWatch(StatusWatchAction),
+
+  /// Lists all metrics the sensor provides; does not access the device
+  Metrics(MetricsAction),
+
+  /// Generates Home Assistant sensor configuration for all metrics; does not
+  /// access the device
+  GenHomeassistant(GenHomeAssistantAction),
+
+  /// Probes i2c addresses 0x70 and 0x71 for a sensor; does not use the ready
+  /// signal
+  Scan(ScanAction),
+
+  /// Checks the i2c and GPIO setup and suggests fixes for common problems;
+  /// does not wait for the ready signal
+  Doctor(DoctorAction),
+
+  /// Takes a single on-demand measurement and prints one metric
+  Read(ReadAction),
+
+  /// Takes a single measurement and checks it against thresholds, printing
+  /// one status line and exiting 0 (OK), 1 (WARNING), 2 (CRITICAL) or 3
+  /// (UNKNOWN) per monitoring plugin conventions
+  Check(CheckAction),
+
+  /// Replays recorded readings through the output formats and threshold
+  /// checks; does not access the device
+  Replay(ReplayAction),
+
+  /// Converts recorded readings to CSV, Parquet, or SQLite; does not access
+  /// the device
+  Convert(ConvertAction),
+
+  /// Configures or clears the light interrupt
+  LightInt(LightIntAction),
+
+  /// Configures or clears the sound interrupt
+  SoundInt(SoundIntAction),
+
+  /// Prints light and sound interrupt events as they occur
+  MonitorInterrupts(MonitorInterruptsAction),
+
+  /// Logs cycle readings to rotating files until stopped
+  Log(LogAction),
+
+  /// Records cycle readings to a SQLite database until stopped
+  #[cfg(feature = "sqlite")]
+  Record(RecordAction),
+
+  /// Pushes cycle readings to InfluxDB until stopped
+  #[cfg(feature = "influx")]
+  InfluxPush(InfluxPushAction),
+
+  /// Shows live readings in an interactive terminal dashboard
+  #[cfg(feature = "dashboard")]
+  Dashboard(DashboardAction),
+
+  /// Times measurements, READY waits, and register reads
+  Bench(BenchAction),
+
+  /// Alternates block reads and mode changes for a long period, reporting
+  /// every error and the overall error rate; for qualifying cabling and
+  /// pull-ups
+  Stress(StressAction),
+
+  /// Resets the sensor, then checks measurements, mode changes, and the
+  /// READY signal against the datasheet; a first stop for wiring problems
+  SelfTest(SelfTestAction),
+
+  /// Reads two sensors in lockstep and prints their differences
+  Compare(CompareAction),
+
+  /// Resets the sensor
+  Reset,
+
+  /// Displays sensor events
+  Watch(WatchAction),
+
+  /// Displays sensor events in cycle mode
+  CycleWatch(CycleWatchAction),
+
+  /// Displays sensor events in async cycle mode. This is meant as a library
+  /// example and is not functionally different from regular `cycle-watch`.
+  CycleWatchAsync(CycleWatchAction),
+}
+
+impl Action {
+  /// Returns true if this action reads continuously until stopped.
+  fn is_long_running(&self) -> bool {
+    match self {
+      Action::Watch(_) | Action::CycleWatch(_) | Action::CycleWatchAsync(_)
+        | Action::MonitorInterrupts(_) | Action::Log(_) | Action::Compare(_)
+        | Action::Stress(_) => true,
+      #[cfg(feature = "sqlite")]
+      Action::Record(_) => true,
+      #[cfg(feature = "dashboard")]
+      Action::Dashboard(_) => true,
+      #[cfg(feature = "influx")]
+      Action::InfluxPush(_) => true,
+      _ => false,
+    }
+  }
+}
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(name = "metriful-tool")]
+struct Options {
+  /// system i2c device, e.g. /dev/i2c-1 [env: METRIFUL_I2C_DEVICE, default:
+  /// /dev/i2c-1]
+  #[structopt(long, short, parse(from_os_str), global = true)]
+  device: Option<PathBuf>,
+
+  /// Metriful device i2c address; usually 0x71, or 0x70 if the solder bridge is
+  /// closed. Can specify a plain base-10 int or hex with a `0x` prefix.
+  /// [env: METRIFUL_I2C_ADDRESS, default: 0x71]
+  #[structopt(long, parse(try_from_str = parse_i2c_address), global = true)]
+  i2c_address: Option<u16>,
+
+  /// GPIO number for the ready signal. Note that this is a GPIO number, not a
+  /// physical pin number - the mapping between the two numbers varies by
+  /// device. [env: METRIFUL_GPIO_READY, default: 11]
+  #[structopt(long, global = true)]
+  gpio_ready: Option<u64>,
+
+  /// Global timeout for any individual sensor command in seconds.
+  /// [env: METRIFUL_TIMEOUT]
+  #[structopt(long, parse(try_from_str = parse_timeout_secs), global = true)]
+  timeout: Option<Duration>,
+
+  /// Logic level at which the READY signal is asserted, either `active-low`
+  /// or `active-high` (e.g. with an inverting level shifter).
+  /// [env: METRIFUL_READY_POLARITY, default: active-low]
+  #[structopt(long, global = true)]
+  ready_polarity: Option<ReadyPolarity>,
+
+  /// GPIO chip (e.g. `gpiochip0`) for the ready signal. If set, `gpio-ready`
+  /// is a line offset on this chip and the GPIO character device interface is
+  /// used instead of sysfs. [env: METRIFUL_GPIO_CHIP]
+  #[structopt(long, parse(from_os_str), global = true)]
+  gpio_chip: Option<PathBuf>,
+
+  /// Number of decimal places shown for values in plain output and JSON
+  /// `formatted_value` fields; by default values are shown at full precision
+  #[structopt(long, global = true)]
+  precision: Option<usize>,
+
+  /// Units for plain and CSV output, either `metric` or `imperial` (degrees
+  /// Fahrenheit and inches of mercury). JSON and InfluxDB output are always
+  /// metric.
+  #[structopt(long, global = true, env = "METRIFUL_UNITS", default_value = "metric")]
+  units: UnitSystem,
+
+  /// Value of the `location` tag on InfluxDB readings, e.g. `living-room`
+  #[structopt(long, global = true, env = "METRIFUL_LOCATION")]
+  location: Option<String>,
+
+  /// Sensor options resolved from the flags above and the `METRIFUL_*`
+  /// environment variables
+  #[structopt(skip)]
+  sensor: MetrifulOptions,
+
+  #[structopt(subcommand)]
+  action: Action
+}
+
+impl Options {
+  /// Resolves sensor options from the environment, with any flags given on the
+  /// command line taking precedence.
+  fn resolve_sensor_options(&mut self) -> Result<()> {
+    let mut sensor = MetrifulOptions::from_env()
+      .wrap_err("invalid sensor options in environment")?;
+
+    if let Some(device) = &self.device {
+      sensor.i2c_device = device.clone();
+    }
+
+    if let Some(i2c_address) = self.i2c_address {
+      sensor.i2c_address = i2c_address;
+    }
+
+    if let Some(gpio_ready) = self.gpio_ready {
+      sensor.gpio_ready = gpio_ready;
+    }
+
+    if self.timeout.is_some() {
+      sensor.timeout = self.timeout;
+    }
+
+    if let Some(ready_polarity) = self.ready_polarity {
+      sensor.ready_polarity = ready_polarity;
+    }
+
+    if self.gpio_chip.is_some() {
+      sensor.gpio_chip = self.gpio_chip.clone();
+    }
+
+    self.sensor = sensor;
+    Ok(())
+  }
+}
+
+/// Writes a JSON error object to stdout if JSON output is enabled, so
+/// consumers of the output stream can tell why it ended.
+fn report_error(output: OutputMode, e: MetrifulError) -> Error {
+  if let OutputMode::JSON = output {
+    println!("{}", json!({ "error": &e }));
+  }
+
+  e.into()
+}
+
+fn show_info(_opts: &Options, action: &InfoAction, mut metriful: Metriful) -> Result<()> {
+  let status = metriful.read_status().map_err(|e| report_error(action.output, e))?;
+
+  match action.output {
+    OutputMode::Plain => println!("{:#?}", status),
+    OutputMode::JSON => println!("{}", serde_json::to_string(&status)?),
+    OutputMode::CSV => {
+      let (header, record) = status_csv(&status);
+      println!("{}", header);
+      println!("{}", record);
+    },
+    OutputMode::Influx => return Err(influx_unsupported()),
+  }
+
+  Ok(())
+}
+
+fn list_metrics(action: &MetricsAction) -> Result<()> {
+  match action.output {
+    OutputMode::Plain => {
+      for info in metrics() {
+        let mut requires = Vec::new();
+        if info.validity.cycle_mode {
+          requires.push("cycle mode");
+        }
+
+        if info.validity.particle_sensor {
+          requires.push("particle sensor");
+        }
+
+        let unit = match info.unit_symbol {
+          Some(symbol) => format!("{} ({})", info.unit_name, symbol),
+          None => info.unit_name.to_string(),
+        };
+
+        println!("{} [0x{:02x}, {:?}]", info.id, info.register, info.category);
+        println!("  {}", info.description);
+        println!("  unit: {}", unit);
+        if !requires.is_empty() {
+          println!("  requires: {}", requires.join(", "));
+        }
+      }
+    },
+    OutputMode::JSON => println!("{}", serde_json::to_string(metrics())?),
+    OutputMode::CSV => {
+      println!("{}", csv_record(&[
+        "id", "register", "category", "description", "unit_name", "unit_symbol",
+        "prometheus_name", "requires_cycle_mode", "requires_particle_sensor",
+      ]));
+
+      for info in metrics() {
+        println!("{}", csv_record(&[
+          info.id.to_string(),
+          format!("0x{:02x}", info.register),
+          csv_name(&info.category),
+          info.description.to_string(),
+          info.unit_name.to_string(),
+          info.unit_symbol.unwrap_or_default().to_string(),
+          info.prometheus_name.unwrap_or_default().to_string(),
+          info.validity.cycle_mode.to_string(),
+          info.validity.particle_sensor.to_string(),
+        ]));
+      }
+    },
+    OutputMode::Influx => return Err(influx_unsupported()),
+  }
+
+  Ok(())
+}
+
+/// Flattens a reading into CSV columns: one per value, named after the
+/// reading or, for combined reads, their components. Lists of values (i.e.
+/// sound levels by band) get one column per value, suffixed with its index.
+fn flatten_reading(reading: &DynReading, columns: &mut Vec<(String, String)>) {
+  match &reading.value {
+    ReadingValue::Number(n) => columns.push((reading.name.to_string(), n.to_string())),
+    ReadingValue::Numbers(values) => {
+      for (i, n) in values.iter().enumerate() {
+        columns.push((format!("{}_{}", reading.name, i), n.to_string()));
+      }
+    },
+    ReadingValue::Text(s) => columns.push((reading.name.to_string(), s.clone())),
+    ReadingValue::Group(components) => {
+      for component in components {
+        flatten_reading(component, columns);
+      }
+    },
+  }
+}
+
+/// Describes the configured READY line, so results from different GPIO
+/// backends can be told apart.
+fn ready_line_description(opts: &Options) -> String {
+  match &opts.sensor.gpio_chip {
+    Some(chip) => format!("{} line {} (cdev)", chip.display(), opts.sensor.gpio_ready),
+    None => format!("gpio {} (sysfs)", opts.sensor.gpio_ready),
+  }
+}
+
+fn reset(_opts: &Options, mut metriful: Metriful) -> Result<()> {
+  metriful.reset()?;
+  info!("reset command sent, waiting for ready...");
+
+  let now = Instant::now();
+  metriful.wait_for_ready()?;
+
+  info!("reset finished, device became ready in {:?}", now.elapsed());
+
+  Ok(())
+}
+
+/// Set by SIGTERM and SIGINT handlers.
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn request_shutdown(_signal: libc::c_int) {
+  SHUTDOWN.store(true, Ordering::SeqCst);
+}
+
+/// Returns a token cancelled on SIGTERM or SIGINT. A cancel token can't safely
+/// be touched from a signal handler, so a thread watches for the handler's
+/// flag instead.
+fn shutdown_token() -> CancelToken {
+  let handler = request_shutdown as extern "C" fn(libc::c_int) as libc::sighandler_t;
+  unsafe {
+    libc::signal(libc::SIGTERM, handler);
+    libc::signal(libc::SIGINT, handler);
+  }
+
+  let token = CancelToken::new();
+  let canceller = token.clone();
+  thread::spawn(move || {
+    while !SHUTDOWN.load(Ordering::SeqCst) {
+      thread::sleep(CANCEL_CHECK_INTERVAL);
+    }
+
+    canceller.cancel();
+  });
+
+  token
+}
+
+fn main() -> Result<()> {
+  color_eyre::install()?;
+
+  let env = env_logger::Env::default()
+    .filter_or("METRIFUL_LOG", "info")
+    .write_style_or("METRIFUL_STYLE", "always");
+
+  env_logger::Builder::from_env(env)
+    .target(env_logger::Target::Stderr)
+    .init();
+
+  let mut opts: Options = Options::from_args();
+  opts.resolve_sensor_options()?;
+  debug!("options: {:?}", opts);
+
+  if let Some(precision) = opts.precision {
+    set_default_format_options(FormatOptions {
+      precision: Some(precision),
+      ..FormatOptions::default()
+    });
+  }
+
+  // metric metadata is static, so no device is needed to list it
+  if let Action::Metrics(action) = &opts.action {
+    return list_metrics(action);
+  }
+
+  if let Action::GenHomeassistant(action) = &opts.action {
+    return gen_home_assistant(action);
+  }
+
+  if let Action::Replay(action) = &opts.action {
+    return replay(&opts, action);
+  }
+
+  if let Action::Convert(action) = &opts.action {
+    return convert(&opts, action);
+  }
+
+  // a sensor that never becomes ready should fail the test, not hang it
+  if let Action::SelfTest(action) = &opts.action {
+    return self_test(&opts, action);
+  }
+
+  // probing doesn't need (and shouldn't wait on) the ready signal
+  if let Action::Scan(action) = &opts.action {
+    return scan(&opts, action);
+  }
+
+  if let Action::Doctor(action) = &opts.action {
+    return doctor(&opts, action);
+  }
+
+  // monitoring plugins report errors on stdout and via their exit status
+  if let Action::Check(action) = &opts.action {
+    std::process::exit(check(&opts, action) as i32);
+  }
+
+  info!("waiting for sensor to become ready...");
+  let mut metriful = opts.sensor.open()?;
+
+  // watch actions leave the device cycling; return it to standby and release
+  // the READY pin when they end
+  if opts.action.is_long_running() {
+    metriful.set_shutdown_options(ShutdownOptions {
+      on_drop: true,
+      ..ShutdownOptions::default()
+    });
+  }
+
+  info!("metriful sensor is ready");
+  let counters = metriful.counters();
+
+  let result = match &opts.action {
+    Action::Info(action) => show_info(&opts, action, metriful),
+    Action::StatusWatch(action) => status_watch(&opts, action, metriful),
+    Action::Metrics(action) => list_metrics(action),
+    Action::GenHomeassistant(action) => gen_home_assistant(action),
+    Action::Scan(action) => scan(&opts, action),
+    Action::Doctor(action) => doctor(&opts, action),
+    Action::Read(action) => read_metric(&opts, action, metriful),
+    Action::Check(_) => unreachable!("handled above"),
+    Action::Replay(action) => replay(&opts, action),
+    Action::Convert(action) => convert(&opts, action),
+    Action::LightInt(action) => light_int(&opts, action, metriful),
+    Action::SoundInt(action) => sound_int(&opts, action, metriful),
+    Action::MonitorInterrupts(action) => monitor_interrupts(&opts, action, metriful),
+    Action::Log(action) => log(&opts, action, metriful),
+    #[cfg(feature = "sqlite")]
+    Action::Record(action) => record(&opts, action, metriful),
+    #[cfg(feature = "influx")]
+    Action::InfluxPush(action) => influx_push(&opts, action, metriful),
+    #[cfg(feature = "dashboard")]
+    Action::Dashboard(action) => dashboard::run(&opts, action, metriful),
+    Action::Bench(action) => bench(&opts, action, metriful),
+    Action::Stress(action) => stress(&opts, action, metriful),
+    Action::SelfTest(_) => unreachable!("handled above"),
+    Action::Compare(action) => compare(&opts, action, metriful),
+    Action::Reset => reset(&opts, metriful),
+    Action::Watch(action) => watch(&opts, action, metriful),
+    Action::CycleWatch(action) => cycle_watch(&opts, action, metriful),
+    Action::CycleWatchAsync(action) => cycle_watch_async(&opts, action, metriful),
+  };
+
+  info!("device stats: {}", counters.snapshot());
+  result
+}
@@ -0,0 +1,88 @@
+//! The `monitor-interrupts` subcommand.
+
+use super::*;
+use super::csv::csv_record;
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct MonitorInterruptsAction {
+  /// GPIO number connected to the light interrupt (LIT) output; uses the
+  /// same GPIO interface and polarity as the ready signal
+  #[structopt(long)]
+  light_gpio: Option<u64>,
+
+  /// GPIO number connected to the sound interrupt (SIT) output
+  #[structopt(long)]
+  sound_gpio: Option<u64>,
+
+  /// Leave latched interrupts asserted rather than clearing them once
+  /// reported
+  #[structopt(long)]
+  no_clear: bool,
+
+  /// Data output format, one of: plain, json, csv
+  #[structopt(long, short, default_value = "plain")]
+  output: OutputMode,
+
+  /// Cycle period, one of: 3s, 100s, 5m (or 0, 1, 2)
+  #[structopt(long, short, default_value = "3s", env = "METRIFUL_INTERVAL")]
+  interval: CyclePeriod
+}
+
+pub(crate) fn monitor_interrupts(opts: &Options, action: &MonitorInterruptsAction, metriful: Metriful) -> Result<()> {
+  if action.light_gpio.is_none() && action.sound_gpio.is_none() {
+    return Err(eyre!("at least one of --light-gpio or --sound-gpio is required"));
+  }
+
+  if let OutputMode::Influx = action.output {
+    return Err(influx_unsupported());
+  }
+
+  // interrupt outputs are wired like READY, so open them the same way
+  let open_line = |gpio| opts.sensor.clone().gpio_ready(gpio).open_ready_line();
+
+  let config = EventConfig {
+    cycle_period: action.interval,
+    timeout: opts.sensor.timeout,
+    light_interrupt: action.light_gpio.map(open_line).transpose()?,
+    sound_interrupt: action.sound_gpio.map(open_line).transpose()?,
+    auto_clear_interrupts: !action.no_clear,
+    ..EventConfig::default()
+  };
+
+  // readings are discarded; the device just needs to be cycling
+  let (_cmd_tx, events, _handle) = metriful.event_stream(METRIC_ILLUMINANCE, config);
+
+  if let OutputMode::CSV = action.output {
+    println!("{}", csv_record(&["timestamp", "interrupt"]));
+  }
+
+  for event in events {
+    let source = match event {
+      MetrifulEvent::Interrupt(source) => source,
+      MetrifulEvent::Error(e) => {
+        warn!("error while monitoring interrupts: {}", e);
+        continue;
+      },
+      MetrifulEvent::ModeChanged { previous, current } => {
+        warn!("device mode changed from {:?} to {:?}", previous, current);
+        continue;
+      },
+      _ => continue,
+    };
+
+    let timestamp = chrono::Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    let name = match source {
+      InterruptSource::Light => "light",
+      InterruptSource::Sound => "sound",
+    };
+
+    match action.output {
+      OutputMode::Plain => println!("{} {} interrupt", timestamp, name),
+      OutputMode::JSON => println!("{}", json!({ "timestamp": timestamp, "interrupt": name })),
+      OutputMode::CSV => println!("{}", csv_record(&[timestamp.as_str(), name])),
+      OutputMode::Influx => unreachable!("rejected above"),
+    }
+  }
+
+  Err(eyre!("interrupt monitor stopped after repeated errors"))
+}
@@ -0,0 +1,47 @@
+//! The `read` subcommand.
+
+use super::*;
+use super::csv::csv_record;
+use super::influx::InfluxFormatter;
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct ReadAction {
+  /// Name of the metric to read, e.g. temperature or combined-sound; see
+  /// `metriful-tool metrics` for a full list
+  metric: DynamicMetric,
+
+  /// Data output format, one of: plain, json, csv, influx
+  #[structopt(long, short, default_value = "plain")]
+  output: OutputMode,
+}
+
+pub(crate) fn read_metric(opts: &Options, action: &ReadAction, mut metriful: Metriful) -> Result<()> {
+  let reading = metriful.set_mode_timeout(OperationalMode::Standby, opts.sensor.timeout)
+    .and_then(|_| metriful.execute_measurement())
+    .and_then(|()| metriful.wait_for_ready())
+    .and_then(|()| metriful.read_dyn(action.metric.as_dyn()))
+    .map_err(|e| report_error(action.output, e))?;
+
+  match action.output {
+    OutputMode::Plain => println!("{}", reading.to_unit_system(opts.units)),
+    OutputMode::JSON => println!("{}", serde_json::to_string(&reading)?),
+    OutputMode::CSV => {
+      let mut columns = vec![(
+        "timestamp".to_string(),
+        reading.time.to_rfc3339_opts(SecondsFormat::Secs, true),
+      )];
+      flatten_reading(&reading.to_unit_system(opts.units), &mut columns);
+
+      let (header, record): (Vec<_>, Vec<_>) = columns.into_iter().unzip();
+      println!("{}", csv_record(&header));
+      println!("{}", csv_record(&record));
+    },
+    OutputMode::Influx => {
+      if let Some(line) = InfluxFormatter::new(opts).line(reading.time, &[reading]) {
+        println!("{}", line);
+      }
+    },
+  }
+
+  Ok(())
+}
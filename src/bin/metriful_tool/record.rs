@@ -0,0 +1,142 @@
+//! The `record` subcommand.
+
+use super::*;
+use super::csv::{TEXT_COLUMNS, combined_csv_header, combined_fields};
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct RecordAction {
+  /// SQLite database file to record to; created if it doesn't exist
+  #[structopt(long, parse(from_os_str), default_value = "metriful.db")]
+  database: PathBuf,
+
+  /// Cycle period, one of: 3s, 100s, 5m (or 0, 1, 2)
+  #[structopt(long, short, default_value = "3s", env = "METRIFUL_INTERVAL")]
+  interval: CyclePeriod
+}
+
+/// Creates the database schema if needed. Each run adds a row to `sessions`
+/// with the device status and options it started with; `readings` has one row
+/// per reading, with the same columns as CSV output.
+pub(crate) fn create_schema(db: &rusqlite::Connection) -> Result<()> {
+  let columns: Vec<String> = combined_csv_header(UnitSystem::Metric).iter()
+    .map(|name| match TEXT_COLUMNS.contains(&name.as_str()) {
+      true => format!("{} TEXT", name),
+      false => format!("{} REAL", name),
+    })
+    .collect();
+
+  db.execute_batch(&format!(
+    "CREATE TABLE IF NOT EXISTS sessions (
+      id INTEGER PRIMARY KEY,
+      started TEXT NOT NULL,
+      tool_version TEXT NOT NULL,
+      cycle_period_s INTEGER NOT NULL,
+      status TEXT NOT NULL,
+      options TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS readings (
+      id INTEGER PRIMARY KEY,
+      session_id INTEGER NOT NULL REFERENCES sessions(id),
+      {}
+    );
+
+    CREATE INDEX IF NOT EXISTS readings_timestamp ON readings(timestamp);",
+    columns.join(",\n      ")
+  ))?;
+
+  Ok(())
+}
+
+/// Adds a row to `sessions` and returns its id.
+pub(crate) fn insert_session(db: &rusqlite::Connection, cycle_period_s: i64, status: &str, options: &str) -> Result<i64> {
+  use rusqlite::params;
+
+  db.execute(
+    "INSERT INTO sessions (started, tool_version, cycle_period_s, status, options)
+      VALUES (?1, ?2, ?3, ?4, ?5)",
+    params![
+      Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+      env!("CARGO_PKG_VERSION"),
+      cycle_period_s,
+      status,
+      options,
+    ],
+  )?;
+
+  Ok(db.last_insert_rowid())
+}
+
+/// Prepares an insert into `readings`; see [`insert_reading()`].
+pub(crate) fn prepare_insert(db: &rusqlite::Connection) -> Result<rusqlite::Statement<'_>> {
+  let header = combined_csv_header(UnitSystem::Metric);
+
+  Ok(db.prepare(&format!(
+    "INSERT INTO readings (session_id, {}) VALUES (?1, {})",
+    header.join(", "),
+    (2..=header.len() + 1).map(|i| format!("?{}", i)).collect::<Vec<_>>().join(", "),
+  ))?)
+}
+
+pub(crate) fn insert_reading(
+  insert: &mut rusqlite::Statement<'_>,
+  session_id: i64,
+  reading: &UnitValue<UnitCombinedData>,
+) -> Result<()> {
+  // numeric text is stored as numbers per the columns' affinity; empty
+  // (unavailable) values are stored as NULL
+  let values = combined_fields(reading, UnitSystem::Metric).into_iter()
+    .map(|field| Some(field).filter(|f| !f.is_empty()));
+
+  insert.execute(std::iter::once(Some(session_id.to_string())).chain(values))?;
+  Ok(())
+}
+
+pub(crate) fn record(opts: &Options, action: &RecordAction, mut metriful: Metriful) -> Result<()> {
+  use rusqlite::{Connection, NO_PARAMS};
+
+  let db = Connection::open(&action.database)
+    .with_context(|| format!("could not open database {:?}", action.database))?;
+
+  // WAL keeps the database consistent if we're killed mid-write, and lets
+  // other processes read while recording
+  db.query_row("PRAGMA journal_mode = WAL", NO_PARAMS, |_| Ok(()))?;
+  create_schema(&db)?;
+
+  let status = metriful.read_status()?;
+  let session_id = insert_session(
+    &db,
+    action.interval.to_duration().as_secs() as i64,
+    &serde_json::to_string(&status)?,
+    &serde_json::to_string(&opts.sensor)?,
+  )?;
+
+  info!("recording to {:?} (session {})", action.database, session_id);
+  let mut insert = prepare_insert(&db)?;
+
+  let policy = ReadPolicy {
+    max_retries: 3,
+    on_error: OnError::Continue,
+    ..ReadPolicy::default()
+  };
+
+  let iter = metriful
+    .cycle_read_iter_timeout(METRIC_COMBINED_ALL, action.interval, opts.sensor.timeout)
+    .with_policy(policy)
+    .with_cancel(shutdown_token());
+
+  for reading in iter {
+    let reading = match reading {
+      Ok(reading) => reading,
+      Err(e) => {
+        warn!("read failed, continuing: {}", e);
+        continue;
+      },
+    };
+
+    insert_reading(&mut insert, session_id, &reading)?;
+  }
+
+  info!("shutting down");
+  Ok(())
+}
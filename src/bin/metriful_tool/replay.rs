@@ -0,0 +1,171 @@
+//! The `replay` subcommand.
+
+use super::*;
+use super::check::{CheckState, Thresholds};
+use super::csv::{combined_csv_header, combined_csv_record, csv_record};
+use super::influx::InfluxFormatter;
+use super::units::{combined_as_set, print_combined_in_units, set_in_units};
+
+/// Parses a replay speed relative to the original timing, e.g. `10x` or
+/// `0.5`; `max` (or 0) replays without delay.
+fn try_speed_from_str(s: &str) -> Result<f64> {
+  let s = s.trim();
+  if s.eq_ignore_ascii_case("max") {
+    return Ok(0.0);
+  }
+
+  let speed: f64 = s.trim_end_matches(&['x', 'X'][..])
+    .parse()
+    .with_context(|| format!("invalid speed {:?}, expected e.g. 10x or max", s))?;
+
+  if !speed.is_finite() || speed < 0.0 {
+    return Err(eyre!("speed must be a positive number"));
+  }
+
+  Ok(speed)
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct ReplayAction {
+  /// NDJSON file of combined readings, as written by `log --format ndjson` or
+  /// `cycle-watch -o json`; `-` reads from stdin
+  #[structopt(parse(from_os_str))]
+  file: PathBuf,
+
+  /// Playback speed relative to the recorded timing, e.g. 10x; `max` replays
+  /// without delay
+  #[structopt(long, default_value = "1x", parse(try_from_str = try_speed_from_str))]
+  speed: f64,
+
+  /// Data output format, one of: plain, json, csv, influx
+  #[structopt(long, short, default_value = "plain")]
+  output: OutputMode,
+
+  /// Thresholds to check each reading against, as with `check`; changes in
+  /// state are logged
+  #[structopt(flatten)]
+  thresholds: Thresholds,
+}
+
+/// Returns true if a line of `-o json` output is an error object, written
+/// when a watch ended due to an error.
+fn is_error_line(line: &str) -> bool {
+  serde_json::from_str::<serde_json::Value>(line)
+    .map(|value| value.get("error").is_some())
+    .unwrap_or(false)
+}
+
+/// Combined readings from an NDJSON recording, as written by
+/// `log --format ndjson` or `cycle-watch -o json`. Recorded errors are
+/// skipped.
+pub(crate) struct Recording {
+  path: PathBuf,
+  lines: io::Lines<Box<dyn BufRead>>,
+  line: usize,
+}
+
+impl Recording {
+  /// Opens a recording; `-` reads from stdin.
+  pub(crate) fn open(path: &Path) -> Result<Recording> {
+    let input: Box<dyn BufRead> = if path == Path::new("-") {
+      Box::new(BufReader::new(io::stdin()))
+    } else {
+      let file = File::open(path)
+        .with_context(|| format!("could not open {:?}", path))?;
+
+      Box::new(BufReader::new(file))
+    };
+
+    Ok(Recording { path: path.to_path_buf(), lines: input.lines(), line: 0 })
+  }
+}
+
+impl Iterator for Recording {
+  type Item = Result<UnitValue<UnitCombinedData>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      let line = match self.lines.next()? {
+        Ok(line) => line,
+        Err(e) => return Some(Err(e).with_context(|| format!("could not read {:?}", self.path))),
+      };
+
+      self.line += 1;
+      if line.trim().is_empty() {
+        continue;
+      }
+
+      match serde_json::from_str(&line) {
+        Ok(value) => return Some(Ok(value)),
+        Err(_) if is_error_line(&line) => {
+          warn!("skipping recorded error on line {}: {}", self.line, line);
+        },
+        Err(e) => {
+          let (line, path) = (self.line, &self.path);
+          return Some(Err(e).with_context(|| format!("invalid reading on line {} of {:?}", line, path)));
+        },
+      }
+    }
+  }
+}
+
+pub(crate) fn replay(opts: &Options, action: &ReplayAction) -> Result<()> {
+  let recording = Recording::open(&action.file)?;
+
+  if let OutputMode::CSV = action.output {
+    println!("{}", csv_record(&combined_csv_header(opts.units)));
+  }
+
+  let influx = InfluxFormatter::new(opts);
+  let mut previous: Option<DateTime<Utc>> = None;
+  let mut state = CheckState::Ok;
+  let mut readings = 0;
+  let mut alerts = 0;
+
+  for value in recording {
+    let value = value?;
+
+    // keep the recorded gaps between readings; out of order readings are
+    // replayed immediately
+    if let (Some(previous), true) = (previous, action.speed > 0.0) {
+      if let Ok(gap) = value.time.signed_duration_since(previous).to_std() {
+        thread::sleep(gap.div_f64(action.speed));
+      }
+    }
+
+    previous = Some(value.time);
+
+    match &action.output {
+      OutputMode::Plain if opts.units != UnitSystem::Metric => print_combined_in_units(&value, opts.units),
+      OutputMode::Plain => {
+        println!("{}", value);
+        println!("---");
+      },
+      OutputMode::JSON => println!("{}", serde_json::to_string(&value)?),
+      OutputMode::CSV => println!("{}", combined_csv_record(&value, opts.units)),
+      OutputMode::Influx => println!("{}", influx.combined_line(&value)),
+    }
+
+    readings += 1;
+    if action.thresholds.is_empty() {
+      continue;
+    }
+
+    let result = action.thresholds.check(&set_in_units(&combined_as_set(&value), opts.units));
+    if result.state != state {
+      let time = value.time.to_rfc3339_opts(SecondsFormat::Secs, true);
+      match result.state {
+        CheckState::Ok => info!("{}: {}", time, result),
+        _ => {
+          warn!("{}: {}", time, result);
+          alerts += 1;
+        },
+      }
+
+      state = result.state;
+    }
+  }
+
+  info!("replayed {} readings, {} alerts", readings, alerts);
+  Ok(())
+}
@@ -0,0 +1,149 @@
+//! The `scan` subcommand.
+
+use super::*;
+use super::csv::csv_record;
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct ScanAction {
+  /// Probes every i2c bus in /dev rather than only the one selected with
+  /// `--device`
+  #[structopt(long)]
+  all_buses: bool,
+
+  /// Data output format, one of: plain, json, csv
+  #[structopt(long, short, default_value = "plain")]
+  output: OutputMode,
+}
+
+/// i2c addresses the MS430 may use, depending on its solder bridge.
+pub(crate) const SCAN_ADDRESSES: &[u16] = &[0x71, 0x70];
+
+/// The result of probing a single i2c address.
+#[derive(Debug, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub(crate) enum Probe {
+  /// The device's status registers decoded as an MS430's
+  Found { status: DeviceStatus },
+
+  /// A device responded, but its registers aren't an MS430's
+  Unrecognized { error: MetrifulError },
+
+  /// Nothing responded, or the bus couldn't be opened
+  NoResponse { error: MetrifulError },
+}
+
+impl Probe {
+  /// Probes an address by reading the device status; only the MS430 should
+  /// return valid values for every status register.
+  pub(crate) fn run(device: &Path, address: u16) -> Probe {
+    let result = LinuxTransport::open(device, address, TransferMode::Smbus)
+      .and_then(|mut transport| DeviceStatus::read(&mut transport));
+
+    match result {
+      Ok(status) => Probe::Found { status },
+      Err(error) => match error.kind() {
+        ErrorKind::Protocol => Probe::Unrecognized { error },
+        _ => Probe::NoResponse { error },
+      },
+    }
+  }
+
+  fn name(&self) -> &'static str {
+    match self {
+      Probe::Found { .. } => "found",
+      Probe::Unrecognized { .. } => "unrecognized",
+      Probe::NoResponse { .. } => "no_response",
+    }
+  }
+
+  fn detail(&self) -> String {
+    match self {
+      Probe::Found { status } => format!("{:?}, particle sensor {:?}", status.mode, status.particle_sensor),
+      Probe::Unrecognized { error } | Probe::NoResponse { error } => error.to_string(),
+    }
+  }
+}
+
+/// Lists i2c bus devices in /dev, in bus number order.
+pub(crate) fn i2c_buses() -> Result<Vec<PathBuf>> {
+  let mut buses: Vec<(u32, PathBuf)> = fs::read_dir("/dev")?
+    .filter_map(|entry| entry.ok())
+    .filter_map(|entry| {
+      let name = entry.file_name();
+      let bus = name.to_str()?.strip_prefix("i2c-")?.parse().ok()?;
+
+      Some((bus, entry.path()))
+    })
+    .collect();
+
+  buses.sort();
+  Ok(buses.into_iter().map(|(_, path)| path).collect())
+}
+
+pub(crate) fn scan(opts: &Options, action: &ScanAction) -> Result<()> {
+  if let OutputMode::Influx = action.output {
+    return Err(influx_unsupported());
+  }
+
+  let buses = if action.all_buses {
+    i2c_buses()?
+  } else {
+    vec![opts.sensor.i2c_device.clone()]
+  };
+
+  if let OutputMode::CSV = action.output {
+    println!("{}", csv_record(&["device", "address", "result", "detail"]));
+  }
+
+  let mut found = Vec::new();
+  for device in &buses {
+    for &address in SCAN_ADDRESSES {
+      let probe = Probe::run(device, address);
+
+      match action.output {
+        OutputMode::Plain => println!(
+          "{} 0x{:02x}: {} ({})",
+          device.display(), address, probe.name().replace('_', " "), probe.detail()
+        ),
+        OutputMode::JSON => println!("{}", json!({
+          "device": device,
+          "address": format!("0x{:02x}", address),
+          "probe": &probe,
+        })),
+        OutputMode::CSV => println!("{}", csv_record(&[
+          device.display().to_string(),
+          format!("0x{:02x}", address),
+          probe.name().to_string(),
+          probe.detail(),
+        ])),
+        OutputMode::Influx => unreachable!("rejected above"),
+      }
+
+      if let Probe::Found { .. } = probe {
+        found.push((device, address));
+      }
+    }
+  }
+
+  let (device, address) = match found.as_slice() {
+    [] => return Err(eyre!(
+      "no sensor found; check wiring, or try --all-buses to probe other i2c buses"
+    )),
+    [first, ..] => *first,
+  };
+
+  if let OutputMode::Plain = action.output {
+    if found.len() > 1 {
+      println!("found {} sensors; pass --device and --i2c-address to select one", found.len());
+    } else if *device == opts.sensor.i2c_device && address == opts.sensor.i2c_address {
+      println!("sensor found at the configured device and address");
+    } else {
+      println!(
+        "sensor found; use: --device {} --i2c-address 0x{:02x}",
+        device.display(), address
+      );
+    }
+  }
+
+  Ok(())
+}
@@ -0,0 +1,232 @@
+//! The `self-test` subcommand.
+
+use super::*;
+use super::csv::csv_record;
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct SelfTestAction {
+  /// Data output format, one of: plain, json, csv
+  #[structopt(long, short, default_value = "plain")]
+  output: OutputMode,
+}
+
+/// Sensor timeout for `self-test` when none is configured.
+const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time READY is deasserted during an on-demand measurement, per the
+/// datasheet.
+const MEASUREMENT_TIME: Duration = Duration::from_millis(550);
+
+/// Allowance over datasheet timings for bus and READY polling overhead.
+const SELF_TEST_SLACK: Duration = Duration::from_millis(100);
+
+/// A single `self-test` check.
+#[derive(Debug, Serialize)]
+struct SelfTestCheck {
+  name: String,
+  passed: bool,
+
+  /// Measured duration, for timing checks
+  elapsed_ms: Option<f64>,
+
+  /// Expected duration per the datasheet, for timing checks
+  expected_ms: Option<f64>,
+
+  detail: String,
+}
+
+/// Checks recorded by `self-test`, in the order run.
+#[derive(Default)]
+struct SelfTestReport {
+  checks: Vec<SelfTestCheck>,
+}
+
+impl SelfTestReport {
+  fn check(&mut self, name: impl Into<String>, passed: bool, detail: impl Into<String>) {
+    self.checks.push(SelfTestCheck {
+      name: name.into(),
+      passed,
+      elapsed_ms: None,
+      expected_ms: None,
+      detail: detail.into(),
+    });
+  }
+
+  /// Records a timing check, which passes if `elapsed` is within `expected`
+  /// plus some slack (or, if `exact`, differs from it by at most the slack).
+  fn timed(&mut self, name: impl Into<String>, elapsed: Duration, expected: Duration, exact: bool) {
+    let slack = expected / 10 + SELF_TEST_SLACK;
+    let passed = if exact {
+      elapsed + slack >= expected && elapsed <= expected + slack
+    } else {
+      elapsed <= expected + slack
+    };
+
+    self.checks.push(SelfTestCheck {
+      name: name.into(),
+      passed,
+      elapsed_ms: Some(elapsed.as_secs_f64() * 1000.0),
+      expected_ms: Some(expected.as_secs_f64() * 1000.0),
+      detail: format!(
+        "took {:?}, expected {}{:?}",
+        elapsed, if exact { "" } else { "at most " }, expected
+      ),
+    });
+  }
+
+  fn failures(&self) -> usize {
+    self.checks.iter().filter(|c| !c.passed).count()
+  }
+}
+
+/// Runs the `self-test` checks, stopping at the first error.
+fn self_test_checks(metriful: &mut Metriful, timeout: Option<Duration>, report: &mut SelfTestReport) -> Result<()> {
+  let start = Instant::now();
+  let status = metriful.reset_timeout(timeout)?;
+  report.timed("reset", start.elapsed(), MODE_CHANGE_SETTLE, false);
+  report.check(
+    "standby after reset",
+    status.mode == OperationalMode::Standby,
+    format!("mode is {:?}", status.mode),
+  );
+
+  // a reset leaves the device ready, so rate limits are the only wait
+  let start = loop {
+    let attempt = Instant::now();
+    match metriful.execute_measurement() {
+      Err(MetrifulError::RateLimited { retry_after }) => thread::sleep(retry_after),
+      result => {
+        result?;
+        break attempt;
+      },
+    }
+  };
+
+  let asserted = metriful.is_ready()?;
+  report.check("ready deasserted while measuring", !asserted, if asserted {
+    "READY stayed asserted; check the READY wiring, --gpio-ready, and --ready-polarity"
+  } else {
+    "READY deasserted"
+  });
+
+  metriful.wait_for_ready_timeout(timeout)?;
+  report.timed("measurement", start.elapsed(), MEASUREMENT_TIME, !asserted);
+
+  let temperature = metriful.read(METRIC_TEMPERATURE)?;
+  report.check(
+    "temperature in range",
+    (-40.0..=85.0).contains(&temperature.value),
+    format!("read {}", temperature),
+  );
+
+  for period in CyclePeriod::ALL {
+    let name = format!("{}s cycle", period.to_duration().as_secs());
+
+    // per the datasheet, the first cycle takes longer to start for longer
+    // periods
+    let expected = match period {
+      CyclePeriod::Period0 => Duration::from_millis(600),
+      _ => Duration::from_millis(2600),
+    };
+
+    let start = Instant::now();
+    let status = metriful.set_mode_timeout(OperationalMode::Cycle(*period), timeout.map(|t| t + expected))?;
+    report.timed(format!("enter {}", name), start.elapsed(), expected, false);
+    report.check(
+      format!("mode after entering {}", name),
+      status.mode == OperationalMode::Cycle(*period),
+      format!("mode is {:?}", status.mode),
+    );
+
+    // the time between cycles is only practical to check for the shortest
+    if let CyclePeriod::Period0 = period {
+      let timeout = timeout.map(|t| t + period.to_duration());
+      metriful.wait_for_not_ready_timeout(timeout)?;
+      let start = Instant::now();
+
+      metriful.wait_for_ready_timeout(timeout)?;
+      metriful.wait_for_not_ready_timeout(timeout)?;
+      report.timed(format!("{} period", name), start.elapsed(), period.to_duration(), true);
+
+      // mode changes wait for the measurement in progress
+      metriful.wait_for_ready_timeout(timeout)?;
+    }
+
+    let start = Instant::now();
+    let status = metriful.set_mode_timeout(OperationalMode::Standby, timeout)?;
+    report.timed(format!("exit {}", name), start.elapsed(), MODE_CHANGE_SETTLE, false);
+    report.check(
+      format!("standby after exiting {}", name),
+      status.mode == OperationalMode::Standby,
+      format!("mode is {:?}", status.mode),
+    );
+  }
+
+  Ok(())
+}
+
+pub(crate) fn self_test(opts: &Options, action: &SelfTestAction) -> Result<()> {
+  if let OutputMode::Influx = action.output {
+    return Err(influx_unsupported());
+  }
+
+  let sensor = opts.sensor.clone().timeout(opts.sensor.timeout.or(Some(SELF_TEST_TIMEOUT)));
+  let mut report = SelfTestReport::default();
+
+  let start = Instant::now();
+  let result = match sensor.open() {
+    Ok(mut metriful) => {
+      report.check("ready", true, format!("READY asserted after {:?}", start.elapsed()));
+
+      // return to standby if a check fails partway through a cycle
+      metriful.set_shutdown_options(ShutdownOptions {
+        on_drop: true,
+        ..ShutdownOptions::default()
+      });
+
+      self_test_checks(&mut metriful, sensor.timeout, &mut report)
+    },
+    Err(e) => Err(eyre!("sensor did not become ready (check the wiring and --gpio-ready): {}", e)),
+  };
+
+  if let Err(e) = &result {
+    report.check("error", false, e.to_string());
+  }
+
+  match action.output {
+    OutputMode::Plain => {
+      println!("ready line: {}", ready_line_description(opts));
+      for check in &report.checks {
+        println!(
+          "{} {:<32} {}",
+          if check.passed { "PASS" } else { "FAIL" }, check.name, check.detail
+        );
+      }
+    },
+    OutputMode::JSON => println!("{}", json!({
+      "ready_line": ready_line_description(opts),
+      "passed": report.failures() == 0,
+      "checks": &report.checks,
+    })),
+    OutputMode::CSV => {
+      println!("{}", csv_record(&["name", "passed", "elapsed_ms", "expected_ms", "detail"]));
+      for check in &report.checks {
+        let ms = |ms: Option<f64>| ms.map(|ms| ms.to_string()).unwrap_or_default();
+
+        println!("{}", csv_record(&[
+          check.name.clone(),
+          check.passed.to_string(),
+          ms(check.elapsed_ms),
+          ms(check.expected_ms),
+          check.detail.clone(),
+        ]));
+      }
+    },
+    OutputMode::Influx => unreachable!("rejected above"),
+  }
+
+  match report.failures() {
+    0 => Ok(()),
+    n => Err(eyre!("self-test failed: {} of {} checks failed", n, report.checks.len())),
+  }
+}
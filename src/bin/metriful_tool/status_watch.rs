@@ -0,0 +1,93 @@
+//! The `status-watch` subcommand.
+
+use super::*;
+use super::csv::{STATUS_COLUMNS, csv_record, status_fields};
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct StatusWatchAction {
+  /// Time between status reads, e.g. `1s` or `5m`
+  #[structopt(
+    long, short,
+    default_value = "1s",
+    parse(try_from_str = try_duration_from_str)
+  )]
+  interval: Duration,
+
+  /// Data output format, one of: plain, json, csv
+  #[structopt(long, short, default_value = "plain")]
+  output: OutputMode,
+}
+
+pub(crate) fn status_watch(_opts: &Options, action: &StatusWatchAction, mut metriful: Metriful) -> Result<()> {
+  if let OutputMode::Influx = action.output {
+    return Err(influx_unsupported());
+  }
+
+  if action.interval == Duration::from_secs(0) {
+    return Err(eyre!("interval must be at least 1 second"));
+  }
+
+  if let OutputMode::CSV = action.output {
+    let mut header = vec!["timestamp"];
+    header.extend(STATUS_COLUMNS);
+    println!("{}", csv_record(&header));
+  }
+
+  let mut previous: Option<Vec<String>> = None;
+  loop {
+    // another process may reconfigure an enabled interrupt, so cached
+    // interrupt settings can't be trusted
+    let status = match metriful.read_status_uncached() {
+      Ok(status) => status,
+      Err(e) => {
+        warn!("could not read device status: {}", e);
+        thread::sleep(action.interval);
+        continue;
+      },
+    };
+
+    let fields = status_fields(&status);
+    let changes: Vec<(&str, Option<&String>, &String)> = STATUS_COLUMNS.iter()
+      .enumerate()
+      .map(|(i, column)| (*column, previous.as_ref().map(|p| &p[i]), &fields[i]))
+      .filter(|(_, previous, current)| *previous != Some(*current))
+      .collect();
+
+    if !changes.is_empty() {
+      let timestamp = chrono::Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+      let shown = |value: &str| if value.is_empty() { "none".to_string() } else { value.to_string() };
+
+      match action.output {
+        OutputMode::Plain => {
+          let changes: Vec<String> = changes.iter()
+            .map(|(column, previous, current)| match previous {
+              Some(previous) => format!("{} {} -> {}", column, shown(previous), shown(current)),
+              None => format!("{} {}", column, shown(current)),
+            })
+            .collect();
+
+          println!("{} {}", timestamp, changes.join(", "));
+        },
+        OutputMode::JSON => {
+          let changes: serde_json::Map<String, serde_json::Value> = changes.iter()
+            .filter(|(_, previous, _)| previous.is_some())
+            .map(|(column, previous, current)| {
+              (column.to_string(), json!({ "previous": previous, "current": current }))
+            })
+            .collect();
+
+          println!("{}", json!({ "timestamp": timestamp, "status": status, "changes": changes }));
+        },
+        OutputMode::CSV => {
+          let mut record = vec![timestamp];
+          record.extend(fields.iter().cloned());
+          println!("{}", csv_record(&record));
+        },
+        OutputMode::Influx => unreachable!("rejected above"),
+      }
+    }
+
+    previous = Some(fields);
+    thread::sleep(action.interval);
+  }
+}
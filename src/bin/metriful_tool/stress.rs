@@ -0,0 +1,275 @@
+//! The `stress` subcommand.
+
+use super::*;
+use super::csv::csv_record;
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct StressAction {
+  /// How long to run, e.g. `90s`, `10m` or `24h`; SIGTERM or Ctrl-C stops
+  /// early, still printing the summary
+  #[structopt(
+    long,
+    default_value = "10m",
+    parse(try_from_str = try_duration_from_str)
+  )]
+  duration: Duration,
+
+  /// Number of times each data register is read between mode changes
+  #[structopt(long, default_value = "10")]
+  reads_per_mode: usize,
+
+  /// Data output format, one of: plain, json, csv
+  #[structopt(long, short, default_value = "plain")]
+  output: OutputMode,
+}
+
+/// Sensor timeout for `stress` when none is configured, so a missing READY
+/// signal is recorded as an error rather than hanging the test.
+const STRESS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay after a failed operation in `stress`, so a disconnected sensor
+/// doesn't flood the output.
+const STRESS_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Data registers read by `stress`. All are valid in both modes, so no
+/// measurement is needed between mode changes.
+const STRESS_REGISTERS: &[Register] = &[
+  Register::AirData, Register::AirQualityData, Register::LightData, Register::SoundData,
+];
+
+/// A failed operation during `stress`.
+#[derive(Debug, Serialize)]
+struct StressError {
+  timestamp: String,
+
+  /// Time since the start of the test
+  elapsed_s: f64,
+
+  operation: String,
+
+  /// The OS error number, for bus errors
+  errno: Option<i32>,
+
+  /// Time taken by the failed operation
+  duration_ms: f64,
+
+  error: String,
+}
+
+/// Totals over a `stress` run.
+#[derive(Debug, Default, Serialize)]
+struct StressSummary {
+  elapsed_s: f64,
+  operations: u64,
+  errors: u64,
+
+  /// Errors per operation
+  error_rate: f64,
+
+  /// Mean time between errors, if there were any
+  mtbf_s: Option<f64>,
+
+  /// Longest time for READY to assert after a mode change
+  worst_ready_latency_ms: Option<f64>,
+
+  /// Error counts keyed by errno, or `none` for errors without one
+  errors_by_errno: BTreeMap<String, u64>,
+
+  errors_by_operation: BTreeMap<String, u64>,
+}
+
+impl StressSummary {
+  fn lines(&self) -> Vec<String> {
+    let mut lines = vec![
+      format!("elapsed: {:.0}s", self.elapsed_s),
+      format!("operations: {}", self.operations),
+      format!("errors: {} (rate {:.2e})", self.errors, self.error_rate),
+      match self.mtbf_s {
+        Some(mtbf) => format!("mean time between errors: {:.1}s", mtbf),
+        None => "mean time between errors: no errors".to_string(),
+      },
+      match self.worst_ready_latency_ms {
+        Some(latency) => format!("worst READY latency: {:.1}ms", latency),
+        None => "worst READY latency: no mode changes".to_string(),
+      },
+    ];
+
+    for (errno, count) in &self.errors_by_errno {
+      lines.push(format!("errno {}: {} errors", errno, count));
+    }
+
+    for (operation, count) in &self.errors_by_operation {
+      lines.push(format!("{}: {} errors", operation, count));
+    }
+
+    lines
+  }
+}
+
+/// Runs and records operations for `stress`.
+struct Stress {
+  output: OutputMode,
+  start: Instant,
+  summary: StressSummary,
+  worst_ready: Option<Duration>,
+}
+
+impl Stress {
+  fn new(output: OutputMode) -> Stress {
+    Stress {
+      output,
+      start: Instant::now(),
+      summary: StressSummary::default(),
+      worst_ready: None,
+    }
+  }
+
+  /// Runs an operation, printing and counting any error. Rate limited
+  /// attempts are retried and not counted. If `ready` is set, the operation's
+  /// duration counts towards the worst READY latency.
+  fn run<T>(
+    &mut self,
+    operation: &str,
+    ready: bool,
+    mut f: impl FnMut() -> std::result::Result<T, MetrifulError>,
+  ) -> Option<T> {
+    let (attempt, result) = loop {
+      let attempt = Instant::now();
+      match f() {
+        Err(MetrifulError::RateLimited { retry_after }) => thread::sleep(retry_after),
+        result => break (attempt, result),
+      }
+    };
+
+    let duration = attempt.elapsed();
+    self.summary.operations += 1;
+
+    let e = match result {
+      Ok(value) => {
+        if ready {
+          self.worst_ready = self.worst_ready.max(Some(duration));
+        }
+
+        return Some(value);
+      },
+      Err(e) => e,
+    };
+
+    let error = StressError {
+      timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+      elapsed_s: self.start.elapsed().as_secs_f64(),
+      operation: operation.to_string(),
+      errno: e.raw_os_error(),
+      duration_ms: duration.as_secs_f64() * 1000.0,
+      error: e.to_string(),
+    };
+
+    let errno = error.errno.map(|n| n.to_string()).unwrap_or_else(|| "none".to_string());
+    *self.summary.errors_by_errno.entry(errno).or_default() += 1;
+    *self.summary.errors_by_operation.entry(error.operation.clone()).or_default() += 1;
+    self.summary.errors += 1;
+
+    match self.output {
+      OutputMode::Plain => println!(
+        "{} +{:.1}s {} failed after {:.1}ms ({}): {}",
+        error.timestamp, error.elapsed_s, error.operation, error.duration_ms,
+        error.errno.map(|n| format!("errno {}", n)).unwrap_or_else(|| "no errno".to_string()),
+        error.error
+      ),
+      OutputMode::JSON => println!("{}", json!({ "error": error })),
+      OutputMode::CSV => println!("{}", csv_record(&[
+        error.timestamp.clone(),
+        error.elapsed_s.to_string(),
+        error.operation.clone(),
+        error.errno.map(|n| n.to_string()).unwrap_or_default(),
+        error.duration_ms.to_string(),
+        error.error.clone(),
+      ])),
+      OutputMode::Influx => unreachable!("rejected above"),
+    }
+
+    thread::sleep(STRESS_ERROR_BACKOFF);
+    None
+  }
+
+  fn finish(mut self) -> StressSummary {
+    let elapsed = self.start.elapsed().as_secs_f64();
+
+    self.summary.elapsed_s = elapsed;
+    self.summary.error_rate = match self.summary.operations {
+      0 => 0.0,
+      n => self.summary.errors as f64 / n as f64,
+    };
+
+    self.summary.mtbf_s = match self.summary.errors {
+      0 => None,
+      n => Some(elapsed / n as f64),
+    };
+
+    self.summary.worst_ready_latency_ms = self.worst_ready.map(|d| d.as_secs_f64() * 1000.0);
+    self.summary
+  }
+}
+
+pub(crate) fn stress(opts: &Options, action: &StressAction, mut metriful: Metriful) -> Result<()> {
+  if let OutputMode::Influx = action.output {
+    return Err(influx_unsupported());
+  }
+
+  let timeout = opts.sensor.timeout.or(Some(STRESS_TIMEOUT));
+  let shutdown = shutdown_token();
+
+  if let OutputMode::CSV = action.output {
+    println!("{}", csv_record(&["timestamp", "elapsed_s", "operation", "errno", "duration_ms", "error"]));
+  }
+
+  let mut stress = Stress::new(action.output);
+  while stress.start.elapsed() < action.duration && !shutdown.is_cancelled() {
+    // after an error the mode is uncertain, so always read it back
+    let mode = match stress.run("read_status", false, || metriful.read_status()) {
+      Some(status) => status.mode,
+      None => continue,
+    };
+
+    let (operation, next) = match mode {
+      OperationalMode::Standby => ("enter_cycle", OperationalMode::Cycle(CyclePeriod::Period0)),
+      OperationalMode::Cycle(_) => ("enter_standby", OperationalMode::Standby),
+    };
+
+    // the first cycle only starts once READY asserts
+    let mode_timeout = timeout.map(|t| t + next.ready_duration());
+    if stress.run(operation, true, || metriful.set_mode_timeout(next, mode_timeout)).is_none() {
+      continue;
+    }
+
+    for _ in 0..action.reads_per_mode {
+      for register in STRESS_REGISTERS {
+        stress.run(&format!("read {:?}", register), false, || metriful.read_register(*register));
+      }
+    }
+  }
+
+  let summary = stress.finish();
+  match action.output {
+    OutputMode::Plain => {
+      println!("ready line: {}", ready_line_description(opts));
+      for line in summary.lines() {
+        println!("{}", line);
+      }
+    },
+    OutputMode::JSON => println!("{}", json!({
+      "ready_line": ready_line_description(opts),
+      "summary": summary,
+    })),
+
+    // the summary doesn't fit the error columns
+    OutputMode::CSV => {
+      for line in summary.lines() {
+        info!("{}", line);
+      }
+    },
+    OutputMode::Influx => unreachable!("rejected above"),
+  }
+
+  Ok(())
+}
@@ -0,0 +1,50 @@
+//! Unit conversion for printed readings.
+
+use super::*;
+
+/// Converts each reading of a set to the given units.
+pub(crate) fn set_in_units(reading: &MetricSetReading, units: UnitSystem) -> MetricSetReading {
+  MetricSetReading {
+    time: reading.time,
+    readings: reading.readings.iter().map(|r| r.to_unit_system(units)).collect(),
+  }
+}
+
+/// Splits a combined reading into readings of the individual metrics it
+/// contains, named by metric id as if read with `read_set`.
+pub(crate) fn combined_as_set(reading: &UnitValue<UnitCombinedData>) -> MetricSetReading {
+  let data = &reading.value;
+  let mut readings = vec![
+    DynReading::from_value("temperature", &data.air.value.temperature),
+    DynReading::from_value("pressure", &data.air.value.pressure),
+    DynReading::from_value("relative_humidity", &data.air.value.humidity),
+    DynReading::from_value("gas_resistance", &data.air.value.gas_sensor_resistance),
+    DynReading::from_value("aqi", &data.air_quality.value.aqi),
+    DynReading::from_value("estimated_co2", &data.air_quality.value.estimated_co2),
+    DynReading::from_value("estimated_voc", &data.air_quality.value.estimated_voc),
+    DynReading::from_value("aqi_accuracy", &data.air_quality.value.aqi_accuracy),
+    DynReading::from_value("illuminance", &data.light.value.illuminance),
+    DynReading::from_value("white_light_level", &data.light.value.white_level),
+    DynReading::from_value("weighted_sound_level", &data.sound.value.weighted_spl),
+    DynReading::from_value("sound_level", &data.sound.value.spl_bands),
+    DynReading::from_value("peak_sound_amplitude", &data.sound.value.peak_amplitude),
+    DynReading::from_value("sound_measurement_stability", &data.sound.value.measurement_stability),
+  ];
+
+  if let Some(particle) = &data.particle {
+    readings.extend(vec![
+      DynReading::from_value("particle_sensor_duty_cycle", &particle.value.duty_cycle),
+      DynReading::from_value("particle_concentration", &particle.value.concentration),
+      DynReading::from_value("particle_data_valid", &particle.value.validity),
+    ]);
+  }
+
+  MetricSetReading { time: reading.time, readings }
+}
+
+/// Prints a combined reading converted to the given units, for plain output
+/// in units other than those the sensor reports.
+pub(crate) fn print_combined_in_units(reading: &UnitValue<UnitCombinedData>, units: UnitSystem) {
+  println!("{}", DynReading::from_value("combined_all", reading).to_unit_system(units));
+  println!("---");
+}
@@ -0,0 +1,386 @@
+//! The `watch`, `cycle-watch`, and `cycle-watch-async` subcommands.
+
+use super::*;
+use super::csv::{combined_csv_header, combined_csv_record, csv_record};
+use super::influx::InfluxFormatter;
+use super::units::{print_combined_in_units, set_in_units};
+
+/// Limits on how long a watch runs; by default, watches run until stopped.
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct WatchLimits {
+  /// Exit after this many readings
+  #[structopt(long)]
+  count: Option<usize>,
+
+  /// Exit after this long, e.g. 90s, 10m or 1h
+  #[structopt(long, parse(try_from_str = try_duration_from_str))]
+  duration: Option<Duration>,
+}
+
+impl WatchLimits {
+  /// Returns true if a watch started at `start` that has printed `readings`
+  /// readings should exit.
+  pub(crate) fn reached(&self, readings: usize, start: Instant) -> bool {
+    self.count.map(|count| readings >= count).unwrap_or(false)
+      || self.duration.map(|duration| start.elapsed() >= duration).unwrap_or(false)
+  }
+
+  /// Returns a token cancelled once the duration limit (if any) elapses, so
+  /// cycle reads don't wait out a full cycle before exiting.
+  fn cancel_token(&self) -> CancelToken {
+    let token = CancelToken::new();
+
+    if let Some(duration) = self.duration {
+      let canceller = token.clone();
+      thread::spawn(move || {
+        thread::sleep(duration);
+        canceller.cancel();
+      });
+    }
+
+    token
+  }
+
+  /// Returns how long to sleep between readings taken every `interval`, so
+  /// as not to sleep past the duration limit.
+  pub(crate) fn sleep(&self, interval: Duration, start: Instant) -> Duration {
+    match self.duration {
+      Some(duration) => interval.min(duration.saturating_sub(start.elapsed())),
+      None => interval,
+    }
+  }
+}
+
+/// Selects the metrics a watch reads, rather than all of them.
+#[derive(Debug, Clone, StructOpt)]
+struct MetricSelection {
+  /// Comma-separated metrics to read, e.g. temperature,humidity,sound; see
+  /// `metriful-tool metrics` for names. By default, all metrics are read.
+  #[structopt(long = "metrics", alias = "metric", use_delimiter = true)]
+  metrics: Vec<DynamicMetric>,
+}
+
+impl MetricSelection {
+  /// Returns the selected metrics as a set, or None if all should be read.
+  fn to_set(&self) -> Option<MetricSet> {
+    if self.metrics.is_empty() {
+      return None;
+    }
+
+    Some(self.metrics.iter().fold(MetricSet::new(), |set, metric| set.with(*metric)))
+  }
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct WatchAction {
+  /// If set, writes incoming queries to stdout in the given format. Note that
+  /// log messages are always written to stderr. JSON messages are one JSON
+  /// object per line. One of: plain, json, csv, influx
+  #[structopt(long, short, default_value = "plain")]
+  output: OutputMode,
+
+  /// Time interval between measurements in seconds
+  #[structopt(
+    long, short,
+    default_value = "2",
+    parse(try_from_str = try_watch_interval_from_str)
+  )]
+  interval: Duration,
+
+  #[structopt(flatten)]
+  limits: WatchLimits,
+
+  #[structopt(flatten)]
+  selection: MetricSelection,
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct CycleWatchAction {
+  /// Data output format, one of: plain, json, csv, influx
+  #[structopt(long, short, default_value = "plain")]
+  output: OutputMode,
+
+  /// Cycle period, one of: 3s, 100s, 5m (or 0, 1, 2)
+  #[structopt(long, short, default_value = "3s", env = "METRIFUL_INTERVAL")]
+  interval: CyclePeriod,
+
+  #[structopt(flatten)]
+  limits: WatchLimits,
+
+  #[structopt(flatten)]
+  selection: MetricSelection,
+}
+
+/// Prints readings of a selected set of metrics, along with a CSV header
+/// before the first.
+struct SetPrinter {
+  output: OutputMode,
+  units: UnitSystem,
+  influx: InfluxFormatter,
+  header: bool,
+}
+
+impl SetPrinter {
+  fn new(opts: &Options, output: OutputMode) -> SetPrinter {
+    SetPrinter { output, units: opts.units, influx: InfluxFormatter::new(opts), header: false }
+  }
+
+  fn print(&mut self, reading: &MetricSetReading) -> Result<()> {
+    match self.output {
+      OutputMode::Plain => {
+        for r in &set_in_units(reading, self.units).readings {
+          match &r.value {
+            ReadingValue::Group(_) => println!("{}:\n{}", r.name, textwrap::indent(&r.to_string(), "  ")),
+            _ => println!("{}: {}", r.name, r),
+          }
+        }
+
+        println!("---");
+      },
+      OutputMode::JSON => println!("{}", serde_json::to_string(reading)?),
+      OutputMode::CSV => {
+        let mut columns = vec![(
+          "timestamp".to_string(),
+          reading.time.to_rfc3339_opts(SecondsFormat::Secs, true),
+        )];
+
+        for r in &set_in_units(reading, self.units).readings {
+          flatten_reading(r, &mut columns);
+        }
+
+        let (header, record): (Vec<_>, Vec<_>) = columns.into_iter().unzip();
+        if !self.header {
+          println!("{}", csv_record(&header));
+          self.header = true;
+        }
+
+        println!("{}", csv_record(&record));
+      },
+      OutputMode::Influx => {
+        if let Some(line) = self.influx.line(reading.time, &reading.readings) {
+          println!("{}", line);
+        }
+      },
+    }
+
+    Ok(())
+  }
+}
+
+/// Like `watch`, but reads only the given metrics.
+fn watch_set(opts: &Options, action: &WatchAction, set: MetricSet, mut metriful: Metriful) -> Result<()> {
+  metriful.set_mode_timeout(OperationalMode::Standby, opts.sensor.timeout)?;
+
+  let mut printer = SetPrinter::new(opts, action.output);
+  let start = Instant::now();
+  let mut readings = 0;
+
+  while !action.limits.reached(readings, start) {
+    let reading = metriful.execute_measurement()
+      .and_then(|()| metriful.wait_for_ready())
+      .and_then(|()| metriful.read_set(&set))
+      .map_err(|e| report_error(action.output, e))?;
+
+    printer.print(&reading)?;
+
+    readings += 1;
+    if action.limits.reached(readings, start) {
+      break;
+    }
+
+    thread::sleep(action.limits.sleep(action.interval, start));
+  }
+
+  Ok(())
+}
+
+/// Like `cycle-watch`, but reads only the given metrics.
+fn cycle_watch_set(opts: &Options, action: &CycleWatchAction, set: MetricSet, mut metriful: Metriful) -> Result<()> {
+  // waits span a full cycle on top of the usual timeout
+  let timeout = opts.sensor.timeout.map(|t| t + action.interval.to_duration());
+  let cancel = action.limits.cancel_token();
+
+  metriful.set_mode_timeout(OperationalMode::Cycle(action.interval), opts.sensor.timeout)?;
+
+  let mut printer = SetPrinter::new(opts, action.output);
+  let start = Instant::now();
+  let mut readings = 0;
+
+  while !action.limits.reached(readings, start) {
+    let result = metriful.wait_for_ready_cancellable(timeout, &cancel)
+      .and_then(|()| metriful.read_set(&set))
+      .and_then(|reading| {
+        // wait for the next cycle to start before waiting for it to finish
+        metriful.wait_for_not_ready_cancellable(timeout, &cancel)?;
+        Ok(reading)
+      });
+
+    let reading = match result {
+      Ok(reading) => reading,
+      Err(MetrifulError::Cancelled) => break,
+      Err(e) => return Err(report_error(action.output, e)),
+    };
+
+    printer.print(&reading)?;
+    readings += 1;
+  }
+
+  Ok(())
+}
+
+pub(crate) fn watch(opts: &Options, action: &WatchAction, mut metriful: Metriful) -> Result<()> {
+  if let Some(set) = action.selection.to_set() {
+    return watch_set(opts, action, set, metriful);
+  }
+
+  metriful.set_mode_timeout(OperationalMode::Standby, opts.sensor.timeout)?;
+
+  if let OutputMode::CSV = action.output {
+    println!("{}", csv_record(&combined_csv_header(opts.units)));
+  }
+
+  let influx = InfluxFormatter::new(opts);
+  let start = Instant::now();
+  let mut readings = 0;
+
+  while !action.limits.reached(readings, start) {
+    let result = metriful.execute_measurement()
+      .and_then(|()| metriful.wait_for_ready())
+      .and_then(|()| metriful.read(METRIC_COMBINED_ALL))
+      .map_err(|e| report_error(action.output, e))?;
+
+    match action.output {
+      OutputMode::Plain if opts.units != UnitSystem::Metric => print_combined_in_units(&result, opts.units),
+      OutputMode::Plain => {
+        println!(
+          "air data:\n{}",
+          textwrap::indent(&result.value.air.to_string(), "  ")
+        );
+
+        println!(
+          "light data:\n{}",
+          textwrap::indent(&result.value.light.to_string(), "  ")
+        );
+
+        println!(
+          "sound data:\n{}",
+          textwrap::indent(&result.value.sound.to_string(), "  ")
+        );
+
+        match &result.value.particle {
+          Some(particle) => println!(
+            "particle data: \n{}",
+            textwrap::indent(&particle.to_string(), "  ")
+          ),
+          None => println!("particle data: not available"),
+        }
+
+        println!("---");
+      },
+      OutputMode::JSON => println!("{}", serde_json::to_string(&result)?),
+      OutputMode::CSV => println!("{}", combined_csv_record(&result, opts.units)),
+      OutputMode::Influx => println!("{}", influx.combined_line(&result)),
+    }
+
+    readings += 1;
+    if action.limits.reached(readings, start) {
+      break;
+    }
+
+    thread::sleep(action.limits.sleep(action.interval, start));
+  }
+
+  Ok(())
+}
+
+pub(crate) fn cycle_watch(opts: &Options, action: &CycleWatchAction, mut metriful: Metriful) -> Result<()> {
+  if let Some(set) = action.selection.to_set() {
+    return cycle_watch_set(opts, action, set, metriful);
+  }
+
+  let iter = metriful.cycle_read_iter_timeout(
+    METRIC_COMBINED_ALL,
+    action.interval,
+    opts.sensor.timeout
+  ).with_cancel(action.limits.cancel_token());
+
+  if let OutputMode::CSV = action.output {
+    println!("{}", csv_record(&combined_csv_header(opts.units)));
+  }
+
+  let influx = InfluxFormatter::new(opts);
+  for value in iter.take(action.limits.count.unwrap_or(usize::MAX)) {
+    let value = value.map_err(|e| report_error(action.output, e))?;
+
+    match &action.output {
+      OutputMode::Plain if opts.units != UnitSystem::Metric => print_combined_in_units(&value, opts.units),
+      OutputMode::Plain => {
+        println!("{}", value);
+        println!("---");
+      },
+      OutputMode::JSON => {
+        println!("{}", serde_json::to_string(&value)?)
+      }
+      OutputMode::CSV => println!("{}", combined_csv_record(&value, opts.units)),
+      OutputMode::Influx => println!("{}", influx.combined_line(&value)),
+    }
+  }
+
+  Ok(())
+}
+
+pub(crate) fn cycle_watch_async(opts: &Options, action: &CycleWatchAction, mut metriful: Metriful) -> Result<()> {
+  if !action.selection.metrics.is_empty() {
+    return Err(eyre!("--metrics is not supported by cycle-watch-async; use cycle-watch instead"));
+  }
+
+  let cancel = action.limits.cancel_token();
+  metriful.set_cancel_token(Some(cancel.clone()));
+
+  let (_cmd_tx, metric_rx, handle) = metriful.async_cycle_read_timeout(
+    METRIC_COMBINED_ALL,
+    action.interval,
+    opts.sensor.timeout
+  );
+
+  if let OutputMode::CSV = action.output {
+    println!("{}", csv_record(&combined_csv_header(opts.units)));
+  }
+
+  let influx = InfluxFormatter::new(opts);
+  let start = Instant::now();
+  let mut readings = 0;
+
+  while !action.limits.reached(readings, start) {
+    if let Ok(value) = metric_rx.try_recv() {
+      if let OutputMode::Plain = action.output {
+        println!();
+      }
+
+      readings += 1;
+
+      let value = value.map_err(|e| report_error(action.output, e))?;
+
+      match &action.output {
+        OutputMode::Plain if opts.units != UnitSystem::Metric => print_combined_in_units(&value, opts.units),
+        OutputMode::Plain => {
+          println!("{}", value);
+          println!("---");
+        },
+        OutputMode::JSON => {
+          println!("{}", serde_json::to_string(&value)?)
+        }
+        OutputMode::CSV => println!("{}", combined_csv_record(&value, opts.units)),
+        OutputMode::Influx => println!("{}", influx.combined_line(&value)),
+      }
+    }
+
+    thread::sleep(Duration::from_millis(100));
+  }
+
+  // stop the reader and wait for it, so the device is returned to standby
+  cancel.cancel();
+  handle.join().map_err(|_| eyre!("reader thread panicked"))?;
+
+  Ok(())
+}
@@ -0,0 +1,271 @@
+//! A builder for opening a device with construction-time configuration.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use log::trace;
+
+use crate::{Calibration, Metriful, MetrifulOptions, RateLimit, ShutdownOptions};
+use crate::cancel::CancelToken;
+use crate::error::*;
+use crate::plausibility::Plausibility;
+use crate::ready::{ReadyLine, ReadyPolarity, ReadyPolling};
+use crate::sched::ThreadScheduling;
+use crate::status::ParticleSensorMode;
+
+/// Opens a [`Metriful`] and applies initial configuration in one step.
+///
+/// Connection settings default to those of [`MetrifulOptions::default()`]. By
+/// default the device is neither reset nor reconfigured, matching
+/// [`Metriful::try_new_timeout()`].
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use metriful::{Metriful, ParticleSensorMode};
+///
+/// # fn main() -> metriful::error::Result<()> {
+/// let metriful = Metriful::builder()
+///   .i2c("/dev/i2c-1")
+///   .address(0x71)
+///   .ready_gpio(17)
+///   .particle_sensor(ParticleSensorMode::EnabledSDS011)
+///   .reset_on_open(true)
+///   .timeout(Duration::from_secs(5))
+///   .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct MetrifulBuilder {
+  options: MetrifulOptions,
+  ready_line: Option<Box<dyn ReadyLine>>,
+  particle_sensor: Option<ParticleSensorMode>,
+  reset_on_open: bool,
+  rate_limit: Option<RateLimit>,
+  shutdown: Option<ShutdownOptions>,
+  calibration: Option<Calibration>,
+  plausibility: Option<Plausibility>,
+  enforce_mode_validity: Option<bool>,
+  cancel: Option<CancelToken>,
+  ready_polling: Option<ReadyPolling>,
+  thread_scheduling: Option<ThreadScheduling>,
+}
+
+impl fmt::Debug for MetrifulBuilder {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("MetrifulBuilder")
+      .field("options", &self.options)
+      .field("ready_line", &self.ready_line)
+      .field("particle_sensor", &self.particle_sensor)
+      .field("reset_on_open", &self.reset_on_open)
+      .field("rate_limit", &self.rate_limit)
+      .field("shutdown", &self.shutdown)
+      .field("calibration", &self.calibration)
+      .field("plausibility", &self.plausibility)
+      .field("enforce_mode_validity", &self.enforce_mode_validity)
+      .field("cancel", &self.cancel)
+      .field("ready_polling", &self.ready_polling)
+      .field("thread_scheduling", &self.thread_scheduling)
+      .finish()
+  }
+}
+
+impl MetrifulBuilder {
+  /// Creates a builder using default connection settings.
+  pub fn new() -> MetrifulBuilder {
+    MetrifulBuilder::default()
+  }
+
+  /// Creates a builder using the given connection settings, e.g. from
+  /// [`MetrifulOptions::from_env()`].
+  pub fn from_options(options: MetrifulOptions) -> MetrifulBuilder {
+    MetrifulBuilder {
+      options,
+      ..MetrifulBuilder::default()
+    }
+  }
+
+  /// Sets the system i2c device, e.g. `/dev/i2c-1`.
+  pub fn i2c(mut self, i2c_device: impl Into<PathBuf>) -> Self {
+    self.options.i2c_device = i2c_device.into();
+    self
+  }
+
+  /// Sets the device i2c address; usually 0x71, or 0x70 if the solder bridge
+  /// is closed.
+  pub fn address(mut self, i2c_address: u16) -> Self {
+    self.options.i2c_address = i2c_address;
+    self
+  }
+
+  /// Sets the GPIO number (or line offset, with [`MetrifulBuilder::gpio_chip()`])
+  /// of the READY signal.
+  pub fn ready_gpio(mut self, gpio: u64) -> Self {
+    self.options.gpio_ready = gpio;
+    self
+  }
+
+  /// Sets the READY signal polarity.
+  pub fn ready_polarity(mut self, polarity: ReadyPolarity) -> Self {
+    self.options.ready_polarity = polarity;
+    self
+  }
+
+  /// Requests the READY line from the given GPIO chip via the character device
+  /// interface rather than sysfs. Requires the `cdev` feature.
+  pub fn gpio_chip(mut self, chip: impl Into<PathBuf>) -> Self {
+    self.options.gpio_chip = Some(chip.into());
+    self
+  }
+
+  /// Uses a custom READY line, overriding any GPIO settings.
+  pub fn ready_line(mut self, ready_line: impl ReadyLine + 'static) -> Self {
+    self.ready_line = Some(Box::new(ready_line));
+    self
+  }
+
+  /// Sets the timeout used while opening and configuring the device. Accepts
+  /// either a [`Duration`] or `None` to wait indefinitely.
+  pub fn timeout(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+    self.options.timeout = timeout.into();
+    self
+  }
+
+  /// Configures the external particle sensor once the device is open.
+  pub fn particle_sensor(mut self, mode: ParticleSensorMode) -> Self {
+    self.particle_sensor = Some(mode);
+    self
+  }
+
+  /// If true, resets the device once it is open, as recommended by the
+  /// datasheet. Defaults to false.
+  pub fn reset_on_open(mut self, reset: bool) -> Self {
+    self.reset_on_open = reset;
+    self
+  }
+
+  /// Sets the command rate limit.
+  pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+    self.rate_limit = Some(limit);
+    self
+  }
+
+  /// Sets the cleanup performed by [`Metriful::close()`] and, optionally, on
+  /// drop.
+  pub fn shutdown(mut self, options: ShutdownOptions) -> Self {
+    self.shutdown = Some(options);
+    self
+  }
+
+  /// Sets the calibration applied to readings.
+  pub fn calibration(mut self, calibration: Calibration) -> Self {
+    self.calibration = Some(calibration);
+    self
+  }
+
+  /// Sets the plausibility checks applied to readings; see
+  /// [`Metriful::set_plausibility()`].
+  pub fn plausibility(mut self, plausibility: Plausibility) -> Self {
+    self.plausibility = Some(plausibility);
+    self
+  }
+
+  /// Sets whether cycle-mode-only metrics may be read in standby; see
+  /// [`Metriful::set_enforce_mode_validity()`].
+  pub fn enforce_mode_validity(mut self, enforce: bool) -> Self {
+    self.enforce_mode_validity = Some(enforce);
+    self
+  }
+
+  /// Sets a token that stops read loops started from the device; see
+  /// [`Metriful::set_cancel_token()`].
+  pub fn cancel_token(mut self, cancel: CancelToken) -> Self {
+    self.cancel = Some(cancel);
+    self
+  }
+
+  /// Sets how the device waits for its READY line; see
+  /// [`Metriful::set_ready_polling()`].
+  pub fn ready_polling(mut self, polling: ReadyPolling) -> Self {
+    self.ready_polling = Some(polling);
+    self
+  }
+
+  /// Sets a scheduling hint for background read threads; see
+  /// [`Metriful::set_thread_scheduling()`].
+  pub fn thread_scheduling(mut self, scheduling: ThreadScheduling) -> Self {
+    self.thread_scheduling = Some(scheduling);
+    self
+  }
+
+  /// Returns the connection settings configured so far.
+  pub fn options(&self) -> &MetrifulOptions {
+    &self.options
+  }
+
+  /// Opens the device and applies the configured reset and particle sensor
+  /// settings, in that order.
+  pub fn build(self) -> Result<Metriful> {
+    trace!("MetrifulBuilder::build({:?})", self);
+
+    let timeout = self.options.timeout;
+    let reopen_ready = self.ready_line.is_none();
+    let ready_line = match self.ready_line {
+      Some(ready_line) => ready_line,
+      None => self.options.open_ready_line()?,
+    };
+
+    let mut metriful = Metriful::try_new_ready_timeout(
+      ready_line,
+      &self.options.i2c_device,
+      self.options.i2c_address,
+      timeout,
+    )?;
+
+    metriful.set_connection(self.options.clone(), reopen_ready);
+
+    if let Some(limit) = self.rate_limit {
+      metriful.set_rate_limit(limit);
+    }
+
+    if let Some(shutdown) = self.shutdown {
+      metriful.set_shutdown_options(shutdown);
+    }
+
+    if let Some(calibration) = self.calibration {
+      metriful.set_calibration(calibration);
+    }
+
+    if self.plausibility.is_some() {
+      metriful.set_plausibility(self.plausibility);
+    }
+
+    if let Some(enforce) = self.enforce_mode_validity {
+      metriful.set_enforce_mode_validity(enforce);
+    }
+
+    if self.cancel.is_some() {
+      metriful.set_cancel_token(self.cancel);
+    }
+
+    if let Some(polling) = self.ready_polling {
+      metriful.set_ready_polling(polling);
+    }
+
+    if self.thread_scheduling.is_some() {
+      metriful.set_thread_scheduling(self.thread_scheduling);
+    }
+
+    if self.reset_on_open {
+      metriful.reset_timeout(timeout)?;
+    }
+
+    if let Some(mode) = self.particle_sensor {
+      metriful.set_particle_sensor_timeout(mode, timeout)?;
+    }
+
+    Ok(metriful)
+  }
+}
@@ -0,0 +1,60 @@
+//! Offsets applied to readings to correct for self-heating and enclosure
+//! effects.
+//!
+//! The MS430's temperature sensor tends to read consistently high once the
+//! board has warmed up, particularly inside an enclosure. A [`Calibration`] set
+//! via [`Metriful::set_calibration()`] is applied to every value returned by
+//! [`Metriful::read()`], and so to all iterators, background readers and
+//! combined reads built on it. Calibrated values are what gets displayed and
+//! serialized.
+//!
+//! Offsets are applied independently; in particular, relative humidity is not
+//! recalculated to account for a temperature offset.
+//!
+//! # Example
+//! ```
+//! use metriful::calibration::Calibration;
+//! use metriful::unit::*;
+//!
+//! let calibration = Calibration {
+//!   temp_offset_c: -1.5,
+//!   humidity_offset: 5.0,
+//!   ..Calibration::default()
+//! };
+//!
+//! let mut temperature = 24.0;
+//! UnitDegreesCelsius::calibrate(&mut temperature, &calibration);
+//! assert_eq!(temperature, 22.5);
+//!
+//! // humidity is clamped to a valid percentage
+//! let mut humidity = 98.0;
+//! UnitRelativeHumidity::calibrate(&mut humidity, &calibration);
+//! assert_eq!(humidity, 100.0);
+//! ```
+
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+#[cfg(doc)] use crate::Metriful;
+
+/// Offsets added to raw readings. The default applies no correction.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Calibration {
+  /// Added to temperature readings, in degrees Celsius. Usually negative to
+  /// compensate for self-heating.
+  pub temp_offset_c: f32,
+
+  /// Added to relative humidity readings, in percentage points. Results are
+  /// clamped to 0-100%.
+  pub humidity_offset: f32,
+
+  /// Added to pressure readings, in Pascals.
+  pub pressure_offset_pa: i32,
+}
+
+impl Calibration {
+  /// Returns true if this calibration leaves readings unchanged.
+  pub fn is_identity(&self) -> bool {
+    *self == Calibration::default()
+  }
+}
@@ -0,0 +1,148 @@
+//! Cooperative cancellation of read loops.
+//!
+//! Read iterators and background readers may block for a full cycle period
+//! (up to 300 seconds) waiting for the next measurement. A [`CancelToken`]
+//! shared with another thread lets shutdown paths interrupt these waits
+//! promptly: cancelled loops check the token at least every
+//! [`CANCEL_CHECK_INTERVAL`] and end without yielding an error.
+//!
+//! Tokens can be attached to individual iterators via e.g.
+//! [`CycleReadIterator::with_cancel()`], or to the device via
+//! [`Metriful::set_cancel_token()`], in which case every read loop started
+//! from it afterward (including background readers such as
+//! [`Metriful::async_cycle_read_timeout()`]) uses the token.
+//!
+//! # Example
+//! ```no_run
+//! use std::thread;
+//! use std::time::Duration;
+//! use metriful::{Metriful, CyclePeriod, cancel::CancelToken, metric::*};
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+//!
+//! let token = CancelToken::new();
+//! let canceller = token.clone();
+//! thread::spawn(move || {
+//!   thread::sleep(Duration::from_secs(30));
+//!   canceller.cancel();
+//! });
+//!
+//! let iter = metriful
+//!   .cycle_read_iter_timeout(METRIC_COMBINED_ALL, CyclePeriod::Period2, None)
+//!   .with_cancel(token);
+//!
+//! // ends within ~100ms of cancellation rather than after the current cycle
+//! for reading in iter {
+//!   println!("{}", reading?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::*;
+
+#[cfg(doc)] use crate::{Metriful, CycleReadIterator};
+
+/// Maximum time a cancellable wait blocks between checks of its token.
+pub const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A flag that can be set from any thread to ask read loops to stop.
+///
+/// Clones share state; cancelling any clone cancels them all. Once cancelled,
+/// a token stays cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+  cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+  /// Creates a new, uncancelled token.
+  pub fn new() -> CancelToken {
+    CancelToken::default()
+  }
+
+  /// Requests cancellation of any loops using this token.
+  pub fn cancel(&self) {
+    self.cancelled.store(true, Ordering::SeqCst);
+  }
+
+  /// Returns true if [`CancelToken::cancel()`] has been called on this token
+  /// or any of its clones.
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::SeqCst)
+  }
+
+  /// Returns [`MetrifulError::Cancelled`] if this token has been cancelled.
+  pub fn check(&self) -> Result<()> {
+    if self.is_cancelled() {
+      Err(MetrifulError::Cancelled)
+    } else {
+      Ok(())
+    }
+  }
+}
+
+/// Sleeps for the given duration, returning early with
+/// [`MetrifulError::Cancelled`] if the token (if any) is cancelled.
+pub(crate) fn sleep(duration: Duration, cancel: Option<&CancelToken>) -> Result<()> {
+  let cancel = match cancel {
+    Some(cancel) => cancel,
+    None => {
+      thread::sleep(duration);
+      return Ok(());
+    }
+  };
+
+  let deadline = Instant::now() + duration;
+  loop {
+    cancel.check()?;
+
+    let now = Instant::now();
+    if now >= deadline {
+      return Ok(());
+    }
+
+    thread::sleep((deadline - now).min(CANCEL_CHECK_INTERVAL));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn clones_share_cancellation() {
+    let token = CancelToken::new();
+    let clone = token.clone();
+    assert!(clone.check().is_ok());
+
+    token.cancel();
+    assert!(matches!(clone.check(), Err(MetrifulError::Cancelled)));
+  }
+
+  #[test]
+  fn sleep_returns_early_when_cancelled() {
+    let token = CancelToken::new();
+    let canceller = token.clone();
+    thread::spawn(move || {
+      thread::sleep(Duration::from_millis(20));
+      canceller.cancel();
+    });
+
+    let start = Instant::now();
+    assert!(matches!(sleep(Duration::from_secs(10), Some(&token)), Err(MetrifulError::Cancelled)));
+    assert!(start.elapsed() < Duration::from_secs(1));
+  }
+
+  #[test]
+  fn sleep_completes_without_cancellation() {
+    assert!(sleep(Duration::from_millis(5), Some(&CancelToken::new())).is_ok());
+    assert!(sleep(Duration::from_millis(5), None).is_ok());
+  }
+}
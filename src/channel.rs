@@ -0,0 +1,429 @@
+//! Bounded channels with configurable backpressure, used by the background
+//! readers.
+//!
+//! Unlike [`std::sync::mpsc::channel`], these channels never hold more than a
+//! fixed number of values. When the channel is full, the configured
+//! [`BackpressurePolicy`] determines whether the sender waits or which value
+//! is discarded. This prevents a slow or stalled consumer from causing
+//! unbounded memory growth in long-running processes. The number of values
+//! discarded so far is available from [`BoundedReceiver::dropped()`].
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::sync::mpsc::{RecvError, RecvTimeoutError, TryRecvError};
+use std::time::{Duration, Instant};
+
+/// Behavior of a bounded channel when a value is sent while it is full.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BackpressurePolicy {
+  /// The sender blocks until the receiver makes room.
+  Block,
+
+  /// The oldest queued value is discarded to make room.
+  DropOldest,
+
+  /// The value being sent is discarded.
+  DropNewest,
+
+  /// Only the most recent value is kept, regardless of capacity.
+  CoalesceLatest,
+}
+
+#[derive(Debug)]
+struct State<T> {
+  queue: VecDeque<T>,
+  senders: usize,
+  receiver: bool,
+
+  /// Number of values discarded due to backpressure
+  dropped: u64,
+}
+
+impl<T> State<T> {
+  /// Discards queued values from the front until fewer than `len` remain.
+  fn evict_to(&mut self, len: usize) {
+    while self.queue.len() >= len && self.queue.pop_front().is_some() {
+      self.dropped += 1;
+    }
+  }
+}
+
+#[derive(Debug)]
+struct Shared<T> {
+  state: Mutex<State<T>>,
+  capacity: usize,
+  policy: BackpressurePolicy,
+
+  /// Signalled when a value is pushed or all senders disconnect
+  not_empty: Condvar,
+
+  /// Signalled when a value is popped or the receiver disconnects
+  not_full: Condvar,
+}
+
+impl<T> Shared<T> {
+  fn lock(&self) -> MutexGuard<'_, State<T>> {
+    self.state.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+/// Creates a new bounded channel holding at most `capacity` values (minimum 1).
+pub fn bounded<T>(
+  capacity: usize,
+  policy: BackpressurePolicy,
+) -> (BoundedSender<T>, BoundedReceiver<T>) {
+  let shared = Arc::new(Shared {
+    state: Mutex::new(State {
+      queue: VecDeque::new(),
+      senders: 1,
+      receiver: true,
+      dropped: 0,
+    }),
+    capacity: capacity.max(1),
+    policy,
+    not_empty: Condvar::new(),
+    not_full: Condvar::new(),
+  });
+
+  (
+    BoundedSender { shared: Arc::clone(&shared) },
+    BoundedReceiver { shared },
+  )
+}
+
+/// An error returned when sending on a channel whose receiver is gone. The
+/// unsent value is returned.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+/// The sending half of a [`bounded`] channel.
+#[derive(Debug)]
+pub struct BoundedSender<T> {
+  shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for BoundedSender<T> {
+  fn clone(&self) -> Self {
+    self.shared.lock().senders += 1;
+
+    BoundedSender {
+      shared: Arc::clone(&self.shared),
+    }
+  }
+}
+
+impl<T> Drop for BoundedSender<T> {
+  fn drop(&mut self) {
+    let mut state = self.shared.lock();
+    state.senders -= 1;
+    if state.senders == 0 {
+      self.shared.not_empty.notify_all();
+    }
+  }
+}
+
+impl<T> BoundedSender<T> {
+  /// Sends a value, applying the channel's [`BackpressurePolicy`] if it is
+  /// full. Returns `Ok(true)` if the value was queued and `Ok(false)` if it was
+  /// discarded per [`BackpressurePolicy::DropNewest`].
+  pub fn send(&self, value: T) -> Result<bool, SendError<T>> {
+    let shared = &self.shared;
+    let mut state = shared.lock();
+
+    if !state.receiver {
+      return Err(SendError(value));
+    }
+
+    match shared.policy {
+      BackpressurePolicy::Block => {
+        while state.receiver && state.queue.len() >= shared.capacity {
+          state = shared.not_full.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+
+        if !state.receiver {
+          return Err(SendError(value));
+        }
+      },
+      BackpressurePolicy::DropOldest => state.evict_to(shared.capacity),
+      BackpressurePolicy::DropNewest => {
+        if state.queue.len() >= shared.capacity {
+          state.dropped += 1;
+          return Ok(false);
+        }
+      },
+      BackpressurePolicy::CoalesceLatest => state.evict_to(1),
+    }
+
+    state.queue.push_back(value);
+    shared.not_empty.notify_one();
+
+    Ok(true)
+  }
+
+  /// Sends a value that must not be discarded, e.g. a terminal error. If the
+  /// channel is full, the oldest queued value is evicted to make room
+  /// regardless of policy.
+  pub fn force_send(&self, value: T) -> Result<(), SendError<T>> {
+    let shared = &self.shared;
+    let mut state = shared.lock();
+
+    if !state.receiver {
+      return Err(SendError(value));
+    }
+
+    if shared.policy == BackpressurePolicy::CoalesceLatest {
+      state.evict_to(1);
+    }
+
+    state.evict_to(shared.capacity);
+
+    state.queue.push_back(value);
+    shared.not_empty.notify_one();
+
+    Ok(())
+  }
+
+  /// Returns the number of values discarded so far due to backpressure.
+  pub fn dropped(&self) -> u64 {
+    self.shared.lock().dropped
+  }
+}
+
+/// The receiving half of a [`bounded`] channel.
+#[derive(Debug)]
+pub struct BoundedReceiver<T> {
+  shared: Arc<Shared<T>>,
+}
+
+impl<T> Drop for BoundedReceiver<T> {
+  fn drop(&mut self) {
+    self.shared.lock().receiver = false;
+    self.shared.not_full.notify_all();
+  }
+}
+
+impl<T> BoundedReceiver<T> {
+  fn pop(&self, state: &mut State<T>) -> Option<T> {
+    let value = state.queue.pop_front();
+    if value.is_some() {
+      self.shared.not_full.notify_one();
+    }
+
+    value
+  }
+
+  /// Blocks until a value is available. Returns an error once the channel is
+  /// empty and all senders have disconnected.
+  pub fn recv(&self) -> Result<T, RecvError> {
+    let mut state = self.shared.lock();
+
+    loop {
+      if let Some(value) = self.pop(&mut state) {
+        return Ok(value);
+      }
+
+      if state.senders == 0 {
+        return Err(RecvError);
+      }
+
+      state = self.shared.not_empty.wait(state).unwrap_or_else(|e| e.into_inner());
+    }
+  }
+
+  /// Returns a value if one is immediately available.
+  pub fn try_recv(&self) -> Result<T, TryRecvError> {
+    let mut state = self.shared.lock();
+
+    match self.pop(&mut state) {
+      Some(value) => Ok(value),
+      None if state.senders == 0 => Err(TryRecvError::Disconnected),
+      None => Err(TryRecvError::Empty),
+    }
+  }
+
+  /// Blocks for at most `timeout` waiting for a value.
+  pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+    let deadline = Instant::now() + timeout;
+    let mut state = self.shared.lock();
+
+    loop {
+      if let Some(value) = self.pop(&mut state) {
+        return Ok(value);
+      }
+
+      if state.senders == 0 {
+        return Err(RecvTimeoutError::Disconnected);
+      }
+
+      let now = Instant::now();
+      if now >= deadline {
+        return Err(RecvTimeoutError::Timeout);
+      }
+
+      state = self.shared.not_empty.wait_timeout(state, deadline - now)
+        .unwrap_or_else(|e| e.into_inner())
+        .0;
+    }
+  }
+
+  /// Returns the number of values currently queued.
+  pub fn len(&self) -> usize {
+    self.shared.lock().queue.len()
+  }
+
+  /// Returns true if no values are currently queued.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Returns the number of values discarded so far due to backpressure,
+  /// whether by the channel's [`BackpressurePolicy`] or to make room for a
+  /// value sent via [`BoundedSender::force_send()`].
+  ///
+  /// # Example
+  /// ```
+  /// use metriful::channel::{bounded, BackpressurePolicy};
+  ///
+  /// let (tx, rx) = bounded(2, BackpressurePolicy::DropOldest);
+  /// for i in 0..5 {
+  ///   tx.send(i).unwrap();
+  /// }
+  ///
+  /// assert_eq!(rx.dropped(), 3);
+  /// assert_eq!(rx.try_recv(), Ok(3));
+  /// ```
+  pub fn dropped(&self) -> u64 {
+    self.shared.lock().dropped
+  }
+
+  /// Returns a blocking iterator over received values that ends once all
+  /// senders disconnect.
+  pub fn iter(&self) -> Iter<'_, T> {
+    Iter { rx: self }
+  }
+}
+
+/// A blocking iterator over a [`BoundedReceiver`].
+#[derive(Debug)]
+pub struct Iter<'a, T> {
+  rx: &'a BoundedReceiver<T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+  type Item = T;
+
+  fn next(&mut self) -> Option<T> {
+    self.rx.recv().ok()
+  }
+}
+
+/// An owning blocking iterator over a [`BoundedReceiver`].
+#[derive(Debug)]
+pub struct IntoIter<T> {
+  rx: BoundedReceiver<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+  type Item = T;
+
+  fn next(&mut self) -> Option<T> {
+    self.rx.recv().ok()
+  }
+}
+
+impl<T> IntoIterator for BoundedReceiver<T> {
+  type Item = T;
+  type IntoIter = IntoIter<T>;
+
+  fn into_iter(self) -> IntoIter<T> {
+    IntoIter { rx: self }
+  }
+}
+
+impl<'a, T> IntoIterator for &'a BoundedReceiver<T> {
+  type Item = T;
+  type IntoIter = Iter<'a, T>;
+
+  fn into_iter(self) -> Iter<'a, T> {
+    self.iter()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::thread;
+
+  use super::*;
+
+  #[test]
+  fn drop_newest_discards_the_sent_value() {
+    let (tx, rx) = bounded(2, BackpressurePolicy::DropNewest);
+    assert_eq!(tx.send(1), Ok(true));
+    assert_eq!(tx.send(2), Ok(true));
+    assert_eq!(tx.send(3), Ok(false));
+
+    assert_eq!(rx.iter().take(2).collect::<Vec<_>>(), vec![1, 2]);
+    assert_eq!(tx.dropped(), 1);
+  }
+
+  #[test]
+  fn coalesce_keeps_only_the_latest() {
+    let (tx, rx) = bounded(10, BackpressurePolicy::CoalesceLatest);
+    for i in 0..5 {
+      tx.send(i).unwrap();
+    }
+
+    assert_eq!(rx.len(), 1);
+    assert_eq!(rx.try_recv(), Ok(4));
+    assert_eq!(rx.dropped(), 4);
+  }
+
+  #[test]
+  fn force_send_evicts_regardless_of_policy() {
+    let (tx, rx) = bounded(1, BackpressurePolicy::DropNewest);
+    tx.send("reading").unwrap();
+    tx.force_send("error").unwrap();
+
+    assert_eq!(rx.try_recv(), Ok("error"));
+    assert_eq!(rx.dropped(), 1);
+  }
+
+  #[test]
+  fn block_waits_for_room() {
+    let (tx, rx) = bounded(1, BackpressurePolicy::Block);
+    tx.send(1).unwrap();
+
+    let sender = thread::spawn(move || tx.send(2));
+    assert_eq!(rx.recv(), Ok(1));
+    assert_eq!(sender.join().unwrap(), Ok(true));
+    assert_eq!(rx.recv(), Ok(2));
+  }
+
+  #[test]
+  fn blocked_senders_fail_when_the_receiver_is_dropped() {
+    let (tx, rx) = bounded(1, BackpressurePolicy::Block);
+    tx.send(1).unwrap();
+
+    let sender = thread::spawn(move || tx.send(2));
+    thread::sleep(Duration::from_millis(20));
+    drop(rx);
+
+    assert_eq!(sender.join().unwrap(), Err(SendError(2)));
+  }
+
+  #[test]
+  fn receivers_see_disconnection_after_draining() {
+    let (tx, rx) = bounded(4, BackpressurePolicy::Block);
+    let tx2 = tx.clone();
+    tx.send(1).unwrap();
+    drop(tx);
+
+    assert_eq!(rx.try_recv(), Ok(1));
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(rx.recv_timeout(Duration::from_millis(10)), Err(RecvTimeoutError::Timeout));
+
+    drop(tx2);
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(rx.recv(), Err(RecvError));
+  }
+}
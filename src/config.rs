@@ -0,0 +1,117 @@
+//! Declarative device configuration.
+//!
+//! A [`DeviceConfig`] describes the desired state of the MS430's settings: the
+//! particle sensor, both interrupts, and whether the device should be cycling.
+//! [`Metriful::apply_config()`] brings the device into that state, changing
+//! only what differs, so a configuration stored in a file (with the `serde`
+//! feature) may safely be re-applied on every startup.
+//!
+//! # Example
+//! ```no_run
+//! use metriful::{Metriful, DeviceConfig, CyclePeriod, ParticleSensorMode};
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+//!
+//! let config = DeviceConfig {
+//!   particle_sensor: ParticleSensorMode::EnabledSDS011,
+//!   cycle_period: Some(CyclePeriod::Period0),
+//!   ..DeviceConfig::default()
+//! };
+//!
+//! let status = metriful.apply_config(&config)?;
+//! assert!(config.is_applied(&status));
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+use crate::status::*;
+
+#[cfg(doc)] use crate::Metriful;
+
+/// Desired device settings, as applied by [`Metriful::apply_config()`].
+///
+/// The default configuration disables the particle sensor and both interrupts
+/// and leaves the device in standby mode, matching its power-on state.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct DeviceConfig {
+  /// External particle sensor mode
+  pub particle_sensor: ParticleSensorMode,
+
+  /// Light interrupt configuration
+  pub light_interrupt: LightInterruptConfig,
+
+  /// Sound interrupt configuration
+  pub sound_interrupt: SoundInterruptConfig,
+
+  /// If set, the device is left in cycle mode with this period; otherwise it
+  /// is left in standby mode.
+  pub cycle_period: Option<CyclePeriod>,
+}
+
+impl Default for DeviceConfig {
+  fn default() -> Self {
+    DeviceConfig {
+      particle_sensor: ParticleSensorMode::Disabled,
+      light_interrupt: InterruptStatus::Disabled,
+      sound_interrupt: InterruptStatus::Disabled,
+      cycle_period: None,
+    }
+  }
+}
+
+impl DeviceConfig {
+  /// Returns the configuration currently in effect per the given status, e.g.
+  /// to save a device's current settings.
+  pub fn from_status(status: &DeviceStatus) -> DeviceConfig {
+    DeviceConfig {
+      particle_sensor: status.particle_sensor,
+      light_interrupt: status.light_int.clone(),
+      sound_interrupt: status.sound_int.clone(),
+      cycle_period: match status.mode {
+        OperationalMode::Cycle(period) => Some(period),
+        OperationalMode::Standby => None,
+      },
+    }
+  }
+
+  /// Returns the operational mode this configuration leaves the device in.
+  pub fn mode(&self) -> OperationalMode {
+    match self.cycle_period {
+      Some(period) => OperationalMode::Cycle(period),
+      None => OperationalMode::Standby,
+    }
+  }
+
+  /// Returns true if the particle sensor is configured as in the given status.
+  pub(crate) fn particle_sensor_applied(&self, status: &DeviceStatus) -> bool {
+    self.particle_sensor == status.particle_sensor
+  }
+
+  /// Returns true if the light interrupt is configured as in the given status,
+  /// ignoring threshold precision the device cannot store.
+  pub(crate) fn light_interrupt_applied(&self, status: &DeviceStatus) -> bool {
+    match (&self.light_interrupt, &status.light_int) {
+      (InterruptStatus::Disabled, InterruptStatus::Disabled) => true,
+      (InterruptStatus::Enabled(a), InterruptStatus::Enabled(b)) => a.is_equivalent(b),
+      _ => false,
+    }
+  }
+
+  /// Returns true if the sound interrupt is configured as in the given status.
+  pub(crate) fn sound_interrupt_applied(&self, status: &DeviceStatus) -> bool {
+    self.sound_interrupt == status.sound_int
+  }
+
+  /// Returns true if the given status already matches this configuration, in
+  /// which case applying it would change nothing.
+  pub fn is_applied(&self, status: &DeviceStatus) -> bool {
+    self.particle_sensor_applied(status)
+      && self.light_interrupt_applied(status)
+      && self.sound_interrupt_applied(status)
+      && self.mode() == status.mode
+  }
+}
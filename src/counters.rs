@@ -0,0 +1,215 @@
+//! Health counters maintained by each device.
+//!
+//! Every [`Metriful`] counts its reads, failures, retries, waits for READY,
+//! and missed cycles. [`Metriful::stats()`] returns a snapshot of these as
+//! [`DeviceStats`]. As the device is usually moved into an iterator or
+//! background thread while reading, the counters themselves can be shared
+//! beforehand via [`Metriful::counters()`] and read from any thread.
+//!
+//! # Example
+//! ```no_run
+//! use metriful::{Metriful, CyclePeriod, metric::*};
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+//! let counters = metriful.counters();
+//!
+//! let (_tx, rx, _handle) = metriful.async_cycle_read_timeout(
+//!   METRIC_COMBINED_ALL, CyclePeriod::Period0, None
+//! );
+//!
+//! for reading in rx.iter().take(10) {
+//!   println!("{}", reading?);
+//! }
+//!
+//! println!("{}", counters.snapshot());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+#[cfg(feature = "serde")] use serde::{Serialize, Serializer};
+
+#[cfg(doc)] use crate::{Metriful, retry::ReadPolicy};
+
+/// A snapshot of a device's [`Counters`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DeviceStats {
+  /// Successful reads
+  pub reads: u64,
+
+  /// Failed reads, including those later retried successfully
+  pub errors: u64,
+
+  /// Reads and commands retried per a [`ReadPolicy`]
+  pub retries: u64,
+
+  /// Completed waits for the READY line to change state
+  pub ready_waits: u64,
+
+  /// Waits for the READY line that timed out
+  pub ready_timeouts: u64,
+
+  /// Total time spent in completed waits for the READY line
+  #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_secs"))]
+  pub ready_wait_total: Duration,
+
+  /// Longest completed wait for the READY line
+  #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_secs"))]
+  pub ready_wait_max: Duration,
+
+  /// Cycle mode measurements that completed without being read
+  pub missed_cycles: u64,
+}
+
+#[cfg(feature = "serde")]
+fn serialize_secs<S>(duration: &Duration, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+  S: Serializer
+{
+  serializer.serialize_f64(duration.as_secs_f64())
+}
+
+impl DeviceStats {
+  /// Returns the mean duration of completed waits for the READY line, if
+  /// there were any.
+  pub fn ready_wait_mean(&self) -> Option<Duration> {
+    match self.ready_waits {
+      0 => None,
+      n => Some(self.ready_wait_total / n.min(u64::from(u32::MAX)) as u32),
+    }
+  }
+}
+
+impl fmt::Display for DeviceStats {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f, "{} reads, {} errors, {} retries, {} missed cycles, {} ready timeouts",
+      self.reads, self.errors, self.retries, self.missed_cycles, self.ready_timeouts
+    )?;
+
+    if let Some(mean) = self.ready_wait_mean() {
+      write!(f, ", ready wait mean {:?} (max {:?})", mean, self.ready_wait_max)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Counters updated by a device as it is used. Shared via
+/// [`Metriful::counters()`].
+#[derive(Debug, Default)]
+pub struct Counters {
+  reads: AtomicU64,
+  errors: AtomicU64,
+  retries: AtomicU64,
+  ready_waits: AtomicU64,
+  ready_timeouts: AtomicU64,
+  ready_wait_total_us: AtomicU64,
+  ready_wait_max_us: AtomicU64,
+  missed_cycles: AtomicU64,
+}
+
+impl Counters {
+  /// Returns the current counter values.
+  pub fn snapshot(&self) -> DeviceStats {
+    let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+
+    DeviceStats {
+      reads: load(&self.reads),
+      errors: load(&self.errors),
+      retries: load(&self.retries),
+      ready_waits: load(&self.ready_waits),
+      ready_timeouts: load(&self.ready_timeouts),
+      ready_wait_total: Duration::from_micros(load(&self.ready_wait_total_us)),
+      ready_wait_max: Duration::from_micros(load(&self.ready_wait_max_us)),
+      missed_cycles: load(&self.missed_cycles),
+    }
+  }
+
+  /// Resets all counters to zero.
+  pub fn reset(&self) {
+    for counter in &[
+      &self.reads, &self.errors, &self.retries, &self.ready_waits,
+      &self.ready_timeouts, &self.ready_wait_total_us, &self.ready_wait_max_us,
+      &self.missed_cycles,
+    ] {
+      counter.store(0, Ordering::Relaxed);
+    }
+  }
+
+  pub(crate) fn record_read<T, E>(&self, result: &Result<T, E>) {
+    match result {
+      Ok(_) => self.reads.fetch_add(1, Ordering::Relaxed),
+      Err(_) => self.errors.fetch_add(1, Ordering::Relaxed),
+    };
+  }
+
+  pub(crate) fn record_retry(&self) {
+    self.retries.fetch_add(1, Ordering::Relaxed);
+  }
+
+  pub(crate) fn record_ready_wait(&self, elapsed: Duration) {
+    let us = elapsed.as_micros().min(u128::from(u64::MAX)) as u64;
+
+    self.ready_waits.fetch_add(1, Ordering::Relaxed);
+    self.ready_wait_total_us.fetch_add(us, Ordering::Relaxed);
+    self.ready_wait_max_us.fetch_max(us, Ordering::Relaxed);
+  }
+
+  pub(crate) fn record_ready_timeout(&self) {
+    self.ready_timeouts.fetch_add(1, Ordering::Relaxed);
+  }
+
+  pub(crate) fn record_missed_cycles(&self, missed: u64) {
+    self.missed_cycles.fetch_add(missed, Ordering::Relaxed);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reads_and_errors_are_counted() {
+    let counters = Counters::default();
+    counters.record_read::<(), ()>(&Ok(()));
+    counters.record_read::<(), ()>(&Ok(()));
+    counters.record_read::<(), ()>(&Err(()));
+    counters.record_retry();
+    counters.record_missed_cycles(3);
+
+    let stats = counters.snapshot();
+    assert_eq!((stats.reads, stats.errors, stats.retries, stats.missed_cycles), (2, 1, 1, 3));
+  }
+
+  #[test]
+  fn ready_waits_track_mean_and_max() {
+    let counters = Counters::default();
+    assert_eq!(counters.snapshot().ready_wait_mean(), None);
+
+    counters.record_ready_wait(Duration::from_millis(10));
+    counters.record_ready_wait(Duration::from_millis(30));
+    counters.record_ready_timeout();
+
+    let stats = counters.snapshot();
+    assert_eq!(stats.ready_waits, 2);
+    assert_eq!(stats.ready_timeouts, 1);
+    assert_eq!(stats.ready_wait_mean(), Some(Duration::from_millis(20)));
+    assert_eq!(stats.ready_wait_max, Duration::from_millis(30));
+  }
+
+  #[test]
+  fn reset_clears_everything() {
+    let counters = Counters::default();
+    counters.record_read::<(), ()>(&Err(()));
+    counters.record_ready_wait(Duration::from_millis(5));
+
+    counters.reset();
+    assert_eq!(counters.snapshot(), DeviceStats::default());
+  }
+}
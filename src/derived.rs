@@ -0,0 +1,175 @@
+//! Comfort metrics derived from air data.
+//!
+//! The MS430 reports temperature and relative humidity, but HVAC and comfort
+//! dashboards usually also want absolute humidity and an apparent temperature.
+//! [`DerivedAirData`] computes these from a [`CombinedAirData`] reading:
+//!  * absolute humidity, in grams of water vapor per cubic meter of air
+//!  * heat index, the US National Weather Service apparent temperature
+//!  * humidex, the Environment Canada apparent temperature
+//!
+//! # Example
+//! ```no_run
+//! use metriful::{Metriful, metric::*};
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+//!
+//! let air = metriful.read(METRIC_COMBINED_AIR_DATA)?;
+//! println!("{}", air.value.derived());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+#[cfg(feature = "serde")] use chrono::SecondsFormat;
+#[cfg(feature = "serde")] use serde::{Serialize, Serializer};
+
+use crate::unit::*;
+
+/// Returns the saturation vapor pressure of water in hPa at the given
+/// temperature, per the Magnus approximation.
+fn saturation_vapor_pressure(temperature_c: f32) -> f32 {
+  6.112 * ((17.67 * temperature_c) / (temperature_c + 243.5)).exp()
+}
+
+/// Returns the partial pressure of water vapor in hPa.
+fn vapor_pressure(temperature_c: f32, relative_humidity: f32) -> f32 {
+  saturation_vapor_pressure(temperature_c) * relative_humidity / 100.0
+}
+
+/// Returns absolute humidity in g/m³ given a temperature in degrees Celsius
+/// and a relative humidity percentage.
+///
+/// # Example
+/// ```
+/// use metriful::derived::absolute_humidity;
+///
+/// assert!((absolute_humidity(20.0, 50.0) - 8.64).abs() < 0.01);
+/// ```
+pub fn absolute_humidity(temperature_c: f32, relative_humidity: f32) -> f32 {
+  216.7 * vapor_pressure(temperature_c, relative_humidity) / (temperature_c + 273.15)
+}
+
+/// Returns the heat index in degrees Celsius given a temperature in degrees
+/// Celsius and a relative humidity percentage, using the US National Weather
+/// Service's Rothfusz regression and adjustments. Below ~27 ℃ this is close to
+/// the air temperature.
+///
+/// # Example
+/// ```
+/// use metriful::derived::heat_index;
+///
+/// assert!((heat_index(32.0, 70.0) - 40.4).abs() < 0.1);
+/// assert!((heat_index(20.0, 50.0) - 19.4).abs() < 0.1);
+/// ```
+pub fn heat_index(temperature_c: f32, relative_humidity: f32) -> f32 {
+  // the regression's coefficients are only meaningful at full precision
+  let t = f64::from(temperature_c) * 9.0 / 5.0 + 32.0;
+  let rh = f64::from(relative_humidity);
+
+  let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+
+  let hi = if (simple + t) / 2.0 < 80.0 {
+    simple
+  } else {
+    let mut hi = -42.379
+      + 2.049_015_23 * t
+      + 10.143_331_27 * rh
+      - 0.224_755_41 * t * rh
+      - 0.006_837_83 * t * t
+      - 0.054_817_17 * rh * rh
+      + 0.001_228_74 * t * t * rh
+      + 0.000_852_82 * t * rh * rh
+      - 0.000_001_99 * t * t * rh * rh;
+
+    if rh < 13.0 && (80.0..=112.0).contains(&t) {
+      hi -= ((13.0 - rh) / 4.0) * ((17.0 - (t - 95.0).abs()) / 17.0).sqrt();
+    } else if rh > 85.0 && (80.0..=87.0).contains(&t) {
+      hi += ((rh - 85.0) / 10.0) * ((87.0 - t) / 5.0);
+    }
+
+    hi
+  };
+
+  ((hi - 32.0) * 5.0 / 9.0) as f32
+}
+
+/// Returns the humidex given a temperature in degrees Celsius and a relative
+/// humidity percentage. The humidex is dimensionless but is intended to be
+/// compared to temperatures in degrees Celsius.
+///
+/// # Example
+/// ```
+/// use metriful::derived::humidex;
+///
+/// assert_eq!(humidex(30.0, 70.0).round(), 41.0);
+/// ```
+pub fn humidex(temperature_c: f32, relative_humidity: f32) -> f32 {
+  temperature_c + 0.5555 * (vapor_pressure(temperature_c, relative_humidity) - 10.0)
+}
+
+/// Comfort metrics derived from a single air data reading.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DerivedAirData {
+  /// Absolute humidity in grams per cubic meter
+  pub absolute_humidity: f32,
+
+  /// Heat index in degrees Celsius
+  pub heat_index: f32,
+
+  /// Humidex (dimensionless, comparable to degrees Celsius)
+  pub humidex: f32,
+
+  /// The time the underlying temperature was read
+  #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_time"))]
+  pub time: DateTime<Utc>,
+}
+
+#[cfg(feature = "serde")]
+fn serialize_time<S>(time: &DateTime<Utc>, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+  S: Serializer
+{
+  serializer.serialize_str(&time.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+impl DerivedAirData {
+  /// Computes derived metrics from the given temperature and relative
+  /// humidity.
+  pub fn new(temperature_c: f32, relative_humidity: f32, time: DateTime<Utc>) -> DerivedAirData {
+    DerivedAirData {
+      absolute_humidity: absolute_humidity(temperature_c, relative_humidity),
+      heat_index: heat_index(temperature_c, relative_humidity),
+      humidex: humidex(temperature_c, relative_humidity),
+      time,
+    }
+  }
+}
+
+impl From<&CombinedAirData> for DerivedAirData {
+  fn from(air: &CombinedAirData) -> Self {
+    DerivedAirData::new(air.temperature.value, air.humidity.value, air.temperature.time)
+  }
+}
+
+impl CombinedAirData {
+  /// Computes derived comfort metrics from this reading; see the
+  /// [`derived`](crate::derived) module.
+  pub fn derived(&self) -> DerivedAirData {
+    DerivedAirData::from(self)
+  }
+}
+
+impl fmt::Display for DerivedAirData {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "absolute humidity: {:.1} g/m³", self.absolute_humidity)?;
+    writeln!(f, "heat index:        {:.1} ℃", self.heat_index)?;
+    writeln!(f, "humidex:           {:.1}", self.humidex)?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,298 @@
+//! Type-erased metrics for heterogeneous collections.
+//!
+//! [`Metric<U>`] is generic over its unit, so metrics of different units
+//! cannot be stored together. [`DynMetric`] is an object-safe view of any
+//! metric that reads into a [`DynReading`], allowing applications to read an
+//! arbitrary, user-selected set of metrics:
+//!
+//! ```no_run
+//! use metriful::Metriful;
+//! use metriful::dyn_metric::DynMetric;
+//! use metriful::metric::*;
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+//!
+//! let selected: Vec<&dyn DynMetric> = vec![
+//!   &METRIC_TEMPERATURE,
+//!   &METRIC_ILLUMINANCE,
+//!   by_name("combined_sound").unwrap().as_dyn(),
+//! ];
+//!
+//! for metric in selected {
+//!   let reading = metriful.read_dyn(metric)?;
+//!   println!("{}: {}", reading.name, reading.formatted_value);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+#[cfg(feature = "serde")] use chrono::SecondsFormat;
+#[cfg(feature = "serde")] use serde::{Serialize, ser::{Serializer, SerializeStruct}};
+
+use crate::calibration::Calibration;
+use crate::error::*;
+use crate::metric::Metric;
+use crate::status::DeviceStatus;
+use crate::transport::MetrifulTransport;
+use crate::unit::*;
+
+/// A unit-independent representation of a read value.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(untagged))]
+pub enum ReadingValue {
+  /// A single numeric value
+  Number(f64),
+
+  /// A list of numeric values, e.g. sound levels by frequency band
+  Numbers(Vec<f64>),
+
+  /// A non-numeric value, e.g. an accuracy or validity indicator, as text
+  Text(String),
+
+  /// The component values of a combined read
+  Group(Vec<DynReading>),
+}
+
+/// Widens an `f32` to the `f64` with the same shortest decimal representation,
+/// e.g. `21.3` rather than `21.299999237060547`.
+pub(crate) fn decimal_f64(value: f32) -> f64 {
+  value.to_string().parse().unwrap_or(value as f64)
+}
+
+impl ReadingValue {
+  /// A single numeric value from an `f32` reading; see [`ReadingValue::Number`].
+  pub fn from_f32(value: f32) -> ReadingValue {
+    ReadingValue::Number(decimal_f64(value))
+  }
+
+  /// Returns the value as a number, if it is one.
+  pub fn as_f64(&self) -> Option<f64> {
+    match self {
+      ReadingValue::Number(n) => Some(*n),
+      _ => None,
+    }
+  }
+}
+
+/// A value read via a [`DynMetric`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynReading {
+  /// The metric's id (see [`MetricInfo::id`](crate::metric::MetricInfo::id))
+  /// or, for combined read components, the field name
+  pub name: &'static str,
+
+  /// The human-readable name of the unit
+  pub unit_name: &'static str,
+
+  /// The unit's symbol, if any
+  pub unit_symbol: Option<&'static str>,
+
+  /// The read value
+  pub value: ReadingValue,
+
+  /// The value formatted per its unit, as with [`UnitValue`]'s `Display`
+  pub formatted_value: String,
+
+  /// The system time (UTC) when the metric was read by the library.
+  pub time: DateTime<Utc>,
+}
+
+impl DynReading {
+  /// Erases the unit of a typed value.
+  ///
+  /// # Example
+  /// ```
+  /// use metriful::dyn_metric::{DynReading, ReadingValue};
+  /// use metriful::unit::*;
+  ///
+  /// let value = UnitValue::<UnitDegreesCelsius>::new(21.3);
+  /// let reading = DynReading::from_value("temperature", &value);
+  ///
+  /// assert_eq!(reading.value, ReadingValue::Number(21.3));
+  /// assert_eq!(reading.unit_symbol, Some("\u{2103}"));
+  /// assert_eq!(reading.to_string(), "21.3 \u{2103}");
+  /// ```
+  pub fn from_value<U: MetrifulUnit>(name: &'static str, value: &UnitValue<U>) -> DynReading {
+    DynReading {
+      name,
+      unit_name: U::name(),
+      unit_symbol: U::symbol(),
+      value: U::to_reading_value(&value.value),
+      formatted_value: U::format_value(&value.value),
+      time: value.time,
+    }
+  }
+
+  /// Converts this reading, and any components of a combined read, to the
+  /// given system of units. Values already in that system, or with no
+  /// equivalent in it, are unchanged.
+  ///
+  /// # Example
+  /// ```
+  /// use metriful::dyn_metric::{DynReading, ReadingValue};
+  /// use metriful::unit::*;
+  ///
+  /// let value = UnitValue::<UnitDegreesCelsius>::new(21.5);
+  /// let reading = DynReading::from_value("temperature", &value)
+  ///   .to_unit_system(UnitSystem::Imperial);
+  ///
+  /// assert_eq!(reading.value, ReadingValue::Number(70.7));
+  /// assert_eq!(reading.to_string(), "70.7 \u{2109}");
+  ///
+  /// let value = UnitValue::<UnitRelativeHumidity>::new(45.0);
+  /// let reading = DynReading::from_value("relative_humidity", &value);
+  /// assert_eq!(reading.to_unit_system(UnitSystem::Imperial), reading);
+  /// ```
+  pub fn to_unit_system(&self, system: UnitSystem) -> DynReading {
+    let n = match &self.value {
+      ReadingValue::Number(n) => *n,
+      ReadingValue::Group(components) => return self.group_to_unit_system(components, system),
+      _ => return self.clone(),
+    };
+
+    let unit = self.unit_name;
+    match system {
+      UnitSystem::Imperial if unit == UnitDegreesCelsius::name() => {
+        self.converted::<UnitDegreesCelsius, UnitDegreesFahrenheit>(n as f32)
+      },
+      UnitSystem::Imperial if unit == UnitPascals::name() => {
+        self.converted::<UnitPascals, UnitInchesOfMercury>(n as u32)
+      },
+      UnitSystem::Metric if unit == UnitDegreesFahrenheit::name() => {
+        self.converted::<UnitDegreesFahrenheit, UnitDegreesCelsius>(n as f32)
+      },
+      UnitSystem::Metric if unit == UnitInchesOfMercury::name() => {
+        self.converted::<UnitInchesOfMercury, UnitPascals>(n as f32)
+      },
+      _ => self.clone(),
+    }
+  }
+
+  /// Converts a value of unit `U` (this reading's unit) to unit `V`.
+  fn converted<U, V>(&self, value: U::Output) -> DynReading
+  where
+    U: ConvertUnit<V>,
+    V: MetrifulUnit,
+  {
+    let mut value = UnitValue::<U>::new(value);
+    value.time = self.time;
+
+    DynReading::from_value(self.name, &value.convert::<V>())
+  }
+
+  /// Converts the components of a combined read, reformatting it as one
+  /// `name: value` line per component if any were converted.
+  fn group_to_unit_system(&self, components: &[DynReading], system: UnitSystem) -> DynReading {
+    let converted: Vec<DynReading> = components.iter()
+      .map(|component| component.to_unit_system(system))
+      .collect();
+
+    if converted == components {
+      return self.clone();
+    }
+
+    let formatted_value = converted.iter()
+      .map(|component| match component.value {
+        ReadingValue::Group(_) => format!(
+          "{}:\n{}", component.name, textwrap::indent(&component.formatted_value, "  ")
+        ),
+        _ => format!("{}: {}", component.name, component.formatted_value),
+      })
+      .collect::<Vec<_>>()
+      .join("\n");
+
+    DynReading {
+      value: ReadingValue::Group(converted),
+      formatted_value,
+      ..self.clone()
+    }
+  }
+}
+
+impl fmt::Display for DynReading {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.formatted_value)
+  }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for DynReading {
+  fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+  where
+      S: Serializer
+  {
+    let mut state = serializer.serialize_struct("DynReading", 6)?;
+    state.serialize_field("name", self.name)?;
+    state.serialize_field("timestamp", &self.time.to_rfc3339_opts(SecondsFormat::Secs, true))?;
+    state.serialize_field("unit_name", self.unit_name)?;
+    state.serialize_field("unit_symbol", &self.unit_symbol)?;
+    state.serialize_field("value", &self.value)?;
+    state.serialize_field("formatted_value", &self.formatted_value)?;
+    state.end()
+  }
+}
+
+/// An object-safe view of a [`Metric`] of any unit.
+pub trait DynMetric: fmt::Debug + Send + Sync {
+  /// The metric's id, or its unit name if the metric is unknown.
+  fn name(&self) -> &'static str;
+
+  /// The register the metric is read from.
+  fn register(&self) -> u8;
+
+  /// The human-readable name of the metric's unit.
+  fn unit_name(&self) -> &'static str;
+
+  /// The symbol of the metric's unit, if any.
+  fn unit_symbol(&self) -> Option<&'static str>;
+
+  /// Reads the metric from the device and applies the given calibration.
+  /// If a status is given, parts of the reading it shows to be meaningless
+  /// are masked; see [`MetrifulUnit::mask_unavailable()`]. Prefer
+  /// [`Metriful::read_dyn()`](crate::Metriful::read_dyn), which also checks
+  /// that the device is ready.
+  fn read_dyn(
+    &self,
+    device: &mut dyn MetrifulTransport,
+    calibration: &Calibration,
+    status: Option<&DeviceStatus>,
+  ) -> Result<DynReading>;
+}
+
+impl<U: MetrifulUnit> DynMetric for Metric<U> {
+  fn name(&self) -> &'static str {
+    self.metadata().map(|info| info.id).unwrap_or_else(U::name)
+  }
+
+  fn register(&self) -> u8 {
+    self.register
+  }
+
+  fn unit_name(&self) -> &'static str {
+    U::name()
+  }
+
+  fn unit_symbol(&self) -> Option<&'static str> {
+    U::symbol()
+  }
+
+  fn read_dyn(
+    &self,
+    device: &mut dyn MetrifulTransport,
+    calibration: &Calibration,
+    status: Option<&DeviceStatus>,
+  ) -> Result<DynReading> {
+    let mut value = self.read(device)?;
+    U::calibrate(&mut value.value, calibration);
+    if let Some(status) = status {
+      U::mask_unavailable(&mut value.value, status);
+    }
+
+    Ok(DynReading::from_value(DynMetric::name(self), &value))
+  }
+}
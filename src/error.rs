@@ -1,7 +1,114 @@
+use std::fmt;
+use std::io;
+
 use err_derive::Error;
 use i2cdev::linux::LinuxI2CError;
 
+#[cfg(feature = "serde")] use serde::{Serialize, ser::{Serializer, SerializeStruct}};
+
 use crate::OperationalMode;
+use crate::plausibility::Implausible;
+use crate::registers::Register;
+
+/// Linux errno values considered transient: `EIO`, `EAGAIN`, `ETIMEDOUT` and
+/// `EREMOTEIO` (a NACK).
+pub const TRANSIENT_ERRNOS: &[i32] = &[5, 11, 110, 121];
+
+/// A broad classification of errors; see [`MetrifulError::kind()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "snake_case"))]
+pub enum ErrorKind {
+  /// A bus or I/O error that may succeed if retried, e.g. a NACK or a
+  /// truncated read
+  TransientBus,
+
+  /// A bus, GPIO, or I/O error unlikely to resolve itself, e.g. a missing
+  /// device or insufficient permissions
+  Bus,
+
+  /// Timed out waiting for the device to become ready
+  Timeout,
+
+  /// The device isn't in a state that allows the operation, e.g. it isn't
+  /// ready or is in the wrong mode
+  InvalidState,
+
+  /// The device returned data that couldn't be decoded, or that was
+  /// implausible
+  Protocol,
+
+  /// An invalid argument, configuration, or unsupported operation
+  InvalidInput,
+
+  /// The operation was cancelled via a [`CancelToken`](crate::cancel::CancelToken)
+  Cancelled,
+
+  /// Any other error, e.g. a failed background task
+  Other,
+}
+
+/// What was being done with a register when a bus error occurred.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "snake_case"))]
+pub enum BusPhase {
+  /// Sending a command, e.g. `0xE2` (reset)
+  Command,
+
+  /// Reading from the register
+  Read,
+
+  /// Writing to the register
+  Write,
+}
+
+/// Where a bus error occurred; see [`MetrifulError::Bus`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ErrorContext {
+  /// The library operation in progress, e.g. `configure_light_interrupt`
+  pub operation: &'static str,
+
+  /// The register or command address accessed
+  pub register: u8,
+
+  /// What was being done with the register
+  pub phase: BusPhase,
+}
+
+impl fmt::Display for ErrorContext {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let phase = match self.phase {
+      BusPhase::Command => "command",
+      BusPhase::Read => "read",
+      BusPhase::Write => "write",
+    };
+
+    match Register::from_address(self.register) {
+      Some(register) => write!(f, "{}: {} {}", self.operation, phase, register),
+      None => write!(f, "{}: {} 0x{:02X}", self.operation, phase, self.register),
+    }
+  }
+}
+
+/// The READY line state a timed-out wait expected; see
+/// [`MetrifulError::ReadyTimeoutExceeded`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReadyWait {
+  /// Waiting for READY to assert, e.g. for a command or measurement to finish
+  Ready,
+
+  /// Waiting for READY to deassert, e.g. for the next cycle to begin
+  NotReady,
+}
+
+impl fmt::Display for ReadyWait {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ReadyWait::Ready => f.write_str("ready"),
+      ReadyWait::NotReady => f.write_str("not ready"),
+    }
+  }
+}
 
 #[derive(Debug, Error)]
 pub enum MetrifulError {
@@ -11,6 +118,21 @@ pub enum MetrifulError {
   #[error(display = "gpio error: {}", _0)]
   GPIOError(#[error(source)] sysfs_gpio::Error),
 
+  #[cfg(feature = "cdev")]
+  #[error(display = "gpio character device error: {}", _0)]
+  GpioCdevError(#[error(source)] gpio_cdev::Error),
+
+  #[cfg(feature = "hal")]
+  #[error(display = "i2c bus error: {:?}", _0)]
+  HalI2CError(embedded_hal::i2c::ErrorKind),
+
+  #[cfg(feature = "hal")]
+  #[error(display = "digital input error: {:?}", _0)]
+  HalDigitalError(embedded_hal::digital::ErrorKind),
+
+  #[error(display = "io error: {}", _0)]
+  IOError(#[error(source)] std::io::Error),
+
   #[error(display = "invalid particle sensor mode: {:x}", _0)]
   InvalidParticleSensorMode(u8),
 
@@ -20,11 +142,36 @@ pub enum MetrifulError {
   #[error(display = "invalid cycle period: {}", _0)]
   InvalidCyclePeriodString(String),
 
+  #[error(display = "no supported cycle period near {:?}", _0)]
+  InvalidCyclePeriodDuration(std::time::Duration),
+
   #[error(display = "invalid operational mode: {:x}", _0)]
   InvalidOperationalMode(u8),
 
-  #[error(display = "exceeded timeout waiting for sensor to become ready")]
-  ReadyTimeoutExceeded,
+  /// A wait for the READY line timed out. A device that never becomes ready
+  /// (even after a reset) usually indicates a wiring or polarity problem with
+  /// the READY GPIO, while occasional timeouts during e.g. mode changes
+  /// suggest the timeout is too short.
+  #[error(
+    display = "{}: sensor did not become {} within {:?} (waited {:?})",
+    operation, wait, timeout, elapsed
+  )]
+  ReadyTimeoutExceeded {
+    /// The library operation in progress, e.g. `set_mode`
+    operation: &'static str,
+
+    /// The READY state being waited for
+    wait: ReadyWait,
+
+    /// The configured timeout
+    timeout: std::time::Duration,
+
+    /// Time spent waiting before giving up
+    elapsed: std::time::Duration,
+  },
+
+  #[error(display = "operation cancelled")]
+  Cancelled,
 
   #[error(display = "device status is required")]
   StatusMissing,
@@ -32,12 +179,22 @@ pub enum MetrifulError {
   #[error(display = "sensor is not in ready state")]
   NotReady,
 
+  #[error(display = "command sent too soon, retry after {:?}", retry_after)]
+  RateLimited {
+    retry_after: std::time::Duration,
+  },
+
   #[error(display = "command requires mode {:?} but current mode is {:?}", required, current)]
   InvalidMode {
     current: OperationalMode,
     required: OperationalMode,
   },
 
+  #[error(display = "metric {} is only valid in cycle mode, but the device is in standby", metric)]
+  MetricRequiresCycleMode {
+    metric: &'static str,
+  },
+
   #[error(display = "invalid AQI accuracy value: {}", _0)]
   InvalidAQIAccuracy(u8),
 
@@ -49,6 +206,336 @@ pub enum MetrifulError {
 
   #[error(display = "combined data may not be constructed from bytes")]
   InvalidCombinedDataFromBytes,
+
+  #[error(display = "{}", _0)]
+  RecordedError(String),
+
+  #[error(display = "metric at register {:#x} spans several reads and has no raw representation", _0)]
+  RawReadUnsupported(u8),
+
+  #[error(display = "this operation requires the {:?} feature", _0)]
+  FeatureRequired(&'static str),
+
+  #[error(display = "background task failed: {}", _0)]
+  AsyncTaskError(String),
+
+  #[error(display = "invalid READY polarity: {:?}", _0)]
+  InvalidReadyPolarity(String),
+
+  #[error(display = "invalid value for {}: {:?}", name, value)]
+  InvalidOption {
+    name: String,
+    value: String,
+  },
+
+  #[error(display = "unknown metric: {:?}", _0)]
+  InvalidMetricName(String),
+
+  #[error(display = "invalid IAQ baseline entry: {:?}", _0)]
+  InvalidIaqBaseline(String),
+
+  #[error(display = "register {} is not readable", _0)]
+  RegisterNotReadable(Register),
+
+  #[error(display = "register {} is not writable", _0)]
+  RegisterNotWritable(Register),
+
+  #[error(display = "register {} expects {} bytes but {} were given", register, expected, actual)]
+  InvalidRegisterLength {
+    register: Register,
+    expected: u8,
+    actual: usize,
+  },
+
+  /// A bus error (e.g. [`MetrifulError::I2CError`]) with the operation and
+  /// register it occurred at. See [`MetrifulError::root()`] to inspect the
+  /// underlying error.
+  #[error(display = "{}: {}", context, source)]
+  Bus {
+    context: ErrorContext,
+    #[error(source)] source: Box<MetrifulError>,
+  },
+
+  #[error(display = "short read from register {:#x}: expected {} bytes but got {}", register, expected, actual)]
+  ShortRead {
+    register: u8,
+    expected: usize,
+    actual: usize,
+  },
+
+  #[error(display = "implausible reading: {}", _0)]
+  ImplausibleReading(Implausible),
+
+  #[error(display = "device was not opened from an i2c device path and cannot be reopened")]
+  RecoverUnsupported,
+
+  /// A bus operation did not complete in time; see
+  /// [`TimeoutTransport`](crate::transport::TimeoutTransport).
+  #[error(display = "{} did not complete within {:?}", operation, timeout)]
+  TransactionTimeout {
+    operation: &'static str,
+    timeout: std::time::Duration,
+  },
+
+  #[error(display = "metric {} requires a particle sensor, but none is enabled", metric)]
+  ParticleSensorDisabled {
+    metric: &'static str,
+  },
+
+  /// [`Metriful::recover()`](crate::Metriful::recover) released the READY
+  /// line but could not reopen it. The device can't be used until a later
+  /// recovery attempt succeeds.
+  #[error(display = "READY line could not be reopened, device is unusable until recovered: {}", _0)]
+  ReadyLineLost(#[error(source)] Box<MetrifulError>),
+}
+
+impl MetrifulError {
+  /// Classifies this error, looking through any [`ErrorContext`].
+  ///
+  /// # Example
+  /// ```
+  /// use std::io;
+  /// use metriful::error::*;
+  ///
+  /// let eio = MetrifulError::IOError(io::Error::from_raw_os_error(5));
+  /// assert_eq!(eio.kind(), ErrorKind::TransientBus);
+  /// assert!(eio.is_retryable());
+  ///
+  /// let missing = MetrifulError::IOError(io::Error::from_raw_os_error(2));
+  /// assert_eq!(missing.kind(), ErrorKind::Bus);
+  /// assert!(!missing.is_retryable());
+  ///
+  /// assert_eq!(MetrifulError::NotReady.kind(), ErrorKind::InvalidState);
+  /// assert_eq!(MetrifulError::InvalidAQIAccuracy(7).kind(), ErrorKind::Protocol);
+  /// ```
+  pub fn kind(&self) -> ErrorKind {
+    match self.root() {
+      MetrifulError::I2CError(_) | MetrifulError::IOError(_) => match self.raw_os_error() {
+        Some(errno) if TRANSIENT_ERRNOS.contains(&errno) => ErrorKind::TransientBus,
+        _ => ErrorKind::Bus,
+      },
+
+      #[cfg(feature = "hal")]
+      MetrifulError::HalI2CError(kind) => {
+        use embedded_hal::i2c::ErrorKind as HalKind;
+
+        match kind {
+          HalKind::Bus | HalKind::ArbitrationLoss | HalKind::NoAcknowledge(_) | HalKind::Overrun => {
+            ErrorKind::TransientBus
+          },
+          _ => ErrorKind::Bus,
+        }
+      },
+
+      #[cfg(feature = "hal")]
+      MetrifulError::HalDigitalError(_) => ErrorKind::Bus,
+
+      #[cfg(feature = "cdev")]
+      MetrifulError::GpioCdevError(_) => ErrorKind::Bus,
+
+      MetrifulError::GPIOError(_) => ErrorKind::Bus,
+      MetrifulError::ShortRead { .. } => ErrorKind::TransientBus,
+
+      MetrifulError::ReadyTimeoutExceeded { .. }
+      | MetrifulError::TransactionTimeout { .. } => ErrorKind::Timeout,
+
+      MetrifulError::StatusMissing
+      | MetrifulError::NotReady
+      | MetrifulError::RateLimited { .. }
+      | MetrifulError::InvalidMode { .. }
+      | MetrifulError::MetricRequiresCycleMode { .. }
+      | MetrifulError::ParticleSensorDisabled { .. }
+      | MetrifulError::ReadyLineLost(_) => ErrorKind::InvalidState,
+
+      MetrifulError::InvalidParticleSensorMode(_)
+      | MetrifulError::InvalidCyclePeriod(_)
+      | MetrifulError::InvalidOperationalMode(_)
+      | MetrifulError::InvalidAQIAccuracy(_)
+      | MetrifulError::InvalidParticleDataValidity(_)
+      | MetrifulError::DecibelBandsError
+      | MetrifulError::InvalidCombinedDataFromBytes
+      | MetrifulError::ImplausibleReading(_) => ErrorKind::Protocol,
+
+      MetrifulError::InvalidCyclePeriodString(_)
+      | MetrifulError::InvalidCyclePeriodDuration(_)
+      | MetrifulError::RawReadUnsupported(_)
+      | MetrifulError::FeatureRequired(_)
+      | MetrifulError::InvalidReadyPolarity(_)
+      | MetrifulError::InvalidOption { .. }
+      | MetrifulError::InvalidMetricName(_)
+      | MetrifulError::InvalidIaqBaseline(_)
+      | MetrifulError::RegisterNotReadable(_)
+      | MetrifulError::RegisterNotWritable(_)
+      | MetrifulError::InvalidRegisterLength { .. }
+      | MetrifulError::RecoverUnsupported => ErrorKind::InvalidInput,
+
+      MetrifulError::Cancelled => ErrorKind::Cancelled,
+
+      MetrifulError::RecordedError(_)
+      | MetrifulError::AsyncTaskError(_)
+      | MetrifulError::Bus { .. } => ErrorKind::Other,
+    }
+  }
+
+  /// Returns a numeric code identifying the kind of error, looking through
+  /// any [`ErrorContext`]. Codes are stable: they are never reassigned, and
+  /// new errors receive new codes.
+  pub fn code(&self) -> u16 {
+    match self {
+      MetrifulError::I2CError(_) => 1,
+      MetrifulError::GPIOError(_) => 2,
+
+      #[cfg(feature = "cdev")]
+      MetrifulError::GpioCdevError(_) => 3,
+
+      #[cfg(feature = "hal")]
+      MetrifulError::HalI2CError(_) => 4,
+
+      #[cfg(feature = "hal")]
+      MetrifulError::HalDigitalError(_) => 5,
+
+      MetrifulError::IOError(_) => 6,
+      MetrifulError::InvalidParticleSensorMode(_) => 7,
+      MetrifulError::InvalidCyclePeriod(_) => 8,
+      MetrifulError::InvalidCyclePeriodString(_) => 9,
+      MetrifulError::InvalidCyclePeriodDuration(_) => 10,
+      MetrifulError::InvalidOperationalMode(_) => 11,
+      MetrifulError::ReadyTimeoutExceeded { .. } => 12,
+      MetrifulError::Cancelled => 13,
+      MetrifulError::StatusMissing => 14,
+      MetrifulError::NotReady => 15,
+      MetrifulError::RateLimited { .. } => 16,
+      MetrifulError::InvalidMode { .. } => 17,
+      MetrifulError::MetricRequiresCycleMode { .. } => 18,
+      MetrifulError::InvalidAQIAccuracy(_) => 19,
+      MetrifulError::InvalidParticleDataValidity(_) => 20,
+      MetrifulError::DecibelBandsError => 21,
+      MetrifulError::InvalidCombinedDataFromBytes => 22,
+      MetrifulError::RecordedError(_) => 23,
+      MetrifulError::RawReadUnsupported(_) => 24,
+      MetrifulError::FeatureRequired(_) => 25,
+      MetrifulError::AsyncTaskError(_) => 26,
+      MetrifulError::InvalidReadyPolarity(_) => 27,
+      MetrifulError::InvalidOption { .. } => 28,
+      MetrifulError::InvalidMetricName(_) => 29,
+      MetrifulError::InvalidIaqBaseline(_) => 30,
+      MetrifulError::RegisterNotReadable(_) => 31,
+      MetrifulError::RegisterNotWritable(_) => 32,
+      MetrifulError::InvalidRegisterLength { .. } => 33,
+      MetrifulError::Bus { source, .. } => source.code(),
+      MetrifulError::ShortRead { .. } => 34,
+      MetrifulError::ImplausibleReading(_) => 35,
+      MetrifulError::RecoverUnsupported => 36,
+      MetrifulError::TransactionTimeout { .. } => 37,
+      MetrifulError::ParticleSensorDisabled { .. } => 38,
+      MetrifulError::ReadyLineLost(_) => 39,
+    }
+  }
+
+  /// Returns true if repeating the operation may succeed without any other
+  /// intervention: transient bus errors, timeouts, and errors indicating the
+  /// device was busy ([`MetrifulError::NotReady`] and
+  /// [`MetrifulError::RateLimited`]).
+  pub fn is_retryable(&self) -> bool {
+    match self.kind() {
+      ErrorKind::TransientBus | ErrorKind::Timeout => true,
+      _ => matches!(self.root(), MetrifulError::NotReady | MetrifulError::RateLimited { .. }),
+    }
+  }
+
+  /// Returns the OS error number behind an I2C or I/O error, if any.
+  pub fn raw_os_error(&self) -> Option<i32> {
+    match self.root() {
+      MetrifulError::I2CError(LinuxI2CError::Io(e)) | MetrifulError::IOError(e) => e.raw_os_error(),
+      _ => None,
+    }
+  }
+
+  /// Converts I2C errors to their I/O representation, so that
+  /// [`MetrifulError::raw_os_error()`] can inspect them.
+  pub(crate) fn normalized(self) -> MetrifulError {
+    match self {
+      MetrifulError::I2CError(e) => MetrifulError::I2CError(LinuxI2CError::Io(io::Error::from(e))),
+      e => e,
+    }
+  }
+
+  /// Returns the underlying error, i.e. the source of a
+  /// [`MetrifulError::Bus`] error, or this error otherwise.
+  pub fn root(&self) -> &MetrifulError {
+    match self {
+      MetrifulError::Bus { source, .. } => source.root(),
+      e => e,
+    }
+  }
+
+  /// Returns where a bus error occurred, if known.
+  pub fn context(&self) -> Option<&ErrorContext> {
+    match self {
+      MetrifulError::Bus { context, .. } => Some(context),
+      _ => None,
+    }
+  }
+
+  /// Attaches context to bus errors. Other errors, and those that already
+  /// have context, are returned unchanged.
+  pub(crate) fn with_context(self, context: ErrorContext) -> MetrifulError {
+    match self {
+      #[cfg(feature = "hal")]
+      e @ MetrifulError::HalI2CError(_) => MetrifulError::Bus { context, source: Box::new(e) },
+
+      e @ MetrifulError::I2CError(_) | e @ MetrifulError::IOError(_) => {
+        MetrifulError::Bus { context, source: Box::new(e) }
+      },
+      e => e,
+    }
+  }
+}
+
+/// Errors serialize as an object with their [code](MetrifulError::code()),
+/// [kind](MetrifulError::kind()), message, and where available, OS error
+/// number and [`ErrorContext`].
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "serde_json")] {
+/// use std::io;
+/// use metriful::error::MetrifulError;
+///
+/// let e = MetrifulError::IOError(io::Error::from_raw_os_error(121));
+/// let json = serde_json::to_value(&e).unwrap();
+///
+/// assert_eq!(json["code"], 6);
+/// assert_eq!(json["kind"], "transient_bus");
+/// assert_eq!(json["retryable"], true);
+/// assert_eq!(json["errno"], 121);
+/// assert!(json.get("context").is_none());
+/// # }
+/// ```
+#[cfg(feature = "serde")]
+impl Serialize for MetrifulError {
+  fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+  where
+      S: Serializer
+  {
+    let mut state = serializer.serialize_struct("MetrifulError", 6)?;
+    state.serialize_field("code", &self.code())?;
+    state.serialize_field("kind", &self.kind())?;
+    state.serialize_field("message", &self.to_string())?;
+    state.serialize_field("retryable", &self.is_retryable())?;
+
+    match self.raw_os_error() {
+      Some(errno) => state.serialize_field("errno", &errno)?,
+      None => state.skip_field("errno")?,
+    }
+
+    match self.context() {
+      Some(context) => state.serialize_field("context", context)?,
+      None => state.skip_field("context")?,
+    }
+
+    state.end()
+  }
 }
 
 pub type Result<T> = std::result::Result<T, MetrifulError>;
@@ -0,0 +1,563 @@
+//! A single stream of device events: cycle readings, interrupt firings, mode
+//! changes, and recoverable errors.
+//!
+//! [`Metriful::event_stream()`] runs the device in cycle mode on a background
+//! thread and reports everything of interest as a [`MetrifulEvent`] over one
+//! channel, so applications can drive alerting from a single subscription.
+//!
+//! With an [`EventConfig::watchdog`], a device that keeps failing or stops
+//! producing readings is reset and reconfigured automatically, and the stream
+//! reports a [`MetrifulEvent::Recovered`] event rather than ending.
+//!
+//! Interrupts are reported by watching the MS430's light (LIT) and sound (SIT)
+//! interrupt outputs, which must be wired to inputs on the host and supplied
+//! via [`EventConfig`]. Like READY, these outputs are driven low when
+//! asserted, so any [`ReadyLine`] with the appropriate polarity may be used.
+//!
+//! # Example
+//! ```no_run
+//! use metriful::{Metriful, ReadyPolarity, metric::*};
+//! use metriful::events::*;
+//! use metriful::ready::SysfsReadyLine;
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+//!
+//! let mut config = EventConfig::default();
+//! config.sound_interrupt = Some(Box::new(SysfsReadyLine::new(23, ReadyPolarity::ActiveLow)?));
+//!
+//! let (_cmd_tx, events, _handle) = metriful.event_stream(METRIC_COMBINED_ALL, config);
+//! for event in events {
+//!   match event {
+//!     MetrifulEvent::Reading(reading) => println!("{}", reading),
+//!     MetrifulEvent::Interrupt(source) => println!("{:?} interrupt!", source),
+//!     other => println!("{:?}", other),
+//!   }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{trace, warn};
+
+use crate::{Metriful, ReaderCommand, ReaderHandles};
+use crate::cancel::CancelToken;
+use crate::config::DeviceConfig;
+use crate::error::{MetrifulError, Result};
+use crate::metric::Metric;
+use crate::ready::ReadyLine;
+use crate::sched;
+use crate::status::*;
+use crate::transport::MetrifulTransport;
+use crate::unit::*;
+
+/// The source of an interrupt event.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InterruptSource {
+  /// The light interrupt (LIT) output
+  Light,
+
+  /// The sound interrupt (SIT) output
+  Sound,
+}
+
+/// An event reported by [`Metriful::event_stream()`].
+#[derive(Debug)]
+pub enum MetrifulEvent<U>
+where
+  U: MetrifulUnit
+{
+  /// A new cycle reading
+  Reading(UnitValue<U>),
+
+  /// An interrupt output was asserted
+  Interrupt(InterruptSource),
+
+  /// The device's operational mode changed, e.g. because another process
+  /// reconfigured it
+  ModeChanged {
+    previous: OperationalMode,
+    current: OperationalMode,
+  },
+
+  /// An operation failed, but the stream will continue. The stream ends once
+  /// [`EventConfig::max_consecutive_errors`] errors occur in a row, unless a
+  /// [`Watchdog`] is configured.
+  Error(MetrifulError),
+
+  /// The [`Watchdog`] reset the device, re-applied its configuration, and
+  /// resumed cycle mode
+  Recovered(RecoveryReason),
+}
+
+/// Why the [`Watchdog`] reset the device.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RecoveryReason {
+  /// The given number of errors occurred in a row
+  ConsecutiveErrors(usize),
+
+  /// No reading was received for the given duration
+  Stalled(Duration),
+}
+
+/// Automatic recovery for [`Metriful::event_stream()`].
+///
+/// When triggered, the device is reset, the saved configuration is re-applied
+/// and cycle mode is re-entered at the stream's current period.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "testing")] {
+/// use std::time::Duration;
+/// use metriful::{Metriful, ReaderCommand, metric::*};
+/// use metriful::events::*;
+/// use metriful::testing::*;
+///
+/// let mock = MockDevice::new();
+/// mock.set_register(0x21, &[21, 5]);
+///
+/// let plan = FaultPlan::new().inject(FaultTrigger::Register(0x21), Fault::Timeout);
+/// let metriful = Metriful::try_new_device_timeout(
+///   mock.ready_line(),
+///   plan.wrap_device(mock.clone()),
+///   Some(Duration::from_millis(100)),
+/// ).unwrap();
+///
+/// let config = EventConfig {
+///   watchdog: Some(Watchdog { max_consecutive_errors: 1, ..Watchdog::default() }),
+///   ..EventConfig::default()
+/// };
+///
+/// let (cmd_tx, events, handle) = metriful.event_stream(METRIC_TEMPERATURE, config);
+/// assert!(matches!(events.recv().unwrap(), MetrifulEvent::Error(_)));
+/// assert!(matches!(
+///   events.recv().unwrap(),
+///   MetrifulEvent::Recovered(RecoveryReason::ConsecutiveErrors(1))
+/// ));
+/// assert!(matches!(events.recv().unwrap(), MetrifulEvent::Reading(_)));
+///
+/// cmd_tx.send(ReaderCommand::Stop).unwrap();
+/// handle.join().unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watchdog {
+  /// Recover after this many consecutive errors. Defaults to 3.
+  pub max_consecutive_errors: usize,
+
+  /// Recover if no reading arrives within this long after one was due, e.g.
+  /// because READY is stuck deasserted. Defaults to 30 seconds.
+  pub stall_timeout: Duration,
+
+  /// Configuration re-applied after a reset; its cycle period is ignored in
+  /// favor of the stream's. If None, the configuration in effect once the
+  /// stream entered cycle mode is used.
+  pub config: Option<DeviceConfig>,
+
+  /// The stream ends after this many recoveries (successful or not) without
+  /// a reading in between. Defaults to 3.
+  pub max_recoveries: usize,
+}
+
+impl Default for Watchdog {
+  fn default() -> Self {
+    Watchdog {
+      max_consecutive_errors: 3,
+      stall_timeout: Duration::from_secs(30),
+      config: None,
+      max_recoveries: 3,
+    }
+  }
+}
+
+/// Configuration for [`Metriful::event_stream()`].
+pub struct EventConfig {
+  /// Cycle period to read at. Defaults to [`CyclePeriod::Period0`].
+  pub cycle_period: CyclePeriod,
+
+  /// Timeout waiting for the device during setup. Defaults to 5 seconds.
+  pub timeout: Option<Duration>,
+
+  /// How often the READY and interrupt lines are checked. Defaults to 10ms.
+  pub poll_interval: Duration,
+
+  /// Input connected to the light interrupt (LIT) output, if any
+  pub light_interrupt: Option<Box<dyn ReadyLine>>,
+
+  /// Input connected to the sound interrupt (SIT) output, if any
+  pub sound_interrupt: Option<Box<dyn ReadyLine>>,
+
+  /// If true, latched interrupts are cleared after being reported. Defaults
+  /// to true.
+  pub auto_clear_interrupts: bool,
+
+  /// The device status is re-read at least this often (and after every
+  /// reading) to detect mode changes. Defaults to 30 seconds.
+  pub status_interval: Duration,
+
+  /// The stream ends after this many consecutive errors. Defaults to 5.
+  /// Ignored if a watchdog is set.
+  pub max_consecutive_errors: usize,
+
+  /// If set, the device is reset and reconfigured when it fails repeatedly or
+  /// stops producing readings. Defaults to None.
+  pub watchdog: Option<Watchdog>,
+}
+
+impl Default for EventConfig {
+  fn default() -> Self {
+    EventConfig {
+      cycle_period: CyclePeriod::Period0,
+      timeout: Some(Duration::from_secs(5)),
+      poll_interval: Duration::from_millis(10),
+      light_interrupt: None,
+      sound_interrupt: None,
+      auto_clear_interrupts: true,
+      status_interval: Duration::from_secs(30),
+      max_consecutive_errors: 5,
+      watchdog: None,
+    }
+  }
+}
+
+impl fmt::Debug for EventConfig {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("EventConfig")
+      .field("cycle_period", &self.cycle_period)
+      .field("timeout", &self.timeout)
+      .field("poll_interval", &self.poll_interval)
+      .field("light_interrupt", &self.light_interrupt)
+      .field("sound_interrupt", &self.sound_interrupt)
+      .field("auto_clear_interrupts", &self.auto_clear_interrupts)
+      .field("status_interval", &self.status_interval)
+      .field("max_consecutive_errors", &self.max_consecutive_errors)
+      .field("watchdog", &self.watchdog)
+      .finish()
+  }
+}
+
+/// Edge detection state for one interrupt input.
+struct InterruptWatch {
+  source: InterruptSource,
+  line: Box<dyn ReadyLine>,
+  asserted: bool,
+}
+
+struct EventLoop<U, D>
+where
+  U: MetrifulUnit,
+  D: MetrifulTransport,
+{
+  metriful: Metriful<D>,
+  metric: Metric<U>,
+  config: EventConfig,
+  interrupts: Vec<InterruptWatch>,
+  tx: Sender<MetrifulEvent<U>>,
+
+  mode: Option<OperationalMode>,
+  last_status: Instant,
+  last_reading: Instant,
+  was_ready: bool,
+  errors: usize,
+
+  saved_config: DeviceConfig,
+  recoveries: usize,
+}
+
+impl<U, D> EventLoop<U, D>
+where
+  U: MetrifulUnit,
+  D: MetrifulTransport,
+{
+  /// Sends an event, returning false if the receiver has gone away.
+  fn send(&self, event: MetrifulEvent<U>) -> bool {
+    self.tx.send(event).is_ok()
+  }
+
+  /// Reports an error, returning false if the stream should end.
+  fn error(&mut self, error: MetrifulError) -> bool {
+    self.errors += 1;
+    trace!("events: error {}/{}: {}", self.errors, self.config.max_consecutive_errors, error);
+
+    if !self.send(MetrifulEvent::Error(error)) {
+      return false;
+    }
+
+    match &self.config.watchdog {
+      Some(watchdog) if self.errors >= watchdog.max_consecutive_errors => {
+        self.recover(RecoveryReason::ConsecutiveErrors(self.errors))
+      },
+      Some(_) => true,
+      None => self.errors < self.config.max_consecutive_errors,
+    }
+  }
+
+  /// Recovers the device if no reading has arrived for too long, returning
+  /// false if the stream should end.
+  fn check_stalled(&mut self) -> bool {
+    let stall_timeout = match &self.config.watchdog {
+      Some(watchdog) => watchdog.stall_timeout,
+      None => return true,
+    };
+
+    let elapsed = self.last_reading.elapsed();
+    if elapsed > self.config.cycle_period.to_duration() + stall_timeout {
+      self.recover(RecoveryReason::Stalled(elapsed))
+    } else {
+      true
+    }
+  }
+
+  /// Resets and reconfigures the device, returning false if the stream should
+  /// end.
+  fn recover(&mut self, reason: RecoveryReason) -> bool {
+    let max_recoveries = self.config.watchdog.as_ref().map_or(0, |w| w.max_recoveries);
+    if self.recoveries >= max_recoveries {
+      warn!("events: giving up after {} recoveries", self.recoveries);
+      return false;
+    }
+
+    self.recoveries += 1;
+    warn!("events: recovering device ({}/{}): {:?}", self.recoveries, max_recoveries, reason);
+
+    // failures are reported directly, as recovery is only retried on the next
+    // error or stall
+    if let Err(e) = self.reset_and_resume() {
+      self.last_reading = Instant::now();
+      return self.send(MetrifulEvent::Error(e));
+    }
+
+    self.errors = 0;
+    self.send(MetrifulEvent::Recovered(reason))
+  }
+
+  fn reset_and_resume(&mut self) -> Result<()> {
+    let timeout = self.config.timeout;
+    self.metriful.force_reset_timeout(timeout)?;
+
+    let config = DeviceConfig {
+      cycle_period: Some(self.config.cycle_period),
+      ..self.saved_config.clone()
+    };
+
+    self.metriful.apply_config_timeout(&config, timeout)?;
+
+    self.mode = Some(OperationalMode::Cycle(self.config.cycle_period));
+    self.last_status = Instant::now();
+    self.last_reading = Instant::now();
+    self.was_ready = false;
+
+    Ok(())
+  }
+
+  fn check_status(&mut self) -> bool {
+    self.last_status = Instant::now();
+
+    let status = match self.metriful.read_status() {
+      Ok(status) => status,
+      Err(e) => return self.error(e),
+    };
+
+    match self.mode.replace(status.mode) {
+      Some(previous) if previous != status.mode => self.send(MetrifulEvent::ModeChanged {
+        previous,
+        current: status.mode,
+      }),
+      _ => true,
+    }
+  }
+
+  fn check_interrupts(&mut self) -> bool {
+    for i in 0..self.interrupts.len() {
+      let asserted = match self.interrupts[i].line.is_ready() {
+        Ok(asserted) => asserted,
+        Err(e) => return self.error(e),
+      };
+
+      let watch = &mut self.interrupts[i];
+      let rising = asserted && !watch.asserted;
+      let source = watch.source;
+      watch.asserted = asserted;
+
+      if rising && !self.send(MetrifulEvent::Interrupt(source)) {
+        return false;
+      }
+
+      if asserted && self.config.auto_clear_interrupts && self.metriful.is_ready().unwrap_or(false) {
+        let cleared = match source {
+          InterruptSource::Light => self.metriful.clear_light_interrupt(),
+          InterruptSource::Sound => self.metriful.clear_sound_interrupt(),
+        };
+
+        if let Err(e) = cleared {
+          if !self.error(e) {
+            return false;
+          }
+        }
+      }
+    }
+
+    true
+  }
+
+  /// Re-enters cycle mode at the configured period, e.g. after a pause.
+  fn enter_cycle_mode(&mut self) -> bool {
+    let mode = OperationalMode::Cycle(self.config.cycle_period);
+    if let Err(e) = self.metriful.set_mode_timeout(mode, self.config.timeout) {
+      return self.error(e);
+    }
+
+    // deliberate changes aren't reported as mode change events
+    self.mode = Some(mode);
+    self.last_status = Instant::now();
+    self.last_reading = Instant::now();
+    true
+  }
+
+  fn run(mut self, cmd_rx: Receiver<ReaderCommand>) -> Metriful<D> {
+    let mode = OperationalMode::Cycle(self.config.cycle_period);
+    let status = match self.metriful.set_mode_timeout(mode, self.config.timeout) {
+      Ok(status) => status,
+      Err(e) => {
+        self.send(MetrifulEvent::Error(e));
+        return self.metriful;
+      }
+    };
+
+    self.saved_config = match self.config.watchdog.as_ref().and_then(|w| w.config.clone()) {
+      Some(config) => config,
+      None => DeviceConfig::from_status(&status),
+    };
+
+    self.mode = Some(mode);
+    self.last_status = Instant::now();
+    self.last_reading = Instant::now();
+
+    // the device is ready immediately after entering cycle mode
+    self.was_ready = false;
+
+    loop {
+      if self.metriful.cancel_token().is_some_and(CancelToken::is_cancelled) {
+        trace!("events: cancelled");
+        break;
+      }
+
+      let resumed = match cmd_rx.try_recv() {
+        Ok(ReaderCommand::Stop) => {
+          trace!("events: stop");
+          break;
+        },
+        Ok(ReaderCommand::Pause) => {
+          let cancel = self.metriful.cancel_token();
+          if !crate::wait_for_resume(&cmd_rx, &mut self.config.cycle_period, cancel) {
+            break;
+          }
+
+          true
+        },
+        Ok(ReaderCommand::ChangeCyclePeriod(period)) => {
+          self.config.cycle_period = period;
+          true
+        },
+        Ok(ReaderCommand::Resume) | Err(_) => false,
+      };
+
+      if resumed {
+        if !self.enter_cycle_mode() {
+          break;
+        }
+
+        self.was_ready = false;
+      }
+
+      if !self.check_interrupts() || !self.check_stalled() {
+        break;
+      }
+
+      let ready = match self.metriful.is_ready() {
+        Ok(ready) => ready,
+        Err(e) => if self.error(e) {
+          thread::sleep(self.config.poll_interval);
+          continue;
+        } else {
+          break;
+        }
+      };
+
+      // recovery below may reset this, so it's updated first
+      let was_ready = std::mem::replace(&mut self.was_ready, ready);
+      if ready && !was_ready {
+        match self.metriful.read(self.metric) {
+          Ok(reading) => {
+            self.errors = 0;
+            self.recoveries = 0;
+            self.last_reading = Instant::now();
+            if !self.send(MetrifulEvent::Reading(reading)) || !self.check_status() {
+              break;
+            }
+          },
+          Err(e) => if !self.error(e) {
+            break;
+          },
+        }
+      } else if self.last_status.elapsed() >= self.config.status_interval && !self.check_status() {
+        break;
+      }
+
+      thread::sleep(self.config.poll_interval);
+    }
+
+    self.metriful
+  }
+}
+
+/// Spawns the event thread; see [`Metriful::event_stream()`].
+pub(crate) fn spawn<U, D>(
+  metriful: Metriful<D>,
+  metric: Metric<U>,
+  mut config: EventConfig,
+) -> ReaderHandles<D, Receiver<MetrifulEvent<U>>>
+where
+  U: MetrifulUnit + 'static,
+  D: MetrifulTransport + Send + 'static,
+{
+  let (cmd_tx, cmd_rx) = mpsc::channel();
+  let (tx, rx) = mpsc::channel();
+
+  let mut interrupts = Vec::new();
+  if let Some(line) = config.light_interrupt.take() {
+    interrupts.push(InterruptWatch { source: InterruptSource::Light, line, asserted: false });
+  }
+
+  if let Some(line) = config.sound_interrupt.take() {
+    interrupts.push(InterruptWatch { source: InterruptSource::Sound, line, asserted: false });
+  }
+
+  let event_loop = EventLoop {
+    metriful,
+    metric,
+    config,
+    interrupts,
+    tx,
+    mode: None,
+    last_status: Instant::now(),
+    last_reading: Instant::now(),
+    was_ready: false,
+    errors: 0,
+    saved_config: DeviceConfig::default(),
+    recoveries: 0,
+  };
+
+  let scheduling = event_loop.metriful.thread_scheduling();
+  let handle = thread::spawn(move || {
+    sched::apply_hint(scheduling);
+    event_loop.run(cmd_rx)
+  });
+
+  (cmd_tx, rx, handle)
+}
@@ -0,0 +1,142 @@
+//! Display formatting options for read values.
+//!
+//! [`UnitValue`]'s `Display` impl and the `formatted_value` field in its serde
+//! output use the crate-wide default [`FormatOptions`], which initially match
+//! Rust's default float formatting (e.g. `21.5 ℃`). Applications wanting tidy,
+//! column-aligned output can change the default once at startup with
+//! [`set_default_format_options()`], or format individual values with
+//! [`UnitValue::format_with()`].
+//!
+//! Options apply to scalar values; combined reads (e.g.
+//! [`CombinedAirData`](crate::unit::CombinedAirData)) format each component
+//! using the crate-wide default.
+//!
+//! # Example
+//! ```
+//! use metriful::format::{FormatOptions, UnitPlacement};
+//! use metriful::unit::*;
+//!
+//! let pressure: UnitValue<UnitPascals> = UnitValue::new(84247);
+//! let temperature: UnitValue<UnitDegreesCelsius> = UnitValue::new(21.5);
+//!
+//! let options = FormatOptions {
+//!   precision: Some(2),
+//!   thousands_separator: Some(','),
+//!   ..FormatOptions::default()
+//! };
+//!
+//! assert_eq!(pressure.format_with(&options), "84,247 Pa");
+//! assert_eq!(temperature.format_with(&options), "21.50 ℃");
+//!
+//! let options = FormatOptions {
+//!   unit_placement: UnitPlacement::Omit,
+//!   ..FormatOptions::default()
+//! };
+//! assert_eq!(temperature.format_with(&options), "21.5");
+//! ```
+
+use std::fmt;
+use std::sync::RwLock;
+
+#[cfg(doc)] use crate::unit::UnitValue;
+
+/// Where a unit's symbol is placed relative to a formatted value.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum UnitPlacement {
+  /// After the value, separated by a space, e.g. `21.5 ℃`
+  Suffix,
+
+  /// Directly after the value, e.g. `21.5℃`
+  Attached,
+
+  /// The symbol is left out, e.g. `21.5`
+  Omit,
+}
+
+/// Options controlling how values are formatted for display.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FormatOptions {
+  /// Number of decimal places shown for fractional values. Integer values are
+  /// unaffected. If None, Rust's default float formatting is used.
+  pub precision: Option<usize>,
+
+  /// Where the unit symbol, if any, is placed.
+  pub unit_placement: UnitPlacement,
+
+  /// If set, groups the integer digits of numeric values in threes with the
+  /// given separator, e.g. `84,247`.
+  pub thousands_separator: Option<char>,
+}
+
+impl FormatOptions {
+  const DEFAULT: FormatOptions = FormatOptions {
+    precision: None,
+    unit_placement: UnitPlacement::Suffix,
+    thousands_separator: None,
+  };
+
+  /// Formats a value and optional unit symbol according to these options.
+  pub fn format(&self, value: &impl fmt::Display, symbol: Option<&str>) -> String {
+    let mut ret = match self.precision {
+      Some(precision) => format!("{:.*}", precision, value),
+      None => value.to_string(),
+    };
+
+    if let Some(separator) = self.thousands_separator {
+      ret = group_thousands(&ret, separator);
+    }
+
+    match (symbol, self.unit_placement) {
+      (Some(symbol), UnitPlacement::Suffix) => format!("{} {}", ret, symbol),
+      (Some(symbol), UnitPlacement::Attached) => format!("{}{}", ret, symbol),
+      _ => ret,
+    }
+  }
+}
+
+impl Default for FormatOptions {
+  fn default() -> Self {
+    FormatOptions::DEFAULT
+  }
+}
+
+/// Inserts a separator between groups of three integer digits. Values that
+/// aren't numeric are returned unchanged.
+fn group_thousands(value: &str, separator: char) -> String {
+  let (sign, unsigned) = match value.strip_prefix('-') {
+    Some(unsigned) => ("-", unsigned),
+    None => ("", value),
+  };
+
+  let digits = unsigned.find(|c: char| !c.is_ascii_digit()).unwrap_or(unsigned.len());
+  if digits == 0 {
+    return value.to_string();
+  }
+
+  let (integer, rest) = unsigned.split_at(digits);
+
+  let mut ret = String::from(sign);
+  for (i, c) in integer.chars().enumerate() {
+    if i > 0 && (digits - i) % 3 == 0 {
+      ret.push(separator);
+    }
+
+    ret.push(c);
+  }
+
+  ret.push_str(rest);
+  ret
+}
+
+static DEFAULT_OPTIONS: RwLock<FormatOptions> = RwLock::new(FormatOptions::DEFAULT);
+
+/// Returns the crate-wide default format options.
+pub fn default_format_options() -> FormatOptions {
+  *DEFAULT_OPTIONS.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Sets the crate-wide default format options, used when displaying values
+/// and in serialized `formatted_value` fields.
+pub fn set_default_format_options(options: FormatOptions) {
+  *DEFAULT_OPTIONS.write().unwrap_or_else(|e| e.into_inner()) = options;
+}
@@ -0,0 +1,187 @@
+//! Enforcement of the datasheet's minimum spacing between device commands.
+
+use std::time::{Duration, Instant};
+
+use log::trace;
+
+use crate::error::*;
+
+/// Minimum delay between dependent commands, per the datasheet.
+pub const COMMAND_INTERVAL: Duration = Duration::from_millis(6);
+
+/// Time for the READY signal to respond after a mode change or reset.
+pub const MODE_CHANGE_SETTLE: Duration = Duration::from_millis(11);
+
+/// Minimum interval between on-demand measurements for valid results.
+pub const MEASUREMENT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Minimum spacing enforced between commands sent to the device.
+///
+/// Commands issued too soon are rejected with
+/// [`MetrifulError::RateLimited`] rather than being sent, since the device may
+/// otherwise silently return corrupted readings. Enabled by default; see
+/// [`RateLimit::disabled()`] to opt out.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+  /// If false, no limits are enforced.
+  pub enabled: bool,
+
+  /// Minimum time between any two commands.
+  pub command_interval: Duration,
+
+  /// Minimum time after a mode change or reset before any further command.
+  pub mode_change_settle: Duration,
+
+  /// Minimum time between on-demand measurements.
+  pub measurement_interval: Duration,
+}
+
+impl Default for RateLimit {
+  fn default() -> Self {
+    RateLimit {
+      enabled: true,
+      command_interval: COMMAND_INTERVAL,
+      mode_change_settle: MODE_CHANGE_SETTLE,
+      measurement_interval: MEASUREMENT_INTERVAL,
+    }
+  }
+}
+
+impl RateLimit {
+  /// Returns a `RateLimit` that enforces nothing.
+  pub fn disabled() -> RateLimit {
+    RateLimit {
+      enabled: false,
+      ..RateLimit::default()
+    }
+  }
+}
+
+/// Classes of commands subject to different limits.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum CommandKind {
+  /// An on-demand measurement (0xE1)
+  Measurement,
+
+  /// A mode change or reset (0xE2, 0xE4, 0xE5)
+  ModeChange,
+
+  /// Any other command or register write
+  Other,
+}
+
+/// Tracks command history and applies a [`RateLimit`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CommandGuard {
+  pub(crate) limit: RateLimit,
+
+  last_command: Option<Instant>,
+  last_measurement: Option<Instant>,
+  last_mode_change: Option<Instant>,
+}
+
+fn remaining_since(since: Option<Instant>, interval: Duration, now: Instant) -> Duration {
+  since
+    .map(|t| interval.checked_sub(now.duration_since(t)).unwrap_or_default())
+    .unwrap_or_default()
+}
+
+impl CommandGuard {
+  /// Returns how long the caller must wait before a command of the given kind
+  /// will be accepted.
+  pub(crate) fn remaining(&self, kind: CommandKind) -> Duration {
+    if !self.limit.enabled {
+      return Duration::from_secs(0);
+    }
+
+    let now = Instant::now();
+    let mut remaining = remaining_since(self.last_command, self.limit.command_interval, now)
+      .max(remaining_since(self.last_mode_change, self.limit.mode_change_settle, now));
+
+    if kind == CommandKind::Measurement {
+      remaining = remaining.max(
+        remaining_since(self.last_measurement, self.limit.measurement_interval, now)
+      );
+    }
+
+    remaining
+  }
+
+  /// Checks that a command of the given kind may be sent now and, if so,
+  /// records it.
+  pub(crate) fn check(&mut self, kind: CommandKind) -> Result<()> {
+    let retry_after = self.remaining(kind);
+    if retry_after > Duration::from_secs(0) {
+      trace!("CommandGuard::check({:?}): rejected, retry after {:?}", kind, retry_after);
+      return Err(MetrifulError::RateLimited { retry_after });
+    }
+
+    let now = Instant::now();
+    self.last_command = Some(now);
+    match kind {
+      CommandKind::Measurement => self.last_measurement = Some(now),
+      CommandKind::ModeChange => self.last_mode_change = Some(now),
+      CommandKind::Other => (),
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn commands_too_close_together_are_rejected() {
+    let mut guard = CommandGuard::default();
+    guard.check(CommandKind::Other).unwrap();
+
+    match guard.check(CommandKind::Other) {
+      Err(MetrifulError::RateLimited { retry_after }) => assert!(retry_after <= COMMAND_INTERVAL),
+      other => panic!("expected RateLimited, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn measurements_are_limited_separately() {
+    let mut guard = CommandGuard::default();
+    guard.limit.command_interval = Duration::from_secs(0);
+
+    guard.check(CommandKind::Measurement).unwrap();
+    guard.check(CommandKind::Other).unwrap();
+    assert!(guard.remaining(CommandKind::Measurement) > COMMAND_INTERVAL);
+    assert!(guard.check(CommandKind::Measurement).is_err());
+  }
+
+  #[test]
+  fn mode_changes_settle_before_any_command() {
+    let mut guard = CommandGuard::default();
+    guard.limit.command_interval = Duration::from_secs(0);
+
+    guard.check(CommandKind::ModeChange).unwrap();
+    assert!(guard.check(CommandKind::Other).is_err());
+  }
+
+  #[test]
+  fn rejected_commands_are_not_recorded() {
+    let mut guard = CommandGuard::default();
+    guard.limit.measurement_interval = Duration::from_millis(50);
+
+    guard.check(CommandKind::Measurement).unwrap();
+    assert!(guard.check(CommandKind::Measurement).is_err());
+
+    std::thread::sleep(Duration::from_millis(60));
+    guard.check(CommandKind::Measurement).unwrap();
+  }
+
+  #[test]
+  fn disabled_limits_accept_everything() {
+    let mut guard = CommandGuard { limit: RateLimit::disabled(), ..Default::default() };
+
+    for _ in 0..3 {
+      guard.check(CommandKind::Measurement).unwrap();
+      guard.check(CommandKind::ModeChange).unwrap();
+    }
+  }
+}
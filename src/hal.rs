@@ -0,0 +1,145 @@
+//! Adapters for driving an MS430 via [`embedded_hal`] I2C and digital input
+//! traits rather than Linux device files.
+//!
+//! [`HalTransport`] wraps any [`embedded_hal::i2c::I2c`] bus as a
+//! [`MetrifulTransport`], and [`HalReadyLine`] wraps any
+//! [`embedded_hal::digital::InputPin`] as a [`ReadyLine`]. Together with
+//! [`Metriful::try_new_device_timeout()`] these allow the register map, parsers
+//! and unit types to be reused with any HAL implementation.
+//!
+//! Note that the crate as a whole still requires `std` (e.g. for timestamps
+//! and background readers), so targets must provide it, as with ESP32 boards
+//! using `esp-idf`.
+//!
+//! # Example
+//! ```ignore
+//! use metriful::{Metriful, ReadyPolarity, metric::*};
+//! use metriful::hal::{HalReadyLine, HalTransport};
+//!
+//! let transport = HalTransport::new(i2c, 0x71);
+//! let ready = HalReadyLine::new(ready_pin, ReadyPolarity::ActiveLow);
+//!
+//! let mut metriful = Metriful::try_new_device_timeout(ready, transport, None)?;
+//! let temperature = metriful.read(METRIC_TEMPERATURE)?;
+//! ```
+
+use std::fmt;
+use std::sync::Mutex;
+
+use embedded_hal::digital::{self, InputPin};
+use embedded_hal::i2c::{self, I2c};
+
+use crate::error::*;
+use crate::ready::{ReadyLine, ReadyPolarity};
+use crate::transport::MetrifulTransport;
+
+#[cfg(doc)] use crate::Metriful;
+
+fn i2c_error<E: i2c::Error>(e: E) -> MetrifulError {
+  MetrifulError::HalI2CError(e.kind())
+}
+
+fn digital_error<E: digital::Error>(e: E) -> MetrifulError {
+  MetrifulError::HalDigitalError(e.kind())
+}
+
+/// An [`embedded_hal`] I2C bus addressing a single MS430.
+#[derive(Debug)]
+pub struct HalTransport<I> {
+  i2c: I,
+  address: u8,
+}
+
+impl<I: I2c> HalTransport<I> {
+  /// Wraps the given bus; `address` is usually 0x71, or 0x70 if the solder
+  /// bridge is closed.
+  pub fn new(i2c: I, address: u8) -> HalTransport<I> {
+    HalTransport { i2c, address }
+  }
+
+  /// Returns the device address.
+  pub fn address(&self) -> u8 {
+    self.address
+  }
+
+  /// Returns the wrapped bus.
+  pub fn into_inner(self) -> I {
+    self.i2c
+  }
+}
+
+impl<I: I2c> MetrifulTransport for HalTransport<I> {
+  fn write_byte(&mut self, command: u8) -> Result<()> {
+    self.i2c.write(self.address, &[command]).map_err(i2c_error)
+  }
+
+  fn read_byte(&mut self, register: u8) -> Result<u8> {
+    let mut buf = [0u8; 1];
+    self.i2c.write_read(self.address, &[register], &mut buf).map_err(i2c_error)?;
+
+    Ok(buf[0])
+  }
+
+  fn write_byte_data(&mut self, register: u8, value: u8) -> Result<()> {
+    self.i2c.write(self.address, &[register, value]).map_err(i2c_error)
+  }
+
+  fn read_block(&mut self, register: u8, len: u8) -> Result<Vec<u8>> {
+    let mut buf = vec![0u8; len as usize];
+    self.i2c.write_read(self.address, &[register], &mut buf).map_err(i2c_error)?;
+
+    Ok(buf)
+  }
+
+  fn write_block(&mut self, register: u8, values: &[u8]) -> Result<()> {
+    let mut buf = Vec::with_capacity(values.len() + 1);
+    buf.push(register);
+    buf.extend_from_slice(values);
+
+    self.i2c.write(self.address, &buf).map_err(i2c_error)
+  }
+}
+
+/// An [`embedded_hal`] digital input connected to the sensor's READY output,
+/// with configurable [`ReadyPolarity`].
+pub struct HalReadyLine<P> {
+  pin: Mutex<P>,
+  polarity: ReadyPolarity,
+}
+
+impl<P> fmt::Debug for HalReadyLine<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("HalReadyLine")
+      .field("polarity", &self.polarity)
+      .finish()
+  }
+}
+
+impl<P: InputPin> HalReadyLine<P> {
+  /// Wraps an input pin already configured by the HAL.
+  pub fn new(pin: P, polarity: ReadyPolarity) -> HalReadyLine<P> {
+    HalReadyLine {
+      pin: Mutex::new(pin),
+      polarity,
+    }
+  }
+
+  /// Returns the configured polarity.
+  pub fn polarity(&self) -> ReadyPolarity {
+    self.polarity
+  }
+
+  /// Returns the wrapped pin.
+  pub fn into_inner(self) -> P {
+    self.pin.into_inner().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+impl<P: InputPin + Send> ReadyLine for HalReadyLine<P> {
+  fn is_ready(&self) -> Result<bool> {
+    let mut pin = self.pin.lock().unwrap_or_else(|e| e.into_inner());
+    let high = pin.is_high().map_err(digital_error)?;
+
+    Ok(self.polarity.is_asserted(high as u8))
+  }
+}
@@ -0,0 +1,264 @@
+//! Host-side indoor air quality (IAQ) estimation.
+//!
+//! The MS430's own air quality outputs ([`METRIC_AQI`] and friends) are
+//! only valid in cycle mode. [`IaqEstimator`] computes a comparable BME680-style
+//! score directly from gas sensor resistance and relative humidity, so it may
+//! also be used with on-demand measurements in standby mode.
+//!
+//! The estimate is relative to a gas resistance baseline learned over time.
+//! The baseline can be saved and restored via [`IaqBaseline::save()`] and
+//! [`IaqBaseline::load()`] so scores remain comparable across restarts.
+//!
+//! Like the on-device AQI, scores range from 0 (excellent) to 500 (extremely
+//! polluted).
+//!
+//! # Example
+//! ```no_run
+//! use std::time::Duration;
+//! use metriful::{Metriful, metric::*, iaq::*};
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+//!
+//! let baseline = IaqBaseline::load("/var/lib/metriful/iaq").unwrap_or_default();
+//! let mut estimator = IaqEstimator::with_baseline(IaqConfig::default(), baseline);
+//!
+//! for air in metriful.read_iter(METRIC_COMBINED_AIR_DATA, Duration::from_secs(3)) {
+//!   let reading = estimator.update_air_data(&air?.value);
+//!   println!("IAQ: {} ({})", reading.iaq, reading.accuracy);
+//!
+//!   estimator.baseline().save("/var/lib/metriful/iaq")?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`METRIC_AQI`]: crate::metric::METRIC_AQI
+
+use std::fs;
+use std::path::Path;
+
+use chrono::Utc;
+
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+use crate::error::*;
+use crate::unit::*;
+
+/// Tuning parameters for [`IaqEstimator`].
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IaqConfig {
+  /// The ideal relative humidity; deviations in either direction reduce the
+  /// score. Defaults to 40% RH.
+  pub humidity_baseline: f32,
+
+  /// Fraction of the score (0-1) contributed by humidity, with the remainder
+  /// contributed by gas resistance. Defaults to 0.25.
+  pub humidity_weighting: f32,
+
+  /// Number of samples to average before the gas baseline is considered
+  /// usable. Scores are reported with [`AQIAccuracy::Invalid`] until then.
+  pub burn_in_samples: u64,
+
+  /// Smoothing factor applied when gas resistance rises above the baseline.
+  pub baseline_rise_alpha: f32,
+
+  /// Smoothing factor applied when gas resistance falls below the baseline.
+  /// This should be much smaller than `baseline_rise_alpha` so that periods of
+  /// poor air quality do not drag the baseline down.
+  pub baseline_fall_alpha: f32,
+}
+
+impl Default for IaqConfig {
+  fn default() -> Self {
+    IaqConfig {
+      humidity_baseline: 40.0,
+      humidity_weighting: 0.25,
+      burn_in_samples: 50,
+      baseline_rise_alpha: 0.1,
+      baseline_fall_alpha: 0.001,
+    }
+  }
+}
+
+/// Learned gas resistance baseline.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IaqBaseline {
+  /// Baseline gas sensor resistance in ohms, representing clean air.
+  pub gas_resistance: f32,
+
+  /// Total number of samples that have contributed to the baseline.
+  pub samples: u64,
+}
+
+impl IaqBaseline {
+  /// Loads a baseline previously written by [`IaqBaseline::save()`].
+  pub fn load(path: impl AsRef<Path>) -> Result<IaqBaseline> {
+    let contents = fs::read_to_string(path)?;
+
+    let mut baseline = IaqBaseline::default();
+    for line in contents.lines().map(str::trim).filter(|l| !l.is_empty()) {
+      let invalid = || MetrifulError::InvalidIaqBaseline(line.to_string());
+
+      let mut parts = line.splitn(2, '=');
+      let key = parts.next().ok_or_else(invalid)?.trim();
+      let value = parts.next().ok_or_else(invalid)?.trim();
+
+      match key {
+        "gas_resistance" => baseline.gas_resistance = value.parse().map_err(|_| invalid())?,
+        "samples" => baseline.samples = value.parse().map_err(|_| invalid())?,
+        _ => return Err(invalid()),
+      }
+    }
+
+    Ok(baseline)
+  }
+
+  /// Writes this baseline to the given path as `key=value` lines.
+  pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+    fs::write(path, format!(
+      "gas_resistance={}\nsamples={}\n",
+      self.gas_resistance, self.samples
+    ))?;
+
+    Ok(())
+  }
+}
+
+/// A single host-computed IAQ result.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IaqReading {
+  /// The IAQ score, 0 (excellent) to 500 (extremely polluted).
+  pub iaq: UnitValue<UnitAirQualityIndex>,
+
+  /// Confidence in the score, based on the maturity of the baseline.
+  pub accuracy: UnitValue<UnitAQIAccuracy>,
+}
+
+/// Computes IAQ scores from gas resistance and humidity samples while tracking
+/// a clean-air gas resistance baseline.
+#[derive(Debug, Clone)]
+pub struct IaqEstimator {
+  config: IaqConfig,
+  baseline: IaqBaseline,
+}
+
+impl IaqEstimator {
+  /// Creates a new estimator with no prior baseline.
+  pub fn new(config: IaqConfig) -> IaqEstimator {
+    IaqEstimator::with_baseline(config, IaqBaseline::default())
+  }
+
+  /// Creates a new estimator resuming from a previously saved baseline.
+  pub fn with_baseline(config: IaqConfig, baseline: IaqBaseline) -> IaqEstimator {
+    IaqEstimator { config, baseline }
+  }
+
+  /// Returns the current baseline, e.g. to persist it.
+  pub fn baseline(&self) -> &IaqBaseline {
+    &self.baseline
+  }
+
+  /// Returns the estimator configuration.
+  pub fn config(&self) -> &IaqConfig {
+    &self.config
+  }
+
+  fn update_baseline(&mut self, gas_resistance: f32) {
+    let baseline = &mut self.baseline;
+    baseline.samples = baseline.samples.saturating_add(1);
+
+    if baseline.samples <= self.config.burn_in_samples || baseline.gas_resistance <= 0.0 {
+      // cumulative mean during burn-in
+      baseline.gas_resistance += (gas_resistance - baseline.gas_resistance) / baseline.samples as f32;
+    } else {
+      let alpha = if gas_resistance > baseline.gas_resistance {
+        self.config.baseline_rise_alpha
+      } else {
+        self.config.baseline_fall_alpha
+      };
+
+      baseline.gas_resistance += alpha * (gas_resistance - baseline.gas_resistance);
+    }
+  }
+
+  fn accuracy(&self) -> AQIAccuracy {
+    let burn_in = self.config.burn_in_samples;
+    let samples = self.baseline.samples;
+
+    if samples < burn_in {
+      AQIAccuracy::Invalid
+    } else if samples < burn_in.saturating_mul(4) {
+      AQIAccuracy::Low
+    } else if samples < burn_in.saturating_mul(20) {
+      AQIAccuracy::Medium
+    } else {
+      AQIAccuracy::High
+    }
+  }
+
+  /// Computes the IAQ score for the given sample without updating the
+  /// baseline.
+  pub fn score(&self, gas_resistance: u32, humidity: f32) -> f32 {
+    let config = &self.config;
+    let gas = gas_resistance as f32;
+    let gas_baseline = self.baseline.gas_resistance;
+    let hum_baseline = config.humidity_baseline;
+    let hum_weight = config.humidity_weighting.clamp(0.0, 1.0) * 100.0;
+
+    let hum_offset = humidity - hum_baseline;
+    let hum_score = if hum_offset > 0.0 {
+      (100.0 - hum_baseline - hum_offset) / (100.0 - hum_baseline) * hum_weight
+    } else {
+      (hum_baseline + hum_offset) / hum_baseline * hum_weight
+    };
+
+    let gas_score = if gas_baseline <= 0.0 || gas >= gas_baseline {
+      100.0 - hum_weight
+    } else {
+      gas / gas_baseline * (100.0 - hum_weight)
+    };
+
+    // 100 is best; invert and scale to the 0-500 AQI range
+    let quality = (hum_score.max(0.0) + gas_score).clamp(0.0, 100.0);
+    (100.0 - quality) * 5.0
+  }
+
+  /// Incorporates a new sample into the baseline and returns its IAQ score.
+  pub fn update(&mut self, gas_resistance: u32, humidity: f32) -> IaqReading {
+    self.update_baseline(gas_resistance as f32);
+
+    let time = Utc::now();
+    IaqReading {
+      iaq: UnitValue {
+        unit: UnitAirQualityIndex,
+        value: self.score(gas_resistance, humidity),
+        time,
+        timing: None,
+        warnings: Vec::new(),
+        #[cfg(feature = "raw-bytes")] raw_bytes: None,
+      },
+      accuracy: UnitValue {
+        unit: UnitAQIAccuracy,
+        value: self.accuracy(),
+        time,
+        timing: None,
+        warnings: Vec::new(),
+        #[cfg(feature = "raw-bytes")] raw_bytes: None,
+      },
+    }
+  }
+
+  /// Convenience wrapper for [`IaqEstimator::update()`] using a combined air
+  /// data reading. The result uses the gas resistance measurement timestamp.
+  pub fn update_air_data(&mut self, air: &CombinedAirData) -> IaqReading {
+    let mut reading = self.update(air.gas_sensor_resistance.value, air.humidity.value);
+    reading.iaq.time = air.gas_sensor_resistance.time;
+    reading.accuracy.time = air.gas_sensor_resistance.time;
+
+    reading
+  }
+}
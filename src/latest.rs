@@ -0,0 +1,251 @@
+//! A shared cell holding the most recent reading from a background reader.
+
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::error::MetrifulError;
+
+struct Slot<T> {
+  value: Option<Arc<T>>,
+  version: u64,
+  last_error: Option<Arc<MetrifulError>>,
+  error_count: u64,
+  closed: bool,
+}
+
+struct Inner<T> {
+  slot: Mutex<Slot<T>>,
+  changed: Condvar,
+}
+
+/// A watch-style cell holding the latest value published by a background
+/// reader.
+///
+/// Clones share the same underlying cell, so any number of consumers may
+/// cheaply [`snapshot()`](LatestReading::snapshot) the current value or block
+/// until a newer one is published. Only the most recent value is retained, so
+/// a slow consumer never causes values to accumulate.
+///
+/// See [`Metriful::async_cycle_read_latest()`](crate::Metriful::async_cycle_read_latest)
+/// to spawn a reader that keeps a `LatestReading` updated.
+pub struct LatestReading<T> {
+  inner: Arc<Inner<T>>,
+}
+
+impl<T> Clone for LatestReading<T> {
+  fn clone(&self) -> Self {
+    LatestReading {
+      inner: Arc::clone(&self.inner),
+    }
+  }
+}
+
+impl<T> Default for LatestReading<T> {
+  fn default() -> Self {
+    LatestReading::new()
+  }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LatestReading<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let slot = self.lock();
+
+    f.debug_struct("LatestReading")
+      .field("value", &slot.value)
+      .field("version", &slot.version)
+      .field("last_error", &slot.last_error)
+      .field("error_count", &slot.error_count)
+      .field("closed", &slot.closed)
+      .finish()
+  }
+}
+
+impl<T> LatestReading<T> {
+  /// Creates a new, empty cell.
+  pub fn new() -> Self {
+    LatestReading {
+      inner: Arc::new(Inner {
+        slot: Mutex::new(Slot {
+          value: None,
+          version: 0,
+          last_error: None,
+          error_count: 0,
+          closed: false,
+        }),
+        changed: Condvar::new(),
+      }),
+    }
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Slot<T>> {
+    self.inner.slot.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Publishes a new value, replacing the previous one and waking any waiting
+  /// consumers.
+  pub fn set(&self, value: T) {
+    let mut slot = self.lock();
+    slot.value = Some(Arc::new(value));
+    slot.version += 1;
+
+    self.inner.changed.notify_all();
+  }
+
+  /// Records a read error. The previous value, if any, is retained.
+  pub fn set_error(&self, error: MetrifulError) {
+    let mut slot = self.lock();
+    slot.last_error = Some(Arc::new(error));
+    slot.error_count += 1;
+
+    self.inner.changed.notify_all();
+  }
+
+  /// Marks the cell as closed, i.e. no further values will be published, and
+  /// wakes any waiting consumers.
+  pub fn close(&self) {
+    self.lock().closed = true;
+    self.inner.changed.notify_all();
+  }
+
+  /// Returns the latest value, if any has been published.
+  pub fn snapshot(&self) -> Option<Arc<T>> {
+    self.lock().value.clone()
+  }
+
+  /// Returns the latest value along with its version.
+  pub fn snapshot_versioned(&self) -> Option<(u64, Arc<T>)> {
+    let slot = self.lock();
+    slot.value.clone().map(|v| (slot.version, v))
+  }
+
+  /// Returns the number of values published so far. This increases by one
+  /// with each call to [`LatestReading::set()`].
+  pub fn version(&self) -> u64 {
+    self.lock().version
+  }
+
+  /// Returns the most recent error recorded via
+  /// [`LatestReading::set_error()`].
+  pub fn last_error(&self) -> Option<Arc<MetrifulError>> {
+    self.lock().last_error.clone()
+  }
+
+  /// Returns the number of errors recorded so far.
+  pub fn error_count(&self) -> u64 {
+    self.lock().error_count
+  }
+
+  /// Returns true if the publisher has closed the cell.
+  pub fn is_closed(&self) -> bool {
+    self.lock().closed
+  }
+
+  /// Blocks until a value newer than `seen` is published, returning it along
+  /// with its version. Pass `0` to wait for the first value, or the version
+  /// returned by a previous call to wait for the next one.
+  ///
+  /// Returns `None` if the cell is closed before a newer value is published.
+  pub fn wait_for_update(&self, seen: u64) -> Option<(u64, Arc<T>)> {
+    self.wait_for_update_timeout(seen, None)
+  }
+
+  /// Like [`LatestReading::wait_for_update()`], but gives up and returns
+  /// `None` once `timeout` (if any) has elapsed.
+  pub fn wait_for_update_timeout(
+    &self,
+    seen: u64,
+    timeout: Option<Duration>,
+  ) -> Option<(u64, Arc<T>)> {
+    let deadline = timeout.map(|t| Instant::now() + t);
+    let mut slot = self.lock();
+
+    loop {
+      if slot.version > seen {
+        if let Some(value) = &slot.value {
+          return Some((slot.version, Arc::clone(value)));
+        }
+      }
+
+      if slot.closed {
+        return None;
+      }
+
+      slot = match deadline {
+        Some(deadline) => {
+          let now = Instant::now();
+          if now >= deadline {
+            return None;
+          }
+
+          self.inner.changed.wait_timeout(slot, deadline - now)
+            .unwrap_or_else(|e| e.into_inner())
+            .0
+        },
+        None => self.inner.changed.wait(slot).unwrap_or_else(|e| e.into_inner()),
+      };
+    }
+  }
+}
+
+/// Closes the wrapped cell when dropped, e.g. when a reader thread exits.
+pub(crate) struct CloseOnDrop<T>(pub(crate) LatestReading<T>);
+
+impl<T> Drop for CloseOnDrop<T> {
+  fn drop(&mut self) {
+    self.0.close();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::thread;
+
+  use super::*;
+
+  #[test]
+  fn errors_keep_the_last_value() {
+    let latest = LatestReading::new();
+    latest.set(1);
+    latest.set_error(MetrifulError::NotReady);
+
+    assert_eq!(latest.snapshot().as_deref(), Some(&1));
+    assert_eq!(latest.version(), 1);
+    assert_eq!(latest.error_count(), 1);
+    assert!(matches!(latest.last_error().as_deref(), Some(MetrifulError::NotReady)));
+  }
+
+  #[test]
+  fn waits_end_when_closed() {
+    let latest: LatestReading<u32> = LatestReading::new();
+    let writer = CloseOnDrop(latest.clone());
+
+    let waiter = thread::spawn(move || latest.wait_for_update(0));
+    drop(writer);
+
+    assert!(waiter.join().unwrap().is_none());
+  }
+
+  #[test]
+  fn waits_return_newer_values_only() {
+    let latest = LatestReading::new();
+    latest.set("a");
+
+    assert_eq!(latest.wait_for_update(0).map(|(v, s)| (v, *s)), Some((1, "a")));
+    assert!(latest.wait_for_update_timeout(1, Some(Duration::from_millis(10))).is_none());
+
+    latest.set("b");
+    assert_eq!(latest.wait_for_update(1).map(|(v, s)| (v, *s)), Some((2, "b")));
+  }
+
+  #[test]
+  fn closed_cells_still_return_unseen_values() {
+    let latest = LatestReading::new();
+    latest.set(5);
+    latest.close();
+
+    assert!(latest.is_closed());
+    assert_eq!(latest.wait_for_update(0).map(|(v, _)| v), Some(1));
+    assert!(latest.wait_for_update(1).is_none());
+  }
+}
@@ -2,13 +2,18 @@
 //! environment sensors.
 //!
 //! This library targets Raspberry Pis and other Linux-based hosts as supported
-//! by [`i2cdev`] and [`sysfs_gpio`].
+//! by [`i2cdev`] and [`sysfs_gpio`]. Other I2C backends (e.g. USB-I2C bridges)
+//! may be used by implementing [`MetrifulTransport`], and with the `hal`
+//! feature, any `embedded-hal` I2C bus and input pin may be used via the
+//! adapters in `hal`.
 //!
 //! ### Getting Started
 //! 
 //! The library is designed to be straightforward to use:
 //!  1. Connect the device per [Metriful's docs](https://github.com/metriful/sensor#raspberry-pi)
-//!  2. Open the device using [`Metriful::try_new_timeout()`]
+//!  2. Open the device using [`Metriful::try_new_timeout()`], or
+//!     [`Metriful::builder()`] to also reset it and configure a particle
+//!     sensor
 //!  3. Read metrics using one of the various helper functions:
 //!     * [`Metriful::read_iter_timeout()`]: reads continuously at a
 //!       user-defined interval
@@ -16,21 +21,31 @@
 //!       interval with the device in cycle mode
 //!     * [`Metriful::async_cycle_read_timeout()`]: reads continuously in a
 //!       background thread and reports results via a
-//!       [`std::sync::mpsc::channel`]
+//!       [`std::sync::mpsc::channel`]; see also
+//!       [`Metriful::async_cycle_read_bounded()`] for long-running consumers,
+//!       `Metriful::crossbeam_cycle_read_timeout()` (with the `crossbeam`
+//!       feature) for use with `crossbeam_channel::select!`, and
+//!       `Metriful::tokio_cycle_read_timeout()` (with the `tokio` feature) for
+//!       async consumers
+//!     * [`Metriful::async_cycle_read_latest()`]: reads continuously in a
+//!       background thread and publishes the most recent result to a
+//!       [`LatestReading`] that any number of consumers may share
+//!     * [`Metriful::event_stream()`]: reports readings alongside interrupts,
+//!       mode changes and recoverable errors as a single event stream
 //!     * [`Metriful::read()`]: to read a single metric once
 //!
 //! The various read functions need to be told which metric to read; see the
 //! [`metric`] module for a complete list of possibilities. To read more than
 //! one metric at once, a number of "combined read" pseudo-metrics are
 //! provided:
-//!  * [`struct@METRIC_COMBINED_AIR_DATA`]: all air data
-//!  * [`struct@METRIC_COMBINED_AIR_QUALITY_DATA`]: all air quality data; only valid
+//!  * [`METRIC_COMBINED_AIR_DATA`]: all air data
+//!  * [`METRIC_COMBINED_AIR_QUALITY_DATA`]: all air quality data; only valid
 //!    in cycle mode
-//!  * [`struct@METRIC_COMBINED_LIGHT_DATA`]: all light data
-//!  * [`struct@METRIC_COMBINED_SOUND_DATA`]: all sound data
-//!  * [`struct@METRIC_COMBINED_PARTICLE_DATA`]: all particle data; only valid if an
+//!  * [`METRIC_COMBINED_LIGHT_DATA`]: all light data
+//!  * [`METRIC_COMBINED_SOUND_DATA`]: all sound data
+//!  * [`METRIC_COMBINED_PARTICLE_DATA`]: all particle data; only valid if an
 //!    external particulate sensor is attached
-//!  * [`struct@METRIC_COMBINED_ALL`]: all data; air quality data is only valid in
+//!  * [`METRIC_COMBINED_ALL`]: all data; air quality data is only valid in
 //!    cycle mode
 //!
 //! ### Example
@@ -44,7 +59,7 @@
 //! let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
 //!
 //! let iter = metriful.cycle_read_iter_timeout(
-//!   *METRIC_COMBINED_ALL,
+//!   METRIC_COMBINED_ALL,
 //!   CyclePeriod::Period0,
 //!   Some(Duration::from_secs(3))
 //! );
@@ -56,26 +71,82 @@
 //! # }
 //! ```
 
+use std::cell::Cell;
+use std::collections::BTreeMap;
 use std::fmt;
+use std::mem;
 use std::path::Path;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use std::sync::mpsc::{channel, Sender, Receiver};
+use std::sync::mpsc::{self, Sender, Receiver, RecvTimeoutError};
 use std::thread::{self, JoinHandle};
 
-use i2cdev::core::*;
+use chrono::{DateTime, Utc};
 use i2cdev::linux::LinuxI2CDevice;
-use log::trace;
-use sysfs_gpio::{Direction, Pin};
-
+use log::{trace, warn};
+
+#[cfg(feature = "async")] pub mod asynchronous;
+#[cfg(feature = "beacon")] pub mod beacon;
+pub mod builder;
+pub mod calibration;
+pub mod cancel;
+pub mod channel;
+pub mod config;
+pub mod counters;
+#[cfg(feature = "derived")] pub mod derived;
+pub mod dyn_metric;
 pub mod error;
+pub mod events;
+pub mod format;
+pub mod guard;
+#[cfg(feature = "hal")] pub mod hal;
+#[cfg(feature = "iaq")] pub mod iaq;
+pub mod latest;
+#[cfg(feature = "loudness")] pub mod loudness;
 pub mod metric;
+pub mod metric_set;
+pub mod options;
+pub mod plausibility;
+pub mod pool;
+#[cfg(feature = "prometheus")] pub mod prometheus;
+pub mod ready;
+pub mod registers;
+pub mod retry;
+pub mod sched;
+#[cfg(feature = "simulator")] pub mod simulator;
+pub mod smoothing;
+pub mod stats;
 pub mod status;
+#[cfg(any(test, feature = "testing"))] pub mod testing;
+pub mod transport;
 pub mod unit;
 pub mod util;
 
+pub use builder::MetrifulBuilder;
+pub use calibration::Calibration;
+use cancel::CancelToken;
+use channel::{BackpressurePolicy, BoundedReceiver};
+pub use config::DeviceConfig;
+use counters::{Counters, DeviceStats};
+use dyn_metric::{DynMetric, DynReading};
 use error::*;
+use guard::{CommandGuard, CommandKind};
+pub use guard::RateLimit;
+pub use latest::LatestReading;
+pub use options::MetrifulOptions;
 use metric::*;
+pub use metric_set::MetricSet;
+use metric_set::MetricSetReading;
+use plausibility::Plausibility;
+pub use ready::{ReadyLine, ReadyPolarity};
+use ready::{DisconnectedReadyLine, ReadyPolling, SysfsReadyLine};
+use registers::Register;
+use retry::{OnError, ReadPolicy};
+use sched::ThreadScheduling;
+use stats::Samples;
 pub use status::*;
+pub use transport::MetrifulTransport;
+use transport::with_context;
 use unit::*;
 
 /// Metriful i2c address. Note: 0x70 if solder bridge is closed.
@@ -87,10 +158,12 @@ pub const READY_POLL_INTERVAL: u64 = 10;
 ///
 /// Unless otherwise limited (e.g. `.take(n)`) this iterator will return results
 /// forever. If an error occurs, it is returned as the next result and the
-/// iterator terminates.
+/// iterator terminates; see [`MetricReadIterator::with_policy()`] to retry
+/// failed reads or continue past errors.
 ///
 /// Each read takes approximately `interval`; intervals should be at least 2
-/// seconds to ensure valid results, though smaller values may still be used.
+/// seconds to ensure valid results. Shorter intervals are stretched to the
+/// device's configured [`RateLimit`], which defaults to 2 seconds.
 /// Note that the device takes roughly 550ms to collect metrics, during which
 /// the thread is blocked, effectively ensuring a minimum interval of 550ms.
 /// The blocking time is automatically adjusted to ensure a consistent read
@@ -100,18 +173,66 @@ pub const READY_POLL_INTERVAL: u64 = 10;
 ///
 /// Additionally, note that these on-demand measurements do not include air
 /// quality data; these are only valid in cycle read mode.
-pub struct MetricReadIterator<'a, U> where U: MetrifulUnit {
-  device: &'a mut Metriful,
+pub struct MetricReadIterator<'a, U, D = LinuxI2CDevice>
+where
+  U: MetrifulUnit,
+  D: MetrifulTransport,
+{
+  device: &'a mut Metriful<D>,
   metric: Metric<U>,
   interval: Duration,
   timeout: Option<Duration>,
   last_instant: Instant,
+  policy: ReadPolicy,
+  cancel: Option<CancelToken>,
   error: bool,
 }
 
-impl<'a, U> Iterator for MetricReadIterator<'a, U>
+impl<'a, U, D> MetricReadIterator<'a, U, D>
+where
+  U: MetrifulUnit,
+  D: MetrifulTransport,
+{
+  /// Sets the policy for retrying failed reads and handling errors.
+  pub fn with_policy(mut self, policy: ReadPolicy) -> Self {
+    self.policy = policy;
+    self
+  }
+
+  /// Sets a token that ends the iterator when cancelled, interrupting any
+  /// wait in progress; see the [`cancel`] module. Defaults to the device's
+  /// [`Metriful::cancel_token()`], if any.
+  pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+    self.cancel = Some(cancel);
+    self
+  }
+
+  /// Returns the next result, marking the iterator finished on error unless
+  /// the policy says to continue. Cancellation ends the iterator without an
+  /// error.
+  fn finish(&mut self, result: Result<UnitValue<U>>) -> Option<Result<UnitValue<U>>> {
+    match result {
+      Err(MetrifulError::Cancelled) => {
+        trace!("read iterator cancelled");
+        self.error = true;
+        None
+      },
+      Err(e) => {
+        if self.policy.on_error == OnError::Stop {
+          self.error = true;
+        }
+
+        Some(Err(e))
+      },
+      Ok(value) => Some(Ok(value)),
+    }
+  }
+}
+
+impl<'a, U, D> Iterator for MetricReadIterator<'a, U, D>
 where
-  U: MetrifulUnit
+  U: MetrifulUnit,
+  D: MetrifulTransport,
 {
   type Item = Result<UnitValue<U>>;
 
@@ -120,35 +241,43 @@ where
       return None;
     }
 
-    match self.device.wait_for_ready_timeout(self.timeout) {
-      Ok(()) => (),
-      Err(e) => {
-        self.error = true;
-        return Some(Err(e));
-      }
-    };
+    let device = &mut *self.device;
+    let counters = device.counters();
+    let timeout = self.timeout;
+    let cancel = self.cancel.as_ref();
+    if let Err(e) = self.policy.run(&counters, || device.wait_for_state("read_iter", true, timeout, cancel)) {
+      return self.finish(Err(e));
+    }
 
     // attempt to correct any time variation < interval
     // if we exceed it, oh well
     let elapsed = self.last_instant.elapsed();
     if elapsed < self.interval {
-      thread::sleep(self.interval - elapsed);
+      if let Err(e) = cancel::sleep(self.interval - elapsed, self.cancel.as_ref()) {
+        return self.finish(Err(e));
+      }
+    }
+
+    // intervals shorter than the configured rate limit are stretched to fit
+    let remaining = self.device.guard.remaining(CommandKind::Measurement);
+    if let Err(e) = cancel::sleep(remaining, self.cancel.as_ref()) {
+      return self.finish(Err(e));
     }
     self.last_instant = Instant::now();
 
-    let res = self.device.execute_measurement()
-      .and_then(|()| self.device.wait_for_ready_timeout(self.timeout))
-      .and_then(|()| self.device.read(self.metric));
+    let device = &mut *self.device;
+    let metric = self.metric;
+    let cancel = self.cancel.as_ref();
+    let res = self.policy.run(&counters, || {
+      // later attempts must also respect the measurement rate limit
+      cancel::sleep(device.guard.remaining(CommandKind::Measurement), cancel)?;
 
-    let ret = match res {
-      Ok(result) => Some(Ok(result)),
-      Err(e) => {
-        self.error = true;
-        Some(Err(e))
-      }
-    };
+      device.execute_measurement()
+        .and_then(|()| device.wait_for_state("read_iter", true, timeout, cancel))
+        .and_then(|()| device.read(metric))
+    });
 
-    ret
+    self.finish(res)
   }
 }
 
@@ -166,21 +295,217 @@ where
 /// relatively quickly (2.6s in the 100s/300s interval cases), however
 /// subsequent reads should be expected to take the full interval of time.
 ///
-/// Note that subsequent calls to `.next()` must be made before the current
-/// cycle ends or a measurement will be skipped. In the worst case, this means
-/// callers have up to 2.95s (per the datasheet) to process a result and call
-/// `.next()` again.
-pub struct CycleReadIterator<'a, U> where U: MetrifulUnit {
-  device: &'a mut Metriful,
+/// Callers that take longer than a cycle to call `.next()` again will miss
+/// measurements, as the sensor overwrites its data each cycle. Missed cycles
+/// are logged and reported via [`CycleReadIterator::sequence()`] and
+/// [`CycleReadIterator::missed_cycles()`]. If the READY line latches edge
+/// events (see [`ReadyLine::take_ready_edges()`], e.g. `CdevReadyLine` on an
+/// interrupt-capable pin), a cycle that completed while the caller was busy is
+/// read immediately, so stalls shorter than a cycle lose nothing. Otherwise,
+/// calls to `.next()` must be made before the current cycle ends; in the
+/// worst case, callers have up to 2.95s (per the datasheet) to process a
+/// result, and missed cycles are inferred from timing.
+///
+/// The iterator also tracks when each cycle completes relative to the
+/// schedule implied by the first, reported via [`CycleReadIterator::drift()`].
+/// If a cycle completes too far from its expected time (e.g. after a stall
+/// left the READY waits out of phase), the schedule is resynchronized to the
+/// device.
+///
+/// If an error occurs, it is returned as the next result and the iterator
+/// terminates; see [`CycleReadIterator::with_policy()`] to retry failed reads
+/// or continue past errors. Retries re-read the current cycle's data.
+pub struct CycleReadIterator<'a, U, D = LinuxI2CDevice>
+where
+  U: MetrifulUnit,
+  D: MetrifulTransport,
+{
+  device: &'a mut Metriful<D>,
   cycle_period: CyclePeriod,
   metric: Metric<U>,
   timeout: Option<Duration>,
 
+  policy: ReadPolicy,
+  cancel: Option<CancelToken>,
   first: bool,
   error: bool,
+
+  /// Time the most recent cycle completed
+  last_cycle: Instant,
+  sequence: Option<u64>,
+  missed: u64,
+
+  /// Time and sequence number of the cycle the schedule is measured from
+  anchor: Option<(Instant, u64)>,
+  drift: Option<CycleDrift>,
+}
+
+impl<'a, U, D> CycleReadIterator<'a, U, D>
+where
+  U: MetrifulUnit,
+  D: MetrifulTransport,
+{
+  /// Sets the policy for retrying failed reads and handling errors.
+  pub fn with_policy(mut self, policy: ReadPolicy) -> Self {
+    self.policy = policy;
+    self
+  }
+
+  /// Sets a token that ends the iterator when cancelled, interrupting any
+  /// wait in progress; see the [`cancel`] module. Defaults to the device's
+  /// [`Metriful::cancel_token()`], if any.
+  pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+    self.cancel = Some(cancel);
+    self
+  }
+
+  /// Returns the sequence number of the cycle most recently read, counting
+  /// from 0 for the first reading, or None before the first reading. Numbers
+  /// skip ahead by the number of cycles missed in between.
+  pub fn sequence(&self) -> Option<u64> {
+    self.sequence
+  }
+
+  /// Returns the total number of cycles missed since the iterator started.
+  ///
+  /// # Example
+  /// ```
+  /// # #[cfg(feature = "simulator")] {
+  /// use std::thread;
+  /// use std::time::Duration;
+  /// use metriful::{CyclePeriod, metric::*};
+  /// use metriful::simulator::*;
+  ///
+  /// // 3 second cycles complete every 30ms
+  /// let sim = Simulator::with_config(SimulatorConfig {
+  ///   time_scale: 100.0,
+  ///   measurement_time: Duration::from_millis(5),
+  ///   ..SimulatorConfig::default()
+  /// });
+  ///
+  /// let mut metriful = sim.open(Some(Duration::from_secs(1))).unwrap();
+  /// let mut iter = metriful.cycle_read_iter_timeout(METRIC_TEMPERATURE, CyclePeriod::Period0, None);
+  ///
+  /// iter.next().unwrap().unwrap();
+  /// assert_eq!(iter.sequence(), Some(0));
+  ///
+  /// // a slow consumer misses some cycles, which the simulated READY line latches
+  /// thread::sleep(Duration::from_millis(100));
+  /// iter.next().unwrap().unwrap();
+  ///
+  /// assert!(iter.missed_cycles() >= 1);
+  /// assert_eq!(iter.sequence(), Some(1 + iter.missed_cycles()));
+  /// # }
+  /// ```
+  pub fn missed_cycles(&self) -> u64 {
+    self.missed
+  }
+
+  /// Returns the timing of the most recent cycle relative to the expected
+  /// schedule, or None until a cycle boundary has been observed (the first
+  /// reading, taken on entering cycle mode, doesn't count).
+  ///
+  /// # Example
+  /// ```no_run
+  /// use metriful::{Metriful, CyclePeriod, metric::*};
+  ///
+  /// # fn main() -> metriful::error::Result<()> {
+  /// let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+  /// let mut iter = metriful.cycle_read_iter_timeout(METRIC_COMBINED_ALL, CyclePeriod::Period1, None);
+  ///
+  /// while let Some(reading) = iter.next() {
+  ///   println!("{}", reading?);
+  ///
+  ///   if let Some(drift) = iter.drift() {
+  ///     println!("cycle offset: {:+.3}s ({:+.1} ppm)", drift.offset, drift.rate_ppm);
+  ///   }
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn drift(&self) -> Option<CycleDrift> {
+    self.drift
+  }
+
+  /// Records a completed cycle, after `missed` others were skipped. If
+  /// `observed`, the cycle completed just now rather than at some earlier
+  /// point, and is used to track drift.
+  fn advance(&mut self, missed: u64, observed: bool) {
+    if missed > 0 {
+      warn!("cycle read iterator missed {} cycle(s)", missed);
+      self.missed += missed;
+    }
+
+    let now = Instant::now();
+    let sequence = self.sequence.map_or(0, |s| s + 1 + missed);
+
+    if observed {
+      self.track_drift(now, sequence);
+    }
+
+    self.last_cycle = now;
+    self.sequence = Some(sequence);
+  }
+
+  /// Compares a cycle's completion time with the schedule, resynchronizing
+  /// the schedule if the two are out of phase.
+  fn track_drift(&mut self, now: Instant, sequence: u64) {
+    let resyncs = self.drift.map_or(0, |d| d.resyncs);
+
+    let (anchor, anchor_sequence) = match self.anchor {
+      Some(anchor) => anchor,
+      None => {
+        self.anchor = Some((now, sequence));
+        self.drift = Some(CycleDrift { offset: 0.0, rate_ppm: 0.0, resyncs });
+        return;
+      }
+    };
+
+    let period = self.cycle_period.to_duration().as_secs_f64();
+    let elapsed = now.duration_since(anchor).as_secs_f64();
+    let offset = elapsed - (sequence - anchor_sequence) as f64 * period;
+
+    if offset.abs() > period * CYCLE_PHASE_TOLERANCE {
+      warn!("cycle read iterator out of phase by {:.3}s, resynchronizing", offset);
+      self.anchor = Some((now, sequence));
+      self.drift = Some(CycleDrift { offset: 0.0, rate_ppm: 0.0, resyncs: resyncs + 1 });
+      return;
+    }
+
+    self.drift = Some(CycleDrift {
+      offset,
+      rate_ppm: if elapsed > 0.0 { offset / elapsed * 1e6 } else { 0.0 },
+      resyncs,
+    });
+  }
+
+  /// Returns the next result, marking the iterator finished on error unless
+  /// the policy says to continue. Cancellation ends the iterator without an
+  /// error.
+  fn finish(&mut self, result: Result<UnitValue<U>>) -> Option<Result<UnitValue<U>>> {
+    match result {
+      Err(MetrifulError::Cancelled) => {
+        trace!("read iterator cancelled");
+        self.error = true;
+        None
+      },
+      Err(e) => {
+        if self.policy.on_error == OnError::Stop {
+          self.error = true;
+        }
+
+        Some(Err(e))
+      },
+      Ok(value) => Some(Ok(value)),
+    }
+  }
 }
 
-impl<'a, U> Iterator for CycleReadIterator<'a, U> where U: MetrifulUnit {
+impl<'a, U, D> Iterator for CycleReadIterator<'a, U, D>
+where
+  U: MetrifulUnit,
+  D: MetrifulTransport,
+{
   type Item = Result<UnitValue<U>>;
 
   fn next(&mut self) -> Option<Self::Item> {
@@ -188,81 +513,262 @@ impl<'a, U> Iterator for CycleReadIterator<'a, U> where U: MetrifulUnit {
       return None;
     }
 
-    if self.first {
-      match self.device.set_mode_timeout(OperationalMode::Cycle(self.cycle_period), self.timeout) {
-        Ok(_) => {
-          self.first = false;
+    let device = &mut *self.device;
+    let counters = device.counters();
+    let metric = self.metric;
+    let timeout = self.timeout;
+    let cancel = self.cancel.as_ref();
 
-          match self.device.read(self.metric) {
-            Ok(res) => Some(Ok(res)),
-            Err(e) => {
-              self.error = true;
-              Some(Err(e))
-            }
-          }
-        },
-        Err(e) => {
-          self.error = true;
-          return Some(Err(e));
-        }
-      }
+    if let Some(Err(e)) = cancel.map(CancelToken::check) {
+      return self.finish(Err(e));
+    }
+
+    let res = if self.first {
+      let mode = OperationalMode::Cycle(self.cycle_period);
+
+      // edges latched before the first cycle don't count as missed
+      self.policy.run(&counters, || device.set_mode_timeout(mode, timeout))
+        .and_then(|_| device.ready_pin.take_ready_edges())
+        .map(|_| (0, false))
     } else {
-      let res = self.device.wait_for_not_ready_timeout(self.timeout)
-        .and_then(|()| self.device.wait_for_ready_timeout(self.timeout))
-        .and_then(|()| self.device.read(self.metric));
+      device.wait_for_cycle(self.cycle_period, self.last_cycle, timeout, cancel)
+    };
 
-      match res {
-        Ok(result) => Some(Ok(result)),
-        Err(e) => {
-          self.error = true;
-          Some(Err(e))
-        }
-      }
+    match res {
+      Ok((missed, observed)) => {
+        self.first = false;
+        self.advance(missed, observed);
+      },
+      Err(e) => return self.finish(Err(e)),
+    }
+
+    let device = &mut *self.device;
+    let res = self.policy.run(&counters, || device.read(metric));
+    self.finish(res)
+  }
+}
+
+/// Fraction of a cycle period by which a cycle may complete early or late
+/// before a [`CycleReadIterator`] resynchronizes its schedule.
+const CYCLE_PHASE_TOLERANCE: f64 = 0.25;
+
+/// Timing of cycles observed by a [`CycleReadIterator`] relative to their
+/// expected schedule; see [`CycleReadIterator::drift()`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CycleDrift {
+  /// Seconds by which the most recent cycle completed after (positive) or
+  /// before (negative) its expected time
+  pub offset: f64,
+
+  /// Rate at which the device's cycles drift relative to the host clock, in
+  /// parts per million, since the schedule was last synchronized
+  pub rate_ppm: f64,
+
+  /// Number of times the schedule was resynchronized after losing phase
+  pub resyncs: u64,
+}
+
+/// Cleanup performed by [`Metriful::close()`] and, if enabled, when a
+/// [`Metriful`] is dropped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ShutdownOptions {
+  /// If true, a device in cycle mode is returned to standby. Defaults to true.
+  pub standby: bool,
+
+  /// If true, the READY line is released via [`ReadyLine::release()`], e.g.
+  /// unexporting a sysfs GPIO pin. Defaults to true.
+  pub release_ready: bool,
+
+  /// Timeout waiting for the device to become ready before entering standby.
+  /// Defaults to 5 seconds.
+  pub timeout: Option<Duration>,
+
+  /// If true, cleanup is also performed on a best-effort basis when the
+  /// device is dropped without calling [`Metriful::close()`]; errors are
+  /// logged rather than returned. Defaults to false.
+  pub on_drop: bool,
+}
+
+impl Default for ShutdownOptions {
+  fn default() -> Self {
+    ShutdownOptions {
+      standby: true,
+      release_ready: true,
+      timeout: Some(Duration::from_secs(5)),
+      on_drop: false,
+    }
+  }
+}
+
+/// A command sent to a background reader thread, e.g. one spawned by
+/// [`Metriful::async_cycle_read_timeout()`], via its `cmd_tx` channel.
+///
+/// Commands are only noticed between readings, which may take a full cycle
+/// period.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "simulator")] {
+/// use std::time::Duration;
+/// use metriful::{CyclePeriod, ReaderCommand, metric::*};
+/// use metriful::simulator::*;
+///
+/// // 3 second cycles complete every 30ms
+/// let sim = Simulator::with_config(SimulatorConfig {
+///   time_scale: 100.0,
+///   measurement_time: Duration::from_millis(5),
+///   ..SimulatorConfig::default()
+/// });
+///
+/// let metriful = sim.open(Some(Duration::from_secs(1))).unwrap();
+/// let (cmd_tx, metric_rx, handle) = metriful.async_cycle_read_timeout(
+///   METRIC_TEMPERATURE, CyclePeriod::Period0, None
+/// );
+///
+/// metric_rx.recv().unwrap().unwrap();
+/// cmd_tx.send(ReaderCommand::Pause).unwrap();
+///
+/// // the reading in progress is delivered, then the thread goes quiet
+/// metric_rx.recv().unwrap().unwrap();
+/// assert!(metric_rx.recv_timeout(Duration::from_millis(200)).is_err());
+///
+/// cmd_tx.send(ReaderCommand::Resume).unwrap();
+/// metric_rx.recv().unwrap().unwrap();
+///
+/// cmd_tx.send(ReaderCommand::Stop).unwrap();
+/// handle.join().unwrap();
+/// # }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReaderCommand {
+  /// Stop reading and leave the bus idle until resumed, e.g. while another
+  /// process reprograms the sensor.
+  Pause,
+
+  /// Resume reading after a pause. Cycle mode is re-entered in case the
+  /// device was reconfigured in the meantime. Ignored if not paused.
+  Resume,
+
+  /// Switch to a new cycle period. If paused, the change takes effect once
+  /// resumed.
+  ChangeCyclePeriod(CyclePeriod),
+
+  /// Terminate the thread.
+  Stop,
+}
+
+/// The handles returned when spawning a background reader: a command sender,
+/// the receiving end `T` for its readings, and the thread, which returns the
+/// device once stopped.
+pub type ReaderHandles<D, T> = (Sender<ReaderCommand>, T, JoinHandle<Metriful<D>>);
+
+/// Blocks a paused reader thread until it is resumed, applying any cycle
+/// period changes received meanwhile. Returns false if the thread should stop
+/// instead, including if the command channel or cancel token say so.
+fn wait_for_resume(
+  cmd_rx: &Receiver<ReaderCommand>,
+  cycle_period: &mut CyclePeriod,
+  cancel: Option<&CancelToken>,
+) -> bool {
+  trace!("wait_for_resume(): paused");
+
+  loop {
+    if cancel.is_some_and(CancelToken::is_cancelled) {
+      return false;
+    }
+
+    match cmd_rx.recv_timeout(cancel::CANCEL_CHECK_INTERVAL) {
+      Ok(ReaderCommand::Resume) => {
+        trace!("wait_for_resume(): resumed");
+        return true;
+      },
+      Ok(ReaderCommand::ChangeCyclePeriod(period)) => *cycle_period = period,
+      Ok(ReaderCommand::Stop) | Err(RecvTimeoutError::Disconnected) => return false,
+      Ok(ReaderCommand::Pause) | Err(RecvTimeoutError::Timeout) => (),
     }
   }
 }
 
+/// How a device opened by path was connected, so [`Metriful::recover()`] can
+/// reopen it.
+#[derive(Debug, Clone)]
+struct Connection {
+  options: MetrifulOptions,
+
+  /// If false, the READY line was provided by the caller and is kept as-is
+  reopen_ready: bool,
+}
+
 /// A Metriful MS430 sensor connected via I2C with a "ready" GPIO pin.
-pub struct Metriful {
-  ready_pin: Pin,
-  device: LinuxI2CDevice,
+///
+/// The I2C device defaults to a [`LinuxI2CDevice`], however any
+/// [`MetrifulTransport`] may be used, e.g. the wrappers in the `testing`
+/// module or a custom USB-I2C bridge.
+pub struct Metriful<D = LinuxI2CDevice> where D: MetrifulTransport {
+  ready_pin: Box<dyn ReadyLine>,
+  device: D,
+  guard: CommandGuard,
+
+  polling: ReadyPolling,
+  thread_scheduling: Option<ThreadScheduling>,
+  /// When READY was last observed becoming asserted, if it hasn't since been
+  /// deasserted
+  ready_since: Cell<Option<(Instant, DateTime<Utc>)>>,
 
   status: Option<DeviceStatus>,
+  /// If true, interrupt settings in `status` may be outdated
+  interrupts_stale: bool,
+  /// Configuration to restore once a pending recovery succeeds
+  restore: Option<DeviceConfig>,
+  calibration: Calibration,
+  plausibility: Option<Plausibility>,
+  enforce_validity: bool,
+  cancel: Option<CancelToken>,
+  counters: Arc<Counters>,
+  connection: Option<Connection>,
+  /// When the current `*_timeout` operation must complete by, if any
+  deadline: Option<Instant>,
+
+  shutdown: ShutdownOptions,
+  closed: bool,
 }
 
-impl fmt::Debug for Metriful {
+impl<D> fmt::Debug for Metriful<D> where D: MetrifulTransport {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     f.debug_struct("Metriful")
       .field("ready_pin", &self.ready_pin)
+      .field("ready_polling", &self.polling)
+      .field("thread_scheduling", &self.thread_scheduling)
+      .field("rate_limit", &self.guard.limit)
       .field("status", &self.status)
+      .field("calibration", &self.calibration)
+      .field("plausibility", &self.plausibility)
+      .field("enforce_validity", &self.enforce_validity)
+      .field("cancel", &self.cancel)
+      .field("counters", &self.counters)
+      .field("connection", &self.connection)
+      .field("shutdown", &self.shutdown)
       .finish()
   }
 }
 
-impl Metriful {
-  /// Creates a new Metriful given a preexisting GPIO [`Pin`] and
-  /// [`LinuxI2CDevice`]. This ensures the device is ready and fetches the
-  /// current state. Returns an error if the timeout is set and exceeded, or if
-  /// device status cannot be read.
-  ///
-  /// Note that this does not reset the device. The manual recommends doing so
-  /// before use; call [`Metriful::reset()`] to do so.
-  pub fn try_new_device_timeout(
-    ready_pin: Pin,
-    device: LinuxI2CDevice,
-    timeout: Option<Duration>,
-  ) -> Result<Metriful> {
-    trace!("Metriful::try_new_device_timeout(.., {:?})", timeout);
-
-    let mut ret = Metriful {
-      ready_pin, device,
-      status: None
-    };
+impl<D> Drop for Metriful<D> where D: MetrifulTransport {
+  fn drop(&mut self) {
+    if !self.shutdown.on_drop {
+      return;
+    }
 
-    ret.wait_for_ready_timeout(timeout)?;
-    ret.read_status()?;
+    if let Err(e) = self.shutdown() {
+      warn!("Metriful::drop(): cleanup failed: {}", e);
+    }
+  }
+}
 
-    Ok(ret)
+impl Metriful {
+  /// Returns a [`MetrifulBuilder`] for opening a device with construction-time
+  /// configuration, e.g. resetting it and enabling a particle sensor.
+  pub fn builder() -> MetrifulBuilder {
+    MetrifulBuilder::new()
   }
 
   /// Initializes a new Metriful instance and fetches the current device status.
@@ -282,21 +788,61 @@ impl Metriful {
       gpio_ready, i2c_device.as_ref().display(), i2c_address, timeout
     );
 
-    let ready_pin = Pin::new(gpio_ready);
-    ready_pin.export()?;
-    ready_pin.set_active_low(false)?;
-    ready_pin.set_direction(Direction::In)?;
+    let ready_pin = SysfsReadyLine::new(gpio_ready, ReadyPolarity::ActiveLow)?;
+    let options = MetrifulOptions::default()
+      .i2c_device(i2c_device.as_ref())
+      .i2c_address(i2c_address)
+      .gpio_ready(gpio_ready)
+      .timeout(timeout);
 
-    let device = LinuxI2CDevice::new(i2c_device, i2c_address)?;
+    let mut ret = Metriful::try_new_ready_timeout(ready_pin, i2c_device, i2c_address, timeout)?;
+    ret.set_connection(options, true);
 
-    let mut ret = Metriful {
-      ready_pin,
-      device,
-      status: None
-    };
+    Ok(ret)
+  }
+
+  /// Initializes a new Metriful instance using a user-provided [`ReadyLine`]
+  /// (e.g. a [`ready::SysfsReadyLine`] with inverted polarity) and fetches the
+  /// current device status. Returns an error if the device does not become
+  /// ready within the configured timeout or if current status cannot be read.
+  ///
+  /// Note that this does not reset the device. The manual recommends doing so
+  /// before use; call [`Metriful::reset()`] to do so.
+  ///
+  /// # Example
+  /// ```no_run
+  /// use std::time::Duration;
+  /// use metriful::{Metriful, ReadyPolarity};
+  /// use metriful::ready::SysfsReadyLine;
+  ///
+  /// # fn main() -> metriful::error::Result<()> {
+  /// // READY passes through an inverting level shifter
+  /// let ready = SysfsReadyLine::new(17, ReadyPolarity::ActiveHigh)?;
+  /// let metriful = Metriful::try_new_ready_timeout(
+  ///   ready, "/dev/i2c-1", 0x71, Some(Duration::from_secs(5))
+  /// )?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn try_new_ready_timeout(
+    ready_pin: impl ReadyLine + 'static,
+    i2c_device: impl AsRef<Path>,
+    i2c_address: u16,
+    timeout: Option<Duration>
+  ) -> Result<Metriful> {
+    trace!(
+      "Metriful::try_new_ready_timeout({:?}, {}, {:x}, {:?})",
+      ready_pin, i2c_device.as_ref().display(), i2c_address, timeout
+    );
+
+    let device = LinuxI2CDevice::new(i2c_device.as_ref(), i2c_address)?;
+    let options = MetrifulOptions::default()
+      .i2c_device(i2c_device.as_ref())
+      .i2c_address(i2c_address)
+      .timeout(timeout);
 
-    ret.wait_for_ready_timeout(timeout)?;
-    ret.read_status()?;
+    let mut ret = Metriful::try_new_device_timeout(ready_pin, device, timeout)?;
+    ret.set_connection(options, false);
 
     Ok(ret)
   }
@@ -315,66 +861,261 @@ impl Metriful {
     Metriful::try_new_timeout(gpio_ready, i2c_device, i2c_address, None)
   }
 
-  /// Returns true if the sensor's ready pin is asserted.
-  pub fn is_ready(&self) -> Result<bool> {
-    Ok(self.ready_pin.get_value()? == 0)
-  }
-
-  /// Returns true if the device is known to be in standby mode.
+  /// Initializes a new Metriful instance using a GPIO character device line
+  /// for the READY signal and fetches the current device status. The chip may
+  /// be given by name (e.g. `gpiochip0`) or path. Returns an error if the
+  /// device does not become ready within the configured timeout or if current
+  /// status cannot be read.
   ///
-  /// If the device status is missing or outdated it may return false.
-  pub fn is_mode_standby(&self) -> bool {
-    if let Some(status) = &self.status {
-      matches!(status.mode, OperationalMode::Standby)
-    } else {
-      false
-    }
+  /// Note that this does not reset the device. The manual recommends doing so
+  /// before use; call [`Metriful::reset()`] to do so.
+  ///
+  /// # Example
+  /// ```no_run
+  /// use std::time::Duration;
+  /// use metriful::Metriful;
+  ///
+  /// # fn main() -> metriful::error::Result<()> {
+  /// let metriful = Metriful::try_new_gpiochip_timeout(
+  ///   "gpiochip0", 17, "/dev/i2c-1", 0x71, Some(Duration::from_secs(5))
+  /// )?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  #[cfg(feature = "cdev")]
+  pub fn try_new_gpiochip_timeout(
+    chip: impl AsRef<Path>,
+    line: u32,
+    i2c_device: impl AsRef<Path>,
+    i2c_address: u16,
+    timeout: Option<Duration>
+  ) -> Result<Metriful> {
+    trace!(
+      "Metriful::try_new_gpiochip_timeout({}, {}, {}, {:x}, {:?})",
+      chip.as_ref().display(), line, i2c_device.as_ref().display(), i2c_address,
+      timeout
+    );
+
+    let ready_pin = ready::CdevReadyLine::new(chip.as_ref(), line, ReadyPolarity::ActiveLow)?;
+    let options = MetrifulOptions::default()
+      .i2c_device(i2c_device.as_ref())
+      .i2c_address(i2c_address)
+      .gpio_ready(u64::from(line))
+      .gpio_chip(Some(chip.as_ref().to_path_buf()))
+      .timeout(timeout);
+
+    let mut ret = Metriful::try_new_ready_timeout(ready_pin, i2c_device, i2c_address, timeout)?;
+    ret.set_connection(options, true);
+
+    Ok(ret)
   }
 
-  /// Returns true if the device is known to be in some cycle mode.
-  ///
-  /// If the device status is missing or outdated it may return false.
-  pub fn is_mode_cycle(&self) -> bool {
-    if let Some(status) = &self.status {
-      matches!(status.mode, OperationalMode::Cycle(_))
-    } else {
-      false
-    }
+  /// Initializes a new Metriful instance using a GPIO character device line
+  /// for the READY signal. May block indefinitely if the device does not
+  /// become ready; see [`Metriful::try_new_gpiochip_timeout()`].
+  #[cfg(feature = "cdev")]
+  pub fn try_new_gpiochip(
+    chip: impl AsRef<Path>,
+    line: u32,
+    i2c_device: impl AsRef<Path>,
+    i2c_address: u16
+  ) -> Result<Metriful> {
+    Metriful::try_new_gpiochip_timeout(chip, line, i2c_device, i2c_address, None)
   }
 
-  /// Ensures the device is currently ready.
-  pub fn ensure_ready(&self) -> Result<()> {
-    if self.is_ready()? {
-      Ok(())
-    } else {
-      return Err(MetrifulError::NotReady)
-    }
+  /// Records how the device was opened, for [`Metriful::recover()`]. If
+  /// `reopen_ready` is false, the current READY line is kept on recovery.
+  pub(crate) fn set_connection(&mut self, options: MetrifulOptions, reopen_ready: bool) {
+    self.connection = Some(Connection { options, reopen_ready });
   }
 
-  /// Sleeps the thread until [`Metriful::is_ready()`] returns true, polling every
-  /// 10ms. If a timeout is set and exceeded, returns an error.
-  pub fn wait_for_ready_timeout(&self, timeout: Option<Duration>) -> Result<()> {
-    let start = Instant::now();
-
-    loop {
-      if self.is_ready()? {
-        trace!("Metriful::wait_for_ready_timeout({:?}): is ready after {:?}", timeout, start.elapsed());
-        return Ok(());
+  /// Reopens the I2C device node and READY line, e.g. after a USB-I2C adapter
+  /// was unplugged and reconnected or the i2c driver was rebound, then waits
+  /// for the device to become ready and restores its configuration. See
+  /// [`Metriful::recover_with()`] for details.
+  ///
+  /// The READY line is only reopened if this library opened it, i.e. not
+  /// when it was passed to [`Metriful::try_new_ready_timeout()`] or
+  /// [`MetrifulBuilder::ready_line()`]. Returns
+  /// [`MetrifulError::RecoverUnsupported`] for devices created from an
+  /// already-open transport.
+  ///
+  /// The old READY line is released before it is reopened. If reopening
+  /// fails, [`MetrifulError::ReadyLineLost`] is returned and every operation
+  /// needing the line fails until a later call succeeds.
+  ///
+  /// # Example
+  /// ```no_run
+  /// use metriful::{Metriful, metric::*};
+  ///
+  /// # fn main() -> metriful::error::Result<()> {
+  /// let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+  ///
+  /// loop {
+  ///   match metriful.read(METRIC_TEMPERATURE) {
+  ///     Ok(temp) => println!("{}", temp),
+  ///     Err(e) => {
+  ///       eprintln!("read failed, reconnecting: {}", e);
+  ///       metriful.recover()?;
+  ///     }
+  ///   }
+  /// }
+  /// # }
+  /// ```
+  pub fn recover_timeout(&mut self, timeout: Option<Duration>) -> Result<DeviceStatus> {
+    trace!("Metriful::recover_timeout({:?})", timeout);
+
+    let connection = self.connection.clone().ok_or(MetrifulError::RecoverUnsupported)?;
+    let options = &connection.options;
+    let device = LinuxI2CDevice::new(&options.i2c_device, options.i2c_address)?;
+
+    let ready_pin = if connection.reopen_ready {
+      // the old line must be released first, as e.g. GPIO character device
+      // lines can only be requested once
+      let old = mem::replace(&mut self.ready_pin, Box::new(DisconnectedReadyLine));
+      if let Err(e) = old.release() {
+        warn!("Metriful::recover_timeout(): releasing READY line failed: {}", e);
       }
 
-      if let Some(timeout) = timeout {
-        if start.elapsed() > timeout {
-          trace!("Metriful::wait_for_ready_timeout({:?}): timeout exceeded", timeout);
-          return Err(MetrifulError::ReadyTimeoutExceeded)
-        } else {
-          thread::sleep(Duration::from_millis(READY_POLL_INTERVAL));
-        }
-      }
+      drop(old);
+      let ready_pin = options.open_ready_line()
+        .map_err(|e| MetrifulError::ReadyLineLost(Box::new(e)))?;
+
+      Some(ready_pin)
+    } else {
+      None
+    };
+
+    self.recover_with(ready_pin, Some(device), timeout)
+  }
+
+  /// Reopens the I2C device node and READY line and restores the device's
+  /// configuration. May block indefinitely if the device does not become
+  /// ready; see [`Metriful::recover_timeout()`].
+  pub fn recover(&mut self) -> Result<DeviceStatus> {
+    self.recover_timeout(None)
+  }
+}
+
+impl<D> Metriful<D> where D: MetrifulTransport {
+  /// Creates a new Metriful given a preexisting [`ReadyLine`] (e.g. a GPIO
+  /// [`sysfs_gpio::Pin`]) and I2C device. This ensures the device is ready and fetches the
+  /// current state. Returns an error if the timeout is set and exceeded, or if
+  /// device status cannot be read.
+  ///
+  /// Note that this does not reset the device. The manual recommends doing so
+  /// before use; call [`Metriful::reset()`] to do so.
+  pub fn try_new_device_timeout(
+    ready_pin: impl ReadyLine + 'static,
+    device: D,
+    timeout: Option<Duration>,
+  ) -> Result<Metriful<D>> {
+    trace!("Metriful::try_new_device_timeout(.., {:?})", timeout);
+
+    let mut ret = Metriful {
+      ready_pin: Box::new(ready_pin),
+      device,
+      guard: CommandGuard::default(),
+      polling: ReadyPolling::default(),
+      thread_scheduling: None,
+      ready_since: Cell::new(None),
+      status: None,
+      interrupts_stale: false,
+      restore: None,
+      calibration: Calibration::default(),
+      plausibility: None,
+      enforce_validity: true,
+      cancel: None,
+      counters: Arc::default(),
+      connection: None,
+      deadline: None,
+      shutdown: ShutdownOptions::default(),
+      closed: false,
+    };
+
+    ret.with_deadline(timeout, |this| {
+      this.wait_for_state("init", true, timeout, None)?;
+      this.read_status()
+    })?;
+
+    Ok(ret)
+  }
+
+  /// Returns true if the sensor's ready pin is asserted.
+  pub fn is_ready(&self) -> Result<bool> {
+    self.ready_pin.is_ready()
+  }
+
+  /// Returns true if the device is known to be in standby mode.
+  ///
+  /// If the device status is missing or outdated it may return false.
+  pub fn is_mode_standby(&self) -> bool {
+    if let Some(status) = &self.status {
+      matches!(status.mode, OperationalMode::Standby)
+    } else {
+      false
+    }
+  }
+
+  /// Returns true if the device is known to be in some cycle mode.
+  ///
+  /// If the device status is missing or outdated it may return false.
+  pub fn is_mode_cycle(&self) -> bool {
+    if let Some(status) = &self.status {
+      matches!(status.mode, OperationalMode::Cycle(_))
+    } else {
+      false
+    }
+  }
+
+  /// Ensures the metric read from the given register is meaningful in the
+  /// device's current mode, per its [`metric::MetricInfo::validity`]. Only
+  /// the cached status is consulted; if it is missing, reads are allowed.
+  fn ensure_valid_mode(&self, register: u8) -> Result<()> {
+    let status = match self.validity_status() {
+      Some(status) => status,
+      None => return Ok(()),
+    };
+
+    match metric::find_by_register(register) {
+      Some(info) if info.validity.cycle_mode && self.is_mode_standby() => {
+        Err(MetrifulError::MetricRequiresCycleMode { metric: info.id })
+      },
+      Some(info) if info.validity.particle_sensor && !status.particle_sensor_enabled() => {
+        Err(MetrifulError::ParticleSensorDisabled { metric: info.id })
+      },
+      _ => Ok(())
+    }
+  }
+
+  /// Returns the cached status to check readings against, if validity is
+  /// enforced and the status is known.
+  fn validity_status(&self) -> Option<&DeviceStatus> {
+    self.status.as_ref().filter(|_| self.enforce_validity)
+  }
+
+  /// Ensures the device is currently ready.
+  pub fn ensure_ready(&self) -> Result<()> {
+    if self.is_ready()? {
+      Ok(())
+    } else {
+      return Err(MetrifulError::NotReady)
     }
   }
 
-  /// Sleeps the thread until [`Metriful::is_ready()`] returns true, polling
-  /// every 10ms. This has no timeout and will wait indefinitely; see
+  /// Sleeps the thread until [`Metriful::is_ready()`] returns true. If a
+  /// timeout is set and exceeded, returns an error.
+  ///
+  /// Where the READY line supports edge events (e.g. [`ready::SysfsReadyLine`]
+  /// on interrupt-capable pins), this wakes as soon as READY asserts;
+  /// otherwise, the line is polled every [`READY_POLL_INTERVAL`] milliseconds.
+  /// See [`Metriful::set_ready_polling()`] to change this.
+  pub fn wait_for_ready_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+    self.wait_for_state("wait_for_ready", true, timeout, None)
+  }
+
+  /// Sleeps the thread until [`Metriful::is_ready()`] returns true. This has
+  /// no timeout and will wait indefinitely; see
   /// [`Metriful::wait_for_ready_timeout()`] if a timeout is desired.
   pub fn wait_for_ready(&self) -> Result<()> {
     self.wait_for_ready_timeout(None)
@@ -384,71 +1125,330 @@ impl Metriful {
   /// the device is explicitly **not** ready, useful for e.g. waiting for a new
   /// cycle period.
   pub fn wait_for_not_ready_timeout(&self, timeout: Option<Duration>) -> Result<()> {
-    let start = Instant::now();
+    self.wait_for_state("wait_for_not_ready", false, timeout, None)
+  }
 
-    loop {
-      if !self.is_ready()? {
-        trace!("Metriful::wait_for_not_ready_timeout({:?}): is not ready after {:?}", timeout, start.elapsed());
-        return Ok(());
-      }
+  /// Like [`Metriful::wait_for_ready_timeout()`], but returns
+  /// [`MetrifulError::Cancelled`] shortly after the given token is cancelled.
+  pub fn wait_for_ready_cancellable(&self, timeout: Option<Duration>, cancel: &CancelToken) -> Result<()> {
+    self.wait_for_state("wait_for_ready", true, timeout, Some(cancel))
+  }
+
+  /// Like [`Metriful::wait_for_not_ready_timeout()`], but returns
+  /// [`MetrifulError::Cancelled`] shortly after the given token is cancelled.
+  pub fn wait_for_not_ready_cancellable(&self, timeout: Option<Duration>, cancel: &CancelToken) -> Result<()> {
+    self.wait_for_state("wait_for_not_ready", false, timeout, Some(cancel))
+  }
 
-      if let Some(timeout) = timeout {
-        if start.elapsed() > timeout {
-          trace!("Metriful::wait_for_not_ready_timeout({:?}): timeout exceeded", timeout);
-          return Err(MetrifulError::ReadyTimeoutExceeded)
-        } else {
-          thread::sleep(Duration::from_millis(READY_POLL_INTERVAL));
+  /// Waits for the READY line to reach the given state on behalf of the named
+  /// operation, which is reported if the timeout is exceeded. If a token is
+  /// given, the wait is split into slices of at most
+  /// [`cancel::CANCEL_CHECK_INTERVAL`] so cancellation is noticed promptly.
+  fn wait_for_state(
+    &self,
+    operation: &'static str,
+    ready: bool,
+    timeout: Option<Duration>,
+    cancel: Option<&CancelToken>,
+  ) -> Result<()> {
+    let start = Instant::now();
+    let timeout = self.within_deadline(timeout);
+
+    let reached = match cancel {
+      None => self.polling.wait_for(&*self.ready_pin, ready, timeout)?,
+      Some(cancel) => {
+        let deadline = timeout.map(|t| start + t);
+        loop {
+          cancel.check()?;
+
+          let wait = match deadline {
+            Some(deadline) => {
+              let now = Instant::now();
+              if now >= deadline {
+                break false;
+              }
+
+              (deadline - now).min(cancel::CANCEL_CHECK_INTERVAL)
+            },
+            None => cancel::CANCEL_CHECK_INTERVAL,
+          };
+
+          if self.polling.wait_for(&*self.ready_pin, ready, Some(wait))? {
+            break true;
+          }
         }
-      } else {
-        thread::sleep(Duration::from_millis(READY_POLL_INTERVAL));
-      }
+      },
+    };
+
+    if reached {
+      self.observe_ready(ready);
+      self.counters.record_ready_wait(start.elapsed());
+      trace!("Metriful::wait_for_state({}, {}): reached after {:?}", operation, ready, start.elapsed());
+      return Ok(());
+    }
+
+    trace!("Metriful::wait_for_state({}, {}, {:?}): timeout exceeded", operation, ready, timeout);
+    self.counters.record_ready_timeout();
+    Err(MetrifulError::ReadyTimeoutExceeded {
+      operation,
+      wait: if ready { ReadyWait::Ready } else { ReadyWait::NotReady },
+      timeout: timeout.unwrap_or_default(),
+      elapsed: start.elapsed(),
+    })
+  }
+
+  /// Runs `f` with a deadline `timeout` from now, or the current deadline if
+  /// sooner, so that every wait and bus operation within it shares the one
+  /// timeout. The deadline is also passed to the transport; see
+  /// [`MetrifulTransport::set_deadline()`].
+  fn with_deadline<T>(
+    &mut self,
+    timeout: Option<Duration>,
+    f: impl FnOnce(&mut Metriful<D>) -> Result<T>,
+  ) -> Result<T> {
+    let previous = self.deadline;
+    let deadline = match (previous, timeout.and_then(|t| Instant::now().checked_add(t))) {
+      (Some(previous), Some(deadline)) => Some(previous.min(deadline)),
+      (previous, deadline) => previous.or(deadline),
+    };
+
+    self.deadline = deadline;
+    self.device.set_deadline(deadline);
+
+    let ret = f(self);
+
+    self.deadline = previous;
+    self.device.set_deadline(previous);
+
+    ret
+  }
+
+  /// Limits a timeout to the time remaining until the current deadline, if
+  /// any.
+  fn within_deadline(&self, timeout: Option<Duration>) -> Option<Duration> {
+    let remaining = self.deadline.map(|d| d.saturating_duration_since(Instant::now()));
+
+    match (timeout, remaining) {
+      (Some(timeout), Some(remaining)) => Some(timeout.min(remaining)),
+      (timeout, remaining) => timeout.or(remaining),
+    }
+  }
+
+  /// Records the READY state reached by a wait. Only the first observation
+  /// of READY being asserted is kept, as later waits return immediately.
+  fn observe_ready(&self, ready: bool) {
+    if !ready {
+      self.ready_since.set(None);
+    } else if self.ready_since.get().is_none() {
+      self.ready_since.set(Some((Instant::now(), Utc::now())));
     }
   }
 
+  /// Waits for the next cycle mode measurement, given the time `last` that
+  /// the previous one completed, and returns the number of measurements
+  /// completed in between, and whether the measurement completed while
+  /// waiting. If the READY line latched a measurement that completed in the
+  /// meantime, this returns without waiting for another.
+  fn wait_for_cycle(
+    &self,
+    period: CyclePeriod,
+    last: Instant,
+    timeout: Option<Duration>,
+    cancel: Option<&CancelToken>,
+  ) -> Result<(u64, bool)> {
+    let (missed, observed) = match self.ready_pin.take_ready_edges()? {
+      Some(0) => {
+        self.wait_for_state("wait_for_cycle", false, timeout, cancel)?;
+        self.wait_for_state("wait_for_cycle", true, timeout, cancel)?;
+        (0, true)
+      },
+      Some(edges) if self.ready_pin.is_ready()? => {
+        // the edge wasn't observed, so its time is unknown
+        self.ready_since.set(None);
+        (u64::from(edges) - 1, false)
+      },
+      Some(edges) => {
+        // a newer measurement is in progress; the latched data is gone
+        self.ready_since.set(None);
+        self.wait_for_state("wait_for_cycle", true, timeout, cancel)?;
+        (u64::from(edges), true)
+      },
+      None => {
+        self.wait_for_state("wait_for_cycle", false, timeout, cancel)?;
+        self.wait_for_state("wait_for_cycle", true, timeout, cancel)?;
+
+        // without latched edges, gaps can only be inferred from timing
+        let cycles = last.elapsed().as_secs_f64() / period.to_duration().as_secs_f64();
+        ((cycles.round() as u64).saturating_sub(1), true)
+      },
+    };
+
+    // edges seen while waiting belong to this measurement
+    self.ready_pin.take_ready_edges()?;
+
+    trace!("Metriful::wait_for_cycle({:?}): missed {} cycle(s)", period, missed);
+    self.counters.record_missed_cycles(missed);
+    Ok((missed, observed))
+  }
+
   /// Waits for `Metriful::is_ready()` to become true and executes the given
   /// function. If the timeout is exceeded, an error is returned.
   pub fn execute_when_ready_timeout<T>(
     &mut self,
-    func: impl FnOnce(&mut Metriful) -> T,
+    func: impl FnOnce(&mut Metriful<D>) -> T,
     timeout: Option<Duration>,
   ) -> Result<T> {
-    let start = Instant::now();
-
-    loop {
-      if self.is_ready()? {
-        return Ok(func(self));
-      }
+    self.with_deadline(timeout, |this| {
+      this.wait_for_state("execute_when_ready", true, timeout, None)?;
 
-      if let Some(timeout) = timeout {
-        if start.elapsed() > timeout {
-          return Err(MetrifulError::ReadyTimeoutExceeded)
-        } else {
-          thread::sleep(Duration::from_millis(READY_POLL_INTERVAL));
-        }
-      }
-    }
+      Ok(func(this))
+    })
   }
 
   /// Waits for [`Metriful::is_ready()`] to become true and executes the given
   /// function. This has no timeout and may wait indefinitely.
   pub fn execute_when_ready<T>(
     &mut self,
-    func: impl FnOnce(&mut Metriful) -> T,
+    func: impl FnOnce(&mut Metriful<D>) -> T,
   ) -> Result<T> {
     self.execute_when_ready_timeout(func, None)
   }
 
   /// Sends a device reset command, waits for it to become ready again, and
-  /// returns a refreshed [`DeviceStatus`]. Raises an error if the device i
-  /// not initially ready.
-  pub fn reset(&mut self) -> Result<DeviceStatus> {
+  /// returns a refreshed [`DeviceStatus`]. Raises an error if the device is
+  /// not initially ready, or if the timeout is set and exceeded while waiting
+  /// for it to become ready again.
+  pub fn reset_timeout(&mut self, timeout: Option<Duration>) -> Result<DeviceStatus> {
     self.ensure_ready()?;
+    self.force_reset_timeout(timeout)
+  }
 
-    self.device.smbus_write_byte(0xE2)?;
-    self.sleep_write();
+  /// Sends a device reset command without first checking that the device is
+  /// ready, e.g. to recover one that has stopped asserting READY. See
+  /// [`Metriful::reset_timeout()`].
+  pub(crate) fn force_reset_timeout(&mut self, timeout: Option<Duration>) -> Result<DeviceStatus> {
+    self.with_deadline(timeout, |this| {
+      this.guard.check(CommandKind::ModeChange)?;
+      with_context(&mut this.device, "reset").write_byte(0xE2)?;
+      this.ready_since.set(None);
+      this.interrupts_stale = true;
+      thread::sleep(guard::MODE_CHANGE_SETTLE);
+
+      this.wait_for_state("reset", true, timeout, None)?;
+      this.read_status()
+    })
+  }
+
+  /// Replaces the READY line and/or I2C device (if given) with reconnected
+  /// ones, waits for the device to become ready, and returns a refreshed
+  /// [`DeviceStatus`]. Returns an error if the timeout is set and exceeded.
+  ///
+  /// If the device lost its configuration in the meantime, e.g. because it
+  /// was power cycled, the particle sensor, interrupt and cycle mode settings
+  /// as of the last status read are restored via
+  /// [`Metriful::apply_config_timeout()`]. If recovery fails, the same
+  /// settings are restored by the next successful attempt.
+  /// Calibration, plausibility checks, counters, and other settings of this
+  /// instance are kept.
+  ///
+  /// # Example
+  /// ```
+  /// # #[cfg(feature = "testing")] {
+  /// use std::time::Duration;
+  /// use metriful::{Metriful, CyclePeriod, OperationalMode};
+  /// use metriful::testing::*;
+  ///
+  /// let mock = MockDevice::new();
+  /// let timeout = Some(Duration::from_millis(100));
+  /// let mut metriful = Metriful::try_new_device_timeout(mock.ready_line(), mock.clone(), timeout).unwrap();
+  /// metriful.set_mode_timeout(OperationalMode::Cycle(CyclePeriod::Period0), timeout).unwrap();
+  ///
+  /// // the sensor was power cycled and reconnected
+  /// let replugged = MockDevice::new();
+  /// let status = metriful.recover_with(
+  ///   Some(Box::new(replugged.ready_line())), Some(replugged.clone()), timeout
+  /// ).unwrap();
+  ///
+  /// assert_eq!(status.mode, OperationalMode::Cycle(CyclePeriod::Period0));
+  /// # }
+  /// ```
+  pub fn recover_with(
+    &mut self,
+    ready_pin: Option<Box<dyn ReadyLine>>,
+    device: Option<D>,
+    timeout: Option<Duration>,
+  ) -> Result<DeviceStatus> {
+    trace!("Metriful::recover_with({:?}, .., {:?})", ready_pin, timeout);
+
+    self.with_deadline(timeout, |this| {
+      // kept until a recovery succeeds, as a failed attempt may already have
+      // read the status of a device that lost its configuration
+      if this.restore.is_none() {
+        this.restore = this.status.as_ref().map(DeviceConfig::from_status);
+      }
+
+      if let Some(ready_pin) = ready_pin {
+        this.ready_pin = ready_pin;
+      }
 
-    self.wait_for_ready()?;
-    Ok(self.read_status()?)
+      if let Some(device) = device {
+        this.device = device;
+      }
+
+      this.ready_since.set(None);
+      this.interrupts_stale = true;
+
+      this.wait_for_state("recover", true, timeout, None)?;
+      let status = this.read_status()?;
+
+      let status = match this.restore.clone() {
+        Some(config) if !config.is_applied(&status) => {
+          trace!("Metriful::recover_with(): restoring {:?}", config);
+          this.apply_config_timeout(&config, timeout)?
+        },
+        _ => status,
+      };
+
+      this.restore = None;
+      Ok(status)
+    })
+  }
+
+  /// Sends a device reset command, waits for it to become ready again, and
+  /// returns a refreshed [`DeviceStatus`]. Raises an error if the device is
+  /// not initially ready. May block indefinitely if the device does not become
+  /// ready; see [`Metriful::reset_timeout()`].
+  pub fn reset(&mut self) -> Result<DeviceStatus> {
+    self.reset_timeout(None)
+  }
+
+  /// Configures the external particle sensor and returns a refreshed
+  /// [`DeviceStatus`]. The device must be in standby mode; waits for it to be
+  /// ready first, returning an error if the timeout is set and exceeded.
+  pub fn set_particle_sensor_timeout(
+    &mut self,
+    mode: ParticleSensorMode,
+    timeout: Option<Duration>,
+  ) -> Result<DeviceStatus> {
+    self.with_deadline(timeout, |this| {
+      this.wait_for_state("set_particle_sensor", true, timeout, None)?;
+
+      let status = this.read_status()?;
+      if !matches!(status.mode, OperationalMode::Standby) {
+        return Err(MetrifulError::InvalidMode {
+          current: status.mode,
+          required: OperationalMode::Standby
+        });
+      }
+
+      this.guard.check(CommandKind::Other)?;
+      with_context(&mut this.device, "set_particle_sensor").write_byte_data(0x07, mode.to_value())?;
+      this.sleep_write();
+
+      trace!("Metriful::set_particle_sensor_timeout({:?}): done", mode);
+
+      this.read_status()
+    })
   }
 
   /// Sends a 'clear light interrupt' command. Will raise an error if the device
@@ -456,7 +1456,8 @@ impl Metriful {
   pub fn clear_light_interrupt(&mut self) -> Result<()> {
     self.ensure_ready()?;
 
-    self.device.smbus_write_byte(0xE6)?;
+    self.guard.check(CommandKind::Other)?;
+    with_context(&mut self.device, "clear_light_interrupt").write_byte(0xE6)?;
     self.sleep_write();
 
     Ok(())
@@ -467,12 +1468,166 @@ impl Metriful {
   pub fn clear_sound_interrupt(&mut self) -> Result<()> {
     self.ensure_ready()?;
 
-    self.device.smbus_write_byte(0xE7)?;
+    self.guard.check(CommandKind::Other)?;
+    with_context(&mut self.device, "clear_sound_interrupt").write_byte(0xE7)?;
     self.sleep_write();
 
     Ok(())
   }
 
+  /// Enables, disables, or reconfigures the light interrupt and returns a
+  /// refreshed [`DeviceStatus`]. When enabling, the threshold, mode and
+  /// polarity are written before the interrupt is enabled. Will raise an error
+  /// if the device is not ready.
+  ///
+  /// # Example
+  /// ```no_run
+  /// use metriful::{Metriful, InterruptMode, InterruptPolarity, InterruptStatus, LightInterrupt};
+  ///
+  /// # fn main() -> metriful::error::Result<()> {
+  /// let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+  ///
+  /// // trigger while illuminance is below 10.5 lux
+  /// metriful.configure_light_interrupt(InterruptStatus::Enabled(LightInterrupt {
+  ///   mode: InterruptMode::Comparator,
+  ///   polarity: InterruptPolarity::Negative,
+  ///   threshold: 10.5,
+  /// }))?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn configure_light_interrupt(&mut self, config: LightInterruptConfig) -> Result<DeviceStatus> {
+    self.ensure_ready()?;
+
+    match &config {
+      InterruptStatus::Disabled => {
+        self.guard.check(CommandKind::Other)?;
+        with_context(&mut self.device, "configure_light_interrupt").write_byte_data(0x81, 0)?;
+        self.sleep_write();
+      },
+      InterruptStatus::Enabled(interrupt) => {
+        self.guard.check(CommandKind::Other)?;
+        interrupt.write(&mut with_context(&mut self.device, "configure_light_interrupt"))?;
+        self.sleep_write();
+
+        self.guard.check(CommandKind::Other)?;
+        with_context(&mut self.device, "configure_light_interrupt").write_byte_data(0x81, 1)?;
+        self.sleep_write();
+      },
+    }
+
+    trace!("Metriful::configure_light_interrupt({:?}): done", config);
+
+    self.read_status_uncached()
+  }
+
+  /// Enables, disables, or reconfigures the sound interrupt and returns a
+  /// refreshed [`DeviceStatus`]. When enabling, the threshold and mode are
+  /// written before the interrupt is enabled. Will raise an error if the
+  /// device is not ready.
+  ///
+  /// # Example
+  /// ```no_run
+  /// use metriful::{Metriful, InterruptMode, InterruptStatus, SoundInterrupt};
+  ///
+  /// # fn main() -> metriful::error::Result<()> {
+  /// let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+  ///
+  /// // trigger when the peak amplitude exceeds 5000 mPa, until cleared
+  /// metriful.configure_sound_interrupt(InterruptStatus::Enabled(SoundInterrupt {
+  ///   mode: InterruptMode::Latch,
+  ///   threshold: 5000,
+  /// }))?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn configure_sound_interrupt(&mut self, config: SoundInterruptConfig) -> Result<DeviceStatus> {
+    self.ensure_ready()?;
+
+    match config {
+      InterruptStatus::Disabled => {
+        self.guard.check(CommandKind::Other)?;
+        with_context(&mut self.device, "configure_sound_interrupt").write_byte_data(0x85, 0)?;
+        self.sleep_write();
+      },
+      InterruptStatus::Enabled(interrupt) => {
+        self.guard.check(CommandKind::Other)?;
+        interrupt.write(&mut with_context(&mut self.device, "configure_sound_interrupt"))?;
+        self.sleep_write();
+
+        self.guard.check(CommandKind::Other)?;
+        with_context(&mut self.device, "configure_sound_interrupt").write_byte_data(0x85, 1)?;
+        self.sleep_write();
+      },
+    }
+
+    trace!("Metriful::configure_sound_interrupt({:?}): done", config);
+
+    self.read_status_uncached()
+  }
+
+  /// Brings the device settings in line with the given [`DeviceConfig`] and
+  /// returns a refreshed [`DeviceStatus`]. Only settings that differ from the
+  /// current status are written, so applying the same configuration again is
+  /// a no-op.
+  ///
+  /// Settings may only be changed in standby mode; if any need changing, the
+  /// device is first switched to standby, and then to the configured mode
+  /// once all settings are written. Waits for the device to become ready
+  /// before each step, returning an error if the timeout is set and exceeded.
+  pub fn apply_config_timeout(
+    &mut self,
+    config: &DeviceConfig,
+    timeout: Option<Duration>,
+  ) -> Result<DeviceStatus> {
+    trace!("Metriful::apply_config_timeout({:?}, {:?})", config, timeout);
+
+    self.with_deadline(timeout, |this| {
+      this.wait_for_state("apply_config", true, timeout, None)?;
+      let mut status = this.read_status()?;
+      if config.is_applied(&status) {
+        trace!("Metriful::apply_config_timeout(): already applied");
+        return Ok(status);
+      }
+
+      let settings_applied = config.particle_sensor_applied(&status)
+        && config.light_interrupt_applied(&status)
+        && config.sound_interrupt_applied(&status);
+
+      if !settings_applied {
+        status = this.set_mode_timeout(OperationalMode::Standby, timeout)?;
+
+        if !config.particle_sensor_applied(&status) {
+          status = this.set_particle_sensor_timeout(config.particle_sensor, timeout)?;
+        }
+
+        if !config.light_interrupt_applied(&status) {
+          this.wait_for_state("apply_config", true, timeout, None)?;
+          status = this.configure_light_interrupt(config.light_interrupt.clone())?;
+        }
+
+        if !config.sound_interrupt_applied(&status) {
+          this.wait_for_state("apply_config", true, timeout, None)?;
+          status = this.configure_sound_interrupt(config.sound_interrupt.clone())?;
+        }
+      }
+
+      if status.mode != config.mode() {
+        status = this.set_mode_timeout(config.mode(), timeout)?;
+      }
+
+      trace!("Metriful::apply_config_timeout(): done");
+
+      Ok(status)
+    })
+  }
+
+  /// Applies a [`DeviceConfig`], waiting indefinitely for the device to become
+  /// ready. See [`Metriful::apply_config_timeout()`].
+  pub fn apply_config(&mut self, config: &DeviceConfig) -> Result<DeviceStatus> {
+    self.apply_config_timeout(config, None)
+  }
+
   /// Naively changes the device's operational mode. This function does not
   /// ensure the device is in a valid state beforehand and may send illegal
   /// commands, however it will not block the thread beyond the required 6ms
@@ -490,20 +1645,30 @@ impl Metriful {
   ///  * 2.6s for standby -> 100/300s cycle
   fn set_mode_naive(&mut self, mode: OperationalMode) -> Result<()> {
     match mode {
-      OperationalMode::Standby => self.device.smbus_write_byte(0xE5)?,
+      OperationalMode::Standby => {
+        self.guard.check(CommandKind::ModeChange)?;
+        with_context(&mut self.device, "set_mode").write_byte(0xE5)?;
+        self.ready_since.set(None);
+
+        // per docs, it takes 11ms to enter standby mode
+        thread::sleep(guard::MODE_CHANGE_SETTLE);
+      },
       OperationalMode::Cycle(period) => {
         // configure the cycle
-        self.device.smbus_write_byte_data(0x89, period.to_value())?;
+        self.guard.check(CommandKind::Other)?;
+        with_context(&mut self.device, "set_mode").write_byte_data(0x89, period.to_value())?;
 
         // per docs, must wait 6ms between commands if commands depend on one
         // another
         self.sleep_write();
 
         // enter cycle mode
-        self.device.smbus_write_byte(0xE4)?;
+        self.guard.check(CommandKind::ModeChange)?;
+        with_context(&mut self.device, "set_mode").write_byte(0xE4)?;
+        self.ready_since.set(None);
 
         // per docs, it takes 11ms to enter cycle mode
-        thread::sleep(Duration::from_millis(11));
+        thread::sleep(guard::MODE_CHANGE_SETTLE);
       }
     }
 
@@ -529,31 +1694,33 @@ impl Metriful {
     mode: OperationalMode,
     timeout: Option<Duration>
   ) -> Result<DeviceStatus> {
-    use OperationalMode::*;
-    self.wait_for_ready_timeout(timeout)?;
-
-    let status = self.read_status()?;
-    match (status.mode, mode) {
-      // no-op
-      (Standby, Standby) => (),
-      (Cycle(a), Cycle(b)) if a == b => (),
-
-      // valid
-      (Standby, Cycle(_)) => self.set_mode_naive(mode)?,
-      (Cycle(_), Standby) => self.set_mode_naive(mode)?,
-
-      // need an intermediate standby
-      (Cycle(_), Cycle(_)) => {
-        self.set_mode_naive(OperationalMode::Standby)?;
-        self.wait_for_ready_timeout(timeout)?;
-        self.set_mode_naive(mode)?;
-      },
-    }
+    self.with_deadline(timeout, |this| {
+      use OperationalMode::*;
+      this.wait_for_state("set_mode", true, timeout, None)?;
+
+      let status = this.read_status()?;
+      match (status.mode, mode) {
+        // no-op
+        (Standby, Standby) => (),
+        (Cycle(a), Cycle(b)) if a == b => (),
+
+        // valid
+        (Standby, Cycle(_)) => this.set_mode_naive(mode)?,
+        (Cycle(_), Standby) => this.set_mode_naive(mode)?,
+
+        // need an intermediate standby
+        (Cycle(_), Cycle(_)) => {
+          this.set_mode_naive(OperationalMode::Standby)?;
+          this.wait_for_state("set_mode", true, timeout, None)?;
+          this.set_mode_naive(mode)?;
+        },
+      }
 
-    self.wait_for_ready_timeout(timeout)?;
-    trace!("Metriful::set_mode_timeout(): finished, ready");
+      this.wait_for_state("set_mode", true, timeout, None)?;
+      trace!("Metriful::set_mode_timeout(): finished, ready");
 
-    Ok(self.read_status()?)
+      this.read_status()
+    })
   }
 
   /// Executes an on-demand measurement.
@@ -561,6 +1728,9 @@ impl Metriful {
   /// Notes:
   ///  * Device must currently be in READY state
   ///  * Device must be in standby mode
+  ///  * Measurements must be spaced at least
+  ///    [`RateLimit::measurement_interval`] apart (2 seconds by default) or
+  ///    [`MetrifulError::RateLimited`] is returned
   pub fn execute_measurement(&mut self) -> Result<()> {
     let status = match &self.status {
       Some(status) => status,
@@ -576,7 +1746,9 @@ impl Metriful {
 
     self.ensure_ready()?;
 
-    self.device.smbus_write_byte(0xE1)?;
+    self.guard.check(CommandKind::Measurement)?;
+    with_context(&mut self.device, "execute_measurement").write_byte(0xE1)?;
+    self.ready_since.set(None);
     self.sleep_write();
 
     trace!("Metriful::execute_measurement(): done");
@@ -587,6 +1759,11 @@ impl Metriful {
   /// Reads the given metric from the device. Note that the device must
   /// currently be in a READY state or an error will be raised.
   ///
+  /// Air quality metrics (e.g. [`METRIC_AQI`]) are only valid in cycle
+  /// mode; reading them while the device is in standby returns
+  /// [`MetrifulError::MetricRequiresCycleMode`] unless disabled via
+  /// [`Metriful::set_enforce_mode_validity()`].
+  ///
   /// # Example
   /// ```no_run
   /// use metriful::{Metriful, metric::*};
@@ -594,16 +1771,167 @@ impl Metriful {
   /// # fn main() -> metriful::error::Result<()> {
   /// let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
   ///
-  /// println!("{}", metriful.read(*METRIC_COMBINED_ALL)?);
+  /// println!("{}", metriful.read(METRIC_COMBINED_ALL)?);
   /// # Ok(())
   /// # }
   /// ```
   pub fn read<U: MetrifulUnit>(&mut self, metric: Metric<U>) -> Result<UnitValue<U>> {
     self.ensure_ready()?;
+    self.ensure_valid_mode(metric.register)?;
 
-    let ret = metric.read(&mut self.device);
-    trace!("Metriful::read({:x?}) -> {:?}", metric, &ret);
-    ret
+    let (ready_instant, ready_time) = self.ready_since.get()
+      .unwrap_or_else(|| (Instant::now(), Utc::now()));
+
+    let start = Instant::now();
+    let ret = metric.read(&mut with_context(&mut self.device, "read")).and_then(|mut value| {
+      U::calibrate(&mut value.value, &self.calibration);
+      if let Some(status) = self.validity_status() {
+        U::mask_unavailable(&mut value.value, status);
+      }
+
+      value.timing = Some(ReadTiming {
+        ready_instant,
+        ready_time,
+        read_duration: start.elapsed(),
+      });
+
+      if let Some(plausibility) = &mut self.plausibility {
+        let name = metric.metadata().map(|info| info.id).unwrap_or("unknown");
+        value.warnings = plausibility.check(name, &value)?;
+      }
+
+      Ok(value)
+    });
+
+    self.counters.record_read(&ret);
+    trace!("Metriful::read({:x?}) -> {:?}", metric, &ret);
+    ret
+  }
+
+  /// Waits for the device to become ready and reads the given metric, as
+  /// with [`Metriful::read()`]. Returns an error if the timeout is set and
+  /// exceeded; with a [`transport::TimeoutTransport`], this includes time
+  /// spent on the bus.
+  pub fn read_timeout<U: MetrifulUnit>(
+    &mut self,
+    metric: Metric<U>,
+    timeout: Option<Duration>,
+  ) -> Result<UnitValue<U>> {
+    self.with_deadline(timeout, |this| {
+      this.wait_for_state("read", true, timeout, None)?;
+      this.read(metric)
+    })
+  }
+
+  /// Reads the given metric's raw register contents without decoding them;
+  /// see [`Metric::read_raw()`]. The device must currently be ready.
+  ///
+  /// With the `raw-bytes` feature, values returned by [`Metriful::read()`]
+  /// also retain their raw contents in the `raw_bytes` field of [`UnitValue`].
+  pub fn read_raw<U: MetrifulUnit>(&mut self, metric: Metric<U>) -> Result<bytes::Bytes> {
+    self.ensure_ready()?;
+
+    let ret = metric.read_raw(&mut with_context(&mut self.device, "read_raw"));
+    trace!("Metriful::read_raw({:x?}) -> {:x?}", metric, &ret);
+    ret
+  }
+
+  /// Reads a metric selected at runtime, e.g. via [`metric::by_name()`]. As
+  /// with [`Metriful::read()`], the device must currently be ready.
+  ///
+  /// # Example
+  /// ```no_run
+  /// use metriful::{Metriful, metric::DynamicMetric};
+  ///
+  /// # fn main() -> metriful::error::Result<()> {
+  /// let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+  ///
+  /// let metric: DynamicMetric = "temperature".parse()?;
+  /// println!("{}", metriful.read_dynamic(metric)?);
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn read_dynamic(&mut self, metric: DynamicMetric) -> Result<DynamicValue> {
+    metric.read(self)
+  }
+
+  /// Reads a type-erased metric, e.g. one of a user-selected set; see the
+  /// [`dyn_metric`] module. As with [`Metriful::read()`], the device must
+  /// currently be ready, and any configured calibration is applied.
+  pub fn read_dyn(&mut self, metric: &dyn DynMetric) -> Result<DynReading> {
+    self.ensure_ready()?;
+    self.ensure_valid_mode(metric.register())?;
+
+    let status = self.status.as_ref().filter(|_| self.enforce_validity);
+    let ret = metric.read_dyn(&mut with_context(&mut self.device, "read_dyn"), &self.calibration, status);
+    self.counters.record_read(&ret);
+    trace!("Metriful::read_dyn({:x?}) -> {:?}", metric, &ret);
+    ret
+  }
+
+  /// Reads every metric in a [`MetricSet`] within a single READY window; all
+  /// readings share one timestamp. The device must currently be ready, and
+  /// if READY deasserts before all metrics are read, [`MetrifulError::NotReady`]
+  /// is returned rather than a possibly inconsistent result.
+  pub fn read_set(&mut self, set: &MetricSet) -> Result<MetricSetReading> {
+    self.ensure_ready()?;
+    for metric in set.metrics() {
+      self.ensure_valid_mode(metric.register())?;
+    }
+
+    let time = chrono::Utc::now();
+    let status = self.status.as_ref().filter(|_| self.enforce_validity);
+    let (device, calibration) = (&mut self.device, &self.calibration);
+    let readings = set.metrics()
+      .map(|metric| metric.read_dyn(&mut with_context(device, "read_set"), calibration, status))
+      .collect::<Result<Vec<_>>>()?;
+
+    self.ensure_ready()?;
+
+    let ret = MetricSetReading::new(time, readings);
+    trace!("Metriful::read_set({:?}) -> {:?}", set, &ret);
+    Ok(ret)
+  }
+
+  /// Reads every individual metric that is meaningful in the device's current
+  /// state, keyed by metric id (see [`metric::MetricInfo::id`]). Air quality
+  /// metrics are skipped in standby mode and particle metrics are skipped
+  /// unless a particle sensor is enabled; combined reads are never included.
+  ///
+  /// As with [`Metriful::read_set()`], all metrics are read within a single
+  /// READY window and share one timestamp.
+  ///
+  /// # Example
+  /// ```no_run
+  /// use metriful::Metriful;
+  ///
+  /// # fn main() -> metriful::error::Result<()> {
+  /// let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+  ///
+  /// for (name, reading) in metriful.read_all_map()? {
+  ///   println!("{}: {}", name, reading);
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn read_all_map(&mut self) -> Result<BTreeMap<&'static str, DynReading>> {
+    let standby = self.is_mode_standby();
+    let particle_sensor = self.status.as_ref()
+      .map(DeviceStatus::particle_sensor_enabled)
+      .unwrap_or(false);
+
+    let set = DynamicMetric::ALL.iter()
+      .filter(|metric| {
+        let info = metric.metadata();
+
+        !info.combined
+          && (!info.validity.cycle_mode || !standby)
+          && (!info.validity.particle_sensor || particle_sensor)
+      })
+      .fold(MetricSet::new(), |set, metric| set.with(*metric));
+
+    let reading = self.read_set(&set)?;
+    Ok(reading.readings.into_iter().map(|r| (r.name, r)).collect())
   }
 
   /// Returns an iterator that reads the given metric repeatedly at a given
@@ -616,7 +1944,7 @@ impl Metriful {
   ///
   /// Only a single "metric" may be read per iteration, however various
   /// combined pseudo-metrics can be be used to read more data, including
-  /// [`struct@METRIC_COMBINED_ALL`].
+  /// [`METRIC_COMBINED_ALL`].
   ///
   /// See the [`MetricReadIterator`] documentation for further information.
   ///
@@ -629,7 +1957,7 @@ impl Metriful {
   /// let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
   ///
   /// let iter = metriful.read_iter_timeout(
-  ///   *METRIC_COMBINED_ALL,
+  ///   METRIC_COMBINED_ALL,
   ///   Duration::from_secs(3),
   ///   Some(Duration::from_secs(3))
   /// );
@@ -645,12 +1973,14 @@ impl Metriful {
     metric: Metric<U>,
     interval: Duration,
     timeout: Option<Duration>,
-  ) -> MetricReadIterator<U>
+  ) -> MetricReadIterator<U, D>
   where
     U: MetrifulUnit
   {
     MetricReadIterator {
+      cancel: self.cancel.clone(),
       device: self,
+      policy: ReadPolicy::default(),
       error: false,
       last_instant: Instant::now(),
       metric,
@@ -669,7 +1999,7 @@ impl Metriful {
   ///
   /// Only a single "metric" may be read per iteration, however various
   /// combined pseudo-metrics can be be used to read more data, including
-  /// [`struct@METRIC_COMBINED_ALL`].
+  /// [`METRIC_COMBINED_ALL`].
   ///
   /// This may block indefinitely if device communication fails; consider using
   /// [`Metriful::read_iter_timeout()`] to specify a timeout.
@@ -684,7 +2014,7 @@ impl Metriful {
   /// # fn main() -> metriful::error::Result<()> {
   /// let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
   ///
-  /// for metric in metriful.read_iter(*METRIC_COMBINED_ALL, Duration::from_secs(3)) {
+  /// for metric in metriful.read_iter(METRIC_COMBINED_ALL, Duration::from_secs(3)) {
   ///   let metric = metric?;
   ///   println!("{}", metric);
   /// }
@@ -695,12 +2025,14 @@ impl Metriful {
     &'a mut self,
     metric: Metric<U>,
     interval: Duration,
-  ) -> MetricReadIterator<U>
+  ) -> MetricReadIterator<U, D>
   where
     U: MetrifulUnit
   {
     MetricReadIterator {
+      cancel: self.cancel.clone(),
       device: self,
+      policy: ReadPolicy::default(),
       error: false,
       timeout: None,
       last_instant: Instant::now(),
@@ -709,6 +2041,55 @@ impl Metriful {
     }
   }
 
+  /// Takes `n` on-demand readings of the given metric, `interval` apart, and
+  /// returns them along with summary statistics. The first reading is taken
+  /// immediately, so this blocks for roughly `(n - 1) * interval`.
+  ///
+  /// This is a convenience for e.g. calibration scripts and self-tests; as
+  /// with [`Metriful::read_iter()`], the device must be in standby mode, and
+  /// the first error encountered is returned.
+  ///
+  /// # Example
+  /// ```
+  /// # #[cfg(feature = "testing")] {
+  /// use std::time::Duration;
+  /// use metriful::{Metriful, RateLimit, metric::*, testing::MockDevice};
+  ///
+  /// let mock = MockDevice::new();
+  /// mock.set_register(0x21, &[21, 5]);
+  ///
+  /// let mut metriful = Metriful::try_new_device_timeout(
+  ///   mock.ready_line(), mock.clone(), Some(Duration::from_millis(100))
+  /// ).unwrap();
+  /// metriful.set_rate_limit(RateLimit::disabled());
+  ///
+  /// let samples = metriful.read_n(METRIC_TEMPERATURE, 3, Duration::from_millis(1)).unwrap();
+  /// assert_eq!(samples.values.len(), 3);
+  ///
+  /// let summary = samples.summary().unwrap();
+  /// assert_eq!(summary.count, 3);
+  /// assert_eq!(summary.mean, 21.5);
+  /// assert_eq!(summary.stddev, 0.0);
+  /// # }
+  /// ```
+  pub fn read_n<U: MetrifulUnit>(
+    &mut self,
+    metric: Metric<U>,
+    n: usize,
+    interval: Duration,
+  ) -> Result<Samples<U>> {
+    let name = metric.metadata().map(|info| info.id).unwrap_or("value");
+
+    let now = Instant::now();
+    let mut iter = self.read_iter(metric, interval);
+    iter.last_instant = now.checked_sub(interval).unwrap_or(now);
+
+    let values = iter.take(n).collect::<Result<Vec<_>>>()?;
+    trace!("Metriful::read_n({:x?}, {}, {:?}): read {} values", metric, n, interval, values.len());
+
+    Ok(Samples::new(name, values))
+  }
+
   /// Returns an iterator that reads the given metric repeatedly at the given
   /// device-supported [`CyclePeriod`]. Note that the thread will block for
   /// `interval` duration on each read. It reads indefinitely or until an error
@@ -716,7 +2097,7 @@ impl Metriful {
   ///
   /// Only a single "metric" may be read per iteration, however various
   /// combined pseudo-metrics can be be used to read more data, including
-  /// [`struct@METRIC_COMBINED_ALL`].
+  /// [`METRIC_COMBINED_ALL`].
   ///
   /// See the [`CycleReadIterator`] documentation for further information.
   ///
@@ -729,7 +2110,7 @@ impl Metriful {
   /// let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
   ///
   /// let iter = metriful.cycle_read_iter_timeout(
-  ///   *METRIC_COMBINED_ALL,
+  ///   METRIC_COMBINED_ALL,
   ///   CyclePeriod::Period0,
   ///   Some(Duration::from_secs(3)),
   /// );
@@ -746,14 +2127,21 @@ impl Metriful {
     metric: Metric<U>,
     cycle_period: CyclePeriod,
     timeout: Option<Duration>,
-  ) -> CycleReadIterator<U>
+  ) -> CycleReadIterator<U, D>
   where
     U: MetrifulUnit
   {
     CycleReadIterator {
+      cancel: self.cancel.clone(),
       device: self,
+      policy: ReadPolicy::default(),
       first: true,
       error: false,
+      last_cycle: Instant::now(),
+      sequence: None,
+      missed: 0,
+      anchor: None,
+      drift: None,
       metric,
       cycle_period,
       timeout,
@@ -763,66 +2151,384 @@ impl Metriful {
   /// Spawns an async cycle read thread that reports metrics.
   ///
   /// This function returns three objects callers may interact with:
-  ///  * `cmd_tx`: send the unit value `()` via this channel to ask the
-  ///    background thread to terminate, e.g. `cmd_tx.send(())?`
+  ///  * `cmd_tx`: send [`ReaderCommand`]s via this channel to pause, resume,
+  ///    or terminate the background thread, e.g.
+  ///    `cmd_tx.send(ReaderCommand::Stop)?`
   ///  * `metric_rx`: read metrics are periodically sent here
   ///  * `handle`: a thread JoinHandle
   ///
   /// This takes ownership of the `Metriful` instance for as long as the
   /// background thread is alive. The original owned [`Metriful`] is returned
-  /// via `.join()` on the returned `JoinHandle`. Send the unit value `()` via
-  /// `cmd_tx` (e.g. `cmd_tx.send(())?`) to ask the thread to terminate before
-  /// attempting to join it to avoid a deadlock.
+  /// via `.join()` on the returned `JoinHandle`. Send [`ReaderCommand::Stop`]
+  /// via `cmd_tx` to ask the thread to terminate before attempting to join it
+  /// to avoid a deadlock.
   ///
   /// If an error occurs, it will be sent via `metric_rx` and the thread will
   /// terminate.
+  ///
+  /// Readings are queued without limit until received; if the consumer may
+  /// stall, see [`Metriful::async_cycle_read_bounded()`] instead.
+  ///
+  /// Commands sent via `cmd_tx` are only noticed after the next reading, which
+  /// may take a full cycle period. To stop the thread promptly, set a
+  /// [`CancelToken`] via [`Metriful::set_cancel_token()`] before spawning it
+  /// and cancel that instead.
   pub fn async_cycle_read_timeout<U>(
-    mut self,
+    self,
     metric: Metric<U>,
     cycle_period: CyclePeriod,
     timeout: Option<Duration>,
-  ) -> (Sender<()>, Receiver<Result<UnitValue<U>>>, JoinHandle<Metriful>)
+  ) -> ReaderHandles<D, Receiver<Result<UnitValue<U>>>>
   where
-    U: MetrifulUnit + 'static
+    U: MetrifulUnit + 'static,
+    D: Send + 'static,
   {
-    let (cmd_tx, cmd_rx) = channel();
-    let (metric_tx, metric_rx) = channel();
+    let (metric_tx, metric_rx) = mpsc::channel();
+    let (cmd_tx, handle) = self.spawn_cycle_reader(
+      metric, cycle_period, timeout,
+      move |result| metric_tx.send(result).is_ok(),
+    );
 
-    let handle = thread::spawn(move || {
-      let iter = self.cycle_read_iter_timeout(metric, cycle_period, timeout);
+    (cmd_tx, metric_rx, handle)
+  }
+
+  /// Spawns a background thread that runs the device in cycle mode and reports
+  /// readings, interrupts, mode changes, and recoverable errors as a single
+  /// stream of [`MetrifulEvent`](events::MetrifulEvent)s. See the
+  /// [`events`] module for details.
+  ///
+  /// As with [`Metriful::async_cycle_read_timeout()`], send [`ReaderCommand`]s
+  /// via `cmd_tx` to pause or stop the thread, and join `handle` to recover
+  /// the `Metriful`. Unlike that function, errors do not end the stream unless
+  /// [`EventConfig::max_consecutive_errors`](events::EventConfig::max_consecutive_errors)
+  /// occur in a row.
+  pub fn event_stream<U>(
+    self,
+    metric: Metric<U>,
+    config: events::EventConfig,
+  ) -> ReaderHandles<D, Receiver<events::MetrifulEvent<U>>>
+  where
+    U: MetrifulUnit + 'static,
+    D: Send + 'static,
+  {
+    trace!("Metriful::event_stream({:?}, {:?})", metric, config);
+
+    events::spawn(self, metric, config)
+  }
+
+  /// Spawns an async cycle read thread that reports metrics via a bounded
+  /// channel.
+  ///
+  /// This behaves like [`Metriful::async_cycle_read_timeout()`], but at most
+  /// `capacity` readings are queued at once; if the consumer falls behind,
+  /// `policy` determines whether the background thread waits or which reading
+  /// is discarded. See [`channel::BackpressurePolicy`] for details.
+  ///
+  /// Errors are never discarded: if the channel is full when an error occurs,
+  /// the oldest queued reading is evicted to make room for it. The number of
+  /// readings discarded is available via [`BoundedReceiver::dropped()`].
+  ///
+  /// Note that with [`channel::BackpressurePolicy::Block`], the thread can
+  /// only notice a termination request sent via `cmd_tx` once the consumer
+  /// makes room in the channel.
+  ///
+  /// # Example
+  /// ```no_run
+  /// use std::time::Duration;
+  /// use metriful::{Metriful, CyclePeriod};
+  /// use metriful::channel::BackpressurePolicy;
+  /// use metriful::metric::METRIC_COMBINED_ALL;
+  ///
+  /// # fn main() -> metriful::error::Result<()> {
+  /// let metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+  /// let (_cmd_tx, metric_rx, _handle) = metriful.async_cycle_read_bounded(
+  ///   METRIC_COMBINED_ALL, CyclePeriod::Period0, Some(Duration::from_secs(5)),
+  ///   1, BackpressurePolicy::CoalesceLatest,
+  /// );
+  ///
+  /// for reading in metric_rx {
+  ///   println!("{}", reading?);
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn async_cycle_read_bounded<U>(
+    self,
+    metric: Metric<U>,
+    cycle_period: CyclePeriod,
+    timeout: Option<Duration>,
+    capacity: usize,
+    policy: BackpressurePolicy,
+  ) -> ReaderHandles<D, BoundedReceiver<Result<UnitValue<U>>>>
+  where
+    U: MetrifulUnit + 'static,
+    D: Send + 'static,
+  {
+    let (metric_tx, metric_rx) = channel::bounded(capacity, policy);
+    let (cmd_tx, handle) = self.spawn_cycle_reader(
+      metric, cycle_period, timeout,
+      move |result| match result {
+        Ok(_) => metric_tx.send(result).is_ok(),
+        Err(_) => metric_tx.force_send(result).is_ok(),
+      },
+    );
 
-      for metric in iter {
-        if cmd_rx.try_recv().is_ok() {
-          trace!("Metriful::async_cycle_read_timeout(): break");
-          break;
+    (cmd_tx, metric_rx, handle)
+  }
+
+  /// Spawns an async cycle read thread that publishes metrics to a
+  /// [`LatestReading`] cell.
+  ///
+  /// Rather than queueing every reading, only the most recent one is kept;
+  /// any number of consumers may snapshot it or wait for updates. If an error
+  /// occurs, it is recorded via [`LatestReading::set_error()`] and the thread
+  /// terminates. The cell is closed once the thread exits.
+  ///
+  /// # Example
+  /// ```no_run
+  /// use std::time::Duration;
+  /// use metriful::{Metriful, CyclePeriod};
+  /// use metriful::metric::METRIC_COMBINED_ALL;
+  ///
+  /// # fn main() -> metriful::error::Result<()> {
+  /// let metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+  /// let (_cmd_tx, latest, _handle) = metriful.async_cycle_read_latest(
+  ///   METRIC_COMBINED_ALL, CyclePeriod::Period0, Some(Duration::from_secs(5)),
+  /// );
+  ///
+  /// let mut seen = 0;
+  /// while let Some((version, reading)) = latest.wait_for_update(seen) {
+  ///   println!("{}", reading);
+  ///   seen = version;
+  /// }
+  ///
+  /// if let Some(e) = latest.last_error() {
+  ///   eprintln!("reader failed: {}", e);
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn async_cycle_read_latest<U>(
+    self,
+    metric: Metric<U>,
+    cycle_period: CyclePeriod,
+    timeout: Option<Duration>,
+  ) -> ReaderHandles<D, LatestReading<UnitValue<U>>>
+  where
+    U: MetrifulUnit + 'static,
+    D: Send + 'static,
+  {
+    let latest = LatestReading::new();
+    let writer = latest::CloseOnDrop(latest.clone());
+
+    let (cmd_tx, handle) = self.spawn_cycle_reader(
+      metric, cycle_period, timeout,
+      move |result| match result {
+        Ok(value) => {
+          writer.0.set(value);
+          true
+        },
+        Err(e) => {
+          writer.0.set_error(e);
+          false
         }
+      },
+    );
+
+    (cmd_tx, latest, handle)
+  }
 
-        let metric = match metric {
-          Ok(m) => m,
-          Err(e) => {
-            metric_tx.send(Err(e)).ok();
-            break;
+  /// Spawns an async cycle read thread that reports metrics via a
+  /// [`crossbeam_channel`] channel.
+  ///
+  /// This behaves like [`Metriful::async_cycle_read_timeout()`], but the
+  /// returned receiver can be used with [`crossbeam_channel::select!`]
+  /// alongside other application channels. If `capacity` is set, the channel
+  /// is bounded and the background thread blocks while it is full; otherwise
+  /// it is unbounded.
+  ///
+  /// # Example
+  /// ```no_run
+  /// use std::time::Duration;
+  /// use crossbeam_channel::{select, tick};
+  /// use metriful::{Metriful, CyclePeriod};
+  /// use metriful::metric::METRIC_COMBINED_ALL;
+  ///
+  /// # fn main() -> metriful::error::Result<()> {
+  /// let metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+  /// let (_cmd_tx, metric_rx, _handle) = metriful.crossbeam_cycle_read_timeout(
+  ///   METRIC_COMBINED_ALL, CyclePeriod::Period0, Some(Duration::from_secs(5)),
+  ///   Some(16),
+  /// );
+  ///
+  /// let heartbeat = tick(Duration::from_secs(60));
+  /// loop {
+  ///   select! {
+  ///     recv(metric_rx) -> reading => match reading {
+  ///       Ok(reading) => println!("{}", reading?),
+  ///       Err(_) => break,
+  ///     },
+  ///     recv(heartbeat) -> _ => println!("still alive"),
+  ///   }
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  #[cfg(feature = "crossbeam")]
+  pub fn crossbeam_cycle_read_timeout<U>(
+    self,
+    metric: Metric<U>,
+    cycle_period: CyclePeriod,
+    timeout: Option<Duration>,
+    capacity: Option<usize>,
+  ) -> ReaderHandles<D, crossbeam_channel::Receiver<Result<UnitValue<U>>>>
+  where
+    U: MetrifulUnit + 'static,
+    D: Send + 'static,
+  {
+    let (metric_tx, metric_rx) = match capacity {
+      Some(capacity) => crossbeam_channel::bounded(capacity),
+      None => crossbeam_channel::unbounded(),
+    };
+
+    let (cmd_tx, handle) = self.spawn_cycle_reader(
+      metric, cycle_period, timeout,
+      move |result| metric_tx.send(result).is_ok(),
+    );
+
+    (cmd_tx, metric_rx, handle)
+  }
+
+  /// Spawns an async cycle read thread that reports metrics via a bounded
+  /// [`tokio::sync::mpsc`] channel.
+  ///
+  /// This behaves like [`Metriful::async_cycle_read_timeout()`], but the
+  /// returned receiver can be awaited directly from async code without
+  /// bridging from a std channel. At most `capacity` readings (minimum 1) are
+  /// queued; the background thread blocks while the channel is full.
+  ///
+  /// # Example
+  /// ```no_run
+  /// use std::time::Duration;
+  /// use metriful::{Metriful, CyclePeriod};
+  /// use metriful::metric::METRIC_COMBINED_ALL;
+  ///
+  /// # async fn run() -> metriful::error::Result<()> {
+  /// let metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+  /// let (_cmd_tx, mut metric_rx, _handle) = metriful.tokio_cycle_read_timeout(
+  ///   METRIC_COMBINED_ALL, CyclePeriod::Period0, Some(Duration::from_secs(5)), 16,
+  /// );
+  ///
+  /// while let Some(reading) = metric_rx.recv().await {
+  ///   println!("{}", reading?);
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  #[cfg(feature = "tokio")]
+  pub fn tokio_cycle_read_timeout<U>(
+    self,
+    metric: Metric<U>,
+    cycle_period: CyclePeriod,
+    timeout: Option<Duration>,
+    capacity: usize,
+  ) -> ReaderHandles<D, tokio::sync::mpsc::Receiver<Result<UnitValue<U>>>>
+  where
+    U: MetrifulUnit + 'static,
+    D: Send + 'static,
+  {
+    let (metric_tx, metric_rx) = tokio::sync::mpsc::channel(capacity.max(1));
+
+    let (cmd_tx, handle) = self.spawn_cycle_reader(
+      metric, cycle_period, timeout,
+      move |result| metric_tx.blocking_send(result).is_ok(),
+    );
+
+    (cmd_tx, metric_rx, handle)
+  }
+
+  /// Spawns a cycle read thread passing each result to `send`, which should
+  /// return false if the consumer has gone away. The thread exits after the
+  /// first error.
+  fn spawn_cycle_reader<U, F>(
+    mut self,
+    metric: Metric<U>,
+    cycle_period: CyclePeriod,
+    timeout: Option<Duration>,
+    mut send: F,
+  ) -> (Sender<ReaderCommand>, JoinHandle<Metriful<D>>)
+  where
+    U: MetrifulUnit + 'static,
+    D: Send + 'static,
+    F: FnMut(Result<UnitValue<U>>) -> bool + Send + 'static,
+  {
+    let (cmd_tx, cmd_rx) = mpsc::channel();
+
+    let scheduling = self.thread_scheduling;
+    let handle = thread::spawn(move || {
+      sched::apply_hint(scheduling);
+      let mut cycle_period = cycle_period;
+
+      'reader: loop {
+        let cancel = self.cancel.clone();
+        let iter = self.cycle_read_iter_timeout(metric, cycle_period, timeout);
+
+        for metric in iter {
+          let command = cmd_rx.try_recv().ok();
+          if command == Some(ReaderCommand::Stop) {
+            trace!("Metriful::spawn_cycle_reader(): break");
+            break 'reader;
           }
-        };
 
-        match metric_tx.send(Ok(metric)) {
-          Ok(_) => (),
-          Err(_e) => {
+          let metric = match metric {
+            Ok(m) => m,
+            Err(e) => {
+              send(Err(e));
+              break 'reader;
+            }
+          };
+
+          if !send(Ok(metric)) {
             // channel is dead, just quit
-            break;
+            break 'reader;
+          }
+
+          match command {
+            Some(ReaderCommand::Pause) => {
+              if wait_for_resume(&cmd_rx, &mut cycle_period, cancel.as_ref()) {
+                continue 'reader;
+              }
+
+              break 'reader;
+            },
+            Some(ReaderCommand::ChangeCyclePeriod(period)) => {
+              trace!("Metriful::spawn_cycle_reader(): changing period to {:?}", period);
+              cycle_period = period;
+              continue 'reader;
+            },
+            _ => (),
           }
         }
+
+        // the iterator was cancelled
+        break;
       }
 
       self
     });
 
-    (cmd_tx, metric_rx, handle)
+    (cmd_tx, handle)
   }
 
   /// Fetches the current device status. This does *not* wait for the device to
   /// become ready and may fail if [`Metriful::is_ready()`] is false.
   ///
+  /// To reduce bus traffic, the settings of interrupts that remain enabled
+  /// are reused from the previous status (see [`DeviceStatus::refresh()`]);
+  /// changes made through this `Metriful` are always picked up. If another
+  /// process may reconfigure interrupts, use
+  /// [`Metriful::read_status_uncached()`] instead.
+  ///
   /// # Example
   /// ```no_run
   /// use metriful::Metriful;
@@ -835,15 +2541,603 @@ impl Metriful {
   /// # }
   /// ```
   pub fn read_status(&mut self) -> Result<DeviceStatus> {
-    let status = DeviceStatus::read(&mut self.device)?;
+    let status = match &self.status {
+      Some(previous) if !self.interrupts_stale => previous.refresh(&mut with_context(&mut self.device, "read_status"))?,
+      _ => DeviceStatus::read(&mut with_context(&mut self.device, "read_status"))?,
+    };
+
     self.status = Some(status.clone());
+    self.interrupts_stale = false;
     trace!("Metriful::read_status() -> {:?}", &self.status);
 
     Ok(status)
   }
 
+  /// Fetches the full device status, re-reading all interrupt settings. See
+  /// [`Metriful::read_status()`].
+  pub fn read_status_uncached(&mut self) -> Result<DeviceStatus> {
+    self.interrupts_stale = true;
+    self.read_status()
+  }
+
+  /// Returns the device status as of the most recent status read, if any.
+  /// This may be outdated; see [`Metriful::read_status()`] to refresh it.
+  pub fn status(&self) -> Option<&DeviceStatus> {
+    self.status.as_ref()
+  }
+
+  /// Reads the raw contents of a register. This is an escape hatch for
+  /// registers not otherwise modeled; prefer [`Metriful::read()`] and
+  /// [`Metriful::read_status()`] where possible. Will raise an error if the
+  /// device is not ready or the register is write-only.
+  pub fn read_register(&mut self, register: Register) -> Result<Vec<u8>> {
+    if !register.access().is_readable() {
+      return Err(MetrifulError::RegisterNotReadable(register));
+    }
+
+    self.ensure_ready()?;
+
+    let ret = with_context(&mut self.device, "read_register").read_block(register.address(), register.length());
+    trace!("Metriful::read_register({}) -> {:x?}", register, &ret);
+    ret
+  }
+
+  /// Writes raw data to a register, or sends a command if `register` is a
+  /// command (in which case `data` must be empty). `data` must match the
+  /// register's documented length. Will raise an error if the device is not
+  /// ready or the register is read-only.
+  ///
+  /// Writes are subject to the configured [`RateLimit`] but otherwise bypass
+  /// the checks made by higher-level functions, and are not reflected in
+  /// [`Metriful::status()`]; call [`Metriful::read_status()`] afterward if the
+  /// write may have changed the device status.
+  ///
+  /// # Example
+  /// ```no_run
+  /// use metriful::{Metriful, registers::Register};
+  ///
+  /// # fn main() -> metriful::error::Result<()> {
+  /// let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+  ///
+  /// // set the light interrupt threshold to 100.5 lux
+  /// metriful.write_register(Register::LightInterruptThreshold, &[100, 0, 50])?;
+  /// println!("{:?}", metriful.read_register(Register::LightInterruptThreshold)?);
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn write_register(&mut self, register: Register, data: &[u8]) -> Result<()> {
+    if !register.access().is_writable() {
+      return Err(MetrifulError::RegisterNotWritable(register));
+    }
+
+    if data.len() != register.length() as usize {
+      return Err(MetrifulError::InvalidRegisterLength {
+        register,
+        expected: register.length(),
+        actual: data.len(),
+      });
+    }
+
+    self.ensure_ready()?;
+
+    self.guard.check(register.command_kind())?;
+    match data {
+      [] => with_context(&mut self.device, "write_register").write_byte(register.address())?,
+      [value] => with_context(&mut self.device, "write_register").write_byte_data(register.address(), *value)?,
+      _ => with_context(&mut self.device, "write_register").write_block(register.address(), data)?,
+    }
+    self.sleep_write();
+
+    if (0x81..=0x87).contains(&register.address()) {
+      self.interrupts_stale = true;
+    }
+
+    trace!("Metriful::write_register({}, {:x?}): done", register, data);
+
+    Ok(())
+  }
+
   /// Sleeps for 6ms, as recommended after a write.
   pub fn sleep_write(&self) {
-    thread::sleep(Duration::from_millis(6));
+    thread::sleep(guard::COMMAND_INTERVAL);
+  }
+
+  /// Returns the cleanup performed by [`Metriful::close()`].
+  pub fn shutdown_options(&self) -> &ShutdownOptions {
+    &self.shutdown
+  }
+
+  /// Replaces the cleanup performed by [`Metriful::close()`] and, if
+  /// [`ShutdownOptions::on_drop`] is set, on drop.
+  pub fn set_shutdown_options(&mut self, options: ShutdownOptions) {
+    self.shutdown = options;
+  }
+
+  /// Returns the device to standby and releases the READY line, per the
+  /// configured [`ShutdownOptions`]. Both steps are attempted even if the
+  /// first fails; the first error is returned.
+  ///
+  /// # Example
+  /// ```no_run
+  /// use metriful::{Metriful, CyclePeriod, metric::*};
+  ///
+  /// # fn main() -> metriful::error::Result<()> {
+  /// let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+  /// for reading in metriful.cycle_read_iter_timeout(METRIC_TEMPERATURE, CyclePeriod::Period0, None).take(10) {
+  ///   println!("{}", reading?);
+  /// }
+  ///
+  /// // leave the device in standby and unexport the READY pin
+  /// metriful.close()?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn close(mut self) -> Result<()> {
+    self.shutdown()
+  }
+
+  fn shutdown(&mut self) -> Result<()> {
+    if self.closed {
+      return Ok(());
+    }
+
+    self.closed = true;
+    let options = self.shutdown;
+    trace!("Metriful::shutdown({:?})", options);
+
+    let mut result = Ok(());
+    if options.standby {
+      result = self.read_status().and_then(|status| match status.mode {
+        OperationalMode::Cycle(_) => self.set_mode_timeout(OperationalMode::Standby, options.timeout).map(|_| ()),
+        OperationalMode::Standby => Ok(()),
+      });
+    }
+
+    if options.release_ready {
+      let released = self.ready_pin.release();
+      if result.is_ok() {
+        result = released;
+      }
+    }
+
+    result
+  }
+
+  /// Returns a snapshot of this device's health counters; see the
+  /// [`counters`] module.
+  ///
+  /// # Example
+  /// ```
+  /// # #[cfg(feature = "testing")] {
+  /// use std::time::Duration;
+  /// use metriful::{Metriful, metric::*};
+  /// use metriful::testing::MockDevice;
+  ///
+  /// let mock = MockDevice::new();
+  /// mock.set_register(0x21, &[21, 5]);
+  ///
+  /// let mut metriful = Metriful::try_new_device_timeout(
+  ///   mock.ready_line(),
+  ///   mock.clone(),
+  ///   Some(Duration::from_millis(100)),
+  /// ).unwrap();
+  ///
+  /// metriful.read(METRIC_TEMPERATURE).unwrap();
+  ///
+  /// let stats = metriful.stats();
+  /// assert_eq!(stats.reads, 1);
+  /// assert_eq!(stats.errors, 0);
+  /// assert_eq!(stats.ready_waits, 1);
+  /// # }
+  /// ```
+  pub fn stats(&self) -> DeviceStats {
+    self.counters.snapshot()
+  }
+
+  /// Returns this device's health counters, e.g. to read them from another
+  /// thread while the device is in use.
+  pub fn counters(&self) -> Arc<Counters> {
+    self.counters.clone()
+  }
+
+  /// Returns the calibration applied to readings.
+  pub fn calibration(&self) -> &Calibration {
+    &self.calibration
+  }
+
+  /// Replaces the calibration applied to all subsequent readings; see the
+  /// [`calibration`] module.
+  pub fn set_calibration(&mut self, calibration: Calibration) {
+    trace!("Metriful::set_calibration({:?})", calibration);
+    self.calibration = calibration;
+  }
+
+  /// Returns the plausibility checks applied to readings, if any.
+  pub fn plausibility(&self) -> Option<&Plausibility> {
+    self.plausibility.as_ref()
+  }
+
+  /// Sets the plausibility checks applied to all subsequent readings, or
+  /// disables them if None; see the [`plausibility`] module.
+  pub fn set_plausibility(&mut self, plausibility: Option<Plausibility>) {
+    trace!("Metriful::set_plausibility({:?})", plausibility);
+    self.plausibility = plausibility;
+  }
+
+  /// Returns true if reads of metrics that are only valid in cycle mode (e.g.
+  /// [`METRIC_AQI`]) fail while the device is in standby.
+  pub fn enforce_mode_validity(&self) -> bool {
+    self.enforce_validity
+  }
+
+  /// Sets whether reads of cycle-mode-only metrics fail with
+  /// [`MetrifulError::MetricRequiresCycleMode`] while the device is known to
+  /// be in standby. Enabled by default; disable to read the raw registers
+  /// regardless, e.g. if the mode is changed outside this library.
+  pub fn set_enforce_mode_validity(&mut self, enforce: bool) {
+    trace!("Metriful::set_enforce_mode_validity({})", enforce);
+    self.enforce_validity = enforce;
+  }
+
+  /// Returns the token used by read loops started from this device, if any.
+  pub fn cancel_token(&self) -> Option<&CancelToken> {
+    self.cancel.as_ref()
+  }
+
+  /// Sets a token that stops read loops subsequently started from this
+  /// device, i.e. read iterators, background cycle readers, and event
+  /// streams; see the [`cancel`] module. Other operations, such as mode
+  /// changes, are unaffected.
+  pub fn set_cancel_token(&mut self, cancel: Option<CancelToken>) {
+    trace!("Metriful::set_cancel_token({:?})", cancel);
+    self.cancel = cancel;
+  }
+
+  /// Returns how this device waits for its READY line.
+  pub fn ready_polling(&self) -> &ReadyPolling {
+    &self.polling
+  }
+
+  /// Sets how this device waits for its READY line, e.g. to poll less often
+  /// on battery-powered hosts at the cost of latency.
+  pub fn set_ready_polling(&mut self, polling: ReadyPolling) {
+    trace!("Metriful::set_ready_polling({:?})", polling);
+    self.polling = polling;
+  }
+
+  /// Returns the scheduling hint applied to background threads, if any.
+  pub fn thread_scheduling(&self) -> Option<ThreadScheduling> {
+    self.thread_scheduling
+  }
+
+  /// Sets a scheduling hint applied to background threads subsequently
+  /// spawned for this device, i.e. background cycle readers and event
+  /// streams; see the [`sched`] module.
+  pub fn set_thread_scheduling(&mut self, scheduling: Option<ThreadScheduling>) {
+    trace!("Metriful::set_thread_scheduling({:?})", scheduling);
+    self.thread_scheduling = scheduling;
+  }
+
+  /// Returns the current command rate limit.
+  pub fn rate_limit(&self) -> &RateLimit {
+    &self.guard.limit
+  }
+
+  /// Replaces the command rate limit. Limits are enforced by default; pass
+  /// [`RateLimit::disabled()`] to send commands regardless of spacing.
+  pub fn set_rate_limit(&mut self, limit: RateLimit) {
+    self.guard.limit = limit;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::*;
+  use crate::error::{ErrorKind, ReadyWait};
+  use crate::retry::{OnError, ReadPolicy};
+  use crate::testing::*;
+
+  const TIMEOUT: Option<Duration> = Some(Duration::from_millis(100));
+
+  type FaultyMetriful = Metriful<FaultyDevice<MockDevice>>;
+
+  /// Opens a mock device through the given fault plan, with rate limits
+  /// disabled so tests don't depend on timing.
+  fn open(mock: &MockDevice, plan: &FaultPlan) -> FaultyMetriful {
+    let mut metriful = Metriful::try_new_device_timeout(
+      plan.wrap_ready(mock.ready_line()),
+      plan.wrap_device(mock.clone()),
+      TIMEOUT,
+    ).unwrap();
+
+    metriful.set_rate_limit(RateLimit::disabled());
+    metriful
+  }
+
+  #[test]
+  fn read_reports_bus_errors_with_context() {
+    let mock = MockDevice::new();
+    mock.set_register(0x21, &[21, 5]);
+
+    let plan = FaultPlan::new().inject(FaultTrigger::Register(0x21), Fault::Nack);
+    let mut metriful = open(&mock, &plan);
+
+    let err = metriful.read(METRIC_TEMPERATURE).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(121));
+    assert_eq!(err.kind(), ErrorKind::TransientBus);
+
+    let context = err.context().unwrap();
+    assert_eq!(context.operation, "read");
+    assert_eq!(context.register, 0x21);
+
+    assert_eq!(metriful.read(METRIC_TEMPERATURE).unwrap().value, 21.5);
+
+    let stats = metriful.stats();
+    assert_eq!(stats.errors, 1);
+    assert_eq!(stats.reads, 1);
+  }
+
+  #[test]
+  fn bus_timeout_is_transient() {
+    let mock = MockDevice::new();
+    let plan = FaultPlan::new().inject(FaultTrigger::Register(0x31), Fault::Timeout);
+    let mut metriful = open(&mock, &plan);
+
+    let err = metriful.read(METRIC_ILLUMINANCE).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(110));
+    assert!(err.is_retryable());
+  }
+
+  #[test]
+  fn truncated_block_read_is_rejected() {
+    let mock = MockDevice::new();
+    let plan = FaultPlan::new().inject(FaultTrigger::Register(0x13), Fault::TruncatedRead(3));
+    let mut metriful = open(&mock, &plan);
+
+    let err = metriful.read(METRIC_COMBINED_SOUND_DATA).unwrap_err();
+    assert!(matches!(err.root(), MetrifulError::ShortRead { register: 0x13, actual: 3, .. }));
+  }
+
+  #[test]
+  fn stuck_ready_times_out() {
+    let mock = MockDevice::new();
+    let plan = FaultPlan::new().inject(FaultTrigger::Command(0xE1), Fault::StuckReady(1000));
+    let mut metriful = open(&mock, &plan);
+
+    metriful.execute_measurement().unwrap();
+    let err = metriful.read_timeout(METRIC_TEMPERATURE, Some(Duration::from_millis(30))).unwrap_err();
+    assert!(matches!(
+      err,
+      MetrifulError::ReadyTimeoutExceeded { operation: "read", wait: ReadyWait::Ready, .. }
+    ));
+    assert_eq!(err.kind(), ErrorKind::Timeout);
+    assert_eq!(metriful.stats().ready_timeouts, 1);
+  }
+
+  #[test]
+  fn read_fails_when_not_ready() {
+    let mock = MockDevice::new();
+    let mut metriful = open(&mock, &FaultPlan::new());
+
+    mock.set_ready(false);
+    assert!(matches!(metriful.read(METRIC_TEMPERATURE), Err(MetrifulError::NotReady)));
+  }
+
+  #[test]
+  fn cycle_only_metrics_are_rejected_in_standby() {
+    let mock = MockDevice::new();
+    let mut metriful = open(&mock, &FaultPlan::new());
+
+    assert!(matches!(
+      metriful.read(METRIC_AQI),
+      Err(MetrifulError::MetricRequiresCycleMode { metric: "aqi" })
+    ));
+
+    // the raw register is still readable if enforcement is disabled
+    metriful.set_enforce_mode_validity(false);
+    assert!(metriful.read(METRIC_AQI).is_ok());
+  }
+
+  #[test]
+  fn particle_metrics_require_a_particle_sensor() {
+    let mock = MockDevice::new();
+    let mut metriful = open(&mock, &FaultPlan::new());
+
+    assert!(matches!(
+      metriful.read(METRIC_PARTICLE_DATA_VALID),
+      Err(MetrifulError::ParticleSensorDisabled { .. })
+    ));
+  }
+
+  #[test]
+  fn rate_limit_rejects_early_commands() {
+    let mock = MockDevice::new();
+    let mut metriful = open(&mock, &FaultPlan::new());
+    metriful.set_rate_limit(RateLimit::default());
+
+    metriful.execute_measurement().unwrap();
+    let err = metriful.execute_measurement().unwrap_err();
+    assert!(matches!(err, MetrifulError::RateLimited { retry_after } if retry_after > Duration::from_secs(1)));
+    assert_eq!(mock.commands(), vec![0xE1]);
+  }
+
+  #[test]
+  fn read_iter_stops_after_error_by_default() {
+    let mock = MockDevice::new();
+    let plan = FaultPlan::new().inject(FaultTrigger::Register(0x21), Fault::Nack);
+    let mut metriful = open(&mock, &plan);
+
+    let results: Vec<_> = metriful
+      .read_iter_timeout(METRIC_TEMPERATURE, Duration::from_millis(0), TIMEOUT)
+      .take(3)
+      .collect();
+
+    assert_eq!(results.len(), 1);
+    assert!(results[0].is_err());
+  }
+
+  #[test]
+  fn read_iter_continues_past_errors_per_policy() {
+    let mock = MockDevice::new();
+    let plan = FaultPlan::new().inject(FaultTrigger::Register(0x21), Fault::Nack);
+    let mut metriful = open(&mock, &plan);
+
+    let policy = ReadPolicy {
+      on_error: OnError::Continue,
+      ..ReadPolicy::default()
+    };
+
+    let results: Vec<_> = metriful
+      .read_iter_timeout(METRIC_TEMPERATURE, Duration::from_millis(0), TIMEOUT)
+      .with_policy(policy)
+      .take(3)
+      .collect();
+
+    assert_eq!(results.len(), 3);
+    assert!(results[0].is_err());
+    assert!(results[1..].iter().all(Result::is_ok));
+  }
+
+  #[test]
+  fn read_iter_retries_per_policy() {
+    let mock = MockDevice::new();
+    let plan = FaultPlan::new()
+      .inject(FaultTrigger::Register(0x21), Fault::Nack)
+      .inject(FaultTrigger::Register(0x21), Fault::Timeout);
+    let mut metriful = open(&mock, &plan);
+
+    let policy = ReadPolicy {
+      max_retries: 2,
+      backoff: Duration::from_millis(1),
+      on_error: OnError::Stop,
+    };
+
+    let results: Vec<_> = metriful
+      .read_iter_timeout(METRIC_TEMPERATURE, Duration::from_millis(0), TIMEOUT)
+      .with_policy(policy)
+      .take(2)
+      .collect();
+
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(plan.injected(), vec![Fault::Nack, Fault::Timeout]);
+    assert_eq!(metriful.stats().retries, 2);
+  }
+
+  #[test]
+  fn cancelled_read_iter_ends_without_error() {
+    let mock = MockDevice::new();
+    let mut metriful = open(&mock, &FaultPlan::new());
+
+    let cancel = CancelToken::new();
+    cancel.cancel();
+
+    let mut iter = metriful
+      .read_iter_timeout(METRIC_TEMPERATURE, Duration::from_millis(0), TIMEOUT)
+      .with_cancel(cancel);
+
+    assert!(iter.next().is_none());
+  }
+
+  #[test]
+  fn read_iter_cancels_during_rate_limit_wait() {
+    let mock = MockDevice::new();
+    let mut metriful = open(&mock, &FaultPlan::new());
+    metriful.set_rate_limit(RateLimit::default());
+
+    let cancel = CancelToken::new();
+    let mut iter = metriful
+      .read_iter_timeout(METRIC_TEMPERATURE, Duration::from_millis(0), TIMEOUT)
+      .with_cancel(cancel.clone());
+
+    assert!(iter.next().unwrap().is_ok());
+
+    // the next read has to wait out the measurement rate limit
+    let canceller = thread::spawn(move || {
+      thread::sleep(Duration::from_millis(50));
+      cancel.cancel();
+    });
+
+    let start = Instant::now();
+    assert!(iter.next().is_none());
+    assert!(start.elapsed() < guard::MEASUREMENT_INTERVAL / 2);
+    canceller.join().unwrap();
+  }
+
+  #[test]
+  fn device_reset_mid_cycle_is_visible_in_status() {
+    let mock = MockDevice::new();
+    let plan = FaultPlan::new().inject(FaultTrigger::Register(0x21), Fault::DeviceReset);
+    let mut metriful = open(&mock, &plan);
+
+    let cycle = OperationalMode::Cycle(CyclePeriod::Period0);
+    metriful.set_mode_timeout(cycle, TIMEOUT).unwrap();
+    assert_eq!(metriful.read_status().unwrap().mode, cycle);
+
+    // the device resets just before the read, dropping back to standby
+    metriful.read(METRIC_TEMPERATURE).unwrap();
+    assert_eq!(metriful.read_status_uncached().unwrap().mode, OperationalMode::Standby);
+  }
+
+  #[test]
+  fn register_access_is_checked() {
+    let mock = MockDevice::new();
+    let mut metriful = open(&mock, &FaultPlan::new());
+
+    assert!(matches!(
+      metriful.write_register(Register::Temperature, &[1, 2]),
+      Err(MetrifulError::RegisterNotWritable(Register::Temperature))
+    ));
+  }
+
+  #[test]
+  fn close_returns_device_to_standby() {
+    let mock = MockDevice::new();
+    let mut metriful = open(&mock, &FaultPlan::new());
+
+    metriful.set_mode_timeout(OperationalMode::Cycle(CyclePeriod::Period0), TIMEOUT).unwrap();
+    metriful.close().unwrap();
+
+    assert_eq!(mock.commands(), vec![0xE4, 0xE5]);
+    assert_eq!(mock.register(0x8A), vec![0]);
+  }
+
+  #[test]
+  fn recovery_restores_configuration() {
+    let mock = MockDevice::new();
+    let mut metriful = open(&mock, &FaultPlan::new());
+
+    metriful.set_particle_sensor_timeout(ParticleSensorMode::EnabledPPD42, TIMEOUT).unwrap();
+    metriful.configure_sound_interrupt(SoundInterruptConfig::Enabled(SoundInterrupt {
+      mode: InterruptMode::Comparator,
+      threshold: 500,
+    })).unwrap();
+    metriful.set_mode_timeout(OperationalMode::Cycle(CyclePeriod::Period1), TIMEOUT).unwrap();
+
+    // leaves the cached interrupt settings stale
+    metriful.write_register(Register::LightInterruptType, &[0]).unwrap();
+
+    // the sensor was power cycled, and isn't ready at first
+    let replugged = MockDevice::new();
+    replugged.set_ready(false);
+
+    let err = metriful.recover_with(
+      Some(Box::new(replugged.ready_line())),
+      Some(FaultPlan::new().wrap_device(replugged.clone())),
+      TIMEOUT,
+    ).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Timeout);
+
+    replugged.set_ready(true);
+    let status = metriful.recover_with(None, None, TIMEOUT).unwrap();
+
+    assert_eq!(status.mode, OperationalMode::Cycle(CyclePeriod::Period1));
+    assert_eq!(replugged.register(0x07), vec![1]);
+    assert_eq!(replugged.register(0x85), vec![1]);
+    assert_eq!(replugged.register(0x86), vec![0xF4, 0x01]);
+    assert_eq!(replugged.register(0x87), vec![1]);
+    assert_eq!(replugged.register(0x89), vec![1]);
+    assert_eq!(replugged.register(0x8A), vec![1]);
   }
 }
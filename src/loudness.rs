@@ -0,0 +1,237 @@
+//! Detection of discrete loudness events ("noise incidents") from sound data.
+//!
+//! [`LoudnessDetector`] watches A-weighted SPL and peak sound amplitude across
+//! successive readings and emits a [`LoudnessEvent`] once a period of noise
+//! above the configured thresholds has ended. Loud readings separated by less
+//! than the configured cooldown are merged into a single event.
+//!
+//! # Example
+//! ```no_run
+//! use metriful::{Metriful, CyclePeriod, metric::*, loudness::*};
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+//! let mut detector = LoudnessDetector::new(LoudnessConfig::default());
+//!
+//! let iter = metriful.cycle_read_iter_timeout(
+//!   METRIC_COMBINED_SOUND_DATA, CyclePeriod::Period0, None
+//! );
+//!
+//! for sound in iter {
+//!   if let Some(event) = detector.update(&sound?.value) {
+//!     println!("noise incident: {}", event);
+//!   }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+#[cfg(feature = "serde")] use serde::{Serialize, Serializer};
+
+use crate::unit::*;
+
+/// Tuning parameters for [`LoudnessDetector`].
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct LoudnessConfig {
+  /// Readings with an A-weighted SPL at or above this level (in dBA) are
+  /// considered loud. Defaults to 70 dBA.
+  pub spl_threshold: Option<f32>,
+
+  /// Readings with a peak amplitude at or above this level (in mPa) are
+  /// considered loud. Disabled by default.
+  pub peak_amplitude_threshold: Option<f32>,
+
+  /// An event ends once no loud reading has been seen for this long; loud
+  /// readings within the cooldown extend the current event. Defaults to 10
+  /// seconds.
+  pub cooldown: Duration,
+
+  /// Events shorter than this are discarded. Defaults to zero, i.e. a single
+  /// loud reading is reported.
+  pub min_duration: Duration,
+}
+
+impl Default for LoudnessConfig {
+  fn default() -> Self {
+    LoudnessConfig {
+      spl_threshold: Some(70.0),
+      peak_amplitude_threshold: None,
+      cooldown: Duration::from_secs(10),
+      min_duration: Duration::from_secs(0),
+    }
+  }
+}
+
+/// The loudest values observed during a [`LoudnessEvent`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct LoudnessPeak {
+  /// Maximum A-weighted SPL in dBA
+  pub weighted_spl: f32,
+
+  /// Maximum peak sound amplitude in mPa
+  pub peak_amplitude: f32,
+}
+
+/// A period of sustained noise above the configured thresholds.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct LoudnessEvent {
+  /// Time of the first loud reading
+  #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_time"))]
+  pub start: DateTime<Utc>,
+
+  /// Time of the last loud reading
+  #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_time"))]
+  pub end: DateTime<Utc>,
+
+  /// The loudest values observed during the event
+  pub peak: LoudnessPeak,
+
+  /// Time between the first and last loud readings
+  pub duration: Duration,
+
+  /// Number of loud readings merged into this event
+  pub samples: u32,
+}
+
+#[cfg(feature = "serde")]
+fn serialize_time<S>(time: &DateTime<Utc>, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+  S: Serializer
+{
+  serializer.serialize_str(&time.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+impl fmt::Display for LoudnessEvent {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f, "{} for {}s, peak {:.1} dBA / {:.2} mPa ({} samples)",
+      self.start.to_rfc3339_opts(SecondsFormat::Secs, true), self.duration.as_secs(),
+      self.peak.weighted_spl, self.peak.peak_amplitude, self.samples
+    )
+  }
+}
+
+/// Groups loud sound readings into [`LoudnessEvent`]s.
+#[derive(Debug, Clone)]
+pub struct LoudnessDetector {
+  config: LoudnessConfig,
+  active: Option<LoudnessEvent>,
+}
+
+impl LoudnessDetector {
+  /// Creates a new detector.
+  pub fn new(config: LoudnessConfig) -> LoudnessDetector {
+    LoudnessDetector {
+      config,
+      active: None,
+    }
+  }
+
+  /// Returns the detector configuration.
+  pub fn config(&self) -> &LoudnessConfig {
+    &self.config
+  }
+
+  /// Returns the event currently in progress, if any.
+  pub fn active(&self) -> Option<&LoudnessEvent> {
+    self.active.as_ref()
+  }
+
+  fn is_loud(&self, weighted_spl: f32, peak_amplitude: f32) -> bool {
+    let spl = self.config.spl_threshold.is_some_and(|t| weighted_spl >= t);
+    let peak = self.config.peak_amplitude_threshold.is_some_and(|t| peak_amplitude >= t);
+
+    spl || peak
+  }
+
+  fn finish(&mut self) -> Option<LoudnessEvent> {
+    self.active.take().filter(|event| event.duration >= self.config.min_duration)
+  }
+
+  /// Processes a single sample taken at `time`, returning an event if one has
+  /// just ended.
+  ///
+  /// # Example
+  /// ```
+  /// use std::time::Duration;
+  /// use chrono::{TimeZone, Utc};
+  /// use metriful::loudness::*;
+  ///
+  /// let mut detector = LoudnessDetector::new(LoudnessConfig {
+  ///   spl_threshold: Some(70.0),
+  ///   cooldown: Duration::from_secs(5),
+  ///   ..LoudnessConfig::default()
+  /// });
+  ///
+  /// let t = |secs| Utc.timestamp_opt(secs, 0).unwrap();
+  /// assert_eq!(detector.update_at(t(0), 45.0, 1.0), None);
+  /// assert_eq!(detector.update_at(t(3), 82.0, 40.0), None);
+  /// assert_eq!(detector.update_at(t(6), 60.0, 8.0), None);
+  /// assert_eq!(detector.update_at(t(9), 75.0, 25.0), None);
+  ///
+  /// // quiet for longer than the cooldown: the merged event is emitted
+  /// let event = detector.update_at(t(15), 44.0, 1.0).unwrap();
+  /// assert_eq!(event.start, t(3));
+  /// assert_eq!(event.duration, Duration::from_secs(6));
+  /// assert_eq!(event.peak.weighted_spl, 82.0);
+  /// assert_eq!(event.samples, 2);
+  /// ```
+  pub fn update_at(
+    &mut self,
+    time: DateTime<Utc>,
+    weighted_spl: f32,
+    peak_amplitude: f32,
+  ) -> Option<LoudnessEvent> {
+    if self.is_loud(weighted_spl, peak_amplitude) {
+      let event = self.active.get_or_insert_with(|| LoudnessEvent {
+        start: time,
+        end: time,
+        peak: LoudnessPeak { weighted_spl, peak_amplitude },
+        duration: Duration::from_secs(0),
+        samples: 0,
+      });
+
+      event.end = time;
+      event.duration = (time - event.start).to_std().unwrap_or_default();
+      event.peak.weighted_spl = event.peak.weighted_spl.max(weighted_spl);
+      event.peak.peak_amplitude = event.peak.peak_amplitude.max(peak_amplitude);
+      event.samples = event.samples.saturating_add(1);
+
+      return None;
+    }
+
+    let expired = match &self.active {
+      Some(event) => (time - event.end).to_std().unwrap_or_default() >= self.config.cooldown,
+      None => false,
+    };
+
+    if expired {
+      self.finish()
+    } else {
+      None
+    }
+  }
+
+  /// Processes a combined sound reading, returning an event if one has just
+  /// ended. The reading's weighted SPL timestamp is used as the sample time.
+  pub fn update(&mut self, sound: &CombinedSoundData) -> Option<LoudnessEvent> {
+    self.update_at(
+      sound.weighted_spl.time,
+      sound.weighted_spl.value,
+      sound.peak_amplitude.value,
+    )
+  }
+
+  /// Ends any event in progress regardless of the cooldown, e.g. at shutdown.
+  pub fn flush(&mut self) -> Option<LoudnessEvent> {
+    self.finish()
+  }
+}
@@ -3,27 +3,33 @@
 //! All read functions, e.g. [`Metriful::read()`](fn@crate::Metriful::read), only accept a single metric
 //! definition. To read multiple metrics at once, instead use one of the
 //! "combined read" pseudo-metrics:
-//!  * [`struct@METRIC_COMBINED_AIR_DATA`]: all air data
-//!  * [`struct@METRIC_COMBINED_AIR_QUALITY_DATA`]: all air quality data; only valid
+//!  * [`METRIC_COMBINED_AIR_DATA`]: all air data
+//!  * [`METRIC_COMBINED_AIR_QUALITY_DATA`]: all air quality data; only valid
 //!    in cycle mode
-//!  * [`struct@METRIC_COMBINED_LIGHT_DATA`]: all light data
-//!  * [`struct@METRIC_COMBINED_SOUND_DATA`]: all sound data
-//!  * [`struct@METRIC_COMBINED_PARTICLE_DATA`]: all particle data; only valid if an
+//!  * [`METRIC_COMBINED_LIGHT_DATA`]: all light data
+//!  * [`METRIC_COMBINED_SOUND_DATA`]: all sound data
+//!  * [`METRIC_COMBINED_PARTICLE_DATA`]: all particle data; only valid if an
 //!    external particulate sensor is attached
-//!  * [`struct@METRIC_COMBINED_ALL`]: all data; air quality data is only valid in
+//!  * [`METRIC_COMBINED_ALL`]: all data; air quality data is only valid in
 //!    cycle mode
 //!
-//! Note that these are currently [`mod@lazy_static`] singleton instances and
-//! as such need to be dereferenced before use, e.g.
-//! `Metriful::read(*METRIC_TEMPERATURE)`.
+//! Metrics are `const` items and may be passed by value directly, e.g.
+//! `Metriful::read(METRIC_TEMPERATURE)`. Metrics for other registers can be
+//! defined with [`Metric::new()`].
 //!
-//! This limitation is likely to change as const generics stabilizes.
+//! Every metric also has programmatic metadata, available via
+//! [`Metric::metadata()`] or by enumerating the full catalog with [`metrics()`].
+//! To select a metric at runtime, e.g. from a CLI flag, see [`by_name()`].
 
+use std::sync::OnceLock;
+
+use bytes::Bytes;
 use chrono::Utc;
-use i2cdev::linux::LinuxI2CDevice;
-use lazy_static::lazy_static;
+
+#[cfg(feature = "serde")] use serde::Serialize;
 
 use crate::error::*;
+use crate::transport::{MetrifulTransport, read_block_exact};
 use crate::unit::*;
 
 #[derive(Debug, Copy, Clone)]
@@ -33,107 +39,670 @@ pub struct Metric<U> where U: MetrifulUnit {
 }
 
 impl<U> Metric<U> where U: MetrifulUnit {
-  pub fn read(&self, d: &mut LinuxI2CDevice) -> Result<UnitValue<U>> {
+  /// Creates a metric read from the given register, e.g. for a register not
+  /// covered by the predefined `METRIC_*` constants.
+  pub const fn new(register: u8, unit: U) -> Metric<U> {
+    Metric { register, unit }
+  }
+
+  /// Returns this metric's metadata, or None if its register is unknown.
+  pub fn metadata(&self) -> Option<&'static MetricInfo> {
+    find_by_register(self.register)
+  }
+
+  pub fn read<D>(&self, d: &mut D) -> Result<UnitValue<U>>
+  where
+    D: MetrifulTransport + ?Sized
+  {
+    // retain the register contents when this is a single block read
+    #[cfg(feature = "raw-bytes")]
+    let (value, raw_bytes) = if U::len() > 0 {
+      let raw_bytes = self.read_raw(d)?;
+      (U::from_bytes(&mut raw_bytes.clone())?, Some(raw_bytes))
+    } else {
+      (U::read(d, self.register)?, None)
+    };
+
+    #[cfg(not(feature = "raw-bytes"))]
     let value = U::read(d, self.register)?;
 
     Ok(UnitValue {
       unit: U::default(),
       time: Utc::now(),
-      value
+      timing: None,
+      warnings: Vec::new(),
+      value,
+      #[cfg(feature = "raw-bytes")] raw_bytes,
     })
   }
+
+  /// Reads the metric's register contents without decoding them, e.g. to log
+  /// them when debugging a parser. Combined reads of several register blocks
+  /// such as [`METRIC_COMBINED_ALL`] return
+  /// [`MetrifulError::RawReadUnsupported`].
+  pub fn read_raw<D>(&self, d: &mut D) -> Result<Bytes>
+  where
+    D: MetrifulTransport + ?Sized
+  {
+    if U::len() == 0 {
+      return Err(MetrifulError::RawReadUnsupported(self.register));
+    }
+
+    Ok(Bytes::from(read_block_exact(d, self.register, U::len())?))
+  }
+}
+
+/// Temperature in degrees Celsius
+pub const METRIC_TEMPERATURE: Metric<UnitDegreesCelsius> = Metric::new(0x21, UnitDegreesCelsius);
+
+/// Pressure in Pascals (Pa)
+pub const METRIC_PRESSURE: Metric<UnitPascals> = Metric::new(0x22, UnitPascals);
+
+/// Relative humidity percentage
+pub const METRIC_RELATIVE_HUMIDITY: Metric<UnitRelativeHumidity> = Metric::new(0x23, UnitRelativeHumidity);
+
+/// Gas sensor resistance
+pub const METRIC_GAS_RESISTANCE: Metric<UnitResistance> = Metric::new(0x24, UnitResistance);
+
+/// Combined read of air data metrics (0x21-0x24, inclusive)
+pub const METRIC_COMBINED_AIR_DATA: Metric<UnitCombinedAirData> = Metric::new(0x10, UnitCombinedAirData);
+
+/// Air quality index
+///
+/// Note: only valid during cycle measurements; this limitation is not well
+/// documented.
+pub const METRIC_AQI: Metric<UnitAirQualityIndex> = Metric::new(0x25, UnitAirQualityIndex);
+
+/// Estimated CO2 concentration (based on gas sensor)
+///
+/// Note: only valid during cycle measurements; this limitation is not well
+/// documented.
+pub const METRIC_EST_CO2: Metric<UnitEstimatedCO2> = Metric::new(0x26, UnitEstimatedCO2);
+
+/// "Equivalent breath" VOC concentration
+///
+/// Note: only valid during cycle measurements; this limitation is not well
+/// documented.
+pub const METRIC_VOC: Metric<UnitEstimatedVOC> = Metric::new(0x27, UnitEstimatedVOC);
+
+/// AQI accuracy indicator
+///
+/// Note: only valid during cycle measurements; this limitation is not well
+/// documented.
+pub const METRIC_AQI_ACCURACY: Metric<UnitAQIAccuracy> = Metric::new(0x28, UnitAQIAccuracy);
+
+/// Combined read of air quality metrics (0x25-0x28, inclusive).
+///
+/// Note: only valid during cycle measurements; this limitation is not well
+/// documented.
+pub const METRIC_COMBINED_AIR_QUALITY_DATA: Metric<UnitCombinedAirQualityData> = Metric::new(0x11, UnitCombinedAirQualityData);
+
+/// Illuminance in lux
+pub const METRIC_ILLUMINANCE: Metric<UnitIlluminance> = Metric::new(0x31, UnitIlluminance);
+
+/// White light level
+pub const METRIC_WHITE_LIGHT_LEVEL: Metric<UnitWhiteLevel> = Metric::new(0x32, UnitWhiteLevel);
+
+/// Combined read of light metrics (0x31, 0x32)
+pub const METRIC_COMBINED_LIGHT_DATA: Metric<UnitCombinedLightData> = Metric::new(0x12, UnitCombinedLightData);
+
+/// A-weighted sound pressure level in dBa
+pub const METRIC_WEIGHTED_SOUND_LEVEL: Metric<UnitAWeightedSPL> = Metric::new(0x41, UnitAWeightedSPL);
+
+/// Sound pressure level by frequency band
+pub const METRIC_SOUND_LEVEL: Metric<UnitSPLFrequencyBands> = Metric::new(0x42, UnitSPLFrequencyBands);
+
+/// Measured peak sound amplitude "since last read"
+pub const METRIC_PEAK_SOUND_AMPLITUDE: Metric<UnitMillipascal> = Metric::new(0x43, UnitMillipascal);
+
+/// Self assessment of sound measurement stability
+pub const METRIC_SOUND_MEASUREMENT_STABILITY: Metric<UnitSoundMeasurementStability> = Metric::new(0x44, UnitSoundMeasurementStability);
+
+/// Combined read of sound data (0x41-0x44)
+pub const METRIC_COMBINED_SOUND_DATA: Metric<UnitCombinedSoundData> = Metric::new(0x13, UnitCombinedSoundData);
+
+/// Particle sensor duty cycle
+pub const METRIC_PARTICLE_SENSOR_DUTY_CYCLE: Metric<UnitPercent> = Metric::new(0x51, UnitPercent);
+
+/// Particle concentration as measured by external sensor
+pub const METRIC_PARTICLE_CONCENTRATION: Metric<UnitRawParticleConcentration> = Metric::new(0x52, UnitRawParticleConcentration);
+
+/// Self assessment of state of particle sensor, if attached
+pub const METRIC_PARTICLE_DATA_VALID: Metric<UnitParticleDataValidity> = Metric::new(0x53, UnitParticleDataValidity);
+
+/// Combined read of all particle data in registers 0x51-0x53.
+pub const METRIC_COMBINED_PARTICLE_DATA: Metric<UnitCombinedParticleData> = Metric::new(0x14, UnitCombinedParticleData);
+
+/// Pseudo-metric for a combined read of all METRIC_COMBINED_* fields.
+pub const METRIC_COMBINED_ALL: Metric<UnitCombinedData> = Metric::new(0x0, UnitCombinedData);
+
+/// Pseudo-metric for a combined read of all METRIC_COMBINED_* fields that
+/// tolerates failed reads of individual groups; see [`PartialCombinedData`].
+pub const METRIC_COMBINED_ALL_PARTIAL: Metric<UnitPartialCombinedData> = Metric::new(0x0, UnitPartialCombinedData);
+
+/// Broad groupings of metrics, matching the device's combined reads.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "snake_case"))]
+pub enum MetricCategory {
+  Air,
+  AirQuality,
+  Light,
+  Sound,
+  Particle,
+
+  /// Combined read spanning all categories
+  All,
+}
+
+/// Conditions under which a metric's value is meaningful.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct MetricValidity {
+  /// Only valid when read during cycle mode
+  pub cycle_mode: bool,
+
+  /// Only valid if an external particle sensor is attached and enabled
+  pub particle_sensor: bool,
 }
 
-fn metric<U>(register: u8) -> Metric<U>
+/// Metadata describing a single metric.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct MetricInfo {
+  /// Stable snake_case identifier, e.g. `temperature`
+  pub id: &'static str,
+
+  /// Human-readable description
+  pub description: &'static str,
+
+  /// Register the metric is read from
+  pub register: u8,
+
+  /// Category of the metric
+  pub category: MetricCategory,
+
+  /// Prometheus-safe metric name, as used by the `prometheus` module where
+  /// exported; None for combined reads, which span multiple series
+  pub prometheus_name: Option<&'static str>,
+
+  /// Human-readable name of the metric's unit
+  pub unit_name: &'static str,
+
+  /// Symbol of the metric's unit, if any
+  pub unit_symbol: Option<&'static str>,
+
+  /// Conditions required for valid readings
+  pub validity: MetricValidity,
+
+  /// True if this is a combined read of several metrics
+  pub combined: bool,
+}
+
+const ALWAYS: MetricValidity = MetricValidity {
+  cycle_mode: false,
+  particle_sensor: false,
+};
+
+const CYCLE_MODE: MetricValidity = MetricValidity {
+  cycle_mode: true,
+  particle_sensor: false,
+};
+
+const PARTICLE_SENSOR: MetricValidity = MetricValidity {
+  cycle_mode: false,
+  particle_sensor: true,
+};
+
+fn info<U>(
+  metric: &Metric<U>,
+  id: &'static str,
+  description: &'static str,
+  category: MetricCategory,
+  prometheus_name: Option<&'static str>,
+  validity: MetricValidity,
+) -> MetricInfo
 where
   U: MetrifulUnit
 {
-  U::new_metric(register)
+  MetricInfo {
+    id,
+    description,
+    register: metric.register,
+    category,
+    prometheus_name,
+    unit_name: U::name(),
+    unit_symbol: U::symbol(),
+    validity,
+    combined: prometheus_name.is_none(),
+  }
+}
+
+/// Returns the metadata catalog, built on first use as unit names are not
+/// available in const contexts.
+fn metric_info() -> &'static [MetricInfo] {
+  static METRIC_INFO: OnceLock<Vec<MetricInfo>> = OnceLock::new();
+
+  METRIC_INFO.get_or_init(|| {
+    use MetricCategory::*;
+
+    vec![
+      info(
+        &METRIC_TEMPERATURE, "temperature", "Temperature",
+        Air, Some("metriful_air_temperature"), ALWAYS
+      ),
+      info(
+        &METRIC_PRESSURE, "pressure", "Air pressure",
+        Air, Some("metriful_air_pressure"), ALWAYS
+      ),
+      info(
+        &METRIC_RELATIVE_HUMIDITY, "relative_humidity", "Relative humidity",
+        Air, Some("metriful_air_humidity"), ALWAYS
+      ),
+      info(
+        &METRIC_GAS_RESISTANCE, "gas_resistance", "Gas sensor resistance",
+        Air, Some("metriful_air_gas_sensor_resistance"), ALWAYS
+      ),
+      info(
+        &METRIC_COMBINED_AIR_DATA, "combined_air_data", "All air data",
+        Air, None, ALWAYS
+      ),
+      info(
+        &METRIC_AQI, "aqi", "Air quality index",
+        AirQuality, Some("metriful_air_quality_aqi"), CYCLE_MODE
+      ),
+      info(
+        &METRIC_EST_CO2, "estimated_co2", "Estimated CO2 concentration",
+        AirQuality, Some("metriful_air_quality_estimated_co2"), CYCLE_MODE
+      ),
+      info(
+        &METRIC_VOC, "estimated_voc", "Equivalent breath VOC concentration",
+        AirQuality, Some("metriful_air_quality_estimated_voc"), CYCLE_MODE
+      ),
+      info(
+        &METRIC_AQI_ACCURACY, "aqi_accuracy", "Air quality index accuracy",
+        AirQuality, Some("metriful_air_quality_aqi_accuracy"), CYCLE_MODE
+      ),
+      info(
+        &METRIC_COMBINED_AIR_QUALITY_DATA, "combined_air_quality_data",
+        "All air quality data", AirQuality, None, CYCLE_MODE
+      ),
+      info(
+        &METRIC_ILLUMINANCE, "illuminance", "Illuminance",
+        Light, Some("metriful_light_illuminance"), ALWAYS
+      ),
+      info(
+        &METRIC_WHITE_LIGHT_LEVEL, "white_light_level", "White light level",
+        Light, Some("metriful_light_white_level"), ALWAYS
+      ),
+      info(
+        &METRIC_COMBINED_LIGHT_DATA, "combined_light_data", "All light data",
+        Light, None, ALWAYS
+      ),
+      info(
+        &METRIC_WEIGHTED_SOUND_LEVEL, "weighted_sound_level",
+        "A-weighted sound pressure level", Sound,
+        Some("metriful_sound_weighted_spl"), ALWAYS
+      ),
+      info(
+        &METRIC_SOUND_LEVEL, "sound_level",
+        "Sound pressure level by frequency band", Sound,
+        Some("metriful_sound_spl_band"), ALWAYS
+      ),
+      info(
+        &METRIC_PEAK_SOUND_AMPLITUDE, "peak_sound_amplitude",
+        "Peak sound amplitude since last read", Sound,
+        Some("metriful_sound_peak_amplitude"), ALWAYS
+      ),
+      info(
+        &METRIC_SOUND_MEASUREMENT_STABILITY, "sound_measurement_stability",
+        "Sound measurement stability", Sound,
+        Some("metriful_sound_measurement_stable"), ALWAYS
+      ),
+      info(
+        &METRIC_COMBINED_SOUND_DATA, "combined_sound_data", "All sound data",
+        Sound, None, ALWAYS
+      ),
+      info(
+        &METRIC_PARTICLE_SENSOR_DUTY_CYCLE, "particle_sensor_duty_cycle",
+        "Particle sensor duty cycle", Particle,
+        Some("metriful_particle_sensor_duty_cycle"), PARTICLE_SENSOR
+      ),
+      info(
+        &METRIC_PARTICLE_CONCENTRATION, "particle_concentration",
+        "Particle concentration", Particle,
+        Some("metriful_particle_concentration"), PARTICLE_SENSOR
+      ),
+      info(
+        &METRIC_PARTICLE_DATA_VALID, "particle_data_valid",
+        "Particle sensor data validity", Particle,
+        Some("metriful_particle_data_valid"), PARTICLE_SENSOR
+      ),
+      info(
+        &METRIC_COMBINED_PARTICLE_DATA, "combined_particle_data",
+        "All particle data", Particle, None, PARTICLE_SENSOR
+      ),
+      info(
+        &METRIC_COMBINED_ALL, "combined_all",
+        "All data; air quality data is only valid in cycle mode", All, None,
+        ALWAYS
+      ),
+    ]
+  })
+}
+
+/// Returns metadata for every known metric, in register order within each
+/// category.
+///
+/// # Example
+/// ```
+/// use metriful::metric::{metrics, MetricCategory};
+///
+/// let sound: Vec<&str> = metrics().iter()
+///   .filter(|m| m.category == MetricCategory::Sound && !m.combined)
+///   .map(|m| m.id)
+///   .collect();
+///
+/// assert_eq!(sound, vec![
+///   "weighted_sound_level",
+///   "sound_level",
+///   "peak_sound_amplitude",
+///   "sound_measurement_stability",
+/// ]);
+/// ```
+pub fn metrics() -> &'static [MetricInfo] {
+  metric_info()
 }
 
-// TODO: make these const when const generics lands
-lazy_static! {
-  /// Temperature in degrees Celsius
-  pub static ref METRIC_TEMPERATURE: Metric<UnitDegreesCelsius> = metric(0x21);
+/// Finds a metric's metadata by its register.
+///
+/// # Example
+/// ```
+/// use metriful::metric::METRIC_TEMPERATURE;
+///
+/// let info = METRIC_TEMPERATURE.metadata().unwrap();
+/// assert_eq!(info.id, "temperature");
+/// assert_eq!(info.prometheus_name, Some("metriful_air_temperature"));
+/// assert!(!info.validity.cycle_mode);
+/// ```
+pub fn find_by_register(register: u8) -> Option<&'static MetricInfo> {
+  metric_info().iter().find(|m| m.register == register)
+}
 
-  /// Pressure in Pascals (Pa)
-  pub static ref METRIC_PRESSURE: Metric<UnitPascals> = metric(0x22);
+/// Finds a metric's metadata by its snake_case id.
+pub fn find_by_id(id: &str) -> Option<&'static MetricInfo> {
+  metric_info().iter().find(|m| m.id == id)
+}
 
-  /// Relative humidity percentage
-  pub static ref METRIC_RELATIVE_HUMIDITY: Metric<UnitRelativeHumidity> = metric(0x23);
+/// Finds a metric by name for reading at runtime, e.g. from a CLI flag or
+/// config file.
+///
+/// Names are matched against [`MetricInfo::id`], ignoring case and treating
+/// `-` as `_`. The `combined_` prefix and `_data` suffix of combined reads
+/// may be omitted, e.g. `sound` for `combined_sound_data`, as may the
+/// `relative_` and `estimated_` prefixes, e.g. `co2` for `estimated_co2`.
+///
+/// # Example
+/// ```
+/// use metriful::metric::{by_name, DynamicMetric};
+///
+/// assert_eq!(by_name("temperature"), Some(DynamicMetric::Temperature));
+/// assert_eq!(by_name("combined-sound"), Some(DynamicMetric::CombinedSoundData));
+/// assert_eq!(by_name("sound"), Some(DynamicMetric::CombinedSoundData));
+/// assert_eq!(by_name("humidity"), Some(DynamicMetric::RelativeHumidity));
+/// assert_eq!(by_name("co2"), Some(DynamicMetric::EstimatedCO2));
+/// assert_eq!(by_name("combined_all").unwrap().metadata().register, 0x0);
+/// assert_eq!(by_name("nonsense"), None);
+/// ```
+pub fn by_name(name: &str) -> Option<DynamicMetric> {
+  let name = name.trim().to_ascii_lowercase().replace('-', "_");
 
-  /// Gas sensor resistance
-  pub static ref METRIC_GAS_RESISTANCE: Metric<UnitResistance> = metric(0x24);
+  DynamicMetric::ALL.iter().copied().find(|metric| {
+    let id = metric.metadata().id;
+    let short = id.strip_suffix("_data").unwrap_or(id);
 
-  /// Combined read of air data metrics (0x21-0x24, inclusive)
-  pub static ref METRIC_COMBINED_AIR_DATA: Metric<UnitCombinedAirData> = metric(0x10);
+    let unprefixed = ["combined_", "relative_", "estimated_"].iter()
+      .find_map(|prefix| short.strip_prefix(prefix));
 
-  /// Air quality index
-  ///
-  /// Note: only valid during cycle measurements; this limitation is not well
-  /// documented.
-  pub static ref METRIC_AQI: Metric<UnitAirQualityIndex> = metric(0x25);
+    id == name || short == name || unprefixed == Some(name.as_str())
+  })
+}
 
-  /// Estimated CO2 concentration (based on gas sensor)
-  ///
-  /// Note: only valid during cycle measurements; this limitation is not well
-  /// documented.
-  pub static ref METRIC_EST_CO2: Metric<UnitPartsPerMillion> = metric(0x26);
+impl std::str::FromStr for DynamicMetric {
+  type Err = MetrifulError;
 
-  /// "Equivalent breath" VOC concentration
-  ///
-  /// Note: only valid during cycle measurements; this limitation is not well
-  /// documented.
-  pub static ref METRIC_VOC: Metric<UnitPartsPerMillion> = metric(0x27);
+  fn from_str(s: &str) -> Result<Self> {
+    by_name(s).ok_or_else(|| MetrifulError::InvalidMetricName(s.to_string()))
+  }
+}
 
-  /// AQI accuracy indicator
-  ///
-  /// Note: only valid during cycle measurements; this limitation is not well
-  /// documented.
-  pub static ref METRIC_AQI_ACCURACY: Metric<UnitAQIAccuracy> = metric(0x28);
+impl crate::dyn_metric::DynMetric for DynamicMetric {
+  fn name(&self) -> &'static str {
+    self.metadata().id
+  }
 
-  /// Combined read of air quality metrics (0x25-0x28, inclusive).
-  ///
-  /// Note: only valid during cycle measurements; this limitation is not well
-  /// documented.
-  pub static ref METRIC_COMBINED_AIR_QUALITY_DATA: Metric<UnitCombinedAirQualityData> = metric(0x11);
+  fn register(&self) -> u8 {
+    DynamicMetric::register(self)
+  }
 
-  /// Illuminance in lux
-  pub static ref METRIC_ILLUMINANCE: Metric<UnitIlluminance> = metric(0x31);
+  fn unit_name(&self) -> &'static str {
+    self.as_dyn().unit_name()
+  }
 
-  /// White light level
-  pub static ref METRIC_WHITE_LIGHT_LEVEL: Metric<UnitWhiteLevel> = metric(0x32);
+  fn unit_symbol(&self) -> Option<&'static str> {
+    self.as_dyn().unit_symbol()
+  }
 
-  /// Combined read of light metrics (0x31, 0x32)
-  pub static ref METRIC_COMBINED_LIGHT_DATA: Metric<UnitCombinedLightData> = metric(0x12);
+  fn read_dyn(
+    &self,
+    device: &mut dyn MetrifulTransport,
+    calibration: &crate::calibration::Calibration,
+    status: Option<&crate::status::DeviceStatus>,
+  ) -> Result<crate::dyn_metric::DynReading> {
+    self.as_dyn().read_dyn(device, calibration, status)
+  }
+}
 
-  /// A-weighted sound pressure level in dBa
-  pub static ref METRIC_WEIGHTED_SOUND_LEVEL: Metric<UnitAWeightedSPL> = metric(0x41);
+macro_rules! dynamic_metrics {
+  ($($variant:ident => $metric:ident: $unit:ty),* $(,)?) => {
+    /// A metric selected at runtime; see [`by_name()`].
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    pub enum DynamicMetric {
+      $(
+        #[doc = concat!("See [`", stringify!($metric), "`]")]
+        $variant,
+      )*
+    }
 
-  /// Sound pressure level by frequency band
-  pub static ref METRIC_SOUND_LEVEL: Metric<UnitSPLFrequencyBands> = metric(0x42);
+    /// A value read via a [`DynamicMetric`].
+    // values are short-lived, so combined reads are not worth boxing
+    #[allow(clippy::large_enum_variant)]
+    #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "serde", derive(Serialize), serde(untagged))]
+    pub enum DynamicValue {
+      $($variant(UnitValue<$unit>),)*
+    }
 
-  /// Measured peak sound amplitude "since last read"
-  pub static ref METRIC_PEAK_SOUND_AMPLITUDE: Metric<UnitMillipascal> = metric(0x43);
+    impl DynamicMetric {
+      /// All known metrics, in the same order as [`metrics()`].
+      pub const ALL: &'static [DynamicMetric] = &[$(DynamicMetric::$variant,)*];
 
-  /// Self assessment of sound measurement stability
-  pub static ref METRIC_SOUND_MEASUREMENT_STABILITY: Metric<UnitSoundMeasurementStability> = metric(0x44);
+      /// Returns the register this metric is read from.
+      pub fn register(&self) -> u8 {
+        match self {
+          $(DynamicMetric::$variant => $metric.register,)*
+        }
+      }
 
-  /// Combined read of sound data (0x41-0x44)
-  pub static ref METRIC_COMBINED_SOUND_DATA: Metric<UnitCombinedSoundData> = metric(0x13);
+      /// Returns this metric's metadata.
+      pub fn metadata(&self) -> &'static MetricInfo {
+        find_by_register(self.register()).expect("all metrics have metadata")
+      }
 
-  /// Particle sensor duty cycle
-  pub static ref METRIC_PARTICLE_SENSOR_DUTY_CYCLE: Metric<UnitPercent> = metric(0x51);
+      /// Returns this metric as a [`DynMetric`](crate::dyn_metric::DynMetric).
+      pub fn as_dyn(&self) -> &'static dyn crate::dyn_metric::DynMetric {
+        match self {
+          $(DynamicMetric::$variant => &$metric,)*
+        }
+      }
 
-  /// Particle concentration as measured by external sensor
-  pub static ref METRIC_PARTICLE_CONCENTRATION: Metric<UnitRawParticleConcentration> = metric(0x52);
+      /// Reads this metric via [`Metriful::read()`](fn@crate::Metriful::read),
+      /// including any configured calibration.
+      pub fn read<D>(&self, metriful: &mut crate::Metriful<D>) -> Result<DynamicValue>
+      where
+        D: MetrifulTransport
+      {
+        match self {
+          $(DynamicMetric::$variant => metriful.read($metric).map(DynamicValue::$variant),)*
+        }
+      }
+    }
 
-  /// Self assessment of state of particle sensor, if attached
-  pub static ref METRIC_PARTICLE_DATA_VALID: Metric<UnitParticleDataValidity> = metric(0x53);
+    impl DynamicValue {
+      /// Returns the metric this value was read from.
+      pub fn metric(&self) -> DynamicMetric {
+        match self {
+          $(DynamicValue::$variant(_) => DynamicMetric::$variant,)*
+        }
+      }
 
-  /// Combined read of all particle data in registers 0x51-0x53.
-  pub static ref METRIC_COMBINED_PARTICLE_DATA: Metric<UnitCombinedParticleData> = metric(0x14);
+      /// Returns the time the value was read.
+      pub fn time(&self) -> chrono::DateTime<Utc> {
+        match self {
+          $(DynamicValue::$variant(v) => v.time,)*
+        }
+      }
+    }
 
-  /// Pseudo-metric for a combined read of all METRIC_COMBINED_* fields.
-  pub static ref METRIC_COMBINED_ALL: Metric<UnitCombinedData> = metric(0x0);
+    impl std::fmt::Display for DynamicValue {
+      fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+          $(DynamicValue::$variant(v) => write!(f, "{}", v),)*
+        }
+      }
+    }
+  };
+}
+
+dynamic_metrics! {
+  Temperature => METRIC_TEMPERATURE: UnitDegreesCelsius,
+  Pressure => METRIC_PRESSURE: UnitPascals,
+  RelativeHumidity => METRIC_RELATIVE_HUMIDITY: UnitRelativeHumidity,
+  GasResistance => METRIC_GAS_RESISTANCE: UnitResistance,
+  CombinedAirData => METRIC_COMBINED_AIR_DATA: UnitCombinedAirData,
+  Aqi => METRIC_AQI: UnitAirQualityIndex,
+  EstimatedCO2 => METRIC_EST_CO2: UnitEstimatedCO2,
+  EstimatedVOC => METRIC_VOC: UnitEstimatedVOC,
+  AqiAccuracy => METRIC_AQI_ACCURACY: UnitAQIAccuracy,
+  CombinedAirQualityData => METRIC_COMBINED_AIR_QUALITY_DATA: UnitCombinedAirQualityData,
+  Illuminance => METRIC_ILLUMINANCE: UnitIlluminance,
+  WhiteLightLevel => METRIC_WHITE_LIGHT_LEVEL: UnitWhiteLevel,
+  CombinedLightData => METRIC_COMBINED_LIGHT_DATA: UnitCombinedLightData,
+  WeightedSoundLevel => METRIC_WEIGHTED_SOUND_LEVEL: UnitAWeightedSPL,
+  SoundLevel => METRIC_SOUND_LEVEL: UnitSPLFrequencyBands,
+  PeakSoundAmplitude => METRIC_PEAK_SOUND_AMPLITUDE: UnitMillipascal,
+  SoundMeasurementStability => METRIC_SOUND_MEASUREMENT_STABILITY: UnitSoundMeasurementStability,
+  CombinedSoundData => METRIC_COMBINED_SOUND_DATA: UnitCombinedSoundData,
+  ParticleSensorDutyCycle => METRIC_PARTICLE_SENSOR_DUTY_CYCLE: UnitPercent,
+  ParticleConcentration => METRIC_PARTICLE_CONCENTRATION: UnitRawParticleConcentration,
+  ParticleDataValid => METRIC_PARTICLE_DATA_VALID: UnitParticleDataValidity,
+  CombinedParticleData => METRIC_COMBINED_PARTICLE_DATA: UnitCombinedParticleData,
+  CombinedAll => METRIC_COMBINED_ALL: UnitCombinedData,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn catalog_ids_round_trip() {
+    for metric in DynamicMetric::ALL {
+      let info = metric.metadata();
+      assert_eq!(by_name(info.id), Some(*metric));
+      assert_eq!(info.id.parse::<DynamicMetric>().unwrap(), *metric);
+      assert_eq!(find_by_id(info.id).map(|i| i.register), Some(metric.register()));
+    }
+
+    assert_eq!(DynamicMetric::ALL.len(), metrics().len());
+  }
+
+  #[test]
+  fn unknown_names_are_rejected() {
+    for name in &["", "nonsense", "temperature2", "combined", "_data"] {
+      assert_eq!(by_name(name), None, "{:?}", name);
+      assert!(matches!(
+        name.parse::<DynamicMetric>(),
+        Err(MetrifulError::InvalidMetricName(ref n)) if n == name
+      ));
+    }
+
+    assert!(find_by_register(0xFF).is_none());
+    assert!(Metric::new(0xFF, UnitIlluminance).metadata().is_none());
+  }
+
+  #[test]
+  fn out_of_range_bytes_are_rejected() {
+    let mock = crate::testing::MockDevice::new();
+    let mut device = mock.clone();
+
+    mock.set_register(METRIC_AQI_ACCURACY.register, &[4]);
+    assert!(matches!(
+      METRIC_AQI_ACCURACY.read(&mut device),
+      Err(MetrifulError::InvalidAQIAccuracy(4))
+    ));
+
+    mock.set_register(METRIC_PARTICLE_DATA_VALID.register, &[2]);
+    assert!(matches!(
+      METRIC_PARTICLE_DATA_VALID.read(&mut device),
+      Err(MetrifulError::InvalidParticleDataValidity(2))
+    ));
+  }
+
+  #[cfg(feature = "serde_json")]
+  fn round_trip<U>(value: &UnitValue<U>)
+  where
+    U: MetrifulUnit,
+    U::Output: serde::de::DeserializeOwned,
+  {
+    let json = serde_json::to_value(value).unwrap();
+    let parsed: UnitValue<U> = serde_json::from_value(json.clone()).unwrap();
+    let reserialized = serde_json::to_value(&parsed).unwrap();
+
+    assert_eq!(reserialized["value"], json["value"], "{}", U::name());
+    assert_eq!(reserialized["timestamp"], json["timestamp"], "{}", U::name());
+  }
+
+  #[cfg(feature = "serde_json")]
+  #[test]
+  fn catalog_readings_round_trip_through_json() {
+    let mock = crate::testing::MockDevice::new();
+    mock.set_register(0x07, &[1]);
+    mock.set_register(METRIC_TEMPERATURE.register, &[21, 5]);
+    mock.set_register(METRIC_AQI_ACCURACY.register, &[2]);
+    mock.set_register(METRIC_SOUND_LEVEL.register, &[30, 35, 40, 45, 50, 55, 1, 2, 3, 4, 5, 6]);
+
+    let mut metriful = crate::Metriful::try_new_device_timeout(
+      mock.ready_line(), mock.clone(), Some(std::time::Duration::from_millis(100)),
+    ).unwrap();
+    metriful.set_enforce_mode_validity(false);
+
+    macro_rules! round_trip_all {
+      ($value:expr, $($variant:ident),*) => {
+        match $value { $(DynamicValue::$variant(v) => round_trip(&v),)* }
+      };
+    }
+
+    for metric in DynamicMetric::ALL {
+      let value = metric.read(&mut metriful).unwrap();
+      assert_eq!(value.metric(), *metric);
+
+      round_trip_all!(value,
+        Temperature, Pressure, RelativeHumidity, GasResistance, CombinedAirData,
+        Aqi, EstimatedCO2, EstimatedVOC, AqiAccuracy, CombinedAirQualityData,
+        Illuminance, WhiteLightLevel, CombinedLightData,
+        WeightedSoundLevel, SoundLevel, PeakSoundAmplitude, SoundMeasurementStability,
+        CombinedSoundData, ParticleSensorDutyCycle, ParticleConcentration,
+        ParticleDataValid, CombinedParticleData, CombinedAll
+      );
+    }
+  }
 }
@@ -0,0 +1,136 @@
+//! Reading an arbitrary subset of metrics together.
+//!
+//! A [`MetricSet`] groups any number of metrics, which
+//! [`Metriful::read_set()`] reads within a single READY window and reports
+//! with one shared timestamp. This sits between reading one metric at a time
+//! and reading everything via [`METRIC_COMBINED_ALL`].
+//!
+//! # Example
+//! ```no_run
+//! use metriful::{Metriful, MetricSet, metric::*};
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+//!
+//! let set = MetricSet::new()
+//!   .with(METRIC_TEMPERATURE)
+//!   .with(METRIC_ILLUMINANCE)
+//!   .with(METRIC_WEIGHTED_SOUND_LEVEL);
+//!
+//! let reading = metriful.read_set(&set)?;
+//! println!("temperature at {}: {}", reading.time, reading.get("temperature").unwrap());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+
+#[cfg(feature = "serde")] use chrono::SecondsFormat;
+#[cfg(feature = "serde")] use serde::{Serialize, ser::{Serializer, SerializeStruct}};
+
+use crate::dyn_metric::{DynMetric, DynReading, ReadingValue};
+
+#[cfg(doc)] use crate::{Metriful, metric::METRIC_COMBINED_ALL};
+
+/// A set of metrics to be read together.
+#[derive(Debug, Default)]
+pub struct MetricSet {
+  metrics: Vec<Box<dyn DynMetric>>,
+}
+
+impl MetricSet {
+  /// Creates an empty set.
+  pub fn new() -> MetricSet {
+    MetricSet::default()
+  }
+
+  /// Adds a metric to the set. Metrics already in the set (by register) are
+  /// ignored.
+  pub fn with(mut self, metric: impl DynMetric + 'static) -> Self {
+    self.add(metric);
+    self
+  }
+
+  /// Adds a metric to the set, returning false if it was already present.
+  pub fn add(&mut self, metric: impl DynMetric + 'static) -> bool {
+    if self.metrics.iter().any(|m| m.register() == metric.register()) {
+      return false;
+    }
+
+    self.metrics.push(Box::new(metric));
+    true
+  }
+
+  /// Returns the metrics in this set, in the order they were added.
+  pub fn metrics(&self) -> impl Iterator<Item = &dyn DynMetric> {
+    self.metrics.iter().map(|m| m.as_ref())
+  }
+
+  /// Returns the number of metrics in the set.
+  pub fn len(&self) -> usize {
+    self.metrics.len()
+  }
+
+  /// Returns true if the set contains no metrics.
+  pub fn is_empty(&self) -> bool {
+    self.metrics.is_empty()
+  }
+}
+
+/// The result of reading a [`MetricSet`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSetReading {
+  /// The system time (UTC) when the set was read; every reading shares this
+  /// timestamp.
+  pub time: DateTime<Utc>,
+
+  /// Each metric's reading, in the order the metrics were added to the set.
+  pub readings: Vec<DynReading>,
+}
+
+/// Sets the timestamp of a reading and any combined read components.
+fn set_time(reading: &mut DynReading, time: DateTime<Utc>) {
+  reading.time = time;
+
+  if let ReadingValue::Group(components) = &mut reading.value {
+    for component in components {
+      set_time(component, time);
+    }
+  }
+}
+
+impl MetricSetReading {
+  pub(crate) fn new(time: DateTime<Utc>, mut readings: Vec<DynReading>) -> MetricSetReading {
+    for reading in &mut readings {
+      set_time(reading, time);
+    }
+
+    MetricSetReading { time, readings }
+  }
+
+  /// Returns the reading for the metric with the given name, i.e. its
+  /// [`DynMetric::name()`].
+  pub fn get(&self, name: &str) -> Option<&DynReading> {
+    self.readings.iter().find(|r| r.name == name)
+  }
+
+  /// Returns the readings keyed by metric name.
+  pub fn to_map(&self) -> BTreeMap<&'static str, &DynReading> {
+    self.readings.iter().map(|r| (r.name, r)).collect()
+  }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for MetricSetReading {
+  fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+  where
+      S: Serializer
+  {
+    let mut state = serializer.serialize_struct("MetricSetReading", 2)?;
+    state.serialize_field("timestamp", &self.time.to_rfc3339_opts(SecondsFormat::Secs, true))?;
+    state.serialize_field("readings", &self.to_map())?;
+    state.end()
+  }
+}
@@ -0,0 +1,333 @@
+//! Connection options shared by applications and the bundled binaries.
+//!
+//! [`MetrifulOptions::from_env()`] implements the environment variable
+//! contract used by `metriful-tool` and `metriful-exporter`:
+//!
+//! | Variable                  | Default      | Description                       |
+//! |---------------------------|--------------|-----------------------------------|
+//! | `METRIFUL_I2C_DEVICE`     | `/dev/i2c-1` | system i2c device                 |
+//! | `METRIFUL_I2C_ADDRESS`    | `0x71`       | i2c address, base-10 or `0x`-hex  |
+//! | `METRIFUL_GPIO_READY`     | `11`         | GPIO number of the READY signal   |
+//! | `METRIFUL_TIMEOUT`        | (none)       | ready timeout in whole seconds    |
+//! | `METRIFUL_READY_POLARITY` | `active-low` | `active-low` or `active-high`     |
+//! | `METRIFUL_GPIO_CHIP`      | (none)       | GPIO chip for READY, e.g. `gpiochip0` |
+//!
+//! If `METRIFUL_GPIO_CHIP` is set, `METRIFUL_GPIO_READY` is the line offset on
+//! that chip and the GPIO character device interface is used (requires the
+//! `cdev` feature); otherwise, the legacy sysfs GPIO interface is used.
+
+#[cfg(feature = "cdev")] use std::convert::TryFrom;
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[cfg(feature = "serde")] use serde::Serialize;
+
+use crate::Metriful;
+use crate::error::*;
+#[cfg(feature = "cdev")] use crate::ready::CdevReadyLine;
+use crate::ready::{ReadyLine, ReadyPolarity, SysfsReadyLine};
+
+/// Environment variable naming the system i2c device.
+pub const ENV_I2C_DEVICE: &str = "METRIFUL_I2C_DEVICE";
+
+/// Environment variable holding the device's i2c address.
+pub const ENV_I2C_ADDRESS: &str = "METRIFUL_I2C_ADDRESS";
+
+/// Environment variable holding the GPIO number of the READY signal.
+pub const ENV_GPIO_READY: &str = "METRIFUL_GPIO_READY";
+
+/// Environment variable holding the ready timeout in seconds.
+pub const ENV_TIMEOUT: &str = "METRIFUL_TIMEOUT";
+
+/// Environment variable holding the READY signal's [`ReadyPolarity`].
+pub const ENV_READY_POLARITY: &str = "METRIFUL_READY_POLARITY";
+
+/// Environment variable naming the GPIO chip for the READY signal.
+pub const ENV_GPIO_CHIP: &str = "METRIFUL_GPIO_CHIP";
+
+/// Default system i2c device, as used on Raspberry Pis.
+pub const DEFAULT_I2C_DEVICE: &str = "/dev/i2c-1";
+
+/// Default MS430 i2c address, i.e. with the solder bridge open.
+pub const DEFAULT_I2C_ADDRESS: u16 = 0x71;
+
+/// Default GPIO number for the READY signal, per Metriful's wiring guide.
+pub const DEFAULT_GPIO_READY: u64 = 11;
+
+/// Parses an i2c address given as a plain base-10 int or hex with a `0x`
+/// prefix.
+pub fn parse_i2c_address(s: &str) -> Result<u16> {
+  let parsed = match s.strip_prefix("0x") {
+    Some(hex) => u16::from_str_radix(hex, 16),
+    None => s.parse(),
+  };
+
+  parsed.map_err(|_| MetrifulError::InvalidOption {
+    name: ENV_I2C_ADDRESS.to_string(),
+    value: s.to_string(),
+  })
+}
+
+/// Parses a timeout given in whole seconds.
+pub fn parse_timeout_secs(s: &str) -> Result<Duration> {
+  s.parse()
+    .map(Duration::from_secs)
+    .map_err(|_| MetrifulError::InvalidOption {
+      name: ENV_TIMEOUT.to_string(),
+      value: s.to_string(),
+    })
+}
+
+/// Options needed to open a Metriful device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct MetrifulOptions {
+  /// System i2c device, e.g. `/dev/i2c-1`
+  pub i2c_device: PathBuf,
+
+  /// Device i2c address; usually 0x71, or 0x70 if the solder bridge is closed
+  pub i2c_address: u16,
+
+  /// GPIO number (not physical pin number) for the READY signal
+  pub gpio_ready: u64,
+
+  /// Timeout waiting for the device to become ready, if any
+  pub timeout: Option<Duration>,
+
+  /// Logic level at which the READY signal is asserted
+  pub ready_polarity: ReadyPolarity,
+
+  /// GPIO chip (e.g. `gpiochip0`) to request the READY line from via the
+  /// character device interface; if unset, sysfs GPIO is used
+  pub gpio_chip: Option<PathBuf>,
+}
+
+impl Default for MetrifulOptions {
+  fn default() -> Self {
+    MetrifulOptions {
+      i2c_device: PathBuf::from(DEFAULT_I2C_DEVICE),
+      i2c_address: DEFAULT_I2C_ADDRESS,
+      gpio_ready: DEFAULT_GPIO_READY,
+      timeout: None,
+      ready_polarity: ReadyPolarity::default(),
+      gpio_chip: None,
+    }
+  }
+}
+
+impl MetrifulOptions {
+  /// Loads options from the `METRIFUL_*` environment variables, using defaults
+  /// for any that are unset. See the [module docs](self) for details.
+  ///
+  /// # Example
+  /// ```no_run
+  /// use metriful::MetrifulOptions;
+  ///
+  /// # fn main() -> metriful::error::Result<()> {
+  /// let metriful = MetrifulOptions::from_env()?.open()?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn from_env() -> Result<MetrifulOptions> {
+    MetrifulOptions::from_lookup(|name| env::var(name).ok())
+  }
+
+  /// Loads options using an arbitrary variable lookup function, using defaults
+  /// for any variables that are unset or empty.
+  ///
+  /// # Example
+  /// ```
+  /// use std::time::Duration;
+  /// use metriful::{MetrifulOptions, ReadyPolarity};
+  ///
+  /// let opts = MetrifulOptions::from_lookup(|name| match name {
+  ///   "METRIFUL_I2C_ADDRESS" => Some("0x70".to_string()),
+  ///   "METRIFUL_TIMEOUT" => Some("5".to_string()),
+  ///   "METRIFUL_READY_POLARITY" => Some("active-high".to_string()),
+  ///   _ => None,
+  /// }).unwrap();
+  ///
+  /// assert_eq!(opts.i2c_address, 0x70);
+  /// assert_eq!(opts.gpio_ready, 11);
+  /// assert_eq!(opts.timeout, Some(Duration::from_secs(5)));
+  /// assert_eq!(opts.ready_polarity, ReadyPolarity::ActiveHigh);
+  ///
+  /// let invalid = MetrifulOptions::from_lookup(|name| match name {
+  ///   "METRIFUL_GPIO_READY" => Some("seventeen".to_string()),
+  ///   _ => None,
+  /// });
+  /// assert!(invalid.is_err());
+  /// ```
+  pub fn from_lookup<F>(lookup: F) -> Result<MetrifulOptions>
+  where
+    F: Fn(&str) -> Option<String>
+  {
+    let var = |name: &str| lookup(name).filter(|v| !v.is_empty());
+    let mut opts = MetrifulOptions::default();
+
+    if let Some(device) = var(ENV_I2C_DEVICE) {
+      opts.i2c_device = PathBuf::from(device);
+    }
+
+    if let Some(address) = var(ENV_I2C_ADDRESS) {
+      opts.i2c_address = parse_i2c_address(&address)?;
+    }
+
+    if let Some(gpio) = var(ENV_GPIO_READY) {
+      opts.gpio_ready = gpio.parse().map_err(|_| MetrifulError::InvalidOption {
+        name: ENV_GPIO_READY.to_string(),
+        value: gpio.clone(),
+      })?;
+    }
+
+    if let Some(timeout) = var(ENV_TIMEOUT) {
+      opts.timeout = Some(parse_timeout_secs(&timeout)?);
+    }
+
+    if let Some(polarity) = var(ENV_READY_POLARITY) {
+      opts.ready_polarity = polarity.parse()?;
+    }
+
+    if let Some(chip) = var(ENV_GPIO_CHIP) {
+      opts.gpio_chip = Some(PathBuf::from(chip));
+    }
+
+    Ok(opts)
+  }
+
+  /// Sets the system i2c device.
+  pub fn i2c_device(mut self, i2c_device: impl Into<PathBuf>) -> Self {
+    self.i2c_device = i2c_device.into();
+    self
+  }
+
+  /// Sets the device i2c address.
+  pub fn i2c_address(mut self, i2c_address: u16) -> Self {
+    self.i2c_address = i2c_address;
+    self
+  }
+
+  /// Sets the GPIO number for the READY signal.
+  pub fn gpio_ready(mut self, gpio_ready: u64) -> Self {
+    self.gpio_ready = gpio_ready;
+    self
+  }
+
+  /// Sets the ready timeout.
+  pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  /// Sets the READY signal polarity.
+  pub fn ready_polarity(mut self, ready_polarity: ReadyPolarity) -> Self {
+    self.ready_polarity = ready_polarity;
+    self
+  }
+
+  /// Sets the GPIO chip to request the READY line from; if unset, sysfs GPIO
+  /// is used.
+  pub fn gpio_chip(mut self, gpio_chip: Option<PathBuf>) -> Self {
+    self.gpio_chip = gpio_chip;
+    self
+  }
+
+  /// Opens the READY line described by these options.
+  pub fn open_ready_line(&self) -> Result<Box<dyn ReadyLine>> {
+    match &self.gpio_chip {
+      #[cfg(feature = "cdev")]
+      Some(chip) => {
+        let line = u32::try_from(self.gpio_ready).map_err(|_| MetrifulError::InvalidOption {
+          name: ENV_GPIO_READY.to_string(),
+          value: self.gpio_ready.to_string(),
+        })?;
+
+        Ok(Box::new(CdevReadyLine::new(chip, line, self.ready_polarity)?))
+      },
+
+      #[cfg(not(feature = "cdev"))]
+      Some(_) => Err(MetrifulError::FeatureRequired("cdev")),
+
+      None => Ok(Box::new(SysfsReadyLine::new(self.gpio_ready, self.ready_polarity)?)),
+    }
+  }
+
+  /// Opens the device using these options; see
+  /// [`Metriful::try_new_ready_timeout()`].
+  pub fn open(&self) -> Result<Metriful> {
+    let mut metriful = Metriful::try_new_ready_timeout(
+      self.open_ready_line()?,
+      &self.i2c_device,
+      self.i2c_address,
+      self.timeout,
+    )?;
+
+    metriful.set_connection(self.clone(), true);
+
+    Ok(metriful)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lookup(vars: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+    move |name| vars.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string())
+  }
+
+  #[test]
+  fn i2c_addresses_accept_decimal_and_hex() {
+    assert_eq!(parse_i2c_address("113").unwrap(), 0x71);
+    assert_eq!(parse_i2c_address("0x70").unwrap(), 0x70);
+  }
+
+  #[test]
+  fn invalid_values_name_the_option() {
+    for (address, timeout) in &[("0xZZ", "ten"), ("seventy", "-1"), ("", "1.5")] {
+      assert!(matches!(
+        parse_i2c_address(address),
+        Err(MetrifulError::InvalidOption { ref name, .. }) if name == ENV_I2C_ADDRESS
+      ));
+      assert!(matches!(
+        parse_timeout_secs(timeout),
+        Err(MetrifulError::InvalidOption { ref name, .. }) if name == ENV_TIMEOUT
+      ));
+    }
+  }
+
+  #[test]
+  fn missing_and_empty_variables_use_defaults() {
+    let opts = MetrifulOptions::from_lookup(lookup(&[(ENV_GPIO_READY, "")])).unwrap();
+    assert_eq!(opts, MetrifulOptions::default());
+  }
+
+  #[test]
+  fn all_variables_are_read() {
+    let opts = MetrifulOptions::from_lookup(lookup(&[
+      (ENV_I2C_DEVICE, "/dev/i2c-3"),
+      (ENV_I2C_ADDRESS, "0x70"),
+      (ENV_GPIO_READY, "17"),
+      (ENV_TIMEOUT, "5"),
+      (ENV_READY_POLARITY, "active-high"),
+      (ENV_GPIO_CHIP, "gpiochip0"),
+    ])).unwrap();
+
+    assert_eq!(opts.i2c_device, PathBuf::from("/dev/i2c-3"));
+    assert_eq!(opts.i2c_address, 0x70);
+    assert_eq!(opts.gpio_ready, 17);
+    assert_eq!(opts.timeout, Some(Duration::from_secs(5)));
+    assert_eq!(opts.ready_polarity, ReadyPolarity::ActiveHigh);
+    assert_eq!(opts.gpio_chip, Some(PathBuf::from("gpiochip0")));
+  }
+
+  #[test]
+  fn invalid_variables_are_errors() {
+    assert!(MetrifulOptions::from_lookup(lookup(&[(ENV_GPIO_READY, "17a")])).is_err());
+    assert!(matches!(
+      MetrifulOptions::from_lookup(lookup(&[(ENV_READY_POLARITY, "sideways")])),
+      Err(MetrifulError::InvalidReadyPolarity(_))
+    ));
+  }
+}
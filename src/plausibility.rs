@@ -0,0 +1,338 @@
+//! Plausibility checks for readings.
+//!
+//! A glitch on the I2C bus can corrupt a reading without causing a bus error,
+//! producing values that decode fine but are physically implausible: a
+//! sudden -40 ℃, 0 Pa of pressure, or a one-off spike to 100% RH. A
+//! [`Plausibility`] set via [`Metriful::set_plausibility()`] checks every value
+//! returned by [`Metriful::read()`] (and so by iterators and background
+//! readers) against per-metric [`PlausibilityRule`]s.
+//!
+//! Depending on the rule, implausible values are either returned with an
+//! [`Implausible`] warning attached to [`UnitValue::warnings`], or rejected
+//! with [`MetrifulError::ImplausibleReading`].
+//!
+//! Rules are keyed by reading name: the metric id (e.g. `relative_humidity`)
+//! for individual reads, and the field name (e.g. `humidity`) for components
+//! of combined reads, as with [`DynReading`].
+//!
+//! # Example
+//! ```
+//! use metriful::plausibility::*;
+//! use metriful::unit::*;
+//!
+//! let mut plausibility = Plausibility::new().with_rule("temperature", PlausibilityRule {
+//!   min: Some(-40.0),
+//!   max: Some(85.0),
+//!   max_step: Some(5.0),
+//!   action: OnImplausible::Reject,
+//! });
+//!
+//! let temp = |v| UnitValue::<UnitDegreesCelsius>::new(v);
+//! assert!(plausibility.check("temperature", &temp(21.0)).unwrap().is_empty());
+//! assert!(plausibility.check("temperature", &temp(-45.0)).is_err());
+//! assert!(plausibility.check("temperature", &temp(21.5)).unwrap().is_empty());
+//!
+//! // a sudden jump is rejected, unless the next reading confirms it
+//! assert!(plausibility.check("temperature", &temp(35.0)).is_err());
+//! assert!(plausibility.check("temperature", &temp(35.2)).unwrap().is_empty());
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[cfg(feature = "serde")] use serde::Serialize;
+
+use crate::dyn_metric::{DynReading, ReadingValue};
+use crate::error::*;
+use crate::unit::{MetrifulUnit, UnitValue};
+
+#[cfg(doc)] use crate::Metriful;
+
+/// What happens to a reading that breaks a [`PlausibilityRule`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OnImplausible {
+  /// Return the reading with a warning in [`UnitValue::warnings`].
+  Warn,
+
+  /// Fail the read with [`MetrifulError::ImplausibleReading`].
+  Reject,
+}
+
+/// Limits on the values of a single reading.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PlausibilityRule {
+  /// The lowest plausible value, if any
+  pub min: Option<f64>,
+
+  /// The highest plausible value, if any
+  pub max: Option<f64>,
+
+  /// The largest plausible change from the previous accepted reading, if
+  /// any. As this is per reading, it should account for the read interval.
+  pub max_step: Option<f64>,
+
+  /// What happens to readings that break this rule
+  pub action: OnImplausible,
+}
+
+/// Why a reading was found implausible.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "snake_case"))]
+pub enum ImplausibleReason {
+  /// The value was below the rule's minimum.
+  BelowMin { min: f64 },
+
+  /// The value was above the rule's maximum.
+  AboveMax { max: f64 },
+
+  /// The value changed by more than the rule's maximum step.
+  Jump { previous: f64, max_step: f64 },
+}
+
+/// A reading that broke a [`PlausibilityRule`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Implausible {
+  /// Name of the reading, e.g. `temperature`
+  pub name: &'static str,
+
+  /// The implausible value
+  pub value: f64,
+
+  pub reason: ImplausibleReason,
+}
+
+impl fmt::Display for Implausible {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.reason {
+      ImplausibleReason::BelowMin { min } => write!(
+        f, "{} {} is below the minimum of {}", self.name, self.value, min
+      ),
+      ImplausibleReason::AboveMax { max } => write!(
+        f, "{} {} is above the maximum of {}", self.name, self.value, max
+      ),
+      ImplausibleReason::Jump { previous, max_step } => write!(
+        f, "{} jumped from {} to {} (more than {})", self.name, previous, self.value, max_step
+      ),
+    }
+  }
+}
+
+/// Recent accepted values of a reading, for [`PlausibilityRule::max_step`].
+#[derive(Debug, Clone, Default)]
+struct Series {
+  /// The last accepted value
+  last: Option<f64>,
+
+  /// A value rejected for a jump, accepted as a genuine change if the next
+  /// reading agrees with it
+  pending: Option<f64>,
+}
+
+/// Per-reading [`PlausibilityRule`]s, and the recent values needed to detect
+/// jumps. The default has no rules.
+#[derive(Debug, Clone, Default)]
+pub struct Plausibility {
+  rules: BTreeMap<&'static str, PlausibilityRule>,
+  series: BTreeMap<&'static str, Series>,
+}
+
+impl Plausibility {
+  /// Creates a validator with no rules.
+  pub fn new() -> Plausibility {
+    Plausibility::default()
+  }
+
+  /// Creates a validator with rules for the MS430's rated ranges of
+  /// temperature (-40 to 85 ℃), pressure (30-110 kPa), and humidity, plus
+  /// limits on how much they may change between readings suited to read
+  /// intervals of up to a few minutes.
+  pub fn recommended(action: OnImplausible) -> Plausibility {
+    let rule = |min, max, max_step| PlausibilityRule {
+      min: Some(min),
+      max: Some(max),
+      max_step: Some(max_step),
+      action,
+    };
+
+    Plausibility::new()
+      .with_rule("temperature", rule(-40.0, 85.0, 10.0))
+      .with_rule("pressure", rule(30_000.0, 110_000.0, 1_000.0))
+      .with_rule("humidity", rule(0.0, 100.0, 30.0))
+      .with_rule("relative_humidity", rule(0.0, 100.0, 30.0))
+  }
+
+  /// Adds or replaces the rule for the given reading name.
+  pub fn with_rule(mut self, name: &'static str, rule: PlausibilityRule) -> Plausibility {
+    self.set_rule(name, rule);
+    self
+  }
+
+  /// Adds or replaces the rule for the given reading name.
+  pub fn set_rule(&mut self, name: &'static str, rule: PlausibilityRule) {
+    self.rules.insert(name, rule);
+  }
+
+  /// Removes the rule for the given reading name, if any.
+  pub fn remove_rule(&mut self, name: &str) -> Option<PlausibilityRule> {
+    self.series.remove(name);
+    self.rules.remove(name)
+  }
+
+  /// Returns the rule for the given reading name, if any.
+  pub fn rule(&self, name: &str) -> Option<&PlausibilityRule> {
+    self.rules.get(name)
+  }
+
+  /// Forgets previous values, e.g. after the device has been reset.
+  pub fn reset(&mut self) {
+    self.series.clear();
+  }
+
+  /// Checks a reading, and any numeric components of combined reads, against
+  /// the configured rules. Returns warnings for values breaking
+  /// [`OnImplausible::Warn`] rules, or an error for the first value breaking
+  /// an [`OnImplausible::Reject`] rule.
+  ///
+  /// Values outside a rule's range aren't remembered for
+  /// [`PlausibilityRule::max_step`]; rejected jumps are only remembered to
+  /// confirm genuine changes.
+  pub fn check<U: MetrifulUnit>(
+    &mut self,
+    name: &'static str,
+    value: &UnitValue<U>,
+  ) -> Result<Vec<Implausible>> {
+    let mut warnings = Vec::new();
+    self.check_reading(&DynReading::from_value(name, value), &mut warnings)?;
+
+    Ok(warnings)
+  }
+
+  fn check_reading(&mut self, reading: &DynReading, warnings: &mut Vec<Implausible>) -> Result<()> {
+    match &reading.value {
+      ReadingValue::Number(n) => {
+        if let Some((implausible, action)) = self.check_value(reading.name, *n) {
+          match action {
+            OnImplausible::Warn => warnings.push(implausible),
+            OnImplausible::Reject => return Err(MetrifulError::ImplausibleReading(implausible)),
+          }
+        }
+      },
+      ReadingValue::Group(components) => {
+        for component in components {
+          self.check_reading(component, warnings)?;
+        }
+      },
+      _ => (),
+    }
+
+    Ok(())
+  }
+
+  fn check_value(&mut self, name: &'static str, value: f64) -> Option<(Implausible, OnImplausible)> {
+    let rule = *self.rules.get(name)?;
+    let series = self.series.entry(name).or_default();
+
+    let reason = match (rule.min, rule.max, rule.max_step, series.last) {
+      (Some(min), _, _, _) if value < min => Some(ImplausibleReason::BelowMin { min }),
+      (_, Some(max), _, _) if value > max => Some(ImplausibleReason::AboveMax { max }),
+      (_, _, Some(max_step), Some(previous)) if (value - previous).abs() > max_step => {
+        // the previous reading jumped too; if this one agrees, the change is real
+        let confirmed = series.pending.is_some_and(|pending| (value - pending).abs() <= max_step);
+        if confirmed {
+          None
+        } else {
+          Some(ImplausibleReason::Jump { previous, max_step })
+        }
+      },
+      _ => None,
+    };
+
+    let reason = match reason {
+      Some(reason) => reason,
+      None => {
+        series.last = Some(value);
+        series.pending = None;
+        return None;
+      },
+    };
+
+    if let ImplausibleReason::Jump { .. } = reason {
+      match rule.action {
+        OnImplausible::Warn => series.last = Some(value),
+        OnImplausible::Reject => series.pending = Some(value),
+      }
+    }
+
+    Some((Implausible { name, value, reason }, rule.action))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn temp(value: f32) -> UnitValue<crate::unit::UnitDegreesCelsius> {
+    UnitValue::new(value)
+  }
+
+  fn rule(action: OnImplausible) -> PlausibilityRule {
+    PlausibilityRule { min: Some(-40.0), max: Some(85.0), max_step: Some(5.0), action }
+  }
+
+  #[test]
+  fn readings_without_rules_are_accepted() {
+    let mut plausibility = Plausibility::new();
+    assert!(plausibility.check("temperature", &temp(-300.0)).unwrap().is_empty());
+  }
+
+  #[test]
+  fn out_of_range_values_are_rejected() {
+    let mut plausibility = Plausibility::new().with_rule("temperature", rule(OnImplausible::Reject));
+
+    match plausibility.check("temperature", &temp(90.0)) {
+      Err(MetrifulError::ImplausibleReading(implausible)) => {
+        assert_eq!(implausible.name, "temperature");
+        assert_eq!(implausible.reason, ImplausibleReason::AboveMax { max: 85.0 });
+      },
+      other => panic!("expected ImplausibleReading, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn warn_rules_return_the_reading() {
+    let mut plausibility = Plausibility::new().with_rule("temperature", rule(OnImplausible::Warn));
+
+    let warnings = plausibility.check("temperature", &temp(-50.0)).unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].reason, ImplausibleReason::BelowMin { min: -40.0 });
+  }
+
+  #[test]
+  fn unconfirmed_jumps_stay_rejected() {
+    let mut plausibility = Plausibility::new().with_rule("temperature", rule(OnImplausible::Reject));
+
+    plausibility.check("temperature", &temp(20.0)).unwrap();
+    assert!(plausibility.check("temperature", &temp(40.0)).is_err());
+    assert!(plausibility.check("temperature", &temp(60.0)).is_err());
+    assert!(plausibility.check("temperature", &temp(21.0)).unwrap().is_empty());
+  }
+
+  #[test]
+  fn out_of_range_values_are_not_remembered() {
+    let mut plausibility = Plausibility::new().with_rule("temperature", rule(OnImplausible::Reject));
+
+    plausibility.check("temperature", &temp(20.0)).unwrap();
+    assert!(plausibility.check("temperature", &temp(100.0)).is_err());
+    assert!(plausibility.check("temperature", &temp(22.0)).unwrap().is_empty());
+  }
+
+  #[test]
+  fn reset_forgets_previous_values() {
+    let mut plausibility = Plausibility::new().with_rule("temperature", rule(OnImplausible::Reject));
+
+    plausibility.check("temperature", &temp(20.0)).unwrap();
+    plausibility.reset();
+    assert!(plausibility.check("temperature", &temp(40.0)).unwrap().is_empty());
+  }
+}
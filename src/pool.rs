@@ -0,0 +1,256 @@
+//! Support for multiple MS430s sharing a single I2C bus.
+//!
+//! The MS430's address is selectable between 0x71 and 0x70 via a solder
+//! bridge, so two sensors may share a bus. [`MetrifulPool`] manages a set of
+//! such devices: every bus transaction from any device in the pool goes
+//! through a common [`SharedBus`] lock, so transactions are never interleaved
+//! even when devices are read from separate threads.
+//!
+//! # Example
+//! ```no_run
+//! use std::time::Duration;
+//! use metriful::{CyclePeriod, ReadyPolarity, metric::*};
+//! use metriful::pool::MetrifulPool;
+//! use metriful::ready::SysfsReadyLine;
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let timeout = Some(Duration::from_secs(5));
+//! let mut pool = MetrifulPool::new();
+//! pool.open(SysfsReadyLine::new(17, ReadyPolarity::ActiveLow)?, "/dev/i2c-1", 0x71, timeout)?;
+//! pool.open(SysfsReadyLine::new(27, ReadyPolarity::ActiveLow)?, "/dev/i2c-1", 0x70, timeout)?;
+//!
+//! // each device may be moved to its own reader thread
+//! let receivers = pool.into_iter()
+//!   .map(|(address, metriful)| {
+//!     let (_, rx, _) = metriful.async_cycle_read_timeout(
+//!       METRIC_COMBINED_ALL, CyclePeriod::Period0, timeout
+//!     );
+//!     (address, rx)
+//!   })
+//!   .collect::<Vec<_>>();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use i2cdev::linux::LinuxI2CDevice;
+use log::trace;
+
+use crate::Metriful;
+use crate::error::*;
+use crate::ready::ReadyLine;
+use crate::status::DeviceStatus;
+use crate::transport::MetrifulTransport;
+
+/// A lock shared by every device on one physical bus.
+///
+/// Clones refer to the same lock.
+#[derive(Debug, Clone, Default)]
+pub struct SharedBus {
+  lock: Arc<Mutex<()>>,
+}
+
+impl SharedBus {
+  /// Creates a new, independent bus lock.
+  pub fn new() -> SharedBus {
+    SharedBus::default()
+  }
+
+  fn lock(&self) -> MutexGuard<'_, ()> {
+    self.lock.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Wraps a transport so that each of its transactions holds this bus lock.
+  pub fn attach<T: MetrifulTransport>(&self, transport: T) -> BusDevice<T> {
+    BusDevice {
+      bus: self.clone(),
+      inner: transport,
+    }
+  }
+}
+
+/// A transport whose transactions are serialized via a [`SharedBus`].
+#[derive(Debug)]
+pub struct BusDevice<T> {
+  bus: SharedBus,
+  inner: T,
+}
+
+impl<T> BusDevice<T> {
+  /// Returns the shared bus this device is attached to.
+  pub fn bus(&self) -> &SharedBus {
+    &self.bus
+  }
+
+  /// Returns the wrapped transport.
+  pub fn into_inner(self) -> T {
+    self.inner
+  }
+}
+
+impl<T: MetrifulTransport> MetrifulTransport for BusDevice<T> {
+  fn write_byte(&mut self, command: u8) -> Result<()> {
+    let _bus = self.bus.lock();
+    self.inner.write_byte(command)
+  }
+
+  fn read_byte(&mut self, register: u8) -> Result<u8> {
+    let _bus = self.bus.lock();
+    self.inner.read_byte(register)
+  }
+
+  fn write_byte_data(&mut self, register: u8, value: u8) -> Result<()> {
+    let _bus = self.bus.lock();
+    self.inner.write_byte_data(register, value)
+  }
+
+  fn read_block(&mut self, register: u8, len: u8) -> Result<Vec<u8>> {
+    let _bus = self.bus.lock();
+    self.inner.read_block(register, len)
+  }
+
+  fn write_block(&mut self, register: u8, values: &[u8]) -> Result<()> {
+    let _bus = self.bus.lock();
+    self.inner.write_block(register, values)
+  }
+
+  fn set_deadline(&mut self, deadline: Option<Instant>) {
+    self.inner.set_deadline(deadline);
+  }
+}
+
+/// A set of Metriful devices on one bus, keyed by i2c address.
+#[derive(Debug)]
+pub struct MetrifulPool<D = LinuxI2CDevice>
+where
+  D: MetrifulTransport
+{
+  bus: SharedBus,
+  devices: BTreeMap<u16, Metriful<BusDevice<D>>>,
+}
+
+impl<D: MetrifulTransport> Default for MetrifulPool<D> {
+  fn default() -> Self {
+    MetrifulPool {
+      bus: SharedBus::new(),
+      devices: BTreeMap::new(),
+    }
+  }
+}
+
+impl MetrifulPool {
+  /// Opens a device at the given address on a Linux i2c bus and adds it to
+  /// the pool, replacing any device previously added at that address. Returns
+  /// an error if the device does not become ready within the timeout or if its
+  /// status cannot be read.
+  pub fn open(
+    &mut self,
+    ready_pin: impl ReadyLine + 'static,
+    i2c_device: impl AsRef<Path>,
+    i2c_address: u16,
+    timeout: Option<Duration>,
+  ) -> Result<&mut Metriful<BusDevice<LinuxI2CDevice>>> {
+    trace!(
+      "MetrifulPool::open({:?}, {}, {:x}, {:?})",
+      ready_pin, i2c_device.as_ref().display(), i2c_address, timeout
+    );
+
+    let device = LinuxI2CDevice::new(i2c_device, i2c_address)?;
+    self.add(i2c_address, ready_pin, device, timeout)
+  }
+}
+
+impl<D: MetrifulTransport> MetrifulPool<D> {
+  /// Creates an empty pool with its own [`SharedBus`].
+  pub fn new() -> MetrifulPool<D> {
+    MetrifulPool::default()
+  }
+
+  /// Returns the bus lock shared by all devices in this pool.
+  pub fn bus(&self) -> &SharedBus {
+    &self.bus
+  }
+
+  /// Adds an already-opened transport for the device at `address`, replacing
+  /// any device previously added at that address. Ensures the device is ready
+  /// and fetches its status, as with [`Metriful::try_new_device_timeout()`].
+  pub fn add(
+    &mut self,
+    address: u16,
+    ready_pin: impl ReadyLine + 'static,
+    device: D,
+    timeout: Option<Duration>,
+  ) -> Result<&mut Metriful<BusDevice<D>>> {
+    let metriful = Metriful::try_new_device_timeout(ready_pin, self.bus.attach(device), timeout)?;
+
+    self.devices.insert(address, metriful);
+    Ok(self.devices.get_mut(&address).expect("device was just inserted"))
+  }
+
+  /// Returns the device at the given address.
+  pub fn get(&self, address: u16) -> Option<&Metriful<BusDevice<D>>> {
+    self.devices.get(&address)
+  }
+
+  /// Returns the device at the given address mutably, e.g. to start a read
+  /// iterator.
+  pub fn get_mut(&mut self, address: u16) -> Option<&mut Metriful<BusDevice<D>>> {
+    self.devices.get_mut(&address)
+  }
+
+  /// Removes and returns the device at the given address. The device remains
+  /// attached to the pool's bus lock.
+  pub fn remove(&mut self, address: u16) -> Option<Metriful<BusDevice<D>>> {
+    self.devices.remove(&address)
+  }
+
+  /// Returns the addresses of all devices in the pool, in ascending order.
+  pub fn addresses(&self) -> Vec<u16> {
+    self.devices.keys().copied().collect()
+  }
+
+  /// Returns the number of devices in the pool.
+  pub fn len(&self) -> usize {
+    self.devices.len()
+  }
+
+  /// Returns true if the pool contains no devices.
+  pub fn is_empty(&self) -> bool {
+    self.devices.is_empty()
+  }
+
+  /// Returns the last known status of each device, as of its most recent
+  /// status read.
+  pub fn statuses(&self) -> Vec<(u16, Option<&DeviceStatus>)> {
+    self.devices.iter()
+      .map(|(address, metriful)| (*address, metriful.status()))
+      .collect()
+  }
+
+  /// Refreshes and returns the status of every device. Stops at the first
+  /// error.
+  pub fn read_statuses(&mut self) -> Result<Vec<(u16, DeviceStatus)>> {
+    self.devices.iter_mut()
+      .map(|(address, metriful)| Ok((*address, metriful.read_status()?)))
+      .collect()
+  }
+
+  /// Iterates over all devices mutably, in address order.
+  pub fn iter_mut(&mut self) -> btree_map::IterMut<'_, u16, Metriful<BusDevice<D>>> {
+    self.devices.iter_mut()
+  }
+}
+
+impl<D: MetrifulTransport> IntoIterator for MetrifulPool<D> {
+  type Item = (u16, Metriful<BusDevice<D>>);
+  type IntoIter = btree_map::IntoIter<u16, Metriful<BusDevice<D>>>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.devices.into_iter()
+  }
+}
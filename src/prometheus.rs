@@ -0,0 +1,264 @@
+//! Prometheus text exposition encoding for sensor readings.
+//!
+//! This converts a [`CombinedData`] reading into the Prometheus text format
+//! served by `metriful-exporter`, so applications embedding the library in
+//! their own HTTP services can publish identical metrics without duplicating
+//! the name mapping. Metric names are taken from each metric's
+//! [`MetricInfo::prometheus_name`](crate::metric::MetricInfo::prometheus_name).
+//!
+//! # Example
+//! ```no_run
+//! use metriful::{Metriful, metric::*, prometheus::PrometheusEncoder};
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+//! let reading = metriful.read(METRIC_COMBINED_ALL)?;
+//!
+//! let mut encoder = PrometheusEncoder::new();
+//! encoder.reading(Some(&reading));
+//! encoder.gauge("my_app_uptime_seconds", 123u32, &[]);
+//!
+//! print!("{}", encoder.finish());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::{self, Write};
+
+use crate::counters::DeviceStats;
+use crate::metric::*;
+use crate::unit::*;
+
+/// Returns the Prometheus name of a metric per its metadata, if it has one.
+fn prometheus_name<U: MetrifulUnit>(metric: &Metric<U>) -> Option<&'static str> {
+  metric.metadata().and_then(|info| info.prometheus_name)
+}
+
+/// Escapes a label value per the Prometheus text format.
+fn escape_label_value(value: &str) -> String {
+  let mut ret = String::with_capacity(value.len());
+  for c in value.chars() {
+    match c {
+      '\\' => ret.push_str("\\\\"),
+      '"' => ret.push_str("\\\""),
+      '\n' => ret.push_str("\\n"),
+      c => ret.push(c),
+    }
+  }
+
+  ret
+}
+
+/// Incrementally builds a Prometheus text exposition document.
+///
+/// All metrics are written as untyped samples, one per line, in the order they
+/// are added.
+#[derive(Debug, Default, Clone)]
+pub struct PrometheusEncoder {
+  buf: String,
+}
+
+impl PrometheusEncoder {
+  pub fn new() -> PrometheusEncoder {
+    PrometheusEncoder::default()
+  }
+
+  /// Writes a single sample with the given name, value, and labels.
+  pub fn gauge(
+    &mut self,
+    name: &str,
+    value: impl Into<f64>,
+    labels: &[(&str, &str)],
+  ) -> &mut Self {
+    self.buf.push_str(name);
+
+    if !labels.is_empty() {
+      self.buf.push('{');
+      for (i, (key, value)) in labels.iter().enumerate() {
+        if i > 0 {
+          self.buf.push(',');
+        }
+
+        write!(self.buf, "{}=\"{}\"", key, escape_label_value(value)).ok();
+      }
+      self.buf.push('}');
+    }
+
+    writeln!(self.buf, " {}", value.into()).ok();
+    self
+  }
+
+  /// Writes a sample under a metric's Prometheus name, skipping metrics
+  /// without one.
+  fn metric_gauge<U: MetrifulUnit>(
+    &mut self,
+    metric: &Metric<U>,
+    value: impl Into<f64>,
+    labels: &[(&str, &str)],
+  ) -> &mut Self {
+    if let Some(name) = prometheus_name(metric) {
+      self.gauge(name, value, labels);
+    }
+
+    self
+  }
+
+  /// Writes a metric's `UnitValue` under the metric's Prometheus name, with a
+  /// `unit` label taken from its unit name.
+  fn unit_value<U>(&mut self, metric: &Metric<U>, value: &UnitValue<U>) -> &mut Self
+  where
+    U: MetrifulUnit,
+    U::Output: Copy + Into<f64>,
+  {
+    self.metric_gauge(metric, value.value, &[("unit", value.unit.get_name())])
+  }
+
+  fn air(&mut self, air: &CombinedAirData) {
+    self.unit_value(&METRIC_GAS_RESISTANCE, &air.gas_sensor_resistance);
+    self.unit_value(&METRIC_RELATIVE_HUMIDITY, &air.humidity);
+    self.unit_value(&METRIC_PRESSURE, &air.pressure);
+    self.unit_value(&METRIC_TEMPERATURE, &air.temperature);
+  }
+
+  fn air_quality(&mut self, air_quality: &CombinedAirQualityData) {
+    self.unit_value(&METRIC_AQI, &air_quality.aqi);
+    self.metric_gauge(
+      &METRIC_AQI_ACCURACY, air_quality.aqi_accuracy.value.to_uint(),
+      &[("unit", air_quality.aqi_accuracy.unit.get_name())]
+    );
+    self.unit_value(&METRIC_EST_CO2, &air_quality.estimated_co2);
+    self.unit_value(&METRIC_VOC, &air_quality.estimated_voc);
+  }
+
+  fn light(&mut self, light: &CombinedLightData) {
+    self.unit_value(&METRIC_ILLUMINANCE, &light.illuminance);
+    self.unit_value(&METRIC_WHITE_LIGHT_LEVEL, &light.white_level);
+  }
+
+  fn sound(&mut self, sound: &CombinedSoundData) {
+    self.metric_gauge(
+      &METRIC_SOUND_MEASUREMENT_STABILITY,
+      sound.measurement_stability.value.to_uint(),
+      &[("unit", sound.measurement_stability.unit.get_name())]
+    );
+    self.unit_value(&METRIC_PEAK_SOUND_AMPLITUDE, &sound.peak_amplitude);
+    self.unit_value(&METRIC_WEIGHTED_SOUND_LEVEL, &sound.weighted_spl);
+
+    for (i, (info, value)) in sound.spl_bands.value.iter_bands().enumerate() {
+      self.metric_gauge(&METRIC_SOUND_LEVEL, value, &[
+        ("unit", "decibels"),
+        ("band", &(i + 1).to_string()),
+        ("band_midpoint_hz", &info.midpoint_hz.to_string()),
+        ("band_lower_hz", &info.lower_hz.to_string()),
+        ("band_upper_hz", &info.upper_hz.to_string()),
+      ]);
+    }
+  }
+
+  /// Writes all metrics contained in the given combined reading.
+  ///
+  /// Particle data is not currently included as it is only meaningful when a
+  /// particle sensor has been configured.
+  pub fn combined_data(&mut self, data: &CombinedData) -> &mut Self {
+    self.air(&data.air.value);
+    self.air_quality(&data.air_quality.value);
+    self.light(&data.light.value);
+    self.sound(&data.sound.value);
+
+    self
+  }
+
+  /// Writes the metrics of every group that was read successfully, as with
+  /// [`PrometheusEncoder::combined_data()`], followed by
+  /// `metriful_read_error{group="..."}` set to 1 for each group that failed
+  /// and 0 otherwise. As particle metrics are not exported, neither is a
+  /// particle read error.
+  pub fn partial_data(&mut self, data: &PartialCombinedData) -> &mut Self {
+    if let Ok(air) = &data.air {
+      self.air(&air.value);
+    }
+
+    if let Ok(air_quality) = &data.air_quality {
+      self.air_quality(&air_quality.value);
+    }
+
+    if let Ok(light) = &data.light {
+      self.light(&light.value);
+    }
+
+    if let Ok(sound) = &data.sound {
+      self.sound(&sound.value);
+    }
+
+    let errors = data.errors();
+    for group in &["air", "air_quality", "light", "sound"] {
+      let failed = errors.iter().any(|(name, _)| name == group);
+      self.gauge("metriful_read_error", failed as u8, &[("group", group)]);
+    }
+
+    self
+  }
+
+  /// Writes `metriful_ready` and, if a reading is available, all of its
+  /// metrics per [`PrometheusEncoder::combined_data()`].
+  pub fn reading(&mut self, reading: Option<&UnitValue<UnitCombinedData>>) -> &mut Self {
+    match reading {
+      Some(r) => {
+        self.gauge("metriful_ready", 1u8, &[]);
+        self.combined_data(&r.value)
+      },
+      None => self.gauge("metriful_ready", 0u8, &[])
+    }
+  }
+
+  /// Writes `metriful_ready` and, if a reading is available, all of its
+  /// metrics per [`PrometheusEncoder::partial_data()`].
+  pub fn partial_reading(
+    &mut self,
+    reading: Option<&UnitValue<UnitPartialCombinedData>>,
+  ) -> &mut Self {
+    match reading {
+      Some(r) => {
+        self.gauge("metriful_ready", 1u8, &[]);
+        self.partial_data(&r.value)
+      },
+      None => self.gauge("metriful_ready", 0u8, &[])
+    }
+  }
+
+  /// Writes a device's health counters as `metriful_device_*` samples; see
+  /// [`Metriful::stats()`](crate::Metriful::stats).
+  pub fn device_stats(&mut self, stats: &DeviceStats) -> &mut Self {
+    self.gauge("metriful_device_read_count", stats.reads as f64, &[]);
+    self.gauge("metriful_device_error_count", stats.errors as f64, &[]);
+    self.gauge("metriful_device_retry_count", stats.retries as f64, &[]);
+    self.gauge("metriful_device_missed_cycle_count", stats.missed_cycles as f64, &[]);
+    self.gauge("metriful_device_ready_timeout_count", stats.ready_timeouts as f64, &[]);
+    self.gauge("metriful_device_ready_wait_seconds_max", stats.ready_wait_max.as_secs_f64(), &[]);
+
+    if let Some(mean) = stats.ready_wait_mean() {
+      self.gauge("metriful_device_ready_wait_seconds_mean", mean.as_secs_f64(), &[]);
+    }
+
+    self
+  }
+
+  /// Returns the encoded text.
+  pub fn finish(self) -> String {
+    self.buf
+  }
+}
+
+impl fmt::Display for PrometheusEncoder {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.buf)
+  }
+}
+
+/// Encodes a combined reading as Prometheus text, including the
+/// `metriful_ready` indicator.
+pub fn encode_reading(reading: Option<&UnitValue<UnitCombinedData>>) -> String {
+  let mut encoder = PrometheusEncoder::new();
+  encoder.reading(reading);
+  encoder.finish()
+}
@@ -0,0 +1,576 @@
+//! Sources for the MS430's READY signal.
+
+use std::fmt;
+use std::io;
+#[cfg(feature = "cdev")] use std::os::unix::io::AsRawFd;
+#[cfg(feature = "cdev")] use std::path::{Path, PathBuf};
+use std::str::FromStr;
+#[cfg(feature = "cdev")] use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "cdev")] use gpio_cdev::{Chip, EventRequestFlags, EventType, LineEventHandle, LineRequestFlags};
+use log::trace;
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+use sysfs_gpio::{Direction, Edge, Pin};
+
+use crate::READY_POLL_INTERVAL;
+use crate::error::*;
+
+/// Upper bound on a single wait for a READY edge event. The line is re-checked
+/// at least this often in case an edge is missed.
+pub const EDGE_WAIT_FALLBACK: Duration = Duration::from_secs(1);
+
+/// A digital input connected to the sensor's READY output.
+pub trait ReadyLine: fmt::Debug + Send {
+  /// Returns true if the sensor is currently asserting READY.
+  fn is_ready(&self) -> Result<bool>;
+
+  /// Blocks until [`ReadyLine::is_ready()`] returns `ready`, or until the
+  /// timeout (if any) elapses. Returns false if the timeout was exceeded.
+  ///
+  /// The default implementation polls every [`READY_POLL_INTERVAL`]
+  /// milliseconds; implementations with access to edge events should
+  /// override this to wake only when the line changes, and override
+  /// [`ReadyLine::supports_edges()`] to say so.
+  fn wait_for(&self, ready: bool, timeout: Option<Duration>) -> Result<bool> {
+    poll_for(self, ready, timeout)
+  }
+
+  /// Returns true if [`ReadyLine::wait_for()`] is driven by edge events.
+  /// [`ReadyPolling`] polls lines that return false (the default) at its own
+  /// interval rather than calling `wait_for()`.
+  fn supports_edges(&self) -> bool {
+    false
+  }
+
+  /// Returns the number of times READY was asserted since the previous call,
+  /// if the line latches edge events, and resets the count. Edges observed
+  /// while waiting in [`ReadyLine::wait_for()`] may or may not be counted, so
+  /// callers should take (and discard) the count after each wait.
+  ///
+  /// This lets readers detect cycles that completed while they were busy,
+  /// even if READY was only briefly deasserted in between. The default
+  /// implementation returns `Ok(None)`, i.e. edges are not latched.
+  fn take_ready_edges(&self) -> Result<Option<u32>> {
+    Ok(None)
+  }
+
+  /// Releases any system resources held for the line, e.g. unexporting a
+  /// sysfs GPIO pin. The line should not be used afterward. The default
+  /// implementation does nothing.
+  fn release(&self) -> Result<()> {
+    Ok(())
+  }
+}
+
+/// Stands in for a READY line that has been released, e.g. while
+/// [`Metriful::recover()`](crate::Metriful::recover) reopens it. Every read
+/// fails.
+#[derive(Debug)]
+pub(crate) struct DisconnectedReadyLine;
+
+impl ReadyLine for DisconnectedReadyLine {
+  fn is_ready(&self) -> Result<bool> {
+    Err(io::Error::new(io::ErrorKind::NotConnected, "READY line is disconnected, see Metriful::recover()").into())
+  }
+}
+
+/// Waits for the line to report `ready` by polling every
+/// [`READY_POLL_INTERVAL`] milliseconds. This is the default
+/// [`ReadyLine::wait_for()`] implementation.
+pub fn poll_for<R>(line: &R, ready: bool, timeout: Option<Duration>) -> Result<bool>
+where
+  R: ReadyLine + ?Sized
+{
+  ReadyPolling::default().poll_for(line, ready, timeout)
+}
+
+/// How a wait on the READY line is performed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "kebab-case"))]
+pub enum ReadyStrategy {
+  /// Wait for edge events if the line supports them (see
+  /// [`ReadyLine::supports_edges()`]), and poll otherwise
+  #[default]
+  Auto,
+
+  /// Always poll, even if edge events are available
+  Poll,
+}
+
+/// Configures how a [`Metriful`](crate::Metriful) waits for its READY line;
+/// see [`Metriful::set_ready_polling()`](crate::Metriful::set_ready_polling).
+///
+/// Longer poll intervals use less CPU but add latency to every wait. Note
+/// that in cycle mode READY is only deasserted for around half a second per
+/// measurement, so intervals approaching that may miss cycles.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use metriful::ready::{ReadyPolling, ReadyStrategy};
+///
+/// // start at 10ms, backing off to 100ms while the line doesn't change
+/// let polling = ReadyPolling {
+///   strategy: ReadyStrategy::Poll,
+///   max_interval: Some(Duration::from_millis(100)),
+///   ..ReadyPolling::default()
+/// };
+///
+/// assert_eq!(polling.interval_after(0), Duration::from_millis(10));
+/// assert_eq!(polling.interval_after(2), Duration::from_millis(40));
+/// assert_eq!(polling.interval_after(10), Duration::from_millis(100));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ReadyPolling {
+  /// Whether edge events are used where available. Defaults to
+  /// [`ReadyStrategy::Auto`].
+  pub strategy: ReadyStrategy,
+
+  /// Delay between polls. Defaults to [`READY_POLL_INTERVAL`] milliseconds.
+  pub interval: Duration,
+
+  /// If set, the delay doubles after each poll, up to this maximum. Defaults
+  /// to None, i.e. a constant interval.
+  pub max_interval: Option<Duration>,
+}
+
+impl Default for ReadyPolling {
+  fn default() -> Self {
+    ReadyPolling {
+      strategy: ReadyStrategy::Auto,
+      interval: Duration::from_millis(READY_POLL_INTERVAL),
+      max_interval: None,
+    }
+  }
+}
+
+impl ReadyPolling {
+  /// Returns the delay after the given number of unsuccessful polls, counting
+  /// from 0.
+  pub fn interval_after(&self, polls: u32) -> Duration {
+    match self.max_interval {
+      Some(max) => self.interval
+        .saturating_mul(2u32.saturating_pow(polls))
+        .min(max.max(self.interval)),
+      None => self.interval,
+    }
+  }
+
+  /// Waits for the line to report `ready` per this configuration, or until
+  /// the timeout (if any) elapses. Returns false if the timeout was exceeded.
+  pub fn wait_for<R>(&self, line: &R, ready: bool, timeout: Option<Duration>) -> Result<bool>
+  where
+    R: ReadyLine + ?Sized
+  {
+    match self.strategy {
+      ReadyStrategy::Auto if line.supports_edges() => line.wait_for(ready, timeout),
+      _ => self.poll_for(line, ready, timeout),
+    }
+  }
+
+  /// Waits for the line to report `ready` by polling, regardless of the
+  /// configured strategy.
+  pub fn poll_for<R>(&self, line: &R, ready: bool, timeout: Option<Duration>) -> Result<bool>
+  where
+    R: ReadyLine + ?Sized
+  {
+    let deadline = timeout.map(|t| Instant::now() + t);
+    let mut polls = 0;
+
+    loop {
+      if line.is_ready()? == ready {
+        return Ok(true);
+      }
+
+      match next_wait(deadline, self.interval_after(polls)) {
+        Some(wait) => thread::sleep(wait),
+        None => return Ok(false),
+      }
+
+      polls = polls.saturating_add(1);
+    }
+  }
+}
+
+/// Returns how long to wait before re-checking the line, at most `max`, or
+/// None if the deadline has passed.
+fn next_wait(deadline: Option<Instant>, max: Duration) -> Option<Duration> {
+  match deadline {
+    Some(deadline) => {
+      let now = Instant::now();
+      if now >= deadline {
+        None
+      } else {
+        Some((deadline - now).min(max))
+      }
+    },
+    None => Some(max),
+  }
+}
+
+/// Implements [`ReadyLine::wait_for()`] given a function that blocks until an
+/// edge occurs or the given duration elapses.
+fn wait_for_edges<R, F>(
+  line: &R,
+  ready: bool,
+  timeout: Option<Duration>,
+  mut wait_edge: F,
+) -> Result<bool>
+where
+  R: ReadyLine + ?Sized,
+  F: FnMut(Duration) -> Result<()>,
+{
+  let deadline = timeout.map(|t| Instant::now() + t);
+
+  loop {
+    if line.is_ready()? == ready {
+      return Ok(true);
+    }
+
+    match next_wait(deadline, EDGE_WAIT_FALLBACK) {
+      Some(wait) => wait_edge(wait)?,
+      None => return Ok(false),
+    }
+  }
+}
+
+/// A sysfs GPIO pin; READY is asserted when the pin is low.
+impl ReadyLine for Pin {
+  fn is_ready(&self) -> Result<bool> {
+    Ok(self.get_value()? == 0)
+  }
+
+  fn release(&self) -> Result<()> {
+    Ok(self.unexport()?)
+  }
+}
+
+/// The logic level at which READY is asserted, as seen by the host.
+///
+/// The MS430 drives READY low when asserted, but some level shifters invert
+/// the line.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "kebab-case"))]
+pub enum ReadyPolarity {
+  /// READY is asserted when the line is low (the MS430's native polarity)
+  #[default]
+  ActiveLow,
+
+  /// READY is asserted when the line is high, e.g. via an inverting level
+  /// shifter
+  ActiveHigh,
+}
+
+impl FromStr for ReadyPolarity {
+  type Err = MetrifulError;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s.to_ascii_lowercase().as_str() {
+      "low" | "active-low" => Ok(ReadyPolarity::ActiveLow),
+      "high" | "active-high" => Ok(ReadyPolarity::ActiveHigh),
+      other => Err(MetrifulError::InvalidReadyPolarity(other.to_string()))
+    }
+  }
+}
+
+impl ReadyPolarity {
+  /// Returns true if the given raw line value indicates READY.
+  pub fn is_asserted(self, value: u8) -> bool {
+    match self {
+      ReadyPolarity::ActiveLow => value == 0,
+      ReadyPolarity::ActiveHigh => value != 0,
+    }
+  }
+}
+
+/// A sysfs GPIO pin with configurable [`ReadyPolarity`].
+///
+/// If the pin supports interrupts, waits are driven by edge events rather than
+/// polling.
+///
+/// Note that the sysfs GPIO interface is deprecated and unavailable on newer
+/// kernels; prefer `CdevReadyLine` (with the `cdev` feature) where possible.
+#[derive(Debug)]
+pub struct SysfsReadyLine {
+  pin: Pin,
+  polarity: ReadyPolarity,
+  edges: bool,
+}
+
+impl SysfsReadyLine {
+  /// Exports the given GPIO number and configures it as an input.
+  pub fn new(gpio: u64, polarity: ReadyPolarity) -> Result<SysfsReadyLine> {
+    let pin = Pin::new(gpio);
+    pin.export()?;
+    pin.set_active_low(false)?;
+    pin.set_direction(Direction::In)?;
+
+    Ok(SysfsReadyLine::from_pin(pin, polarity))
+  }
+
+  /// Wraps an already-configured pin, enabling edge events if possible.
+  pub fn from_pin(pin: Pin, polarity: ReadyPolarity) -> SysfsReadyLine {
+    let edges = match pin.set_edge(Edge::BothEdges) {
+      Ok(()) => true,
+      Err(e) => {
+        trace!("SysfsReadyLine::from_pin(): edge events unavailable, polling: {}", e);
+        false
+      }
+    };
+
+    SysfsReadyLine { pin, polarity, edges }
+  }
+
+  /// Returns the underlying pin.
+  pub fn pin(&self) -> &Pin {
+    &self.pin
+  }
+
+  /// Returns the configured polarity.
+  pub fn polarity(&self) -> ReadyPolarity {
+    self.polarity
+  }
+}
+
+impl ReadyLine for SysfsReadyLine {
+  fn is_ready(&self) -> Result<bool> {
+    Ok(self.polarity.is_asserted(self.pin.get_value()?))
+  }
+
+  fn wait_for(&self, ready: bool, timeout: Option<Duration>) -> Result<bool> {
+    if !self.edges {
+      return poll_for(self, ready, timeout);
+    }
+
+    let mut poller = self.pin.get_poller()?;
+    wait_for_edges(self, ready, timeout, |wait| {
+      poller.poll(wait.as_millis() as isize)?;
+      Ok(())
+    })
+  }
+
+  fn supports_edges(&self) -> bool {
+    self.edges
+  }
+
+  fn release(&self) -> Result<()> {
+    trace!("SysfsReadyLine::release(): unexporting {}", self.pin.get_pin_num());
+    Ok(self.pin.unexport()?)
+  }
+}
+
+/// Consumer label reported to the kernel for requested GPIO lines.
+#[cfg(feature = "cdev")]
+pub const GPIO_CONSUMER: &str = "metriful";
+
+/// Resolves a GPIO chip name (e.g. `gpiochip0`) to its device path; paths are
+/// returned unchanged.
+#[cfg(feature = "cdev")]
+pub fn gpiochip_path(chip: impl AsRef<Path>) -> PathBuf {
+  let chip = chip.as_ref();
+  if chip.components().count() > 1 {
+    chip.to_path_buf()
+  } else {
+    Path::new("/dev").join(chip)
+  }
+}
+
+#[cfg(feature = "cdev")]
+#[derive(Debug)]
+enum CdevHandle {
+  /// A line requested with edge events
+  Events(Mutex<LineEventHandle>),
+
+  /// A line without edge event support, which must be polled
+  Plain(gpio_cdev::LineHandle),
+}
+
+/// A GPIO line requested via the Linux GPIO character device interface, with
+/// configurable [`ReadyPolarity`].
+///
+/// Unlike the sysfs interface, this remains available on current kernels. If
+/// the line supports interrupts, waits are driven by edge events rather than
+/// polling.
+#[cfg(feature = "cdev")]
+#[derive(Debug)]
+pub struct CdevReadyLine {
+  handle: CdevHandle,
+  polarity: ReadyPolarity,
+}
+
+#[cfg(feature = "cdev")]
+impl CdevReadyLine {
+  /// Requests the given line offset on a GPIO chip as an input. The chip may
+  /// be given by name (e.g. `gpiochip0`) or path (e.g. `/dev/gpiochip0`).
+  pub fn new(
+    chip: impl AsRef<Path>,
+    line: u32,
+    polarity: ReadyPolarity,
+  ) -> Result<CdevReadyLine> {
+    let mut chip = Chip::new(gpiochip_path(chip))?;
+    let line = chip.get_line(line)?;
+
+    let handle = match line.events(
+      LineRequestFlags::INPUT, EventRequestFlags::BOTH_EDGES, GPIO_CONSUMER
+    ) {
+      Ok(events) => CdevHandle::Events(Mutex::new(events)),
+      Err(e) => {
+        trace!("CdevReadyLine::new(): edge events unavailable, polling: {}", e);
+        CdevHandle::Plain(line.request(LineRequestFlags::INPUT, 0, GPIO_CONSUMER)?)
+      }
+    };
+
+    Ok(CdevReadyLine { handle, polarity })
+  }
+
+  /// Returns true if waits are driven by edge events.
+  pub fn has_edge_events(&self) -> bool {
+    matches!(self.handle, CdevHandle::Events(_))
+  }
+
+  /// Returns the configured polarity.
+  pub fn polarity(&self) -> ReadyPolarity {
+    self.polarity
+  }
+}
+
+/// Blocks until an event is available on the handle or `wait` elapses, then
+/// consumes it.
+#[cfg(feature = "cdev")]
+fn wait_cdev_event(handle: &Mutex<LineEventHandle>, wait: Duration) -> Result<()> {
+  let mut handle = handle.lock().unwrap_or_else(|e| e.into_inner());
+  let mut fds = libc::pollfd {
+    fd: handle.as_raw_fd(),
+    events: libc::POLLIN,
+    revents: 0,
+  };
+
+  let timeout_ms = wait.as_millis().min(libc::c_int::MAX as u128) as libc::c_int;
+
+  // safety: fds is a single valid pollfd for the duration of the call
+  let ret = unsafe { libc::poll(&mut fds, 1, timeout_ms) };
+  if ret < 0 {
+    let err = std::io::Error::last_os_error();
+    if err.kind() == std::io::ErrorKind::Interrupted {
+      return Ok(());
+    }
+
+    return Err(err.into());
+  }
+
+  if ret > 0 {
+    handle.get_event()?;
+  }
+
+  Ok(())
+}
+
+#[cfg(feature = "cdev")]
+impl ReadyLine for CdevReadyLine {
+  fn is_ready(&self) -> Result<bool> {
+    let value = match &self.handle {
+      CdevHandle::Events(events) => events.lock().unwrap_or_else(|e| e.into_inner()).get_value()?,
+      CdevHandle::Plain(handle) => handle.get_value()?,
+    };
+
+    Ok(self.polarity.is_asserted(value))
+  }
+
+  fn wait_for(&self, ready: bool, timeout: Option<Duration>) -> Result<bool> {
+    match &self.handle {
+      CdevHandle::Events(events) => wait_for_edges(self, ready, timeout, |wait| {
+        wait_cdev_event(events, wait)
+      }),
+      CdevHandle::Plain(_) => poll_for(self, ready, timeout),
+    }
+  }
+
+  fn supports_edges(&self) -> bool {
+    self.has_edge_events()
+  }
+
+  /// Drains edge events queued by the kernel since the last wait, counting
+  /// those that assert READY.
+  fn take_ready_edges(&self) -> Result<Option<u32>> {
+    let events = match &self.handle {
+      CdevHandle::Events(events) => events,
+      CdevHandle::Plain(_) => return Ok(None),
+    };
+
+    let asserting = match self.polarity {
+      ReadyPolarity::ActiveLow => EventType::FallingEdge,
+      ReadyPolarity::ActiveHigh => EventType::RisingEdge,
+    };
+
+    let mut handle = events.lock().unwrap_or_else(|e| e.into_inner());
+    let mut edges = 0;
+    loop {
+      let mut fds = libc::pollfd {
+        fd: handle.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+      };
+
+      // safety: fds is a single valid pollfd for the duration of the call
+      if unsafe { libc::poll(&mut fds, 1, 0) } <= 0 {
+        break;
+      }
+
+      if handle.get_event()?.event_type() == asserting {
+        edges += 1;
+      }
+    }
+
+    Ok(Some(edges))
+  }
+}
+
+/// Inverts another [`ReadyLine`], e.g. to adapt a custom provider to an
+/// inverting level shifter.
+#[derive(Debug)]
+pub struct Inverted<R>(pub R);
+
+impl<R: ReadyLine> ReadyLine for Inverted<R> {
+  fn is_ready(&self) -> Result<bool> {
+    self.0.is_ready().map(|ready| !ready)
+  }
+
+  fn wait_for(&self, ready: bool, timeout: Option<Duration>) -> Result<bool> {
+    self.0.wait_for(!ready, timeout)
+  }
+
+  fn supports_edges(&self) -> bool {
+    self.0.supports_edges()
+  }
+
+  // the inner line's latched edges deassert READY, so none are reported
+
+  fn release(&self) -> Result<()> {
+    self.0.release()
+  }
+}
+
+impl ReadyLine for Box<dyn ReadyLine> {
+  fn is_ready(&self) -> Result<bool> {
+    (**self).is_ready()
+  }
+
+  fn wait_for(&self, ready: bool, timeout: Option<Duration>) -> Result<bool> {
+    (**self).wait_for(ready, timeout)
+  }
+
+  fn supports_edges(&self) -> bool {
+    (**self).supports_edges()
+  }
+
+  fn take_ready_edges(&self) -> Result<Option<u32>> {
+    (**self).take_ready_edges()
+  }
+
+  fn release(&self) -> Result<()> {
+    (**self).release()
+  }
+}
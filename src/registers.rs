@@ -0,0 +1,321 @@
+//! The MS430 register map, per the datasheet.
+//!
+//! Most registers are already modeled by the [`metric`](crate::metric) and
+//! [`status`](crate::status) modules, or by functions on [`Metriful`]. For
+//! anything not yet covered, [`Metriful::read_register()`] and
+//! [`Metriful::write_register()`] provide checked access by [`Register`]
+//! without resorting to raw i2c calls.
+//!
+//! # Example
+//! ```
+//! use metriful::registers::{Access, Register};
+//!
+//! let register = Register::LightInterruptThreshold;
+//! assert_eq!(register.address(), 0x82);
+//! assert_eq!(register.length(), 3);
+//! assert_eq!(register.access(), Access::ReadWrite);
+//!
+//! assert_eq!(Register::from_address(0xE2), Some(Register::Reset));
+//! assert!(Register::Reset.is_command());
+//! ```
+
+use std::fmt;
+
+#[cfg(feature = "serde")] use serde::Serialize;
+
+use crate::guard::CommandKind;
+
+#[cfg(doc)] use crate::Metriful;
+
+/// How a register may be accessed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "snake_case"))]
+pub enum Access {
+  /// Read-only data or status
+  Read,
+
+  /// Write-only; for commands, the register address itself is the command
+  Write,
+
+  /// Configurable settings
+  ReadWrite,
+}
+
+impl Access {
+  /// Returns true if the register may be read.
+  pub fn is_readable(&self) -> bool {
+    !matches!(self, Access::Write)
+  }
+
+  /// Returns true if the register may be written.
+  pub fn is_writable(&self) -> bool {
+    !matches!(self, Access::Read)
+  }
+}
+
+/// A register (or command) documented in the MS430 datasheet.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "snake_case"))]
+pub enum Register {
+  /// External particle sensor selection (settings)
+  ParticleSensorSelect,
+
+  /// Light interrupt enable (settings)
+  LightInterruptEnable,
+
+  /// Light interrupt threshold: u16 integer part, u8 fractional part (settings)
+  LightInterruptThreshold,
+
+  /// Light interrupt type, latch or comparator (settings)
+  LightInterruptType,
+
+  /// Light interrupt polarity (settings)
+  LightInterruptPolarity,
+
+  /// Sound interrupt enable (settings)
+  SoundInterruptEnable,
+
+  /// Sound interrupt threshold in mPa, u16 (settings)
+  SoundInterruptThreshold,
+
+  /// Sound interrupt type, latch or comparator (settings)
+  SoundInterruptType,
+
+  /// Cycle mode period (settings)
+  CycleTimePeriod,
+
+  /// Current operational mode (status)
+  OperationalMode,
+
+  /// Combined air data, 0x21-0x24
+  AirData,
+
+  /// Combined air quality data, 0x25-0x28
+  AirQualityData,
+
+  /// Combined light data, 0x31-0x32
+  LightData,
+
+  /// Combined sound data, 0x41-0x44
+  SoundData,
+
+  /// Combined particle data, 0x51-0x53
+  ParticleData,
+
+  Temperature,
+  Pressure,
+  Humidity,
+  GasSensorResistance,
+  AirQualityIndex,
+  EstimatedCO2,
+  EstimatedVOC,
+  AirQualityAccuracy,
+  Illuminance,
+  WhiteLightLevel,
+  WeightedSoundLevel,
+  SoundBands,
+  PeakSoundAmplitude,
+  SoundMeasurementStability,
+  ParticleDutyCycle,
+  ParticleConcentration,
+  ParticleDataValidity,
+
+  /// Command: start an on-demand measurement
+  OnDemandMeasure,
+
+  /// Command: reset the device
+  Reset,
+
+  /// Command: enter cycle mode
+  CycleMode,
+
+  /// Command: enter standby mode
+  StandbyMode,
+
+  /// Command: clear the light interrupt
+  LightInterruptClear,
+
+  /// Command: clear the sound interrupt
+  SoundInterruptClear,
+}
+
+impl Register {
+  /// All documented registers, in address order.
+  pub const ALL: &'static [Register] = &[
+    Register::ParticleSensorSelect,
+    Register::AirData,
+    Register::AirQualityData,
+    Register::LightData,
+    Register::SoundData,
+    Register::ParticleData,
+    Register::Temperature,
+    Register::Pressure,
+    Register::Humidity,
+    Register::GasSensorResistance,
+    Register::AirQualityIndex,
+    Register::EstimatedCO2,
+    Register::EstimatedVOC,
+    Register::AirQualityAccuracy,
+    Register::Illuminance,
+    Register::WhiteLightLevel,
+    Register::WeightedSoundLevel,
+    Register::SoundBands,
+    Register::PeakSoundAmplitude,
+    Register::SoundMeasurementStability,
+    Register::ParticleDutyCycle,
+    Register::ParticleConcentration,
+    Register::ParticleDataValidity,
+    Register::LightInterruptEnable,
+    Register::LightInterruptThreshold,
+    Register::LightInterruptType,
+    Register::LightInterruptPolarity,
+    Register::SoundInterruptEnable,
+    Register::SoundInterruptThreshold,
+    Register::SoundInterruptType,
+    Register::CycleTimePeriod,
+    Register::OperationalMode,
+    Register::OnDemandMeasure,
+    Register::Reset,
+    Register::CycleMode,
+    Register::StandbyMode,
+    Register::LightInterruptClear,
+    Register::SoundInterruptClear,
+  ];
+
+  /// Returns the register's i2c address (or command byte).
+  pub fn address(&self) -> u8 {
+    match self {
+      Register::ParticleSensorSelect => 0x07,
+      Register::AirData => 0x10,
+      Register::AirQualityData => 0x11,
+      Register::LightData => 0x12,
+      Register::SoundData => 0x13,
+      Register::ParticleData => 0x14,
+      Register::Temperature => 0x21,
+      Register::Pressure => 0x22,
+      Register::Humidity => 0x23,
+      Register::GasSensorResistance => 0x24,
+      Register::AirQualityIndex => 0x25,
+      Register::EstimatedCO2 => 0x26,
+      Register::EstimatedVOC => 0x27,
+      Register::AirQualityAccuracy => 0x28,
+      Register::Illuminance => 0x31,
+      Register::WhiteLightLevel => 0x32,
+      Register::WeightedSoundLevel => 0x41,
+      Register::SoundBands => 0x42,
+      Register::PeakSoundAmplitude => 0x43,
+      Register::SoundMeasurementStability => 0x44,
+      Register::ParticleDutyCycle => 0x51,
+      Register::ParticleConcentration => 0x52,
+      Register::ParticleDataValidity => 0x53,
+      Register::LightInterruptEnable => 0x81,
+      Register::LightInterruptThreshold => 0x82,
+      Register::LightInterruptType => 0x83,
+      Register::LightInterruptPolarity => 0x84,
+      Register::SoundInterruptEnable => 0x85,
+      Register::SoundInterruptThreshold => 0x86,
+      Register::SoundInterruptType => 0x87,
+      Register::CycleTimePeriod => 0x89,
+      Register::OperationalMode => 0x8A,
+      Register::OnDemandMeasure => 0xE1,
+      Register::Reset => 0xE2,
+      Register::CycleMode => 0xE4,
+      Register::StandbyMode => 0xE5,
+      Register::LightInterruptClear => 0xE6,
+      Register::SoundInterruptClear => 0xE7,
+    }
+  }
+
+  /// Returns the register's data length in bytes; 0 for commands.
+  pub fn length(&self) -> u8 {
+    match self {
+      Register::AirData => 12,
+      Register::AirQualityData => 10,
+      Register::LightData => 5,
+      Register::SoundData => 18,
+      Register::ParticleData => 6,
+      Register::Pressure | Register::GasSensorResistance => 4,
+      Register::AirQualityIndex | Register::EstimatedCO2 | Register::EstimatedVOC => 3,
+      Register::Illuminance | Register::PeakSoundAmplitude | Register::ParticleConcentration => 3,
+      Register::LightInterruptThreshold => 3,
+      Register::SoundBands => 12,
+      Register::Temperature | Register::Humidity | Register::WhiteLightLevel => 2,
+      Register::WeightedSoundLevel | Register::ParticleDutyCycle => 2,
+      Register::SoundInterruptThreshold => 2,
+      Register::OnDemandMeasure | Register::Reset | Register::CycleMode => 0,
+      Register::StandbyMode | Register::LightInterruptClear | Register::SoundInterruptClear => 0,
+      _ => 1,
+    }
+  }
+
+  /// Returns how the register may be accessed.
+  pub fn access(&self) -> Access {
+    match self.address() {
+      0x07 | 0x81..=0x89 => Access::ReadWrite,
+      0xE1..=0xE7 => Access::Write,
+      _ => Access::Read,
+    }
+  }
+
+  /// Returns true if this register is a command, i.e. a write with no data.
+  pub fn is_command(&self) -> bool {
+    self.length() == 0
+  }
+
+  /// Returns the register at the given address, if documented.
+  pub fn from_address(address: u8) -> Option<Register> {
+    Register::ALL.iter().copied().find(|r| r.address() == address)
+  }
+
+  /// The kind of command a write to this register counts as for rate limiting.
+  pub(crate) fn command_kind(&self) -> CommandKind {
+    match self {
+      Register::OnDemandMeasure => CommandKind::Measurement,
+      Register::Reset | Register::CycleMode | Register::StandbyMode => CommandKind::ModeChange,
+      _ => CommandKind::Other,
+    }
+  }
+}
+
+impl fmt::Display for Register {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:?} (0x{:02X})", self, self.address())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn addresses_round_trip() {
+    for register in Register::ALL {
+      assert_eq!(Register::from_address(register.address()), Some(*register));
+    }
+  }
+
+  #[test]
+  fn undocumented_addresses_are_rejected() {
+    for address in &[0x00, 0x08, 0x15, 0x29, 0x88, 0x8B, 0xE0, 0xE3, 0xE8, 0xFF] {
+      assert_eq!(Register::from_address(*address), None, "{:#x}", address);
+    }
+  }
+
+  #[test]
+  fn commands_are_write_only() {
+    for register in Register::ALL {
+      if register.is_command() {
+        assert_eq!(register.access(), Access::Write, "{}", register);
+      } else {
+        assert!(register.access().is_readable(), "{}", register);
+      }
+    }
+  }
+
+  #[test]
+  fn data_registers_are_read_only() {
+    assert_eq!(Register::Temperature.access(), Access::Read);
+    assert!(!Register::AirData.access().is_writable());
+    assert_eq!(Register::CycleTimePeriod.access(), Access::ReadWrite);
+  }
+}
@@ -0,0 +1,438 @@
+//! Retry and error handling policies for read iterators.
+//!
+//! By default, [`MetricReadIterator`] and [`CycleReadIterator`] return the
+//! first error they encounter and then terminate. For long-running logging,
+//! where an occasional transient I2C error is expected, a [`ReadPolicy`] can
+//! retry failed reads and keep the iterator alive past errors.
+//!
+//! # Example
+//! ```no_run
+//! use std::time::Duration;
+//! use metriful::{Metriful, CyclePeriod, metric::*, retry::{ReadPolicy, OnError}};
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+//!
+//! let policy = ReadPolicy {
+//!   max_retries: 3,
+//!   backoff: Duration::from_millis(100),
+//!   on_error: OnError::Continue,
+//! };
+//!
+//! let iter = metriful
+//!   .cycle_read_iter_timeout(METRIC_COMBINED_ALL, CyclePeriod::Period0, None)
+//!   .with_policy(policy);
+//!
+//! for reading in iter {
+//!   match reading {
+//!     Ok(reading) => println!("{}", reading),
+//!     Err(e) => eprintln!("read failed, continuing: {}", e),
+//!   }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Retries at this level repeat whole reads, including the wait for READY.
+//! Individual bus operations can instead be retried by wrapping the device in
+//! a [`RetryTransport`], which keeps a single transient error mid-read from
+//! reaching the iterator at all.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::warn;
+
+use crate::counters::Counters;
+use crate::error::*;
+use crate::transport::MetrifulTransport;
+
+#[cfg(doc)] use crate::{Metriful, MetricReadIterator, CycleReadIterator};
+
+/// Linux errno values treated as transient by default; see
+/// [`TRANSIENT_ERRNOS`].
+pub const DEFAULT_TRANSIENT_ERRNOS: &[i32] = TRANSIENT_ERRNOS;
+
+/// What an iterator does once a read has failed and retries are exhausted.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OnError {
+  /// Yield the error, then terminate.
+  Stop,
+
+  /// Yield the error, then continue reading.
+  Continue,
+}
+
+/// Controls how read iterators respond to failed reads.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ReadPolicy {
+  /// Number of times a failed read is retried before the error is yielded.
+  /// Defaults to 0.
+  pub max_retries: u32,
+
+  /// Delay before the first retry; each subsequent retry waits twice as long
+  /// as the previous one. Defaults to 100ms.
+  pub backoff: Duration,
+
+  /// Behavior once retries are exhausted. Defaults to [`OnError::Stop`].
+  pub on_error: OnError,
+}
+
+impl Default for ReadPolicy {
+  fn default() -> Self {
+    ReadPolicy {
+      max_retries: 0,
+      backoff: Duration::from_millis(100),
+      on_error: OnError::Stop,
+    }
+  }
+}
+
+impl ReadPolicy {
+  /// Returns the delay before the given retry, counting from 0.
+  ///
+  /// # Example
+  /// ```
+  /// use std::time::Duration;
+  /// use metriful::retry::ReadPolicy;
+  ///
+  /// let policy = ReadPolicy::default();
+  /// assert_eq!(policy.delay(0), Duration::from_millis(100));
+  /// assert_eq!(policy.delay(2), Duration::from_millis(400));
+  /// ```
+  pub fn delay(&self, retry: u32) -> Duration {
+    self.backoff.saturating_mul(2u32.saturating_pow(retry))
+  }
+
+  /// Runs `f`, retrying failures per this policy, and returns the first
+  /// success or the final error. Cancellation and invalid input (see
+  /// [`ErrorKind`]) are never retried. Retries are recorded in `counters`.
+  pub(crate) fn run<T>(&self, counters: &Counters, mut f: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut retry = 0;
+
+    loop {
+      match f() {
+        Ok(value) => return Ok(value),
+        Err(e) if retry < self.max_retries && !matches!(e.kind(), ErrorKind::Cancelled | ErrorKind::InvalidInput) => {
+          let delay = self.delay(retry);
+          warn!(
+            "read failed (attempt {} of {}), retrying in {:?}: {}",
+            retry + 1, self.max_retries + 1, delay, e
+          );
+
+          thread::sleep(delay);
+          counters.record_retry();
+          retry += 1;
+        },
+        Err(e) => return Err(e),
+      }
+    }
+  }
+}
+
+/// Controls how a [`RetryTransport`] retries failed bus operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BusRetryPolicy {
+  /// Total number of attempts per operation, including the first. Defaults
+  /// to 3.
+  pub max_attempts: u32,
+
+  /// Delay before the first retry; each subsequent retry waits twice as long
+  /// as the previous one. Defaults to 10ms.
+  pub backoff: Duration,
+
+  /// Upper bound on the delay between retries. Defaults to 1s.
+  pub max_backoff: Duration,
+
+  /// OS error numbers considered transient. Errors with other (or no) error
+  /// numbers are returned immediately. Defaults to
+  /// [`DEFAULT_TRANSIENT_ERRNOS`].
+  pub transient_errnos: Vec<i32>,
+}
+
+impl Default for BusRetryPolicy {
+  fn default() -> Self {
+    BusRetryPolicy {
+      max_attempts: 3,
+      backoff: Duration::from_millis(10),
+      max_backoff: Duration::from_secs(1),
+      transient_errnos: DEFAULT_TRANSIENT_ERRNOS.to_vec(),
+    }
+  }
+}
+
+impl BusRetryPolicy {
+  /// Returns the delay before the given retry, counting from 0.
+  ///
+  /// # Example
+  /// ```
+  /// use std::time::Duration;
+  /// use metriful::retry::BusRetryPolicy;
+  ///
+  /// let policy = BusRetryPolicy::default();
+  /// assert_eq!(policy.delay(0), Duration::from_millis(10));
+  /// assert_eq!(policy.delay(2), Duration::from_millis(40));
+  /// assert_eq!(policy.delay(20), Duration::from_secs(1));
+  /// ```
+  pub fn delay(&self, retry: u32) -> Duration {
+    self.backoff
+      .saturating_mul(2u32.saturating_pow(retry))
+      .min(self.max_backoff)
+  }
+
+  /// Returns true if errors with the given OS error number should be retried.
+  pub fn is_transient(&self, errno: i32) -> bool {
+    self.transient_errnos.contains(&errno)
+  }
+}
+
+/// A transport wrapper that retries bus operations failing with transient
+/// errors, per a [`BusRetryPolicy`].
+///
+/// Any transport may be wrapped, and the result passed to e.g.
+/// [`Metriful::try_new_device_timeout()`].
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "testing")] {
+/// use std::time::Duration;
+/// use metriful::{Metriful, metric::*};
+/// use metriful::retry::{BusRetryPolicy, RetryTransport};
+/// use metriful::testing::*;
+///
+/// let mock = MockDevice::new();
+/// mock.set_register(0x21, &[21, 5]);
+///
+/// // the first read of the temperature register times out
+/// let plan = FaultPlan::new().inject(FaultTrigger::Register(0x21), Fault::Timeout);
+/// let transport = RetryTransport::new(plan.wrap_device(mock.clone()), BusRetryPolicy::default());
+///
+/// let mut metriful = Metriful::try_new_device_timeout(
+///   mock.ready_line(),
+///   transport,
+///   Some(Duration::from_millis(100)),
+/// ).unwrap();
+///
+/// assert_eq!(metriful.read(METRIC_TEMPERATURE).unwrap().value, 21.5);
+/// assert_eq!(plan.injected(), vec![Fault::Timeout]);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RetryTransport<D> {
+  device: D,
+  policy: BusRetryPolicy,
+}
+
+impl<D> RetryTransport<D> where D: MetrifulTransport {
+  pub fn new(device: D, policy: BusRetryPolicy) -> RetryTransport<D> {
+    RetryTransport { device, policy }
+  }
+
+  /// Returns the retry policy in use.
+  pub fn policy(&self) -> &BusRetryPolicy {
+    &self.policy
+  }
+
+  /// Sets the retry policy.
+  pub fn set_policy(&mut self, policy: BusRetryPolicy) {
+    self.policy = policy;
+  }
+
+  /// Returns the wrapped transport.
+  pub fn into_inner(self) -> D {
+    self.device
+  }
+
+  /// Runs `f` against the wrapped transport, retrying transient failures,
+  /// and returns the first success or the final error.
+  fn run<T>(&mut self, op: &str, mut f: impl FnMut(&mut D) -> Result<T>) -> Result<T> {
+    let mut retry = 0;
+
+    loop {
+      let e = match f(&mut self.device) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+      };
+
+      let e = e.normalized();
+      let transient = e.raw_os_error().map(|errno| self.policy.is_transient(errno)).unwrap_or(false);
+      if !transient || retry + 1 >= self.policy.max_attempts {
+        return Err(e);
+      }
+
+      let delay = self.policy.delay(retry);
+      warn!(
+        "{} failed (attempt {} of {}), retrying in {:?}: {}",
+        op, retry + 1, self.policy.max_attempts, delay, e
+      );
+
+      thread::sleep(delay);
+      retry += 1;
+    }
+  }
+}
+
+impl<D> MetrifulTransport for RetryTransport<D> where D: MetrifulTransport {
+  fn write_byte(&mut self, command: u8) -> Result<()> {
+    self.run("write_byte", |d| d.write_byte(command))
+  }
+
+  fn read_byte(&mut self, register: u8) -> Result<u8> {
+    self.run("read_byte", |d| d.read_byte(register))
+  }
+
+  fn write_byte_data(&mut self, register: u8, value: u8) -> Result<()> {
+    self.run("write_byte_data", |d| d.write_byte_data(register, value))
+  }
+
+  fn read_block(&mut self, register: u8, len: u8) -> Result<Vec<u8>> {
+    self.run("read_block", |d| d.read_block(register, len))
+  }
+
+  fn write_block(&mut self, register: u8, values: &[u8]) -> Result<()> {
+    self.run("write_block", |d| d.write_block(register, values))
+  }
+
+  fn max_block_len(&self) -> usize {
+    self.device.max_block_len()
+  }
+
+  fn set_deadline(&mut self, deadline: Option<Instant>) {
+    self.device.set_deadline(deadline);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io;
+  use std::sync::{Arc, Mutex};
+
+  use super::*;
+
+  /// A transport whose reads fail with the queued errnos, then succeed.
+  #[derive(Debug, Default, Clone)]
+  struct Flaky {
+    errnos: Arc<Mutex<Vec<i32>>>,
+    attempts: Arc<Mutex<u32>>,
+  }
+
+  impl Flaky {
+    fn new(errnos: &[i32]) -> Flaky {
+      Flaky {
+        errnos: Arc::new(Mutex::new(errnos.iter().rev().copied().collect())),
+        attempts: Arc::default(),
+      }
+    }
+
+    fn attempts(&self) -> u32 {
+      *self.attempts.lock().unwrap()
+    }
+  }
+
+  impl MetrifulTransport for Flaky {
+    fn write_byte(&mut self, _command: u8) -> Result<()> { Ok(()) }
+    fn write_byte_data(&mut self, _register: u8, _value: u8) -> Result<()> { Ok(()) }
+    fn write_block(&mut self, _register: u8, _values: &[u8]) -> Result<()> { Ok(()) }
+    fn read_block(&mut self, _register: u8, len: u8) -> Result<Vec<u8>> { Ok(vec![0; len as usize]) }
+
+    fn read_byte(&mut self, _register: u8) -> Result<u8> {
+      *self.attempts.lock().unwrap() += 1;
+      match self.errnos.lock().unwrap().pop() {
+        Some(errno) => Err(MetrifulError::IOError(io::Error::from_raw_os_error(errno))),
+        None => Ok(42),
+      }
+    }
+  }
+
+  fn fast_policy() -> BusRetryPolicy {
+    BusRetryPolicy {
+      backoff: Duration::from_millis(1),
+      ..BusRetryPolicy::default()
+    }
+  }
+
+  #[test]
+  fn transient_errors_are_retried() {
+    let flaky = Flaky::new(&[121, 110]);
+    let mut transport = RetryTransport::new(flaky.clone(), fast_policy());
+
+    assert_eq!(transport.read_byte(0x8A).unwrap(), 42);
+    assert_eq!(flaky.attempts(), 3);
+  }
+
+  #[test]
+  fn attempts_are_limited() {
+    let flaky = Flaky::new(&[121, 121, 121, 121]);
+    let mut transport = RetryTransport::new(flaky.clone(), fast_policy());
+
+    let e = transport.read_byte(0x8A).unwrap_err();
+    assert_eq!(e.raw_os_error(), Some(121));
+    assert_eq!(flaky.attempts(), 3);
+  }
+
+  #[test]
+  fn other_errors_are_returned_immediately() {
+    // ENOENT, e.g. the device node went away
+    let flaky = Flaky::new(&[2]);
+    let mut transport = RetryTransport::new(flaky.clone(), fast_policy());
+
+    assert_eq!(transport.read_byte(0x8A).unwrap_err().raw_os_error(), Some(2));
+    assert_eq!(flaky.attempts(), 1);
+  }
+
+  #[test]
+  fn read_policy_retries_then_gives_up() {
+    let counters = Counters::default();
+    let policy = ReadPolicy {
+      max_retries: 2,
+      backoff: Duration::from_millis(1),
+      on_error: OnError::Stop,
+    };
+
+    let mut calls = 0;
+    let res: Result<()> = policy.run(&counters, || {
+      calls += 1;
+      Err(MetrifulError::NotReady)
+    });
+
+    assert!(matches!(res, Err(MetrifulError::NotReady)));
+    assert_eq!(calls, 3);
+    assert_eq!(counters.snapshot().retries, 2);
+  }
+
+  #[test]
+  fn read_policy_never_retries_cancellation_or_invalid_input() {
+    let counters = Counters::default();
+    let policy = ReadPolicy {
+      max_retries: 5,
+      backoff: Duration::from_millis(1),
+      on_error: OnError::Stop,
+    };
+
+    for error in [MetrifulError::Cancelled, MetrifulError::InvalidMetricName("nope".into())] {
+      let mut error = Some(error);
+      let mut calls = 0;
+      let res: Result<()> = policy.run(&counters, || {
+        calls += 1;
+        Err(error.take().unwrap())
+      });
+
+      assert!(res.is_err());
+      assert_eq!(calls, 1);
+    }
+
+    assert_eq!(counters.snapshot().retries, 0);
+  }
+
+  #[test]
+  fn bus_delay_is_capped() {
+    let policy = BusRetryPolicy {
+      max_backoff: Duration::from_millis(50),
+      ..BusRetryPolicy::default()
+    };
+
+    assert_eq!(policy.delay(2), Duration::from_millis(40));
+    assert_eq!(policy.delay(3), Duration::from_millis(50));
+    assert_eq!(policy.delay(u32::MAX), Duration::from_millis(50));
+  }
+}
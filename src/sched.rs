@@ -0,0 +1,103 @@
+//! Scheduling hints for background read threads.
+//!
+//! In 3 second cycle mode, a reader has little time to notice each
+//! measurement before the next one begins. On a busy host (e.g. one that is
+//! also transcoding or compiling), background threads such as those spawned
+//! by [`Metriful::async_cycle_read_timeout()`] and
+//! [`Metriful::event_stream()`] may not be scheduled in time and miss cycles.
+//! A [`ThreadScheduling`] hint set via [`Metriful::set_thread_scheduling()`]
+//! raises the priority of these threads when they start.
+//!
+//! Applying hints requires the `sched` feature and Linux. Hints that can't be
+//! applied, e.g. for lack of privileges, are logged and otherwise ignored.
+//!
+//! # Example
+//! ```no_run
+//! use std::time::Duration;
+//! use metriful::{Metriful, CyclePeriod, metric::*, sched::ThreadScheduling};
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+//! metriful.set_thread_scheduling(Some(ThreadScheduling::Nice(-10)));
+//!
+//! let (_cmd_tx, rx, _handle) = metriful.async_cycle_read_timeout(
+//!   METRIC_COMBINED_ALL,
+//!   CyclePeriod::Period0,
+//!   Some(Duration::from_secs(5)),
+//! );
+//!
+//! for reading in rx {
+//!   println!("{}", reading?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use log::{trace, warn};
+
+use crate::error::*;
+
+#[cfg(doc)] use crate::Metriful;
+
+/// A scheduling priority for a thread.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ThreadScheduling {
+  /// Sets the thread's nice value, from -20 (highest priority) to 19
+  /// (lowest). Values below the current one usually require
+  /// `CAP_SYS_NICE`.
+  Nice(i32),
+
+  /// Uses the `SCHED_FIFO` real-time policy at the given priority, from 1
+  /// (lowest) to 99 (highest). Requires `CAP_SYS_NICE` or a sufficient
+  /// `RLIMIT_RTPRIO`.
+  RealTime(i32),
+}
+
+impl ThreadScheduling {
+  /// Applies this scheduling to the calling thread.
+  #[cfg(all(feature = "sched", target_os = "linux"))]
+  pub fn apply_current(&self) -> Result<()> {
+    trace!("ThreadScheduling::apply_current({:?})", self);
+
+    match *self {
+      ThreadScheduling::Nice(nice) => {
+        // on Linux, a thread ID targets only that thread
+        let tid = unsafe { libc::syscall(libc::SYS_gettid) };
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, nice) } != 0 {
+          return Err(std::io::Error::last_os_error().into());
+        }
+      },
+      ThreadScheduling::RealTime(priority) => {
+        let param = libc::sched_param { sched_priority: priority };
+        let ret = unsafe {
+          libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param)
+        };
+
+        if ret != 0 {
+          return Err(std::io::Error::from_raw_os_error(ret).into());
+        }
+      },
+    }
+
+    Ok(())
+  }
+
+  /// Applies this scheduling to the calling thread. Without the `sched`
+  /// feature on Linux, this always returns
+  /// [`MetrifulError::FeatureRequired`].
+  #[cfg(not(all(feature = "sched", target_os = "linux")))]
+  pub fn apply_current(&self) -> Result<()> {
+    trace!("ThreadScheduling::apply_current({:?})", self);
+    Err(MetrifulError::FeatureRequired("sched"))
+  }
+}
+
+/// Applies a scheduling hint, if any, to the calling thread, logging rather
+/// than returning any failure.
+pub(crate) fn apply_hint(scheduling: Option<ThreadScheduling>) {
+  if let Some(scheduling) = scheduling {
+    if let Err(e) = scheduling.apply_current() {
+      warn!("could not apply thread scheduling {:?}: {}", scheduling, e);
+    }
+  }
+}
@@ -0,0 +1,591 @@
+//! A simulated MS430 for exercising the library without hardware.
+//!
+//! [`Simulator`] implements [`MetrifulTransport`] against an in-memory register
+//! map and pairs with a [`SimulatorReadyLine`] whose READY signal follows the
+//! device's timing: on-demand measurements take
+//! [`SimulatorConfig::measurement_time`] to complete, and in cycle mode a new
+//! measurement starts every cycle period. Data registers are only updated when
+//! a measurement completes, using values from a configurable
+//! [`SimulatedReadings`] generator.
+//!
+//! Cycle periods may be compressed via [`SimulatorConfig::time_scale`] so that
+//! cycle mode can be tested in CI without waiting seconds per reading.
+//!
+//! # Example
+//! ```
+//! use std::time::Duration;
+//! use metriful::{CyclePeriod, metric::*};
+//! use metriful::simulator::*;
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! // 3 second cycles complete every 300ms
+//! let sim = Simulator::with_config(SimulatorConfig {
+//!   time_scale: 10.0,
+//!   measurement_time: Duration::from_millis(55),
+//!   ..SimulatorConfig::default()
+//! });
+//!
+//! // the temperature rises by one degree per measurement
+//! sim.set_generator(|n| SimulatedReadings {
+//!   temperature: 20.0 + n as f32,
+//!   ..SimulatedReadings::default()
+//! });
+//!
+//! let mut metriful = sim.open(Some(Duration::from_secs(1)))?;
+//! let temperatures = metriful
+//!   .cycle_read_iter_timeout(METRIC_TEMPERATURE, CyclePeriod::Period0, None)
+//!   .take(3)
+//!   .map(|r| r.map(|t| t.value))
+//!   .collect::<metriful::error::Result<Vec<_>>>()?;
+//!
+//! assert_eq!(temperatures, vec![20.0, 21.0, 22.0]);
+//! assert_eq!(sim.measurements(), 3);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::Metriful;
+use crate::error::*;
+use crate::ready::ReadyLine;
+use crate::status::CyclePeriod;
+use crate::transport::MetrifulTransport;
+use crate::unit::AQIAccuracy;
+
+/// Combined read registers and the data registers they concatenate.
+const COMBINED_REGISTERS: &[(u8, &[u8])] = &[
+  (0x10, &[0x21, 0x22, 0x23, 0x24]),
+  (0x11, &[0x25, 0x26, 0x27, 0x28]),
+  (0x12, &[0x31, 0x32]),
+  (0x13, &[0x41, 0x42, 0x43, 0x44]),
+  (0x14, &[0x51, 0x52, 0x53]),
+];
+
+/// Configuration registers and their power-on values.
+const CONFIG_DEFAULTS: &[(u8, &[u8])] = &[
+  (0x07, &[0]),
+  (0x81, &[0]), (0x82, &[0, 0, 0]), (0x83, &[0]), (0x84, &[0]),
+  (0x85, &[0]), (0x86, &[0, 0]), (0x87, &[0]),
+  (0x89, &[0]), (0x8A, &[0]),
+];
+
+fn is_config_register(register: u8) -> bool {
+  CONFIG_DEFAULTS.iter().any(|(r, _)| *r == register)
+}
+
+/// Values reported by the simulator when a measurement completes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedReadings {
+  /// Temperature in degrees Celsius
+  pub temperature: f32,
+
+  /// Pressure in Pascals
+  pub pressure: u32,
+
+  /// Relative humidity percentage
+  pub humidity: f32,
+
+  /// Gas sensor resistance in ohms
+  pub gas_resistance: u32,
+
+  /// Air quality index
+  pub aqi: f32,
+
+  /// Estimated CO2 in ppm
+  pub est_co2: f32,
+
+  /// Equivalent breath VOC in ppm
+  pub voc: f32,
+
+  /// AQI accuracy indicator
+  pub aqi_accuracy: AQIAccuracy,
+
+  /// Illuminance in lux
+  pub illuminance: f32,
+
+  /// White light level
+  pub white_level: u16,
+
+  /// A-weighted sound pressure level in dBA
+  pub weighted_spl: f32,
+
+  /// Sound pressure level by frequency band in dB
+  pub spl_bands: [f32; 6],
+
+  /// Peak sound amplitude in mPa
+  pub peak_amplitude: f32,
+
+  /// True if sound measurements are stable
+  pub sound_stable: bool,
+
+  /// Particle sensor duty cycle percentage
+  pub particle_duty_cycle: f32,
+
+  /// Raw particle concentration
+  pub particle_concentration: f32,
+
+  /// True if particle data has settled
+  pub particle_settled: bool,
+}
+
+impl Default for SimulatedReadings {
+  fn default() -> Self {
+    SimulatedReadings {
+      temperature: 21.5,
+      pressure: 101325,
+      humidity: 45.5,
+      gas_resistance: 125000,
+      aqi: 25.0,
+      est_co2: 500.0,
+      voc: 0.5,
+      aqi_accuracy: AQIAccuracy::High,
+      illuminance: 120.5,
+      white_level: 300,
+      weighted_spl: 40.5,
+      spl_bands: [38.0, 36.5, 35.0, 33.5, 30.0, 25.5],
+      peak_amplitude: 10.5,
+      sound_stable: true,
+      particle_duty_cycle: 0.0,
+      particle_concentration: 0.0,
+      particle_settled: false,
+    }
+  }
+}
+
+/// Splits a non-negative value into an integer part and a single decimal
+/// digit, as used by most MS430 registers.
+fn split_decimal(value: f32) -> (f32, u8) {
+  let value = value.max(0.0);
+  let mut int_part = value.trunc();
+  let mut frac_part = ((value - int_part) * 10.0).round() as u8;
+  if frac_part >= 10 {
+    int_part += 1.0;
+    frac_part = 0;
+  }
+
+  (int_part, frac_part)
+}
+
+fn encode_u8_decimal(value: f32) -> Vec<u8> {
+  let (int_part, frac_part) = split_decimal(value);
+  vec![int_part.min(u8::MAX as f32) as u8, frac_part]
+}
+
+fn encode_u16_decimal(value: f32) -> Vec<u8> {
+  let (int_part, frac_part) = split_decimal(value);
+  let mut bytes = (int_part.min(u16::MAX as f32) as u16).to_le_bytes().to_vec();
+  bytes.push(frac_part);
+  bytes
+}
+
+fn encode_temperature(value: f32) -> Vec<u8> {
+  let int_part = value.floor();
+  let (int_part, frac_part) = match ((value - int_part) * 10.0).round() as u8 {
+    10 => (int_part + 1.0, 0),
+    frac => (int_part, frac),
+  };
+
+  let int_part = int_part.max(i8::MIN as f32).min(i8::MAX as f32) as i8;
+  vec![int_part as u8, frac_part]
+}
+
+impl SimulatedReadings {
+  /// Returns the raw contents of each data register for these readings.
+  fn registers(&self) -> Vec<(u8, Vec<u8>)> {
+    let (band_ints, band_fracs): (Vec<u8>, Vec<u8>) = self.spl_bands.iter()
+      .map(|band| {
+        let (int_part, frac_part) = split_decimal(*band);
+        (int_part.min(u8::MAX as f32) as u8, frac_part)
+      })
+      .unzip();
+
+    vec![
+      (0x21, encode_temperature(self.temperature)),
+      (0x22, self.pressure.to_le_bytes().to_vec()),
+      (0x23, encode_u8_decimal(self.humidity)),
+      (0x24, self.gas_resistance.to_le_bytes().to_vec()),
+      (0x25, encode_u16_decimal(self.aqi)),
+      (0x26, encode_u16_decimal(self.est_co2)),
+      (0x27, encode_u16_decimal(self.voc)),
+      (0x28, vec![self.aqi_accuracy.to_uint()]),
+      (0x31, encode_u16_decimal(self.illuminance)),
+      (0x32, self.white_level.to_le_bytes().to_vec()),
+      (0x41, encode_u8_decimal(self.weighted_spl)),
+      (0x42, [band_ints, band_fracs].concat()),
+      (0x43, encode_u16_decimal(self.peak_amplitude)),
+      (0x44, vec![self.sound_stable as u8]),
+      (0x51, encode_u8_decimal(self.particle_duty_cycle)),
+      (0x52, encode_u16_decimal(self.particle_concentration)),
+      (0x53, vec![self.particle_settled as u8]),
+    ]
+  }
+}
+
+/// Timing parameters for a [`Simulator`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SimulatorConfig {
+  /// Time READY is deasserted while a measurement is in progress. Defaults to
+  /// 0.55 seconds, per the datasheet.
+  pub measurement_time: Duration,
+
+  /// Time READY is deasserted after a reset. Defaults to 11ms.
+  pub reset_time: Duration,
+
+  /// Factor by which cycle periods are compressed, e.g. `100.0` to complete a
+  /// 3 second cycle in 30ms. Defaults to `1.0`, i.e. real time.
+  pub time_scale: f64,
+}
+
+impl Default for SimulatorConfig {
+  fn default() -> Self {
+    SimulatorConfig {
+      measurement_time: Duration::from_millis(550),
+      reset_time: Duration::from_millis(11),
+      time_scale: 1.0,
+    }
+  }
+}
+
+type Generator = Box<dyn FnMut(u64) -> SimulatedReadings + Send>;
+
+#[derive(Debug, Copy, Clone)]
+enum SimMode {
+  Standby,
+  Cycle {
+    period: Duration,
+    started: Instant,
+  },
+}
+
+struct SimState {
+  config: SimulatorConfig,
+  registers: HashMap<u8, Vec<u8>>,
+  generator: Generator,
+  mode: SimMode,
+
+  /// End of the current on-demand measurement or reset, if any
+  busy_until: Option<Instant>,
+
+  /// True if `busy_until` marks the end of a measurement, not a reset
+  measuring: bool,
+
+  /// Number of completed measurements
+  measurements: u64,
+
+  /// Value of `measurements` at the last `take_ready_edges()`
+  latched: u64,
+
+  commands: Vec<u8>,
+}
+
+impl fmt::Debug for SimState {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("SimState")
+      .field("config", &self.config)
+      .field("mode", &self.mode)
+      .field("busy_until", &self.busy_until)
+      .field("measurements", &self.measurements)
+      .finish()
+  }
+}
+
+impl SimState {
+  fn new(config: SimulatorConfig) -> SimState {
+    let mut state = SimState {
+      config,
+      registers: HashMap::new(),
+      generator: Box::new(|_| SimulatedReadings::default()),
+      mode: SimMode::Standby,
+      busy_until: None,
+      measuring: false,
+      measurements: 0,
+      latched: 0,
+      commands: Vec::new(),
+    };
+
+    // data registers read as zero until the first measurement completes
+    for (register, value) in SimulatedReadings::default().registers() {
+      state.registers.insert(register, vec![0; value.len()]);
+    }
+
+    state.reset_config();
+    state
+  }
+
+  fn reset_config(&mut self) {
+    for (register, value) in CONFIG_DEFAULTS {
+      self.registers.insert(*register, value.to_vec());
+    }
+  }
+
+  fn complete_measurement(&mut self) {
+    let readings = (self.generator)(self.measurements);
+    for (register, value) in readings.registers() {
+      self.registers.insert(register, value);
+    }
+
+    self.measurements += 1;
+  }
+
+  /// Applies any measurements completed as of `now`.
+  fn advance(&mut self, now: Instant) {
+    if let Some(until) = self.busy_until {
+      if now >= until {
+        self.busy_until = None;
+        if self.measuring {
+          self.measuring = false;
+          self.complete_measurement();
+        }
+      }
+    }
+
+    if let SimMode::Cycle { period, started } = self.mode {
+      let completed = Self::cycles_completed(now - started, period, self.config.measurement_time);
+      while self.measurements < completed {
+        self.complete_measurement();
+      }
+    }
+  }
+
+  fn cycles_completed(elapsed: Duration, period: Duration, measurement_time: Duration) -> u64 {
+    if elapsed < measurement_time {
+      0
+    } else {
+      ((elapsed - measurement_time).as_nanos() / period.as_nanos().max(1)) as u64 + 1
+    }
+  }
+
+  fn is_ready(&mut self, now: Instant) -> bool {
+    self.advance(now);
+
+    if self.busy_until.is_some() {
+      return false;
+    }
+
+    match self.mode {
+      SimMode::Standby => true,
+      SimMode::Cycle { period, started } => {
+        let elapsed = (now - started).as_nanos();
+        let phase = elapsed % period.as_nanos().max(1);
+
+        phase >= self.config.measurement_time.as_nanos()
+      }
+    }
+  }
+
+  fn scaled_period(&self, period: CyclePeriod) -> Duration {
+    period.to_duration().div_f64(self.config.time_scale.max(f64::MIN_POSITIVE))
+  }
+
+  fn read(&mut self, register: u8, len: usize) -> Vec<u8> {
+    self.advance(Instant::now());
+
+    let mut ret = match COMBINED_REGISTERS.iter().find(|(r, _)| *r == register) {
+      Some((_, parts)) => parts.iter()
+        .flat_map(|p| self.registers.get(p).cloned().unwrap_or_default())
+        .collect(),
+      None if is_config_register(register) => self.read_config(register, len),
+      None => self.registers.get(&register).cloned().unwrap_or_default(),
+    };
+
+    ret.resize(len, 0);
+    ret
+  }
+
+  /// Reads consecutive settings registers, as the MS430 allows reading
+  /// e.g. all interrupt settings in one block.
+  fn read_config(&self, register: u8, len: usize) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(len);
+
+    for next in register..=u8::MAX {
+      match self.registers.get(&next) {
+        Some(value) if ret.len() < len && is_config_register(next) => ret.extend_from_slice(value),
+        _ => break,
+      }
+    }
+
+    ret
+  }
+
+  fn write(&mut self, register: u8, values: &[u8]) {
+    self.registers.insert(register, values.to_vec());
+  }
+
+  fn command(&mut self, command: u8) {
+    let now = Instant::now();
+    self.advance(now);
+    self.commands.push(command);
+
+    match command {
+      // on-demand measurement; only valid in standby
+      0xE1 => if let SimMode::Standby = self.mode {
+        self.busy_until = Some(now + self.config.measurement_time);
+        self.measuring = true;
+      },
+
+      // reset
+      0xE2 => {
+        self.reset_config();
+        self.mode = SimMode::Standby;
+        self.busy_until = Some(now + self.config.reset_time);
+        self.measuring = false;
+      },
+
+      // enter cycle mode
+      0xE4 => {
+        let period = self.registers.get(&0x89)
+          .and_then(|v| CyclePeriod::from_value(v[0]).ok())
+          .unwrap_or(CyclePeriod::Period0);
+
+        self.mode = SimMode::Cycle {
+          period: self.scaled_period(period),
+          started: now,
+        };
+        self.write(0x8A, &[1]);
+      },
+
+      // enter standby mode
+      0xE5 => {
+        self.mode = SimMode::Standby;
+        self.write(0x8A, &[0]);
+      },
+
+      _ => (),
+    }
+  }
+}
+
+/// A simulated MS430 implementing [`MetrifulTransport`].
+///
+/// Clones share the same underlying state, so a test may keep a handle to
+/// inspect or adjust the simulation after handing a clone to a
+/// [`Metriful`].
+#[derive(Debug, Clone)]
+pub struct Simulator {
+  state: Arc<Mutex<SimState>>,
+}
+
+impl Default for Simulator {
+  fn default() -> Self {
+    Simulator::new()
+  }
+}
+
+impl Simulator {
+  /// Creates a simulator in standby mode with real-time timing and default
+  /// readings.
+  pub fn new() -> Simulator {
+    Simulator::with_config(SimulatorConfig::default())
+  }
+
+  /// Creates a simulator with the given timing configuration.
+  pub fn with_config(config: SimulatorConfig) -> Simulator {
+    Simulator {
+      state: Arc::new(Mutex::new(SimState::new(config))),
+    }
+  }
+
+  fn state(&self) -> MutexGuard<'_, SimState> {
+    self.state.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Returns a READY line reflecting this simulator's state.
+  pub fn ready_line(&self) -> SimulatorReadyLine {
+    SimulatorReadyLine {
+      state: Arc::clone(&self.state),
+    }
+  }
+
+  /// Opens a [`Metriful`] connected to this simulator, with rate limiting
+  /// disabled so that tests need not wait between commands.
+  pub fn open(&self, timeout: Option<Duration>) -> Result<Metriful<Simulator>> {
+    let mut metriful = Metriful::try_new_device_timeout(self.ready_line(), self.clone(), timeout)?;
+    metriful.set_rate_limit(crate::RateLimit::disabled());
+
+    Ok(metriful)
+  }
+
+  /// Reports the given readings for all subsequent measurements.
+  pub fn set_readings(&self, readings: SimulatedReadings) {
+    self.set_generator(move |_| readings.clone());
+  }
+
+  /// Generates readings for each subsequent measurement from the given
+  /// function, which is passed the zero-based measurement number.
+  pub fn set_generator<F>(&self, generator: F)
+  where
+    F: FnMut(u64) -> SimulatedReadings + Send + 'static
+  {
+    self.state().generator = Box::new(generator);
+  }
+
+  /// Returns the number of measurements completed so far.
+  pub fn measurements(&self) -> u64 {
+    let mut state = self.state();
+    state.advance(Instant::now());
+    state.measurements
+  }
+
+  /// Returns the raw contents of a register.
+  pub fn register(&self, register: u8) -> Vec<u8> {
+    self.state().registers.get(&register).cloned().unwrap_or_default()
+  }
+
+  /// Returns all command bytes (0xE1-0xE7) received so far, in order.
+  pub fn commands(&self) -> Vec<u8> {
+    self.state().commands.clone()
+  }
+}
+
+impl MetrifulTransport for Simulator {
+  fn write_byte(&mut self, command: u8) -> Result<()> {
+    self.state().command(command);
+    Ok(())
+  }
+
+  fn read_byte(&mut self, register: u8) -> Result<u8> {
+    Ok(self.state().read(register, 1)[0])
+  }
+
+  fn write_byte_data(&mut self, register: u8, value: u8) -> Result<()> {
+    self.state().write(register, &[value]);
+    Ok(())
+  }
+
+  fn read_block(&mut self, register: u8, len: u8) -> Result<Vec<u8>> {
+    Ok(self.state().read(register, len as usize))
+  }
+
+  fn write_block(&mut self, register: u8, values: &[u8]) -> Result<()> {
+    self.state().write(register, values);
+    Ok(())
+  }
+}
+
+/// The READY line of a [`Simulator`].
+#[derive(Debug, Clone)]
+pub struct SimulatorReadyLine {
+  state: Arc<Mutex<SimState>>,
+}
+
+impl ReadyLine for SimulatorReadyLine {
+  fn is_ready(&self) -> Result<bool> {
+    let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(state.is_ready(Instant::now()))
+  }
+
+  /// Latches completed measurements, emulating an interrupt-capable pin.
+  fn take_ready_edges(&self) -> Result<Option<u32>> {
+    let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+    state.advance(Instant::now());
+
+    let edges = state.measurements - state.latched;
+    state.latched = state.measurements;
+
+    Ok(Some(edges.min(u64::from(u32::MAX)) as u32))
+  }
+}
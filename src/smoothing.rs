@@ -0,0 +1,398 @@
+//! Smoothing of noisy readings.
+//!
+//! Sound and particle readings in particular fluctuate considerably from one
+//! cycle to the next. This module provides two smoothers for numeric units:
+//!  * [`MovingAverage`]: the mean of the most recent `window` readings
+//!  * [`ExponentialAverage`]: an exponentially weighted moving average
+//!
+//! Both may be applied directly to a read iterator via [`SmoothingExt`], or
+//! fed readings manually via [`Smoother::update()`], e.g. from an async
+//! stream or channel. Smoothed values keep the timestamp of the most recent
+//! reading. Errors are passed through unchanged and do not reset the
+//! smoother.
+//!
+//! # Example
+//! ```no_run
+//! use metriful::{Metriful, CyclePeriod, metric::*, smoothing::SmoothingExt};
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+//!
+//! let iter = metriful
+//!   .cycle_read_iter_timeout(METRIC_WEIGHTED_SOUND_LEVEL, CyclePeriod::Period0, None)
+//!   .smoothed(10);
+//!
+//! for level in iter {
+//!   println!("30s average: {}", level?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::error::*;
+use crate::unit::*;
+
+/// A value that can be averaged, component-wise if it has several parts.
+pub trait Smoothable: Copy {
+  /// The value's numeric components, e.g. `[f64; 1]` for scalars.
+  type Components: AsRef<[f64]> + AsMut<[f64]> + Default + Copy + fmt::Debug;
+
+  /// Converts the value to its components.
+  fn to_components(&self) -> Self::Components;
+
+  /// Converts averaged components back to a value, rounding if necessary.
+  fn from_components(components: Self::Components) -> Self;
+}
+
+impl Smoothable for f32 {
+  type Components = [f64; 1];
+
+  fn to_components(&self) -> Self::Components {
+    [f64::from(*self)]
+  }
+
+  fn from_components(components: Self::Components) -> Self {
+    components[0] as f32
+  }
+}
+
+impl Smoothable for u16 {
+  type Components = [f64; 1];
+
+  fn to_components(&self) -> Self::Components {
+    [f64::from(*self)]
+  }
+
+  fn from_components(components: Self::Components) -> Self {
+    components[0].round() as u16
+  }
+}
+
+impl Smoothable for u32 {
+  type Components = [f64; 1];
+
+  fn to_components(&self) -> Self::Components {
+    [f64::from(*self)]
+  }
+
+  fn from_components(components: Self::Components) -> Self {
+    components[0].round() as u32
+  }
+}
+
+impl Smoothable for RawParticleConcentration {
+  type Components = [f64; 2];
+
+  fn to_components(&self) -> Self::Components {
+    [f64::from(self.sds011_value), f64::from(self.ppd42_value)]
+  }
+
+  fn from_components(components: Self::Components) -> Self {
+    RawParticleConcentration {
+      sds011_value: components[0] as f32,
+      ppd42_value: components[1].round() as u16,
+    }
+  }
+}
+
+/// Replaces a reading's value with a smoothed one, keeping its timestamp. Raw
+/// bytes are dropped as they no longer correspond to the value.
+fn with_value<U: MetrifulUnit>(reading: UnitValue<U>, value: U::Output) -> UnitValue<U> {
+  UnitValue {
+    unit: reading.unit,
+    value,
+    time: reading.time,
+    timing: reading.timing,
+    warnings: reading.warnings,
+    #[cfg(feature = "raw-bytes")] raw_bytes: None,
+  }
+}
+
+/// Produces smoothed values from a sequence of readings.
+pub trait Smoother {
+  /// The unit of the readings being smoothed.
+  type Unit: MetrifulUnit;
+
+  /// Adds a reading and returns the smoothed value as of that reading.
+  fn update(&mut self, reading: UnitValue<Self::Unit>) -> UnitValue<Self::Unit>;
+
+  /// Discards all previous readings.
+  fn reset(&mut self);
+}
+
+/// The arithmetic mean of the most recent `window` readings.
+///
+/// Until `window` readings have been seen, the mean of all readings so far is
+/// returned.
+///
+/// # Example
+/// ```
+/// use metriful::smoothing::{MovingAverage, Smoother};
+/// use metriful::unit::*;
+///
+/// let mut average = MovingAverage::<UnitAWeightedSPL>::new(2);
+/// assert_eq!(average.update(UnitValue::new(40.0)).value, 40.0);
+/// assert_eq!(average.update(UnitValue::new(50.0)).value, 45.0);
+/// assert_eq!(average.update(UnitValue::new(60.0)).value, 55.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MovingAverage<U>
+where
+  U: MetrifulUnit,
+  U::Output: Smoothable,
+{
+  window: usize,
+  values: VecDeque<<U::Output as Smoothable>::Components>,
+}
+
+impl<U> MovingAverage<U>
+where
+  U: MetrifulUnit,
+  U::Output: Smoothable,
+{
+  /// Creates a moving average over the given number of readings (minimum 1).
+  pub fn new(window: usize) -> Self {
+    let window = window.max(1);
+
+    MovingAverage {
+      window,
+      values: VecDeque::with_capacity(window),
+    }
+  }
+}
+
+impl<U> Smoother for MovingAverage<U>
+where
+  U: MetrifulUnit,
+  U::Output: Smoothable,
+{
+  type Unit = U;
+
+  fn update(&mut self, reading: UnitValue<U>) -> UnitValue<U> {
+    if self.values.len() >= self.window {
+      self.values.pop_front();
+    }
+
+    self.values.push_back(reading.value.to_components());
+
+    let mut mean = <U::Output as Smoothable>::Components::default();
+    for value in &self.values {
+      for (sum, component) in mean.as_mut().iter_mut().zip(value.as_ref()) {
+        *sum += component;
+      }
+    }
+
+    let count = self.values.len() as f64;
+    for component in mean.as_mut() {
+      *component /= count;
+    }
+
+    with_value(reading, U::Output::from_components(mean))
+  }
+
+  fn reset(&mut self) {
+    self.values.clear();
+  }
+}
+
+/// An exponentially weighted moving average.
+///
+/// Each reading `x` updates the average `s` as `s + alpha * (x - s)`; larger
+/// values of `alpha` (up to 1) follow changes more closely, while smaller
+/// values smooth more heavily. The first reading is returned unchanged.
+///
+/// # Example
+/// ```
+/// use metriful::smoothing::{ExponentialAverage, Smoother};
+/// use metriful::unit::*;
+///
+/// let mut average = ExponentialAverage::<UnitAWeightedSPL>::new(0.5);
+/// assert_eq!(average.update(UnitValue::new(40.0)).value, 40.0);
+/// assert_eq!(average.update(UnitValue::new(60.0)).value, 50.0);
+/// assert_eq!(average.update(UnitValue::new(60.0)).value, 55.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExponentialAverage<U>
+where
+  U: MetrifulUnit,
+  U::Output: Smoothable,
+{
+  alpha: f64,
+  current: Option<<U::Output as Smoothable>::Components>,
+}
+
+impl<U> ExponentialAverage<U>
+where
+  U: MetrifulUnit,
+  U::Output: Smoothable,
+{
+  /// Creates an exponential average with the given smoothing factor, clamped
+  /// to `0.0..=1.0`.
+  pub fn new(alpha: f64) -> Self {
+    ExponentialAverage {
+      alpha: alpha.clamp(0.0, 1.0),
+      current: None,
+    }
+  }
+}
+
+impl<U> Smoother for ExponentialAverage<U>
+where
+  U: MetrifulUnit,
+  U::Output: Smoothable,
+{
+  type Unit = U;
+
+  fn update(&mut self, reading: UnitValue<U>) -> UnitValue<U> {
+    let value = reading.value.to_components();
+
+    let current = match self.current {
+      Some(mut current) => {
+        for (s, x) in current.as_mut().iter_mut().zip(value.as_ref()) {
+          *s += self.alpha * (x - *s);
+        }
+
+        current
+      },
+      None => value,
+    };
+
+    self.current = Some(current);
+    with_value(reading, U::Output::from_components(current))
+  }
+
+  fn reset(&mut self) {
+    self.current = None;
+  }
+}
+
+/// An iterator adapter that smooths readings; see [`SmoothingExt`].
+#[derive(Debug)]
+pub struct Smoothed<I, S> {
+  iter: I,
+  smoother: S,
+}
+
+impl<I, S> Smoothed<I, S> {
+  /// Returns the smoother, e.g. to reset it.
+  pub fn smoother_mut(&mut self) -> &mut S {
+    &mut self.smoother
+  }
+}
+
+impl<I, S> Iterator for Smoothed<I, S>
+where
+  S: Smoother,
+  I: Iterator<Item = Result<UnitValue<S::Unit>>>,
+{
+  type Item = Result<UnitValue<S::Unit>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.iter.next().map(|result| result.map(|reading| self.smoother.update(reading)))
+  }
+}
+
+/// Smoothing adapters for iterators of readings, e.g.
+/// [`CycleReadIterator`](crate::CycleReadIterator).
+///
+/// # Example
+/// ```
+/// use metriful::smoothing::SmoothingExt;
+/// use metriful::unit::*;
+///
+/// let readings = vec![40.0, 50.0, 60.0]
+///   .into_iter()
+///   .map(|v| Ok(UnitValue::<UnitAWeightedSPL>::new(v)));
+///
+/// let smoothed: Vec<f32> = readings
+///   .smoothed(2)
+///   .map(|r| r.unwrap().value)
+///   .collect();
+///
+/// assert_eq!(smoothed, vec![40.0, 45.0, 55.0]);
+/// ```
+pub trait SmoothingExt<U>: Iterator<Item = Result<UnitValue<U>>> + Sized
+where
+  U: MetrifulUnit
+{
+  /// Replaces each reading with the mean of the most recent `window`
+  /// readings; see [`MovingAverage`].
+  fn smoothed(self, window: usize) -> Smoothed<Self, MovingAverage<U>>
+  where
+    U::Output: Smoothable
+  {
+    self.smooth_with(MovingAverage::new(window))
+  }
+
+  /// Replaces each reading with an exponentially weighted moving average;
+  /// see [`ExponentialAverage`].
+  fn exponential_smoothing(self, alpha: f64) -> Smoothed<Self, ExponentialAverage<U>>
+  where
+    U::Output: Smoothable
+  {
+    self.smooth_with(ExponentialAverage::new(alpha))
+  }
+
+  /// Smooths readings with the given [`Smoother`].
+  fn smooth_with<S>(self, smoother: S) -> Smoothed<Self, S>
+  where
+    S: Smoother<Unit = U>
+  {
+    Smoothed { iter: self, smoother }
+  }
+}
+
+impl<I, U> SmoothingExt<U> for I
+where
+  I: Iterator<Item = Result<UnitValue<U>>>,
+  U: MetrifulUnit,
+{}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn errors_pass_through_without_resetting() {
+    let readings = vec![
+      Ok(UnitValue::<UnitAWeightedSPL>::new(40.0)),
+      Err(MetrifulError::NotReady),
+      Ok(UnitValue::new(60.0)),
+    ];
+
+    let smoothed: Vec<_> = readings.into_iter().smoothed(4).collect();
+    assert_eq!(smoothed[0].as_ref().unwrap().value, 40.0);
+    assert!(matches!(smoothed[1], Err(MetrifulError::NotReady)));
+    assert_eq!(smoothed[2].as_ref().unwrap().value, 50.0);
+  }
+
+  #[test]
+  fn zero_windows_are_clamped() {
+    let mut average = MovingAverage::<UnitAWeightedSPL>::new(0);
+    assert_eq!(average.update(UnitValue::new(40.0)).value, 40.0);
+    assert_eq!(average.update(UnitValue::new(60.0)).value, 60.0);
+  }
+
+  #[test]
+  fn reset_discards_history() {
+    let mut average = ExponentialAverage::<UnitAWeightedSPL>::new(0.5);
+    average.update(UnitValue::new(40.0));
+    average.reset();
+    assert_eq!(average.update(UnitValue::new(60.0)).value, 60.0);
+  }
+
+  #[test]
+  fn integer_values_are_rounded() {
+    let mut average = MovingAverage::<UnitWhiteLevel>::new(2);
+    average.update(UnitValue::new(1));
+    assert_eq!(average.update(UnitValue::new(2)).value, 2);
+
+    let mut average = MovingAverage::<UnitRawParticleConcentration>::new(2);
+    let raw = |ppd42_value| RawParticleConcentration { sds011_value: 0.0, ppd42_value };
+    average.update(UnitValue::new(raw(1)));
+    assert_eq!(average.update(UnitValue::new(raw(2))).value.ppd42_value, 2);
+  }
+}
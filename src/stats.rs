@@ -0,0 +1,396 @@
+//! Windowed summary statistics over readings.
+//!
+//! An [`Accumulator`] collects numeric samples of a single value and
+//! summarizes those within a [`Window`] (the last N samples, or those from
+//! the last N seconds) as a [`Summary`] of min, max, mean and standard
+//! deviation, plus arbitrary percentiles. [`Stats`] maintains one accumulator
+//! per named value, and accepts any reading including combined reads, whose
+//! numeric components are tracked individually.
+//!
+//! For example, to publish 5-minute aggregates from 3-second cycles:
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use metriful::{Metriful, CyclePeriod, metric::*};
+//! use metriful::stats::{Stats, Window};
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let mut metriful = Metriful::try_new(17, "/dev/i2c-1", 0x71)?;
+//! let mut stats = Stats::new(Window::Duration(Duration::from_secs(300)));
+//!
+//! let iter = metriful.cycle_read_iter_timeout(METRIC_COMBINED_ALL, CyclePeriod::Period0, None);
+//! for reading in iter {
+//!   stats.push("combined_all", &reading?);
+//!
+//!   if let Some(summary) = stats.summary("weighted_spl") {
+//!     println!("sound level over the last 5 minutes: {}", summary);
+//!   }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+#[cfg(feature = "serde")] use chrono::SecondsFormat;
+#[cfg(feature = "serde")] use serde::{Serialize, Serializer};
+
+use crate::dyn_metric::{DynReading, ReadingValue};
+use crate::unit::{MetrifulUnit, UnitValue};
+
+/// The samples an [`Accumulator`] summarizes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Window {
+  /// The most recent N samples (minimum 1)
+  Count(usize),
+
+  /// Samples no older than the given duration, relative to the most recent
+  /// sample's timestamp
+  Duration(Duration),
+}
+
+/// Summary statistics over the samples in a window.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Summary {
+  /// Number of samples summarized
+  pub count: usize,
+
+  pub min: f64,
+  pub max: f64,
+  pub mean: f64,
+
+  /// Population standard deviation
+  pub stddev: f64,
+
+  /// Timestamp of the oldest sample
+  #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_time"))]
+  pub start: DateTime<Utc>,
+
+  /// Timestamp of the newest sample
+  #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_time"))]
+  pub end: DateTime<Utc>,
+}
+
+#[cfg(feature = "serde")]
+fn serialize_time<S>(time: &DateTime<Utc>, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+  S: Serializer
+{
+  serializer.serialize_str(&time.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+impl fmt::Display for Summary {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f, "mean {:.2} (min {:.2}, max {:.2}, stddev {:.2}, n={})",
+      self.mean, self.min, self.max, self.stddev, self.count
+    )
+  }
+}
+
+/// Collects samples of a single numeric value within a [`Window`].
+///
+/// # Example
+/// ```
+/// use metriful::stats::{Accumulator, Window};
+/// use metriful::unit::*;
+///
+/// let mut acc = Accumulator::new(Window::Count(4));
+/// for value in &[10.0, 20.0, 30.0, 40.0, 50.0] {
+///   acc.push_value(&UnitValue::<UnitAWeightedSPL>::new(*value));
+/// }
+///
+/// let summary = acc.summary().unwrap();
+/// assert_eq!(summary.count, 4);
+/// assert_eq!(summary.min, 20.0);
+/// assert_eq!(summary.max, 50.0);
+/// assert_eq!(summary.mean, 35.0);
+/// assert_eq!(acc.percentile(0.5), Some(35.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Accumulator {
+  window: Window,
+  samples: VecDeque<(DateTime<Utc>, f64)>,
+}
+
+impl Accumulator {
+  pub fn new(window: Window) -> Accumulator {
+    Accumulator {
+      window,
+      samples: VecDeque::new(),
+    }
+  }
+
+  /// Adds a sample, discarding any that fall outside the window. Samples
+  /// are expected in chronological order.
+  pub fn push(&mut self, time: DateTime<Utc>, value: f64) {
+    self.samples.push_back((time, value));
+
+    match self.window {
+      Window::Count(count) => {
+        while self.samples.len() > count.max(1) {
+          self.samples.pop_front();
+        }
+      },
+      Window::Duration(duration) => {
+        while let Some((oldest, _)) = self.samples.front() {
+          // samples newer than `time` (negative ages) are kept
+          let expired = time.signed_duration_since(*oldest)
+            .to_std()
+            .is_ok_and(|age| age > duration);
+
+          if !expired {
+            break;
+          }
+
+          self.samples.pop_front();
+        }
+      },
+    }
+  }
+
+  /// Adds a reading if it has a single numeric value, returning false
+  /// otherwise (e.g. for combined reads, or values such as
+  /// [`AQIAccuracy`](crate::unit::AQIAccuracy)).
+  pub fn push_value<U: MetrifulUnit>(&mut self, value: &UnitValue<U>) -> bool {
+    match U::to_reading_value(&value.value).as_f64() {
+      Some(n) => {
+        self.push(value.time, n);
+        true
+      },
+      None => false,
+    }
+  }
+
+  /// Returns the number of samples in the window.
+  pub fn len(&self) -> usize {
+    self.samples.len()
+  }
+
+  /// Returns true if the window contains no samples.
+  pub fn is_empty(&self) -> bool {
+    self.samples.is_empty()
+  }
+
+  /// Discards all samples.
+  pub fn clear(&mut self) {
+    self.samples.clear();
+  }
+
+  /// Summarizes the samples in the window, or returns None if it is empty.
+  pub fn summary(&self) -> Option<Summary> {
+    let (start, _) = *self.samples.front()?;
+    let (end, _) = *self.samples.back()?;
+
+    let count = self.samples.len();
+    let values = || self.samples.iter().map(|(_, v)| *v);
+
+    let mean = values().sum::<f64>() / count as f64;
+    let variance = values().map(|v| (v - mean).powi(2)).sum::<f64>() / count as f64;
+
+    Some(Summary {
+      count,
+      min: values().fold(f64::INFINITY, f64::min),
+      max: values().fold(f64::NEG_INFINITY, f64::max),
+      mean,
+      stddev: variance.sqrt(),
+      start,
+      end,
+    })
+  }
+
+  /// Returns the given percentile (`0.0..=1.0`, e.g. `0.95`) of the samples
+  /// in the window, interpolating linearly between samples, or None if it is
+  /// empty.
+  pub fn percentile(&self, q: f64) -> Option<f64> {
+    if self.samples.is_empty() {
+      return None;
+    }
+
+    let mut sorted: Vec<f64> = self.samples.iter().map(|(_, v)| *v).collect();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let rank = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let lower = sorted[rank.floor() as usize];
+    let upper = sorted[rank.ceil() as usize];
+
+    Some(lower + (upper - lower) * rank.fract())
+  }
+}
+
+/// Accumulators for any number of named values sharing one [`Window`].
+///
+/// # Example
+/// ```
+/// use metriful::stats::{Stats, Window};
+/// use metriful::unit::*;
+///
+/// let mut stats = Stats::new(Window::Count(10));
+/// stats.push("temperature", &UnitValue::<UnitDegreesCelsius>::new(21.0));
+/// stats.push("temperature", &UnitValue::<UnitDegreesCelsius>::new(23.0));
+///
+/// assert_eq!(stats.summary("temperature").unwrap().mean, 22.0);
+/// assert!(stats.summary("humidity").is_none());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Stats {
+  window: Window,
+  series: BTreeMap<&'static str, Accumulator>,
+}
+
+impl Stats {
+  pub fn new(window: Window) -> Stats {
+    Stats {
+      window,
+      series: BTreeMap::new(),
+    }
+  }
+
+  /// Adds a reading under the given name. Numeric components of combined
+  /// reads are instead added under their own names, e.g. `temperature` and
+  /// `weighted_spl` for [`METRIC_COMBINED_ALL`](crate::metric::METRIC_COMBINED_ALL).
+  /// Non-numeric values are ignored.
+  pub fn push<U: MetrifulUnit>(&mut self, name: &'static str, value: &UnitValue<U>) {
+    self.push_reading(&DynReading::from_value(name, value));
+  }
+
+  /// Adds a type-erased reading, as with [`Stats::push()`].
+  pub fn push_reading(&mut self, reading: &DynReading) {
+    match &reading.value {
+      ReadingValue::Number(n) => {
+        let window = self.window;
+        self.series.entry(reading.name)
+          .or_insert_with(|| Accumulator::new(window))
+          .push(reading.time, *n);
+      },
+      ReadingValue::Group(components) => {
+        for component in components {
+          self.push_reading(component);
+        }
+      },
+      _ => (),
+    }
+  }
+
+  /// Returns the accumulator for the given name, if any samples were added.
+  pub fn get(&self, name: &str) -> Option<&Accumulator> {
+    self.series.get(name)
+  }
+
+  /// Summarizes the given value, if any samples were added.
+  pub fn summary(&self, name: &str) -> Option<Summary> {
+    self.get(name).and_then(Accumulator::summary)
+  }
+
+  /// Summarizes all values, keyed by name.
+  pub fn summaries(&self) -> BTreeMap<&'static str, Summary> {
+    self.series.iter()
+      .filter_map(|(name, acc)| acc.summary().map(|s| (*name, s)))
+      .collect()
+  }
+
+  /// Discards all samples.
+  pub fn clear(&mut self) {
+    self.series.clear();
+  }
+}
+
+/// Readings collected by [`Metriful::read_n()`](crate::Metriful::read_n),
+/// with summary statistics.
+#[derive(Debug)]
+pub struct Samples<U: MetrifulUnit> {
+  /// Name of the metric read, e.g. `temperature`
+  pub name: &'static str,
+
+  /// All readings, oldest first
+  pub values: Vec<UnitValue<U>>,
+
+  /// Statistics over all readings; components of combined reads are tracked
+  /// under their own names, as with [`Stats::push()`]
+  pub stats: Stats,
+}
+
+impl<U: MetrifulUnit> Samples<U> {
+  /// Summarizes the given readings.
+  pub fn new(name: &'static str, values: Vec<UnitValue<U>>) -> Samples<U> {
+    let mut stats = Stats::new(Window::Count(values.len()));
+    for value in &values {
+      stats.push(name, value);
+    }
+
+    Samples { name, values, stats }
+  }
+
+  /// Summarizes the readings, if they have a single numeric value. For
+  /// combined reads, see [`Samples::stats`].
+  pub fn summary(&self) -> Option<Summary> {
+    self.stats.summary(self.name)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::TimeZone;
+
+  use super::*;
+
+  fn at(secs: i64) -> DateTime<Utc> {
+    Utc.timestamp_opt(secs, 0).unwrap()
+  }
+
+  #[test]
+  fn empty_windows_have_no_summary() {
+    let acc = Accumulator::new(Window::Count(4));
+    assert!(acc.summary().is_none());
+    assert!(acc.percentile(0.5).is_none());
+  }
+
+  #[test]
+  fn count_windows_keep_at_least_one_sample() {
+    let mut acc = Accumulator::new(Window::Count(0));
+    acc.push(at(0), 1.0);
+    acc.push(at(1), 2.0);
+
+    assert_eq!(acc.len(), 1);
+    assert_eq!(acc.summary().unwrap().mean, 2.0);
+  }
+
+  #[test]
+  fn duration_windows_expire_old_samples() {
+    let mut acc = Accumulator::new(Window::Duration(Duration::from_secs(10)));
+    acc.push(at(0), 1.0);
+    acc.push(at(5), 2.0);
+    acc.push(at(12), 3.0);
+
+    let summary = acc.summary().unwrap();
+    assert_eq!(summary.count, 2);
+    assert_eq!((summary.start, summary.end), (at(5), at(12)));
+  }
+
+  #[test]
+  fn summaries_report_spread() {
+    let mut acc = Accumulator::new(Window::Count(10));
+    for (i, value) in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0].iter().enumerate() {
+      acc.push(at(i as i64), *value);
+    }
+
+    let summary = acc.summary().unwrap();
+    assert_eq!((summary.min, summary.max, summary.mean, summary.stddev), (2.0, 9.0, 5.0, 2.0));
+    assert_eq!(acc.percentile(0.0), Some(2.0));
+    assert_eq!(acc.percentile(2.0), Some(9.0));
+  }
+
+  #[test]
+  fn non_numeric_values_are_skipped() {
+    let mut acc = Accumulator::new(Window::Count(10));
+    let accuracy = UnitValue::<crate::unit::UnitAQIAccuracy>::new(crate::unit::AQIAccuracy::High);
+
+    assert!(!acc.push_value(&accuracy));
+    assert!(acc.is_empty());
+  }
+}
@@ -3,12 +3,11 @@ use std::str::FromStr;
 use std::time::Duration;
 
 use bytes::{Bytes, Buf};
-use i2cdev::core::*;
-use i2cdev::linux::LinuxI2CDevice;
 
-#[cfg(feature = "serde")] use serde::{Serialize, ser::{Serializer, SerializeStruct}};
+#[cfg(feature = "serde")] use serde::{Deserialize, Deserializer, Serialize, ser::{Serializer, SerializeStruct}};
 
 use super::error::*;
+use super::transport::{MetrifulTransport, read_block_exact};
 use super::util::*;
 
 /// Supported measurement cycles built in to the MS430.
@@ -24,16 +23,39 @@ pub enum CyclePeriod {
   Period2,
 }
 
+/// Parses a whole number of seconds or minutes, e.g. `3`, `100s` or `5m`.
+fn parse_period_duration(s: &str) -> Option<Duration> {
+  let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+  let (number, suffix) = s.split_at(split);
+  let number: u64 = number.parse().ok()?;
+
+  let multiplier = match suffix.trim() {
+    "" | "s" | "sec" | "secs" => 1,
+    "m" | "min" | "mins" => 60,
+    _ => return None,
+  };
+
+  Some(Duration::from_secs(number.checked_mul(multiplier)?))
+}
+
+/// Accepts register values (`0`, `1`, `2`) or a period given in seconds or
+/// minutes, e.g. `3`, `3s`, `100`, `300s` or `5m`. Durations must exactly match
+/// a supported period; see [`CyclePeriod::from_duration()`] to round instead.
 impl FromStr for CyclePeriod {
   type Err = MetrifulError;
 
   fn from_str(s: &str) -> Result<Self> {
+    let s = s.trim();
     match s {
-      "0" | "3s" => Ok(CyclePeriod::Period0),
-      "1" | "100s" => Ok(CyclePeriod::Period1),
-      "2" | "300s" => Ok(CyclePeriod::Period2),
-      other => Err(MetrifulError::InvalidCyclePeriodString(other.to_string()))
+      "0" => return Ok(CyclePeriod::Period0),
+      "1" => return Ok(CyclePeriod::Period1),
+      "2" => return Ok(CyclePeriod::Period2),
+      _ => (),
     }
+
+    parse_period_duration(s)
+      .and_then(|duration| CyclePeriod::ALL.iter().copied().find(|p| p.to_duration() == duration))
+      .ok_or_else(|| MetrifulError::InvalidCyclePeriodString(s.to_string()))
   }
 }
 
@@ -49,6 +71,23 @@ impl Serialize for CyclePeriod {
   }
 }
 
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for CyclePeriod {
+  fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+  where
+      D: Deserializer<'de>
+  {
+    /// Mirrors the serialized form, e.g. `{"period": "3s"}`
+    #[derive(Deserialize)]
+    struct SerializedCyclePeriod {
+      period: String,
+    }
+
+    let serialized = SerializedCyclePeriod::deserialize(deserializer)?;
+    serialized.period.parse().map_err(serde::de::Error::custom)
+  }
+}
+
 impl fmt::Debug for CyclePeriod {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     f.debug_tuple("CyclePeriod")
@@ -58,6 +97,47 @@ impl fmt::Debug for CyclePeriod {
 }
 
 impl CyclePeriod {
+  /// All supported periods, shortest first.
+  pub const ALL: &'static [CyclePeriod] = &[
+    CyclePeriod::Period0,
+    CyclePeriod::Period1,
+    CyclePeriod::Period2,
+  ];
+
+  /// Returns the supported period nearest to the given duration. Returns an
+  /// error if the duration is less than half the shortest period or more than
+  /// double the longest, i.e. outside of 1.5s to 600s.
+  ///
+  /// # Example
+  /// ```
+  /// use std::time::Duration;
+  /// use metriful::CyclePeriod;
+  ///
+  /// # fn main() -> metriful::error::Result<()> {
+  /// assert_eq!(CyclePeriod::from_duration(Duration::from_secs(5))?, CyclePeriod::Period0);
+  /// assert_eq!(CyclePeriod::from_duration(Duration::from_secs(60))?, CyclePeriod::Period1);
+  /// assert_eq!(CyclePeriod::from_duration(Duration::from_secs(5 * 60))?, CyclePeriod::Period2);
+  /// assert!(CyclePeriod::from_duration(Duration::from_secs(3600)).is_err());
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn from_duration(duration: Duration) -> Result<CyclePeriod> {
+    let shortest = CyclePeriod::Period0.to_duration();
+    let longest = CyclePeriod::Period2.to_duration();
+    if duration < shortest / 2 || duration > longest * 2 {
+      return Err(MetrifulError::InvalidCyclePeriodDuration(duration));
+    }
+
+    let nearest = CyclePeriod::ALL.iter()
+      .min_by_key(|p| {
+        let period = p.to_duration();
+        period.max(duration) - period.min(duration)
+      })
+      .expect("periods are non-empty");
+
+    Ok(*nearest)
+  }
+
   /// Returns a CyclePeriod for a given Metriful register value.
   pub fn from_value(value: u8) -> Result<CyclePeriod> {
     match value {
@@ -90,7 +170,7 @@ impl CyclePeriod {
 
 /// Device operational mode.
 #[derive(Debug, Copy, Clone, PartialEq, Ord, PartialOrd, Eq)]
-#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase", tag = "mode"))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase", tag = "mode"))]
 pub enum OperationalMode {
   Cycle(CyclePeriod),
   Standby
@@ -117,8 +197,8 @@ impl OperationalMode {
   }
 }
 
-#[derive(Debug, Copy, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "kebab-case"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "kebab-case"))]
 pub enum ParticleSensorMode {
   Disabled,
   EnabledPPD42,
@@ -144,22 +224,56 @@ impl ParticleSensorMode {
   }
 }
 
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase", tag = "status"))]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase", tag = "status"))]
 pub enum InterruptStatus<T> {
   Disabled,
   Enabled(T),
 }
 
-#[derive(Debug, Copy, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
 pub enum InterruptMode {
   Latch,
   Comparator
 }
 
-#[derive(Debug, Clone, Copy)]
-#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+impl InterruptMode {
+  /// Returns the Metriful register value for this mode.
+  pub fn to_value(&self) -> u8 {
+    match self {
+      InterruptMode::Latch => 0,
+      InterruptMode::Comparator => 1,
+    }
+  }
+}
+
+/// Accepts `latch` or `comparator`, ignoring case.
+///
+/// # Example
+/// ```
+/// use metriful::InterruptMode;
+///
+/// assert_eq!("latch".parse::<InterruptMode>().unwrap(), InterruptMode::Latch);
+/// assert!("toggle".parse::<InterruptMode>().is_err());
+/// ```
+impl FromStr for InterruptMode {
+  type Err = MetrifulError;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s.trim().to_ascii_lowercase().as_str() {
+      "latch" => Ok(InterruptMode::Latch),
+      "comparator" => Ok(InterruptMode::Comparator),
+      _ => Err(MetrifulError::InvalidOption {
+        name: "interrupt mode".to_string(),
+        value: s.to_string(),
+      }),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
 pub enum InterruptPolarity {
   /// Interrupt triggers when n > threshold
   Positive,
@@ -168,8 +282,34 @@ pub enum InterruptPolarity {
   Negative
 }
 
-#[derive(Debug, Copy, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+impl InterruptPolarity {
+  /// Returns the Metriful register value for this polarity.
+  pub fn to_value(&self) -> u8 {
+    match self {
+      InterruptPolarity::Positive => 0,
+      InterruptPolarity::Negative => 1,
+    }
+  }
+}
+
+/// Accepts `positive` or `negative`, ignoring case.
+impl FromStr for InterruptPolarity {
+  type Err = MetrifulError;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s.trim().to_ascii_lowercase().as_str() {
+      "positive" => Ok(InterruptPolarity::Positive),
+      "negative" => Ok(InterruptPolarity::Negative),
+      _ => Err(MetrifulError::InvalidOption {
+        name: "interrupt polarity".to_string(),
+        value: s.to_string(),
+      }),
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SoundInterrupt {
   pub mode: InterruptMode,
 
@@ -182,22 +322,47 @@ pub struct SoundInterrupt {
 }
 
 impl SoundInterrupt {
-  pub fn read(device: &mut LinuxI2CDevice) -> Result<SoundInterrupt> {
-    let mode = match device.smbus_read_byte_data(0x87)? {
-      0 => InterruptMode::Latch,
-      _ => InterruptMode::Comparator,
-    };
+  pub fn read<D>(device: &mut D) -> Result<SoundInterrupt>
+  where
+    D: MetrifulTransport
+  {
+    let mode = device.read_byte(0x87)?;
+    let mut threshold_bytes = Bytes::from(read_block_exact(device, 0x86, 2)?);
 
-    let mut threshold_bytes = Bytes::from(device.smbus_read_i2c_block_data(0x86, 2)?);
-    Ok(SoundInterrupt {
-      mode,
-      threshold: threshold_bytes.get_u16_le()
-    })
+    Ok(SoundInterrupt::decode(threshold_bytes.get_u16_le(), mode))
+  }
+
+  /// Decodes the threshold (0x86) and mode (0x87) registers.
+  fn decode(threshold: u16, mode: u8) -> SoundInterrupt {
+    SoundInterrupt {
+      mode: match mode {
+        0 => InterruptMode::Latch,
+        _ => InterruptMode::Comparator,
+      },
+      threshold,
+    }
+  }
+
+  /// Writes this interrupt's threshold and mode. This does not enable the
+  /// interrupt; see [`crate::Metriful::configure_sound_interrupt()`].
+  pub fn write<D>(&self, device: &mut D) -> Result<()>
+  where
+    D: MetrifulTransport
+  {
+    device.write_block(0x86, &self.threshold.to_le_bytes())?;
+    device.write_byte_data(0x87, self.mode.to_value())?;
+
+    Ok(())
   }
 }
 
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+/// Desired sound interrupt configuration, as accepted by
+/// [`crate::Metriful::configure_sound_interrupt()`]. This mirrors
+/// [`DeviceStatus::sound_int`].
+pub type SoundInterruptConfig = InterruptStatus<SoundInterrupt>;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct LightInterrupt {
   pub mode: InterruptMode,
 
@@ -209,33 +374,79 @@ pub struct LightInterrupt {
 }
 
 impl LightInterrupt {
-  pub fn read(device: &mut LinuxI2CDevice) -> Result<LightInterrupt> {
-    let mode = match device.smbus_read_byte_data(0x83)? {
-      0 => InterruptMode::Latch,
-      _ => InterruptMode::Comparator,
-    };
+  pub fn read<D>(device: &mut D) -> Result<LightInterrupt>
+  where
+    D: MetrifulTransport
+  {
+    let mode = device.read_byte(0x83)?;
+    let polarity = device.read_byte(0x84)?;
+    let mut threshold_bytes = Bytes::from(read_block_exact(device, 0x82, 3)?);
 
-    let polarity = match device.smbus_read_byte_data(0x84)? {
-      0 => InterruptPolarity::Positive,
-      _ => InterruptPolarity::Negative,
-    };
+    Ok(LightInterrupt::decode(&mut threshold_bytes, mode, polarity))
+  }
 
-    let mut threshold_bytes = Bytes::from(device.smbus_read_i2c_block_data(0x82, 3)?);
+  /// Decodes the threshold (0x82), mode (0x83) and polarity (0x84)
+  /// registers.
+  fn decode(threshold_bytes: &mut Bytes, mode: u8, polarity: u8) -> LightInterrupt {
     let threshold = read_f32_with_u8_denom(
       threshold_bytes.get_u16_le(),
       threshold_bytes.get_u8()
     );
 
-    Ok(LightInterrupt {
-      mode,
-      polarity,
+    LightInterrupt {
+      mode: match mode {
+        0 => InterruptMode::Latch,
+        _ => InterruptMode::Comparator,
+      },
+      polarity: match polarity {
+        0 => InterruptPolarity::Positive,
+        _ => InterruptPolarity::Negative,
+      },
       threshold,
-    })
+    }
+  }
+
+  /// Returns the threshold as written to the device: a u16 integer part
+  /// followed by a single decimal digit.
+  pub fn threshold_bytes(&self) -> [u8; 3] {
+    let threshold = self.threshold.clamp(0.0, u16::MAX as f32);
+    let int_part = threshold.trunc() as u16;
+    let frac_part = ((threshold.fract() * 10.0).round() as u8).min(9);
+    let [lo, hi] = int_part.to_le_bytes();
+
+    [lo, hi, frac_part]
+  }
+
+  /// Returns true if `other` would be stored identically on the device, i.e.
+  /// ignoring threshold precision the device cannot represent.
+  pub fn is_equivalent(&self, other: &LightInterrupt) -> bool {
+    self.mode == other.mode
+      && self.polarity == other.polarity
+      && self.threshold_bytes() == other.threshold_bytes()
+  }
+
+  /// Writes this interrupt's threshold, mode and polarity. This does not
+  /// enable the interrupt; see
+  /// [`crate::Metriful::configure_light_interrupt()`].
+  pub fn write<D>(&self, device: &mut D) -> Result<()>
+  where
+    D: MetrifulTransport
+  {
+    device.write_block(0x82, &self.threshold_bytes())?;
+    device.write_byte_data(0x83, self.mode.to_value())?;
+    device.write_byte_data(0x84, self.polarity.to_value())?;
+
+    Ok(())
   }
 }
 
+/// Desired light interrupt configuration, as accepted by
+/// [`crate::Metriful::configure_light_interrupt()`]. This mirrors
+/// [`DeviceStatus::light_int`].
+pub type LightInterruptConfig = InterruptStatus<LightInterrupt>;
+
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
 pub struct DeviceStatus {
   pub particle_sensor: ParticleSensorMode,
   pub light_int: InterruptStatus<LightInterrupt>,
@@ -243,30 +454,124 @@ pub struct DeviceStatus {
   pub mode: OperationalMode,
 }
 
+/// Length of the interrupt settings block starting at 0x81: the light
+/// interrupt's enable, threshold, mode and polarity (1 + 3 + 1 + 1 bytes),
+/// then the sound interrupt's enable, threshold and mode (1 + 2 + 1 bytes).
+const INTERRUPT_BLOCK_LEN: u8 = 10;
+
 impl DeviceStatus {
-  pub fn read(device: &mut LinuxI2CDevice) -> Result<DeviceStatus> {
-    let particle_sensor = ParticleSensorMode::from_value(
-      device.smbus_read_byte_data(0x07)?
-    )?;
+  /// Returns true if an external particle sensor is enabled, i.e. particle
+  /// readings are meaningful.
+  pub fn particle_sensor_enabled(&self) -> bool {
+    self.particle_sensor != ParticleSensorMode::Disabled
+  }
 
-    let light_int = match device.smbus_read_byte_data(0x81)? {
-      0 => InterruptStatus::Disabled,
-      _ => InterruptStatus::Enabled(LightInterrupt::read(device)?),
+  /// Reads the full device status.
+  ///
+  /// The interrupt settings (0x81-0x87) and the cycle period and mode
+  /// (0x89-0x8A) are each read as one block, so this takes 3 transactions.
+  /// Transports whose [`MetrifulTransport::max_block_len()`] is too short
+  /// for these blocks fall back to reading each register in turn, which
+  /// takes between 4 and 10 transactions.
+  pub fn read<D>(device: &mut D) -> Result<DeviceStatus>
+  where
+    D: MetrifulTransport
+  {
+    DeviceStatus::read_with(device, None)
+  }
+
+  /// Re-reads the device status. When falling back to per-register reads,
+  /// interrupt settings from `self` are reused for interrupts that are still
+  /// enabled, taking at most 5 transactions; changes to the settings of an
+  /// enabled interrupt (e.g. by another process) are then not detected.
+  pub fn refresh<D>(&self, device: &mut D) -> Result<DeviceStatus>
+  where
+    D: MetrifulTransport
+  {
+    DeviceStatus::read_with(device, Some(self))
+  }
+
+  fn read_with<D>(device: &mut D, cached: Option<&DeviceStatus>) -> Result<DeviceStatus>
+  where
+    D: MetrifulTransport
+  {
+    if device.max_block_len() < usize::from(INTERRUPT_BLOCK_LEN) {
+      return DeviceStatus::read_each(device, cached);
+    }
+
+    let mut interrupts = Bytes::from(read_block_exact(device, 0x81, INTERRUPT_BLOCK_LEN)?);
+    let particle_sensor = ParticleSensorMode::from_value(device.read_byte(0x07)?)?;
+
+    // mode and period share a block so a concurrent mode change can't
+    // separate them
+    let mut cycle = Bytes::from(read_block_exact(device, 0x89, 2)?);
+    let period = cycle.get_u8();
+    let mode = match cycle.get_u8() {
+      0 => OperationalMode::Standby,
+      1 => OperationalMode::Cycle(CyclePeriod::from_value(period)?),
+      byte => return Err(MetrifulError::InvalidOperationalMode(byte))
     };
 
-    let sound_int = match device.smbus_read_byte_data(0x86)? {
+    let light_int = match interrupts.get_u8() {
+      0 => {
+        interrupts.advance(5);
+        InterruptStatus::Disabled
+      },
+      _ => {
+        let mut threshold = interrupts.split_to(3);
+        let (mode, polarity) = (interrupts.get_u8(), interrupts.get_u8());
+        InterruptStatus::Enabled(LightInterrupt::decode(&mut threshold, mode, polarity))
+      },
+    };
+
+    let sound_int = match interrupts.get_u8() {
       0 => InterruptStatus::Disabled,
-      _ => InterruptStatus::Enabled(SoundInterrupt::read(device)?)
+      _ => {
+        let threshold = interrupts.get_u16_le();
+        InterruptStatus::Enabled(SoundInterrupt::decode(threshold, interrupts.get_u8()))
+      },
     };
 
-    let mode = match device.smbus_read_byte_data(0x8A)? {
+    Ok(DeviceStatus {
+      particle_sensor,
+      light_int,
+      sound_int,
+      mode,
+    })
+  }
+
+  /// Reads the status one register at a time, for transports that can't
+  /// read the interrupt settings as one block.
+  fn read_each<D>(device: &mut D, cached: Option<&DeviceStatus>) -> Result<DeviceStatus>
+  where
+    D: MetrifulTransport
+  {
+    // mode and period are read back to back as they change most often, so
+    // a concurrent mode change can't separate them
+    let mode = match device.read_byte(0x8A)? {
       0 => OperationalMode::Standby,
       1 => OperationalMode::Cycle(
-        CyclePeriod::from_value(device.smbus_read_byte_data(0x89)?)?
+        CyclePeriod::from_value(device.read_byte(0x89)?)?
       ),
       byte => return Err(MetrifulError::InvalidOperationalMode(byte))
     };
 
+    let particle_sensor = ParticleSensorMode::from_value(
+      device.read_byte(0x07)?
+    )?;
+
+    let light_int = match (device.read_byte(0x81)?, cached.map(|c| &c.light_int)) {
+      (0, _) => InterruptStatus::Disabled,
+      (_, Some(InterruptStatus::Enabled(light))) => InterruptStatus::Enabled(light.clone()),
+      _ => InterruptStatus::Enabled(LightInterrupt::read(device)?),
+    };
+
+    let sound_int = match (device.read_byte(0x85)?, cached.map(|c| &c.sound_int)) {
+      (0, _) => InterruptStatus::Disabled,
+      (_, Some(InterruptStatus::Enabled(sound))) => InterruptStatus::Enabled(*sound),
+      _ => InterruptStatus::Enabled(SoundInterrupt::read(device)?),
+    };
+
     Ok(DeviceStatus {
       particle_sensor,
       light_int,
@@ -275,3 +580,103 @@ impl DeviceStatus {
     })
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing::{FaultPlan, MockDevice};
+
+  /// Limits another transport's block reads, forcing per-register reads.
+  struct Narrow<D>(D);
+
+  impl<D: MetrifulTransport> MetrifulTransport for Narrow<D> {
+    fn write_byte(&mut self, command: u8) -> Result<()> { self.0.write_byte(command) }
+    fn read_byte(&mut self, register: u8) -> Result<u8> { self.0.read_byte(register) }
+    fn write_byte_data(&mut self, register: u8, value: u8) -> Result<()> { self.0.write_byte_data(register, value) }
+    fn read_block(&mut self, register: u8, len: u8) -> Result<Vec<u8>> { self.0.read_block(register, len) }
+    fn write_block(&mut self, register: u8, values: &[u8]) -> Result<()> { self.0.write_block(register, values) }
+
+    fn max_block_len(&self) -> usize {
+      4
+    }
+  }
+
+  fn configured() -> MockDevice {
+    let mock = MockDevice::new();
+    mock.set_register(0x07, &[2]);
+    mock.set_register(0x81, &[1]);
+    mock.set_register(0x82, &[44, 1, 5]);
+    mock.set_register(0x83, &[1]);
+    mock.set_register(0x84, &[1]);
+    mock.set_register(0x85, &[1]);
+    mock.set_register(0x86, &[0x10, 0x27]);
+    mock.set_register(0x87, &[0]);
+    mock.set_register(0x89, &[1]);
+    mock.set_register(0x8A, &[1]);
+    mock
+  }
+
+  fn assert_configured(status: &DeviceStatus) {
+    assert_eq!(status.particle_sensor, ParticleSensorMode::EnabledSDS011);
+    assert_eq!(status.mode, OperationalMode::Cycle(CyclePeriod::Period1));
+    assert_eq!(status.light_int, InterruptStatus::Enabled(LightInterrupt {
+      mode: InterruptMode::Comparator,
+      polarity: InterruptPolarity::Negative,
+      threshold: 300.5,
+    }));
+    assert_eq!(status.sound_int, InterruptStatus::Enabled(SoundInterrupt {
+      mode: InterruptMode::Latch,
+      threshold: 10_000,
+    }));
+  }
+
+  #[test]
+  fn status_is_read_in_blocks() {
+    let plan = FaultPlan::new();
+    let mut device = plan.wrap_device(configured());
+
+    assert_configured(&DeviceStatus::read(&mut device).unwrap());
+    assert_eq!(plan.operations(), 3);
+  }
+
+  #[test]
+  fn narrow_transports_read_each_register() {
+    let plan = FaultPlan::new();
+    let mut device = Narrow(plan.wrap_device(configured()));
+
+    let status = DeviceStatus::read(&mut device).unwrap();
+    assert_configured(&status);
+    assert_eq!(plan.operations(), 10);
+
+    status.refresh(&mut device).unwrap();
+    assert_eq!(plan.operations(), 15);
+  }
+
+  #[test]
+  fn disabled_interrupts_ignore_their_settings() {
+    let mock = configured();
+    mock.set_register(0x81, &[0]);
+    mock.set_register(0x85, &[0]);
+    mock.set_register(0x8A, &[0]);
+    mock.set_register(0x89, &[7]);
+
+    let status = DeviceStatus::read(&mut mock.clone()).unwrap();
+    assert_eq!(status.light_int, InterruptStatus::Disabled);
+    assert_eq!(status.sound_int, InterruptStatus::Disabled);
+    assert_eq!(status.mode, OperationalMode::Standby);
+  }
+
+  #[test]
+  fn invalid_modes_are_rejected() {
+    let mock = configured();
+    mock.set_register(0x89, &[3]);
+    assert!(matches!(DeviceStatus::read(&mut mock.clone()), Err(MetrifulError::InvalidCyclePeriod(3))));
+
+    mock.set_register(0x8A, &[2]);
+    assert!(matches!(DeviceStatus::read(&mut mock.clone()), Err(MetrifulError::InvalidOperationalMode(2))));
+    assert!(matches!(
+      DeviceStatus::read(&mut Narrow(mock.clone())),
+      Err(MetrifulError::InvalidOperationalMode(2))
+    ));
+  }
+}
@@ -0,0 +1,766 @@
+//! Test support: an in-memory MS430 and deterministic fault injection.
+//!
+//! [`MockDevice`] implements [`I2CDevice`] against an in-memory register map
+//! and pairs with a [`MockReadyLine`], so a [`Metriful`](crate::Metriful) can
+//! be driven without hardware. In cycle mode the mock READY line drops for
+//! exactly one poll after each data read, emulating a new cycle.
+//!
+//! A [`FaultPlan`] wraps any device and READY line and injects faults at
+//! specified points: NACKs, bus timeouts, truncated block reads, a stuck READY
+//! line, or a device reset in the middle of a sequence of reads. Plans are
+//! deterministic, so error handling can be exercised in CI.
+//!
+//! # Example
+//! ```
+//! use std::time::Duration;
+//! use metriful::{Metriful, error::MetrifulError, metric::*};
+//! use metriful::testing::*;
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let mock = MockDevice::new();
+//! mock.set_register(0x21, &[21, 5]);
+//!
+//! // the first bus operation after construction fails with a NACK
+//! let plan = FaultPlan::new().inject(FaultTrigger::Register(0x21), Fault::Nack);
+//! let mut metriful = Metriful::try_new_device_timeout(
+//!   plan.wrap_ready(mock.ready_line()),
+//!   plan.wrap_device(mock.clone()),
+//!   Some(Duration::from_millis(100)),
+//! )?;
+//!
+//! let err = metriful.read(METRIC_TEMPERATURE).unwrap_err();
+//! assert!(matches!(err.root(), MetrifulError::I2CError(_)));
+//! assert_eq!(err.context().unwrap().register, 0x21);
+//! assert_eq!(metriful.read(METRIC_TEMPERATURE)?.value, 21.5);
+//! assert_eq!(plan.injected(), vec![Fault::Nack]);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A stuck READY line surfaces as a timeout:
+//! ```
+//! use std::time::Duration;
+//! use metriful::{Metriful, error::{MetrifulError, ReadyWait}};
+//! use metriful::testing::*;
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let mock = MockDevice::new();
+//! let plan = FaultPlan::new().inject(FaultTrigger::Command(0xE1), Fault::StuckReady(100));
+//! let mut metriful = Metriful::try_new_device_timeout(
+//!   plan.wrap_ready(mock.ready_line()),
+//!   plan.wrap_device(mock.clone()),
+//!   Some(Duration::from_millis(100)),
+//! )?;
+//!
+//! metriful.execute_measurement()?;
+//! let res = metriful.wait_for_ready_timeout(Some(Duration::from_millis(50)));
+//! assert!(matches!(
+//!   res,
+//!   Err(MetrifulError::ReadyTimeoutExceeded { operation: "wait_for_ready", wait: ReadyWait::Ready, .. })
+//! ));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A truncated block read is reported rather than decoded:
+//! ```
+//! use std::time::Duration;
+//! use metriful::{Metriful, error::MetrifulError, metric::*};
+//! use metriful::testing::*;
+//!
+//! # fn main() -> metriful::error::Result<()> {
+//! let mock = MockDevice::new();
+//! let plan = FaultPlan::new().inject(FaultTrigger::Register(0x10), Fault::TruncatedRead(5));
+//! let mut metriful = Metriful::try_new_device_timeout(
+//!   plan.wrap_ready(mock.ready_line()),
+//!   plan.wrap_device(mock.clone()),
+//!   Some(Duration::from_millis(100)),
+//! )?;
+//!
+//! let res = metriful.read(METRIC_COMBINED_AIR_DATA);
+//! assert!(matches!(res, Err(MetrifulError::ShortRead { register: 0x10, expected: 12, actual: 5 })));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use i2cdev::core::I2CDevice;
+use i2cdev::linux::LinuxI2CError;
+
+use crate::error::*;
+use crate::ready::ReadyLine;
+
+/// Linux errno reported by i2c adapters when a transfer is not acknowledged.
+const EREMOTEIO: i32 = 121;
+
+/// Linux errno reported when a bus transaction times out.
+const ETIMEDOUT: i32 = 110;
+
+/// Data registers and their lengths in bytes.
+const DATA_REGISTERS: &[(u8, usize)] = &[
+  (0x21, 2), (0x22, 4), (0x23, 2), (0x24, 4),
+  (0x25, 3), (0x26, 3), (0x27, 3), (0x28, 1),
+  (0x31, 3), (0x32, 2),
+  (0x41, 2), (0x42, 12), (0x43, 3), (0x44, 1),
+  (0x51, 2), (0x52, 3), (0x53, 1),
+];
+
+/// Combined read registers and the data registers they concatenate.
+const COMBINED_REGISTERS: &[(u8, &[u8])] = &[
+  (0x10, &[0x21, 0x22, 0x23, 0x24]),
+  (0x11, &[0x25, 0x26, 0x27, 0x28]),
+  (0x12, &[0x31, 0x32]),
+  (0x13, &[0x41, 0x42, 0x43, 0x44]),
+  (0x14, &[0x51, 0x52, 0x53]),
+];
+
+/// Configuration registers and their power-on values.
+const CONFIG_DEFAULTS: &[(u8, &[u8])] = &[
+  (0x07, &[0]),
+  (0x81, &[0]), (0x82, &[0, 0, 0]), (0x83, &[0]), (0x84, &[0]),
+  (0x85, &[0]), (0x86, &[0, 0]), (0x87, &[0]),
+  (0x89, &[0]), (0x8A, &[0]),
+];
+
+fn is_config_register(register: u8) -> bool {
+  CONFIG_DEFAULTS.iter().any(|(r, _)| *r == register)
+}
+
+fn is_data_register(register: u8) -> bool {
+  DATA_REGISTERS.iter().any(|(r, _)| *r == register)
+    || COMBINED_REGISTERS.iter().any(|(r, _)| *r == register)
+}
+
+#[derive(Debug)]
+struct MockState {
+  registers: HashMap<u8, Vec<u8>>,
+  ready: bool,
+  cycle_pending: bool,
+  commands: Vec<u8>,
+}
+
+impl MockState {
+  fn new() -> MockState {
+    let mut state = MockState {
+      registers: HashMap::new(),
+      ready: true,
+      cycle_pending: false,
+      commands: Vec::new(),
+    };
+
+    for (register, len) in DATA_REGISTERS {
+      state.registers.insert(*register, vec![0; *len]);
+    }
+
+    state.reset();
+    state
+  }
+
+  fn reset(&mut self) {
+    for (register, value) in CONFIG_DEFAULTS {
+      self.registers.insert(*register, value.to_vec());
+    }
+
+    self.cycle_pending = false;
+  }
+
+  fn is_cycle(&self) -> bool {
+    self.registers.get(&0x8A).map(|v| v[0] == 1).unwrap_or(false)
+  }
+
+  fn read(&mut self, register: u8, len: usize) -> Vec<u8> {
+    let mut ret = match COMBINED_REGISTERS.iter().find(|(r, _)| *r == register) {
+      Some((_, parts)) => parts.iter()
+        .flat_map(|p| self.registers.get(p).cloned().unwrap_or_default())
+        .collect(),
+      None if is_config_register(register) => self.read_config(register, len),
+      None => self.registers.get(&register).cloned().unwrap_or_default(),
+    };
+
+    if self.is_cycle() && is_data_register(register) {
+      self.cycle_pending = true;
+    }
+
+    ret.resize(len, 0);
+    ret
+  }
+
+  /// Reads consecutive settings registers, as the MS430 allows reading
+  /// e.g. all interrupt settings in one block.
+  fn read_config(&self, register: u8, len: usize) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(len);
+
+    for next in register..=u8::MAX {
+      match self.registers.get(&next) {
+        Some(value) if ret.len() < len && is_config_register(next) => ret.extend_from_slice(value),
+        _ => break,
+      }
+    }
+
+    ret
+  }
+
+  fn write(&mut self, register: u8, values: &[u8]) {
+    self.registers.insert(register, values.to_vec());
+  }
+
+  fn command(&mut self, command: u8) {
+    self.commands.push(command);
+
+    match command {
+      0xE2 => self.reset(),
+      0xE4 => {
+        self.write(0x8A, &[1]);
+        self.cycle_pending = false;
+      },
+      0xE5 => self.write(0x8A, &[0]),
+      _ => (),
+    }
+  }
+}
+
+/// An in-memory MS430 register map implementing [`I2CDevice`].
+///
+/// Clones share the same underlying state, so a test may keep a handle to
+/// inspect or modify the device after handing a clone to a
+/// [`Metriful`](crate::Metriful).
+#[derive(Debug, Clone)]
+pub struct MockDevice {
+  state: Arc<Mutex<MockState>>,
+}
+
+impl Default for MockDevice {
+  fn default() -> Self {
+    MockDevice::new()
+  }
+}
+
+impl MockDevice {
+  /// Creates a new mock device in standby mode with all data registers
+  /// zeroed.
+  pub fn new() -> MockDevice {
+    MockDevice {
+      state: Arc::new(Mutex::new(MockState::new())),
+    }
+  }
+
+  fn state(&self) -> MutexGuard<'_, MockState> {
+    self.state.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Returns a READY line reflecting this device's state.
+  pub fn ready_line(&self) -> MockReadyLine {
+    MockReadyLine {
+      state: Arc::clone(&self.state),
+    }
+  }
+
+  /// Sets the raw contents of a register.
+  pub fn set_register(&self, register: u8, values: &[u8]) {
+    self.state().write(register, values);
+  }
+
+  /// Returns the raw contents of a register.
+  pub fn register(&self, register: u8) -> Vec<u8> {
+    self.state().registers.get(&register).cloned().unwrap_or_default()
+  }
+
+  /// Forces the READY line to the given state.
+  pub fn set_ready(&self, ready: bool) {
+    self.state().ready = ready;
+  }
+
+  /// Returns all command bytes (0xE1-0xE7) received so far, in order.
+  pub fn commands(&self) -> Vec<u8> {
+    self.state().commands.clone()
+  }
+}
+
+impl I2CDevice for MockDevice {
+  type Error = LinuxI2CError;
+
+  fn read(&mut self, data: &mut [u8]) -> std::result::Result<(), Self::Error> {
+    for b in data.iter_mut() {
+      *b = 0;
+    }
+
+    Ok(())
+  }
+
+  fn write(&mut self, data: &[u8]) -> std::result::Result<(), Self::Error> {
+    match data {
+      [] => (),
+      [command] => self.state().command(*command),
+      [register, values @ ..] => self.state().write(*register, values),
+    }
+
+    Ok(())
+  }
+
+  fn smbus_write_quick(&mut self, _bit: bool) -> std::result::Result<(), Self::Error> {
+    Ok(())
+  }
+
+  fn smbus_write_byte(&mut self, value: u8) -> std::result::Result<(), Self::Error> {
+    self.state().command(value);
+    Ok(())
+  }
+
+  fn smbus_read_byte_data(&mut self, register: u8) -> std::result::Result<u8, Self::Error> {
+    Ok(self.state().read(register, 1)[0])
+  }
+
+  fn smbus_write_byte_data(&mut self, register: u8, value: u8) -> std::result::Result<(), Self::Error> {
+    self.state().write(register, &[value]);
+    Ok(())
+  }
+
+  fn smbus_read_block_data(&mut self, register: u8) -> std::result::Result<Vec<u8>, Self::Error> {
+    let mut state = self.state();
+    let len = state.registers.get(&register).map(Vec::len).unwrap_or(0);
+    Ok(state.read(register, len))
+  }
+
+  fn smbus_read_i2c_block_data(&mut self, register: u8, len: u8) -> std::result::Result<Vec<u8>, Self::Error> {
+    Ok(self.state().read(register, len as usize))
+  }
+
+  fn smbus_write_block_data(&mut self, register: u8, values: &[u8]) -> std::result::Result<(), Self::Error> {
+    self.state().write(register, values);
+    Ok(())
+  }
+
+  fn smbus_write_i2c_block_data(&mut self, register: u8, values: &[u8]) -> std::result::Result<(), Self::Error> {
+    self.state().write(register, values);
+    Ok(())
+  }
+
+  fn smbus_process_block(&mut self, register: u8, values: &[u8]) -> std::result::Result<Vec<u8>, Self::Error> {
+    let mut state = self.state();
+    state.write(register, values);
+    Ok(state.read(register, values.len()))
+  }
+}
+
+/// The READY line of a [`MockDevice`].
+#[derive(Debug, Clone)]
+pub struct MockReadyLine {
+  state: Arc<Mutex<MockState>>,
+}
+
+impl ReadyLine for MockReadyLine {
+  fn is_ready(&self) -> Result<bool> {
+    let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+    if !state.ready {
+      return Ok(false);
+    }
+
+    // emulate the start of a new cycle after each read in cycle mode
+    if state.is_cycle() && state.cycle_pending {
+      state.cycle_pending = false;
+      return Ok(false);
+    }
+
+    Ok(true)
+  }
+}
+
+/// A fault to inject.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Fault {
+  /// The operation fails as if the device did not acknowledge it.
+  Nack,
+
+  /// The operation fails with a bus timeout.
+  Timeout,
+
+  /// A block read returns only the given number of bytes. Has no effect on
+  /// other operations.
+  TruncatedRead(u8),
+
+  /// The READY line reports not-ready for the given number of subsequent
+  /// polls. The triggering operation itself succeeds.
+  StuckReady(usize),
+
+  /// The device is sent a reset command immediately before the triggering
+  /// operation, which then proceeds against the freshly reset device.
+  DeviceReset,
+}
+
+/// Determines when a [`Fault`] is injected. Each planned fault fires once.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FaultTrigger {
+  /// The nth bus operation (zero-based) issued through the wrapped device.
+  Operation(usize),
+
+  /// The next operation addressing the given register.
+  Register(u8),
+
+  /// The next write of the given command byte, e.g. `0xE1`.
+  Command(u8),
+}
+
+impl FaultTrigger {
+  fn matches(&self, operation: usize, register: Option<u8>, command: Option<u8>) -> bool {
+    match self {
+      FaultTrigger::Operation(n) => *n == operation,
+      FaultTrigger::Register(r) => register == Some(*r),
+      FaultTrigger::Command(c) => command == Some(*c),
+    }
+  }
+}
+
+#[derive(Debug, Default)]
+struct PlanState {
+  pending: Vec<(FaultTrigger, Fault)>,
+  operations: usize,
+  stuck_polls: usize,
+  injected: Vec<Fault>,
+}
+
+/// A set of faults to inject into a device and READY line.
+///
+/// Clones share state, so the plan may be inspected after the wrapped device
+/// has been moved into a [`Metriful`](crate::Metriful).
+#[derive(Debug, Clone, Default)]
+pub struct FaultPlan {
+  state: Arc<Mutex<PlanState>>,
+}
+
+impl FaultPlan {
+  pub fn new() -> FaultPlan {
+    FaultPlan::default()
+  }
+
+  fn state(&self) -> MutexGuard<'_, PlanState> {
+    self.state.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Adds a fault to the plan. Faults with matching triggers fire in the
+  /// order they were added.
+  pub fn inject(self, trigger: FaultTrigger, fault: Fault) -> FaultPlan {
+    self.state().pending.push((trigger, fault));
+    self
+  }
+
+  /// Returns the number of bus operations observed so far.
+  pub fn operations(&self) -> usize {
+    self.state().operations
+  }
+
+  /// Returns all faults that have fired so far, in order.
+  pub fn injected(&self) -> Vec<Fault> {
+    self.state().injected.clone()
+  }
+
+  /// Returns true if every planned fault has fired.
+  pub fn is_exhausted(&self) -> bool {
+    self.state().pending.is_empty()
+  }
+
+  /// Wraps an I2C device so that planned bus faults are injected.
+  pub fn wrap_device<D>(&self, device: D) -> FaultyDevice<D>
+  where
+    D: I2CDevice<Error = LinuxI2CError>
+  {
+    FaultyDevice {
+      inner: device,
+      plan: self.clone(),
+    }
+  }
+
+  /// Wraps a READY line so that [`Fault::StuckReady`] can take effect.
+  pub fn wrap_ready<R: ReadyLine>(&self, ready: R) -> FaultyReadyLine<R> {
+    FaultyReadyLine {
+      inner: ready,
+      plan: self.clone(),
+    }
+  }
+
+  /// Records a bus operation and returns the fault to apply to it, if any.
+  fn next_fault(&self, register: Option<u8>, command: Option<u8>) -> Option<Fault> {
+    let mut state = self.state();
+    let operation = state.operations;
+    state.operations += 1;
+
+    let index = state.pending.iter()
+      .position(|(trigger, _)| trigger.matches(operation, register, command))?;
+
+    let (_, fault) = state.pending.remove(index);
+    state.injected.push(fault);
+
+    if let Fault::StuckReady(polls) = fault {
+      state.stuck_polls += polls;
+    }
+
+    Some(fault)
+  }
+}
+
+fn fault_error(errno: i32) -> LinuxI2CError {
+  LinuxI2CError::Io(io::Error::from_raw_os_error(errno))
+}
+
+/// An I2C device wrapper that injects faults from a [`FaultPlan`].
+#[derive(Debug)]
+pub struct FaultyDevice<D> {
+  inner: D,
+  plan: FaultPlan,
+}
+
+impl<D> FaultyDevice<D> where D: I2CDevice<Error = LinuxI2CError> {
+  /// Returns the wrapped device.
+  pub fn into_inner(self) -> D {
+    self.inner
+  }
+
+  /// Applies any planned fault for an operation, returning the truncation
+  /// length for block reads if one was requested.
+  fn apply(
+    &mut self,
+    register: Option<u8>,
+    command: Option<u8>,
+  ) -> std::result::Result<Option<u8>, LinuxI2CError> {
+    match self.plan.next_fault(register, command) {
+      Some(Fault::Nack) => Err(fault_error(EREMOTEIO)),
+      Some(Fault::Timeout) => Err(fault_error(ETIMEDOUT)),
+      Some(Fault::TruncatedRead(len)) => Ok(Some(len)),
+      Some(Fault::DeviceReset) => {
+        self.inner.smbus_write_byte(0xE2)?;
+        Ok(None)
+      },
+      Some(Fault::StuckReady(_)) | None => Ok(None),
+    }
+  }
+
+  fn truncate(values: Vec<u8>, len: Option<u8>) -> Vec<u8> {
+    match len {
+      Some(len) => values.into_iter().take(len as usize).collect(),
+      None => values,
+    }
+  }
+}
+
+impl<D> I2CDevice for FaultyDevice<D> where D: I2CDevice<Error = LinuxI2CError> {
+  type Error = LinuxI2CError;
+
+  fn read(&mut self, data: &mut [u8]) -> std::result::Result<(), Self::Error> {
+    self.apply(None, None)?;
+    self.inner.read(data)
+  }
+
+  fn write(&mut self, data: &[u8]) -> std::result::Result<(), Self::Error> {
+    match data {
+      [command] => self.apply(None, Some(*command))?,
+      [register, ..] => self.apply(Some(*register), None)?,
+      [] => self.apply(None, None)?,
+    };
+
+    self.inner.write(data)
+  }
+
+  fn smbus_write_quick(&mut self, bit: bool) -> std::result::Result<(), Self::Error> {
+    self.apply(None, None)?;
+    self.inner.smbus_write_quick(bit)
+  }
+
+  fn smbus_write_byte(&mut self, value: u8) -> std::result::Result<(), Self::Error> {
+    self.apply(None, Some(value))?;
+    self.inner.smbus_write_byte(value)
+  }
+
+  fn smbus_read_byte_data(&mut self, register: u8) -> std::result::Result<u8, Self::Error> {
+    self.apply(Some(register), None)?;
+    self.inner.smbus_read_byte_data(register)
+  }
+
+  fn smbus_write_byte_data(&mut self, register: u8, value: u8) -> std::result::Result<(), Self::Error> {
+    self.apply(Some(register), None)?;
+    self.inner.smbus_write_byte_data(register, value)
+  }
+
+  fn smbus_read_block_data(&mut self, register: u8) -> std::result::Result<Vec<u8>, Self::Error> {
+    let len = self.apply(Some(register), None)?;
+    Ok(Self::truncate(self.inner.smbus_read_block_data(register)?, len))
+  }
+
+  fn smbus_read_i2c_block_data(&mut self, register: u8, len: u8) -> std::result::Result<Vec<u8>, Self::Error> {
+    let truncated = self.apply(Some(register), None)?;
+    Ok(Self::truncate(self.inner.smbus_read_i2c_block_data(register, len)?, truncated))
+  }
+
+  fn smbus_write_block_data(&mut self, register: u8, values: &[u8]) -> std::result::Result<(), Self::Error> {
+    self.apply(Some(register), None)?;
+    self.inner.smbus_write_block_data(register, values)
+  }
+
+  fn smbus_write_i2c_block_data(&mut self, register: u8, values: &[u8]) -> std::result::Result<(), Self::Error> {
+    self.apply(Some(register), None)?;
+    self.inner.smbus_write_i2c_block_data(register, values)
+  }
+
+  fn smbus_process_block(&mut self, register: u8, values: &[u8]) -> std::result::Result<Vec<u8>, Self::Error> {
+    let len = self.apply(Some(register), None)?;
+    Ok(Self::truncate(self.inner.smbus_process_block(register, values)?, len))
+  }
+}
+
+/// A READY line wrapper that honors [`Fault::StuckReady`].
+#[derive(Debug)]
+pub struct FaultyReadyLine<R> {
+  inner: R,
+  plan: FaultPlan,
+}
+
+impl<R> ReadyLine for FaultyReadyLine<R> where R: ReadyLine {
+  fn is_ready(&self) -> Result<bool> {
+    {
+      let mut state = self.plan.state();
+      if state.stuck_polls > 0 {
+        state.stuck_polls -= 1;
+        return Ok(false);
+      }
+    }
+
+    self.inner.is_ready()
+  }
+
+  fn take_ready_edges(&self) -> Result<Option<u32>> {
+    self.inner.take_ready_edges()
+  }
+
+  fn release(&self) -> Result<()> {
+    self.inner.release()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn combined_registers_concatenate_data_registers() {
+    let mut mock = MockDevice::new();
+    mock.set_register(0x31, &[1, 2, 3]);
+    mock.set_register(0x32, &[4, 5]);
+
+    assert_eq!(mock.smbus_read_i2c_block_data(0x12, 5).unwrap(), vec![1, 2, 3, 4, 5]);
+  }
+
+  #[test]
+  fn short_registers_are_zero_padded() {
+    let mut mock = MockDevice::new();
+    assert_eq!(mock.smbus_read_i2c_block_data(0x21, 4).unwrap(), vec![0; 4]);
+  }
+
+  #[test]
+  fn ready_drops_once_after_each_cycle_read() {
+    let mut mock = MockDevice::new();
+    let ready = mock.ready_line();
+
+    // standby: reads don't start a new cycle
+    mock.smbus_read_i2c_block_data(0x21, 2).unwrap();
+    assert!(ready.is_ready().unwrap());
+
+    mock.smbus_write_byte(0xE4).unwrap();
+    mock.smbus_read_i2c_block_data(0x21, 2).unwrap();
+    assert!(!ready.is_ready().unwrap());
+    assert!(ready.is_ready().unwrap());
+  }
+
+  #[test]
+  fn reset_restores_config_defaults() {
+    let mut mock = MockDevice::new();
+    mock.smbus_write_byte_data(0x81, 1).unwrap();
+    mock.smbus_write_byte(0xE4).unwrap();
+
+    mock.smbus_write_byte(0xE2).unwrap();
+    assert_eq!(mock.register(0x81), vec![0]);
+    assert_eq!(mock.register(0x8A), vec![0]);
+    assert_eq!(mock.commands(), vec![0xE4, 0xE2]);
+  }
+
+  #[test]
+  fn faults_fire_once_in_order() {
+    let plan = FaultPlan::new()
+      .inject(FaultTrigger::Register(0x21), Fault::Nack)
+      .inject(FaultTrigger::Register(0x21), Fault::Timeout);
+    let mut device = plan.wrap_device(MockDevice::new());
+
+    let errno = |e: LinuxI2CError| match e {
+      LinuxI2CError::Io(e) => e.raw_os_error(),
+      _ => None,
+    };
+
+    assert_eq!(device.smbus_read_byte_data(0x21).map_err(errno), Err(Some(EREMOTEIO)));
+    assert_eq!(device.smbus_read_byte_data(0x21).map_err(errno), Err(Some(ETIMEDOUT)));
+    assert!(device.smbus_read_byte_data(0x21).is_ok());
+
+    assert!(plan.is_exhausted());
+    assert_eq!(plan.operations(), 3);
+    assert_eq!(plan.injected(), vec![Fault::Nack, Fault::Timeout]);
+  }
+
+  #[test]
+  fn operation_triggers_count_all_operations() {
+    let plan = FaultPlan::new().inject(FaultTrigger::Operation(2), Fault::Nack);
+    let mut device = plan.wrap_device(MockDevice::new());
+
+    assert!(device.smbus_write_byte(0xE1).is_ok());
+    assert!(device.smbus_read_byte_data(0x8A).is_ok());
+    assert!(device.smbus_read_byte_data(0x8A).is_err());
+    assert!(device.smbus_read_byte_data(0x8A).is_ok());
+  }
+
+  #[test]
+  fn command_triggers_ignore_register_access() {
+    let plan = FaultPlan::new().inject(FaultTrigger::Command(0xE4), Fault::Nack);
+    let mock = MockDevice::new();
+    let mut device = plan.wrap_device(mock.clone());
+
+    assert!(device.smbus_write_byte_data(0xE4, 0).is_ok());
+    assert!(device.smbus_write_byte(0xE5).is_ok());
+    assert!(device.smbus_write_byte(0xE4).is_err());
+
+    // the failed command never reached the device
+    assert_eq!(mock.commands(), vec![0xE5]);
+  }
+
+  #[test]
+  fn truncation_only_affects_block_reads() {
+    let plan = FaultPlan::new()
+      .inject(FaultTrigger::Register(0x8A), Fault::TruncatedRead(0))
+      .inject(FaultTrigger::Register(0x10), Fault::TruncatedRead(4));
+    let mut device = plan.wrap_device(MockDevice::new());
+
+    assert!(device.smbus_read_byte_data(0x8A).is_ok());
+    assert_eq!(device.smbus_read_i2c_block_data(0x10, 12).unwrap().len(), 4);
+  }
+
+  #[test]
+  fn stuck_ready_lasts_for_the_given_polls() {
+    let mock = MockDevice::new();
+    let plan = FaultPlan::new().inject(FaultTrigger::Command(0xE1), Fault::StuckReady(2));
+    let mut device = plan.wrap_device(mock.clone());
+    let ready = plan.wrap_ready(mock.ready_line());
+
+    device.smbus_write_byte(0xE1).unwrap();
+    assert!(!ready.is_ready().unwrap());
+    assert!(!ready.is_ready().unwrap());
+    assert!(ready.is_ready().unwrap());
+  }
+
+  #[test]
+  fn device_reset_precedes_the_operation() {
+    let mock = MockDevice::new();
+    let plan = FaultPlan::new().inject(FaultTrigger::Register(0x8A), Fault::DeviceReset);
+    let mut device = plan.wrap_device(mock.clone());
+
+    device.smbus_write_byte(0xE4).unwrap();
+    assert_eq!(device.smbus_read_byte_data(0x8A).unwrap(), 0);
+    assert_eq!(mock.commands(), vec![0xE4, 0xE2]);
+  }
+}
@@ -0,0 +1,597 @@
+//! The bus interface used to communicate with the MS430.
+//!
+//! [`Metriful`](crate::Metriful) talks to the device exclusively through
+//! [`MetrifulTransport`]. Any [`I2CDevice`] whose error converts into a
+//! [`MetrifulError`] (e.g. [`LinuxI2CDevice`](i2cdev::linux::LinuxI2CDevice))
+//! is a transport already; other backends such as USB-I2C bridges or network
+//! proxies may implement the trait directly.
+//!
+//! # Example
+//! ```
+//! use std::collections::HashMap;
+//! use metriful::error::*;
+//! use metriful::metric::*;
+//! use metriful::transport::MetrifulTransport;
+//!
+//! /// A trivial in-memory register map
+//! #[derive(Default)]
+//! struct Registers(HashMap<u8, Vec<u8>>);
+//!
+//! impl MetrifulTransport for Registers {
+//!   fn write_byte(&mut self, _command: u8) -> Result<()> {
+//!     Ok(())
+//!   }
+//!
+//!   fn read_byte(&mut self, register: u8) -> Result<u8> {
+//!     Ok(self.read_block(register, 1)?[0])
+//!   }
+//!
+//!   fn write_byte_data(&mut self, register: u8, value: u8) -> Result<()> {
+//!     self.write_block(register, &[value])
+//!   }
+//!
+//!   fn read_block(&mut self, register: u8, len: u8) -> Result<Vec<u8>> {
+//!     let mut bytes = self.0.get(&register).cloned().unwrap_or_default();
+//!     bytes.resize(len as usize, 0);
+//!     Ok(bytes)
+//!   }
+//!
+//!   fn write_block(&mut self, register: u8, values: &[u8]) -> Result<()> {
+//!     self.0.insert(register, values.to_vec());
+//!     Ok(())
+//!   }
+//! }
+//!
+//! let mut registers = Registers::default();
+//! registers.write_block(0x31, &[41, 0, 5])?;
+//!
+//! let illuminance = METRIC_ILLUMINANCE.read(&mut registers)?;
+//! assert_eq!(illuminance.value, 41.5);
+//! # Ok::<(), MetrifulError>(())
+//! ```
+//!
+//! Bus operations are normally only bounded by the kernel's adapter timeout,
+//! if any; wrap a transport in a [`TimeoutTransport`] to bound them further.
+//!
+//! SMBus block reads, used by the blanket [`I2CDevice`] implementation, are
+//! limited to [`SMBUS_BLOCK_MAX`] bytes. [`LinuxTransport`] can instead read
+//! via plain I2C transfers (the `I2C_RDWR` ioctl), falling back to SMBus on
+//! adapters that don't support them.
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use i2cdev::core::{I2CDevice, I2CMessage, I2CTransfer};
+use i2cdev::linux::{LinuxI2CDevice, LinuxI2CError, LinuxI2CMessage};
+use log::warn;
+
+use crate::error::*;
+
+/// Maximum length of an SMBus block read.
+pub const SMBUS_BLOCK_MAX: usize = 32;
+
+/// Linux errno reported by adapters that don't support plain I2C transfers.
+const EOPNOTSUPP: i32 = 95;
+
+/// A bus capable of the register operations needed to drive an MS430.
+pub trait MetrifulTransport {
+  /// Sends a single-byte command, e.g. `0xE2` to reset the device.
+  fn write_byte(&mut self, command: u8) -> Result<()>;
+
+  /// Reads a single byte from the given register.
+  fn read_byte(&mut self, register: u8) -> Result<u8>;
+
+  /// Writes a single byte to the given register.
+  fn write_byte_data(&mut self, register: u8, value: u8) -> Result<()>;
+
+  /// Reads `len` bytes starting at the given register.
+  fn read_block(&mut self, register: u8, len: u8) -> Result<Vec<u8>>;
+
+  /// Writes a block of bytes starting at the given register.
+  fn write_block(&mut self, register: u8, values: &[u8]) -> Result<()>;
+
+  /// Returns the longest block [`MetrifulTransport::read_block()`] can read
+  /// in one transaction. Defaults to [`SMBUS_BLOCK_MAX`].
+  fn max_block_len(&self) -> usize {
+    SMBUS_BLOCK_MAX
+  }
+
+  /// Sets the instant by which subsequent operations must complete, or
+  /// clears it. [`Metriful`](crate::Metriful)'s `*_timeout` methods set this
+  /// for their duration so that transports supporting transaction timeouts
+  /// (e.g. [`TimeoutTransport`]) can honor the caller's timeout. Ignored by
+  /// default.
+  fn set_deadline(&mut self, _deadline: Option<Instant>) {}
+}
+
+impl<D> MetrifulTransport for D
+where
+  D: I2CDevice,
+  D::Error: Into<MetrifulError>,
+{
+  fn write_byte(&mut self, command: u8) -> Result<()> {
+    self.smbus_write_byte(command).map_err(device_error)
+  }
+
+  fn read_byte(&mut self, register: u8) -> Result<u8> {
+    self.smbus_read_byte_data(register).map_err(device_error)
+  }
+
+  fn write_byte_data(&mut self, register: u8, value: u8) -> Result<()> {
+    self.smbus_write_byte_data(register, value).map_err(device_error)
+  }
+
+  fn read_block(&mut self, register: u8, len: u8) -> Result<Vec<u8>> {
+    self.smbus_read_i2c_block_data(register, len).map_err(device_error)
+  }
+
+  fn write_block(&mut self, register: u8, values: &[u8]) -> Result<()> {
+    self.smbus_write_i2c_block_data(register, values).map_err(device_error)
+  }
+}
+
+/// Converts an [`I2CDevice`] error such that its OS error number, if any, is
+/// available via [`MetrifulError::raw_os_error()`].
+fn device_error(e: impl Into<MetrifulError>) -> MetrifulError {
+  e.into().normalized()
+}
+
+/// Reads exactly `len` bytes starting at the given register, returning
+/// [`MetrifulError::ShortRead`] if the transport returns fewer. Any extra
+/// bytes are discarded.
+///
+/// Decoding functions such as
+/// [`MetrifulUnit::from_bytes()`](crate::unit::MetrifulUnit::from_bytes)
+/// assume a complete buffer, so all block reads of data to be decoded should
+/// go through this.
+pub fn read_block_exact<D>(device: &mut D, register: u8, len: u8) -> Result<Vec<u8>>
+where
+  D: MetrifulTransport + ?Sized
+{
+  let mut bytes = device.read_block(register, len)?;
+  if bytes.len() < len as usize {
+    return Err(MetrifulError::ShortRead {
+      register,
+      expected: len as usize,
+      actual: bytes.len(),
+    });
+  }
+
+  bytes.truncate(len as usize);
+  Ok(bytes)
+}
+
+/// A transport wrapper that attaches [`ErrorContext`] to bus errors.
+pub(crate) struct WithContext<'a, D: ?Sized> {
+  device: &'a mut D,
+  operation: &'static str,
+}
+
+/// Wraps a transport so that its bus errors name the given operation, along
+/// with the register accessed.
+pub(crate) fn with_context<'a, D>(device: &'a mut D, operation: &'static str) -> WithContext<'a, D>
+where
+  D: MetrifulTransport + ?Sized
+{
+  WithContext { device, operation }
+}
+
+impl<D> WithContext<'_, D> where D: MetrifulTransport + ?Sized {
+  fn context(&self, register: u8, phase: BusPhase) -> impl FnOnce(MetrifulError) -> MetrifulError {
+    let context = ErrorContext { operation: self.operation, register, phase };
+    move |e| e.with_context(context)
+  }
+}
+
+impl<D> MetrifulTransport for WithContext<'_, D> where D: MetrifulTransport + ?Sized {
+  fn write_byte(&mut self, command: u8) -> Result<()> {
+    let context = self.context(command, BusPhase::Command);
+    self.device.write_byte(command).map_err(context)
+  }
+
+  fn read_byte(&mut self, register: u8) -> Result<u8> {
+    let context = self.context(register, BusPhase::Read);
+    self.device.read_byte(register).map_err(context)
+  }
+
+  fn write_byte_data(&mut self, register: u8, value: u8) -> Result<()> {
+    let context = self.context(register, BusPhase::Write);
+    self.device.write_byte_data(register, value).map_err(context)
+  }
+
+  fn read_block(&mut self, register: u8, len: u8) -> Result<Vec<u8>> {
+    let context = self.context(register, BusPhase::Read);
+    self.device.read_block(register, len).map_err(context)
+  }
+
+  fn write_block(&mut self, register: u8, values: &[u8]) -> Result<()> {
+    let context = self.context(register, BusPhase::Write);
+    self.device.write_block(register, values).map_err(context)
+  }
+
+  fn max_block_len(&self) -> usize {
+    self.device.max_block_len()
+  }
+
+  fn set_deadline(&mut self, deadline: Option<Instant>) {
+    self.device.set_deadline(deadline);
+  }
+}
+
+/// How a [`LinuxTransport`] performs block reads.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum TransferMode {
+  /// SMBus block reads, limited to [`SMBUS_BLOCK_MAX`] bytes
+  #[default]
+  Smbus,
+
+  /// Plain I2C write-then-read transfers via the `I2C_RDWR` ioctl, up to 255
+  /// bytes. If the adapter doesn't support these, the transport falls back to
+  /// SMBus.
+  Rdwr,
+}
+
+/// A Linux I2C device with a selectable [`TransferMode`].
+///
+/// Commands and writes always use SMBus; only block reads are affected by the
+/// transfer mode.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use metriful::Metriful;
+/// use metriful::ready::{ReadyPolarity, SysfsReadyLine};
+/// use metriful::transport::{LinuxTransport, TransferMode};
+///
+/// # fn main() -> metriful::error::Result<()> {
+/// let transport = LinuxTransport::open("/dev/i2c-1", 0x71, TransferMode::Rdwr)?;
+/// let ready = SysfsReadyLine::new(17, ReadyPolarity::ActiveLow)?;
+///
+/// let metriful = Metriful::try_new_device_timeout(ready, transport, Some(Duration::from_secs(5)))?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct LinuxTransport {
+  device: LinuxI2CDevice,
+  mode: TransferMode,
+}
+
+impl fmt::Debug for LinuxTransport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("LinuxTransport")
+      .field("mode", &self.mode)
+      .finish()
+  }
+}
+
+impl LinuxTransport {
+  pub fn new(device: LinuxI2CDevice, mode: TransferMode) -> LinuxTransport {
+    LinuxTransport { device, mode }
+  }
+
+  /// Opens the I2C device at the given path and address.
+  pub fn open(path: impl AsRef<Path>, address: u16, mode: TransferMode) -> Result<LinuxTransport> {
+    Ok(LinuxTransport::new(LinuxI2CDevice::new(path, address)?, mode))
+  }
+
+  /// Returns the transfer mode in use. This changes from [`TransferMode::Rdwr`]
+  /// to [`TransferMode::Smbus`] if the adapter turns out not to support plain
+  /// I2C transfers.
+  pub fn mode(&self) -> TransferMode {
+    self.mode
+  }
+
+  /// Sets the transfer mode.
+  pub fn set_mode(&mut self, mode: TransferMode) {
+    self.mode = mode;
+  }
+
+  /// Returns the underlying device.
+  pub fn into_inner(self) -> LinuxI2CDevice {
+    self.device
+  }
+
+  /// Reads a block with a register write followed by a read, in a single
+  /// transaction with a repeated start.
+  fn read_block_rdwr(&mut self, register: u8, len: u8) -> std::result::Result<Vec<u8>, LinuxI2CError> {
+    let command = [register];
+    let mut data = vec![0; len as usize];
+
+    let mut messages = [
+      LinuxI2CMessage::write(&command),
+      LinuxI2CMessage::read(&mut data),
+    ];
+
+    self.device.transfer(&mut messages)?;
+    Ok(data)
+  }
+}
+
+impl MetrifulTransport for LinuxTransport {
+  fn write_byte(&mut self, command: u8) -> Result<()> {
+    self.device.write_byte(command)
+  }
+
+  fn read_byte(&mut self, register: u8) -> Result<u8> {
+    self.device.read_byte(register)
+  }
+
+  fn write_byte_data(&mut self, register: u8, value: u8) -> Result<()> {
+    self.device.write_byte_data(register, value)
+  }
+
+  fn read_block(&mut self, register: u8, len: u8) -> Result<Vec<u8>> {
+    if self.mode == TransferMode::Smbus {
+      return self.device.read_block(register, len);
+    }
+
+    match self.read_block_rdwr(register, len) {
+      Ok(data) => Ok(data),
+      Err(e) => {
+        let e = io::Error::from(e);
+        if e.raw_os_error() != Some(EOPNOTSUPP) {
+          return Err(LinuxI2CError::Io(e).into());
+        }
+
+        warn!("LinuxTransport: I2C transfers unsupported by adapter, falling back to SMBus");
+        self.mode = TransferMode::Smbus;
+        self.device.read_block(register, len)
+      }
+    }
+  }
+
+  fn write_block(&mut self, register: u8, values: &[u8]) -> Result<()> {
+    self.device.write_block(register, values)
+  }
+
+  fn max_block_len(&self) -> usize {
+    match self.mode {
+      TransferMode::Smbus => SMBUS_BLOCK_MAX,
+      TransferMode::Rdwr => u8::MAX as usize,
+    }
+  }
+}
+
+/// A bus operation queued for a [`TimeoutTransport`]'s worker thread.
+type Job<D> = Box<dyn FnOnce(&mut D) + Send>;
+
+/// A transport wrapper that bounds how long each bus operation may take.
+///
+/// A wedged bus or misbehaving adapter can block an SMBus call indefinitely,
+/// which no READY timeout can catch. This runs the wrapped transport on a
+/// worker thread and returns [`MetrifulError::TransactionTimeout`] if an
+/// operation doesn't complete within the configured timeout, or by the
+/// deadline set by the caller's `*_timeout` method (see
+/// [`MetrifulTransport::set_deadline()`]), whichever is sooner.
+///
+/// The stuck operation can't be interrupted and keeps running on the worker;
+/// later operations queue behind it and are subject to their own timeouts.
+///
+/// # Example
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+/// use metriful::error::*;
+/// use metriful::transport::{MetrifulTransport, TimeoutTransport};
+///
+/// /// A bus that hangs on every read
+/// struct Wedged;
+///
+/// impl MetrifulTransport for Wedged {
+///   fn write_byte(&mut self, _command: u8) -> Result<()> { Ok(()) }
+///   fn read_byte(&mut self, _register: u8) -> Result<u8> { Ok(0) }
+///   fn write_byte_data(&mut self, _register: u8, _value: u8) -> Result<()> { Ok(()) }
+///   fn write_block(&mut self, _register: u8, _values: &[u8]) -> Result<()> { Ok(()) }
+///
+///   fn read_block(&mut self, _register: u8, len: u8) -> Result<Vec<u8>> {
+///     thread::sleep(Duration::from_millis(500));
+///     Ok(vec![0; len as usize])
+///   }
+/// }
+///
+/// let mut transport = TimeoutTransport::new(Wedged, Some(Duration::from_millis(50)));
+///
+/// let e = transport.read_block(0x21, 2).unwrap_err();
+/// assert_eq!(e.kind(), ErrorKind::Timeout);
+/// ```
+pub struct TimeoutTransport<D> {
+  jobs: Sender<Job<D>>,
+  timeout: Option<Duration>,
+  deadline: Option<Instant>,
+
+  /// The wrapped transport's [`MetrifulTransport::max_block_len()`] as of
+  /// its most recent operation
+  max_block_len: Arc<AtomicUsize>,
+}
+
+impl<D> fmt::Debug for TimeoutTransport<D> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("TimeoutTransport")
+      .field("timeout", &self.timeout)
+      .field("deadline", &self.deadline)
+      .finish()
+  }
+}
+
+impl<D> TimeoutTransport<D> where D: MetrifulTransport + Send + 'static {
+  /// Moves the transport to a new worker thread. If `timeout` is None,
+  /// operations are only bounded by deadlines set via
+  /// [`MetrifulTransport::set_deadline()`].
+  pub fn new(device: D, timeout: Option<Duration>) -> TimeoutTransport<D> {
+    let max_block_len = Arc::new(AtomicUsize::new(device.max_block_len()));
+    let (jobs, rx) = mpsc::channel::<Job<D>>();
+
+    thread::spawn(move || {
+      let mut device = device;
+      for job in rx {
+        job(&mut device);
+      }
+    });
+
+    TimeoutTransport { jobs, timeout, deadline: None, max_block_len }
+  }
+
+  /// Returns the per-operation timeout, if any.
+  pub fn timeout(&self) -> Option<Duration> {
+    self.timeout
+  }
+
+  /// Sets the per-operation timeout.
+  pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+    self.timeout = timeout;
+  }
+
+  /// Returns how long the next operation may take: the lesser of the timeout
+  /// and the time remaining until the deadline, if either is set.
+  fn limit(&self) -> Option<Duration> {
+    let remaining = self.deadline.map(|d| d.saturating_duration_since(Instant::now()));
+
+    match (self.timeout, remaining) {
+      (Some(timeout), Some(remaining)) => Some(timeout.min(remaining)),
+      (timeout, remaining) => timeout.or(remaining),
+    }
+  }
+
+  /// Runs `f` against the wrapped transport on the worker thread, waiting at
+  /// most [`TimeoutTransport::limit()`] for it to complete.
+  fn run<T, F>(&mut self, operation: &'static str, f: F) -> Result<T>
+  where
+    T: Send + 'static,
+    F: FnOnce(&mut D) -> Result<T> + Send + 'static,
+  {
+    let worker_exited = || MetrifulError::AsyncTaskError("transport worker exited".to_string());
+
+    let (tx, rx) = mpsc::sync_channel(1);
+    let max_block_len = Arc::clone(&self.max_block_len);
+    self.jobs.send(Box::new(move |device: &mut D| {
+      let ret = f(device);
+      max_block_len.store(device.max_block_len(), Ordering::Relaxed);
+      tx.send(ret).ok();
+    })).map_err(|_| worker_exited())?;
+
+    let limit = match self.limit() {
+      Some(limit) => limit,
+      None => return rx.recv().unwrap_or_else(|_| Err(worker_exited())),
+    };
+
+    match rx.recv_timeout(limit) {
+      Ok(ret) => ret,
+      Err(RecvTimeoutError::Timeout) => {
+        warn!("TimeoutTransport: {} did not complete within {:?}", operation, limit);
+        Err(MetrifulError::TransactionTimeout { operation, timeout: limit })
+      },
+      Err(RecvTimeoutError::Disconnected) => Err(worker_exited()),
+    }
+  }
+}
+
+impl<D> MetrifulTransport for TimeoutTransport<D> where D: MetrifulTransport + Send + 'static {
+  fn write_byte(&mut self, command: u8) -> Result<()> {
+    self.run("write_byte", move |d| d.write_byte(command))
+  }
+
+  fn read_byte(&mut self, register: u8) -> Result<u8> {
+    self.run("read_byte", move |d| d.read_byte(register))
+  }
+
+  fn write_byte_data(&mut self, register: u8, value: u8) -> Result<()> {
+    self.run("write_byte_data", move |d| d.write_byte_data(register, value))
+  }
+
+  fn read_block(&mut self, register: u8, len: u8) -> Result<Vec<u8>> {
+    self.run("read_block", move |d| d.read_block(register, len))
+  }
+
+  fn write_block(&mut self, register: u8, values: &[u8]) -> Result<()> {
+    let values = values.to_vec();
+    self.run("write_block", move |d| d.write_block(register, &values))
+  }
+
+  fn max_block_len(&self) -> usize {
+    self.max_block_len.load(Ordering::Relaxed)
+  }
+
+  fn set_deadline(&mut self, deadline: Option<Instant>) {
+    self.deadline = deadline;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing::{Fault, FaultPlan, FaultTrigger, MockDevice};
+
+  /// A transport that stalls every operation for the given duration.
+  struct Slow(Duration);
+
+  impl MetrifulTransport for Slow {
+    fn write_byte(&mut self, _command: u8) -> Result<()> {
+      thread::sleep(self.0);
+      Ok(())
+    }
+
+    fn read_byte(&mut self, _register: u8) -> Result<u8> {
+      thread::sleep(self.0);
+      Ok(1)
+    }
+
+    fn write_byte_data(&mut self, _register: u8, _value: u8) -> Result<()> { Ok(()) }
+    fn read_block(&mut self, _register: u8, len: u8) -> Result<Vec<u8>> { Ok(vec![0; len as usize]) }
+    fn write_block(&mut self, _register: u8, _values: &[u8]) -> Result<()> { Ok(()) }
+  }
+
+  #[test]
+  fn read_block_exact_rejects_short_reads() {
+    let plan = FaultPlan::new().inject(FaultTrigger::Register(0x10), Fault::TruncatedRead(7));
+    let mut device = plan.wrap_device(MockDevice::new());
+
+    assert!(matches!(
+      read_block_exact(&mut device, 0x10, 12),
+      Err(MetrifulError::ShortRead { register: 0x10, expected: 12, actual: 7 })
+    ));
+    assert_eq!(read_block_exact(&mut device, 0x10, 12).unwrap().len(), 12);
+  }
+
+  #[test]
+  fn context_is_attached_to_bus_errors() {
+    let plan = FaultPlan::new().inject(FaultTrigger::Command(0xE4), Fault::Nack);
+    let mut device = plan.wrap_device(MockDevice::new());
+
+    let e = with_context(&mut device, "set_mode").write_byte(0xE4).unwrap_err();
+    let context = e.context().unwrap();
+    assert_eq!(context.operation, "set_mode");
+    assert_eq!(context.register, 0xE4);
+    assert_eq!(e.raw_os_error(), Some(121));
+  }
+
+  #[test]
+  fn timeout_transport_times_out_stuck_operations() {
+    let mut transport = TimeoutTransport::new(Slow(Duration::from_millis(200)), Some(Duration::from_millis(20)));
+
+    assert!(matches!(
+      transport.read_byte(0x8A),
+      Err(MetrifulError::TransactionTimeout { operation: "read_byte", .. })
+    ));
+  }
+
+  #[test]
+  fn timeout_transport_honors_deadlines() {
+    let mut transport = TimeoutTransport::new(Slow(Duration::from_millis(200)), None);
+
+    transport.set_deadline(Some(Instant::now() + Duration::from_millis(20)));
+    let e = transport.write_byte(0xE1).unwrap_err();
+    assert!(matches!(e, MetrifulError::TransactionTimeout { timeout, .. } if timeout <= Duration::from_millis(20)));
+  }
+
+  #[test]
+  fn timeout_transport_passes_results_through() {
+    let mut transport = TimeoutTransport::new(Slow(Duration::from_millis(1)), Some(Duration::from_secs(1)));
+    assert_eq!(transport.read_byte(0x8A).unwrap(), 1);
+  }
+}
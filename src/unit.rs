@@ -1,16 +1,24 @@
+use std::cmp::Ordering;
 use std::convert::TryInto;
 use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use bytes::{Bytes, Buf};
 use chrono::{DateTime, Utc};
-use i2cdev::core::I2CDevice;
-use i2cdev::linux::LinuxI2CDevice;
 
 #[cfg(feature = "serde")] use chrono::SecondsFormat;
-#[cfg(feature = "serde")] use serde::{Serialize, ser::{Serializer, SerializeStruct}};
+#[cfg(feature = "serde")] use serde::{Deserialize, Deserializer, Serialize, de, ser::{Serializer, SerializeStruct}};
 
+use crate::calibration::Calibration;
+use crate::dyn_metric::{DynReading, ReadingValue, decimal_f64};
 use crate::error::*;
+use crate::format::{FormatOptions, default_format_options};
 use crate::metric::*;
+use crate::plausibility::Implausible;
+use crate::status::DeviceStatus;
+use crate::transport::{MetrifulTransport, read_block_exact};
 use crate::util::*;
 
 /// A combined unit and value, generally the result of a metric read.
@@ -32,40 +40,383 @@ pub struct UnitValue<U> where U: MetrifulUnit {
   
   /// The system time (UTC) when the metric was read by the library.
   pub time: DateTime<Utc>,
+
+  /// When the measurement completed and how long it took to read, if the
+  /// value was read via [`Metriful::read()`](crate::Metriful::read). Not
+  /// preserved by deserialization. Nested values of combined reads have no
+  /// timing of their own.
+  pub timing: Option<ReadTiming>,
+
+  /// Plausibility problems found with the value; see the
+  /// [`plausibility`](crate::plausibility) module. Not preserved by
+  /// deserialization.
+  pub warnings: Vec<Implausible>,
+
+  /// The exact register contents the value was decoded from, if it was read
+  /// from the device. None for reads spanning several combined reads, e.g.
+  /// [`METRIC_COMBINED_ALL`].
+  #[cfg(feature = "raw-bytes")]
+  pub raw_bytes: Option<Bytes>,
 }
 
 impl<U> UnitValue<U> where U: MetrifulUnit {
+  /// Creates a value of this unit, timestamped now.
+  pub fn new(value: U::Output) -> Self {
+    UnitValue {
+      unit: U::default(),
+      value,
+      time: Utc::now(),
+      timing: None,
+      warnings: Vec::new(),
+      #[cfg(feature = "raw-bytes")] raw_bytes: None,
+    }
+  }
+
+  /// Applies a function to this value, e.g. to apply a calibration offset or
+  /// clamp it to a range. The timestamp is preserved.
+  ///
+  /// # Example
+  /// ```
+  /// use metriful::unit::*;
+  ///
+  /// let humidity: UnitValue<UnitRelativeHumidity> = UnitValue::new(101.2);
+  /// let clamped = humidity.clone().map(|h| h.min(100.0));
+  ///
+  /// assert_eq!(clamped.value, 100.0);
+  /// assert_eq!(clamped.time, humidity.time);
+  /// ```
+  pub fn map<F>(self, f: F) -> UnitValue<U>
+  where
+    F: FnOnce(U::Output) -> U::Output
+  {
+    UnitValue {
+      unit: self.unit,
+      value: f(self.value),
+      time: self.time,
+      timing: self.timing,
+      warnings: self.warnings,
+      #[cfg(feature = "raw-bytes")] raw_bytes: self.raw_bytes,
+    }
+  }
+
+  /// Converts this value to another unit per [`ConvertUnit`]. The timestamp
+  /// is preserved.
+  ///
+  /// # Example
+  /// ```
+  /// use metriful::unit::*;
+  ///
+  /// let celsius: UnitValue<UnitDegreesCelsius> = UnitValue::new(21.5);
+  /// let fahrenheit = celsius.convert::<UnitDegreesFahrenheit>();
+  ///
+  /// assert_eq!(fahrenheit.value, 70.7);
+  /// assert_eq!(fahrenheit.to_string(), "70.7 \u{2109}");
+  /// ```
+  pub fn convert<V>(self) -> UnitValue<V>
+  where
+    U: ConvertUnit<V>,
+    V: MetrifulUnit
+  {
+    UnitValue {
+      unit: V::default(),
+      value: U::convert_value(self.value),
+      time: self.time,
+      timing: self.timing,
+      warnings: self.warnings,
+      #[cfg(feature = "raw-bytes")] raw_bytes: self.raw_bytes,
+    }
+  }
+
+  /// Formats this value using the given options rather than the crate-wide
+  /// default; see the [`format`](crate::format) module.
+  pub fn format_with(&self, options: &FormatOptions) -> String {
+    U::format_value_with(&self.value, options)
+  }
+
   fn from_bytes(bytes: &mut Bytes) -> Result<Self> {
+    #[cfg(feature = "raw-bytes")]
+    let raw_bytes = bytes.get(..U::len() as usize).map(|_| bytes.slice(..U::len() as usize));
+
     Ok(UnitValue {
       unit: U::default(),
       value: U::from_bytes(bytes)?,
       time: Utc::now(),
+      timing: None,
+      warnings: Vec::new(),
+      #[cfg(feature = "raw-bytes")] raw_bytes,
     })
   }
 }
 
+/// When a value's measurement completed, and how long it took to read.
+///
+/// The completion time is when the library observed READY being asserted
+/// while waiting for it, e.g. in [`CycleReadIterator`](crate::CycleReadIterator).
+/// If READY was already asserted when the read began (including when a
+/// cycle completed while the caller was busy), it is instead the time READY
+/// was checked.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "testing")] {
+/// use std::time::Duration;
+/// use metriful::{Metriful, metric::*};
+/// use metriful::testing::MockDevice;
+///
+/// let mock = MockDevice::new();
+/// let mut metriful = Metriful::try_new_device_timeout(
+///   mock.ready_line(),
+///   mock.clone(),
+///   Some(Duration::from_millis(100)),
+/// ).unwrap();
+///
+/// let temperature = metriful.read(METRIC_TEMPERATURE).unwrap();
+/// let timing = temperature.timing.unwrap();
+/// assert!(timing.ready_time <= temperature.time);
+/// println!(
+///   "measured {:?} ago, read in {:?}",
+///   timing.ready_instant.elapsed(), timing.read_duration
+/// );
+/// # }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ReadTiming {
+  /// Monotonic time the measurement completed, unaffected by clock changes
+  pub ready_instant: Instant,
+
+  /// System time (UTC) the measurement completed
+  pub ready_time: DateTime<Utc>,
+
+  /// Time spent reading the value from the device
+  pub read_duration: Duration,
+}
+
+/// Formats raw register contents as lowercase hex, e.g. `1505`.
+#[cfg(all(feature = "serde", feature = "raw-bytes"))]
+fn to_hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Parses raw register contents formatted by [`to_hex()`].
+#[cfg(all(feature = "serde", feature = "raw-bytes"))]
+fn from_hex(hex: &str) -> Option<Bytes> {
+  hex.as_bytes()
+    .chunks(2)
+    .map(|pair| match std::str::from_utf8(pair) {
+      Ok(pair) if pair.len() == 2 => u8::from_str_radix(pair, 16).ok(),
+      _ => None,
+    })
+    .collect::<Option<Vec<u8>>>()
+    .map(Bytes::from)
+}
+
+/// Parses a timestamp as serialized by `UnitValue` and related types.
+#[cfg(feature = "serde")]
+pub(crate) fn parse_timestamp<E: de::Error>(timestamp: &str) -> std::result::Result<DateTime<Utc>, E> {
+  DateTime::parse_from_rfc3339(timestamp)
+    .map(|t| t.with_timezone(&Utc))
+    .map_err(|e| E::custom(format!("invalid timestamp {:?}: {}", timestamp, e)))
+}
+
 impl<U> fmt::Display for UnitValue<U> where U: MetrifulUnit {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     write!(f, "{}", U::format_value(&self.value))
   }
 }
 
+/// Values compare by value alone; timestamps are ignored. Values can also be
+/// compared directly against their unit's output type.
+///
+/// # Example
+/// ```
+/// use metriful::unit::*;
+///
+/// let co2: UnitValue<UnitEstimatedCO2> = UnitValue::new(1200.0);
+/// assert!(co2 > 1000.0);
+/// assert!(co2 <= UnitValue::new(1200.0));
+/// assert!(co2.is_between(1000.0, 2000.0));
+/// ```
+impl<U> PartialEq for UnitValue<U>
+where
+  U: MetrifulUnit,
+  U::Output: PartialEq
+{
+  fn eq(&self, other: &Self) -> bool {
+    self.value == other.value
+  }
+}
+
+impl<U> PartialOrd for UnitValue<U>
+where
+  U: MetrifulUnit,
+  U::Output: PartialOrd
+{
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    self.value.partial_cmp(&other.value)
+  }
+}
+
+/// Implements comparisons between `UnitValue`s and plain values of the given
+/// output types.
+macro_rules! impl_output_cmp {
+  ($($output:ty),*) => {
+    $(
+      impl<U> PartialEq<$output> for UnitValue<U> where U: MetrifulUnit<Output = $output> {
+        fn eq(&self, other: &$output) -> bool {
+          self.value == *other
+        }
+      }
+
+      impl<U> PartialOrd<$output> for UnitValue<U> where U: MetrifulUnit<Output = $output> {
+        fn partial_cmp(&self, other: &$output) -> Option<Ordering> {
+          self.value.partial_cmp(other)
+        }
+      }
+    )*
+  };
+}
+
+impl_output_cmp!(
+  f32, u16, u32,
+  AQIAccuracy, SoundMeasurementStability, ParticleDataValidity, RawParticleConcentration
+);
+
+impl<U> UnitValue<U>
+where
+  U: MetrifulUnit,
+  U::Output: PartialOrd
+{
+  /// Returns true if this value is strictly greater than the threshold.
+  pub fn is_above(&self, threshold: U::Output) -> bool {
+    self.value > threshold
+  }
+
+  /// Returns true if this value is strictly less than the threshold.
+  pub fn is_below(&self, threshold: U::Output) -> bool {
+    self.value < threshold
+  }
+
+  /// Returns true if this value is within the inclusive range `low..=high`.
+  pub fn is_between(&self, low: U::Output, high: U::Output) -> bool {
+    self.value >= low && self.value <= high
+  }
+}
+
 #[cfg(feature = "serde")]
 impl<U> Serialize for UnitValue<U> where U: MetrifulUnit {
   fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
   where
       S: Serializer
   {
-    let mut state = serializer.serialize_struct("UnitValue", 5)?;
+    let mut state = serializer.serialize_struct("UnitValue", 10)?;
     state.serialize_field("timestamp", &self.time.to_rfc3339_opts(SecondsFormat::Secs, true))?;
     state.serialize_field("unit_name", U::name())?;
     state.serialize_field("unit_symbol", &U::symbol())?;
     state.serialize_field("value", &self.value)?;
     state.serialize_field("formatted_value", &U::format_value(&self.value))?;
+
+    match U::level_name(&self.value) {
+      Some(level) => state.serialize_field("level", level)?,
+      None => state.skip_field("level")?,
+    }
+
+    match &self.timing {
+      Some(timing) => {
+        state.serialize_field(
+          "ready_timestamp",
+          &timing.ready_time.to_rfc3339_opts(SecondsFormat::Millis, true)
+        )?;
+        state.serialize_field("read_duration_ms", &(timing.read_duration.as_secs_f64() * 1000.0))?;
+      },
+      None => {
+        state.skip_field("ready_timestamp")?;
+        state.skip_field("read_duration_ms")?;
+      },
+    }
+
+    if self.warnings.is_empty() {
+      state.skip_field("warnings")?;
+    } else {
+      let warnings: Vec<String> = self.warnings.iter().map(ToString::to_string).collect();
+      state.serialize_field("warnings", &warnings)?;
+    }
+
+    #[cfg(feature = "raw-bytes")]
+    state.serialize_field("raw_bytes", &self.raw_bytes.as_deref().map(to_hex))?;
+
     state.end()
   }
 }
 
+/// Reads a value as written by its `Serialize` impl. The unit is implied by
+/// `U`, so the `unit_name`, `unit_symbol`, and `formatted_value` fields are
+/// ignored.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "serde_json")] {
+/// use metriful::unit::*;
+///
+/// let json = r#"{
+///   "timestamp": "2021-03-01T12:00:00Z",
+///   "unit_name": "degrees Celsius",
+///   "unit_symbol": "\u2103",
+///   "value": 21.5,
+///   "formatted_value": "21.5 \u2103"
+/// }"#;
+///
+/// let value: UnitValue<UnitDegreesCelsius> = serde_json::from_str(json).unwrap();
+/// assert_eq!(value.value, 21.5);
+/// assert_eq!(value.time.to_rfc3339(), "2021-03-01T12:00:00+00:00");
+///
+/// // round trip
+/// let json = serde_json::to_string(&value).unwrap();
+/// let parsed: UnitValue<UnitDegreesCelsius> = serde_json::from_str(&json).unwrap();
+/// assert_eq!(parsed.value, value.value);
+/// assert_eq!(parsed.time, value.time);
+/// # }
+/// ```
+#[cfg(feature = "serde")]
+impl<'de, U> Deserialize<'de> for UnitValue<U>
+where
+  U: MetrifulUnit,
+  U::Output: Deserialize<'de>,
+{
+  fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+  where
+      D: Deserializer<'de>
+  {
+    #[derive(Deserialize)]
+    struct Repr<T> {
+      timestamp: String,
+      value: T,
+
+      #[cfg(feature = "raw-bytes")]
+      #[serde(default)]
+      raw_bytes: Option<String>,
+    }
+
+    let repr = Repr::<U::Output>::deserialize(deserializer)?;
+
+    #[cfg(feature = "raw-bytes")]
+    let raw_bytes = match repr.raw_bytes {
+      Some(hex) => Some(from_hex(&hex).ok_or_else(|| {
+        de::Error::custom(format!("invalid raw bytes: {:?}", hex))
+      })?),
+      None => None,
+    };
+
+    Ok(UnitValue {
+      unit: U::default(),
+      value: repr.value,
+      time: parse_timestamp(&repr.timestamp)?,
+      timing: None,
+      warnings: Vec::new(),
+      #[cfg(feature = "raw-bytes")] raw_bytes,
+    })
+  }
+}
+
 #[derive(Debug)]
 struct UnitSymbol(Option<&'static str>);
 
@@ -112,26 +463,55 @@ pub trait MetrifulUnit: Sized + Default + fmt::Debug + Copy + Clone + Send + Syn
     Self::symbol()
   }
 
+  /// Formats a value for display using the crate-wide default
+  /// [`FormatOptions`].
   fn format_value(value: &Self::Output) -> String {
-    if let Some(symbol) = Self::symbol() {
-      format!("{} {}", value, symbol)
-    } else {
-      format!("{}", value)
-    }
+    Self::format_value_with(value, &default_format_options())
+  }
+
+  /// Formats a value for display using the given options.
+  fn format_value_with(value: &Self::Output, options: &FormatOptions) -> String {
+    options.format(value, Self::symbol())
   }
 
   /// Length of this datatype in bytes
   fn len() -> u8;
 
-  /// Reads this datatype from raw bytes
+  /// Reads this datatype from raw bytes. At least [`MetrifulUnit::len()`]
+  /// bytes must be available; see [`read_block_exact()`].
   fn from_bytes(bytes: &mut Bytes) -> Result<Self::Output>;
 
   /// Reads the appropriate value for this unit from the given register.
-  fn read(device: &mut LinuxI2CDevice, register: u8) -> Result<Self::Output> {
-    let mut bytes = Bytes::from(device.smbus_read_i2c_block_data(register, Self::len())?);
+  fn read<D>(device: &mut D, register: u8) -> Result<Self::Output>
+  where
+    D: MetrifulTransport + ?Sized
+  {
+    let mut bytes = Bytes::from(read_block_exact(device, register, Self::len())?);
     Self::from_bytes(&mut bytes)
   }
 
+  /// Applies calibration offsets to a value of this unit. Most units are not
+  /// affected by calibration.
+  fn calibrate(_value: &mut Self::Output, _calibration: &Calibration) {}
+
+  /// Marks parts of a value that the device status shows to be meaningless,
+  /// e.g. the particle data of a combined read while no particle sensor is
+  /// enabled. Most units have no such parts.
+  fn mask_unavailable(_value: &mut Self::Output, _status: &DeviceStatus) {}
+
+  /// Converts a value of this unit to a unit-independent [`ReadingValue`].
+  /// Defaults to the value's text representation.
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::Text(value.to_string())
+  }
+
+  /// Returns a qualitative classification of a value, e.g. `"stale"` for
+  /// [`UnitEstimatedCO2`], if this unit has one. Included as `level` when a
+  /// [`UnitValue`] is serialized.
+  fn level_name(_value: &Self::Output) -> Option<&'static str> {
+    None
+  }
+
   fn new_metric(register: u8) -> Metric<Self> {
     Metric {
       register,
@@ -164,6 +544,66 @@ impl MetrifulUnit for UnitDegreesCelsius {
 
     Ok(read_f32_with_u8_denom(int_part, frac_part))
   }
+
+  fn calibrate(value: &mut Self::Output, calibration: &Calibration) {
+    *value += calibration.temp_offset_c;
+  }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::from_f32(*value)
+  }
+}
+
+/// A conversion from values of this unit to values of another unit, used by
+/// [`UnitValue::convert()`].
+pub trait ConvertUnit<V: MetrifulUnit>: MetrifulUnit {
+  /// Converts a value of this unit to the target unit.
+  fn convert_value(value: Self::Output) -> V::Output;
+}
+
+/// Temperature in degrees Fahrenheit. The sensor reports temperatures in
+/// degrees Celsius; values are converted when read.
+#[derive(Default, Debug, Copy, Clone)]
+pub struct UnitDegreesFahrenheit;
+
+impl MetrifulUnit for UnitDegreesFahrenheit {
+  type Output = f32;
+
+  fn name() -> &'static str {
+    "degrees Fahrenheit"
+  }
+
+  fn symbol() -> Option<&'static str> {
+    "\u{2109}".into()
+  }
+
+  fn len() -> u8 {
+    UnitDegreesCelsius::len()
+  }
+
+  fn from_bytes(bytes: &mut Bytes) -> Result<Self::Output> {
+    UnitDegreesCelsius::from_bytes(bytes).map(UnitDegreesCelsius::convert_value)
+  }
+
+  fn calibrate(value: &mut Self::Output, calibration: &Calibration) {
+    *value += calibration.temp_offset_c * 9.0 / 5.0;
+  }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::from_f32(*value)
+  }
+}
+
+impl ConvertUnit<UnitDegreesFahrenheit> for UnitDegreesCelsius {
+  fn convert_value(value: f32) -> f32 {
+    value * 9.0 / 5.0 + 32.0
+  }
+}
+
+impl ConvertUnit<UnitDegreesCelsius> for UnitDegreesFahrenheit {
+  fn convert_value(value: f32) -> f32 {
+    (value - 32.0) * 5.0 / 9.0
+  }
 }
 
 #[derive(Default, Debug, Copy, Clone)]
@@ -187,6 +627,99 @@ impl MetrifulUnit for UnitPascals {
   fn from_bytes(bytes: &mut Bytes) -> Result<Self::Output> {
     Ok(bytes.get_u32_le())
   }
+
+  fn calibrate(value: &mut Self::Output, calibration: &Calibration) {
+    *value = (*value as i64 + calibration.pressure_offset_pa as i64).max(0) as u32;
+  }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::Number(*value as f64)
+  }
+}
+
+/// Pascals per inch of mercury, at 0 ℃.
+pub const PASCALS_PER_INCH_OF_MERCURY: f32 = 3386.389;
+
+/// Pressure in inches of mercury. The sensor reports pressure in pascals;
+/// values are converted when read.
+#[derive(Default, Debug, Copy, Clone)]
+pub struct UnitInchesOfMercury;
+
+impl MetrifulUnit for UnitInchesOfMercury {
+  type Output = f32;
+
+  fn name() -> &'static str {
+    "inches of mercury"
+  }
+
+  fn symbol() -> Option<&'static str> {
+    Some("inHg")
+  }
+
+  fn len() -> u8 {
+    UnitPascals::len()
+  }
+
+  fn from_bytes(bytes: &mut Bytes) -> Result<Self::Output> {
+    UnitPascals::from_bytes(bytes).map(UnitPascals::convert_value)
+  }
+
+  fn calibrate(value: &mut Self::Output, calibration: &Calibration) {
+    *value = (*value + calibration.pressure_offset_pa as f32 / PASCALS_PER_INCH_OF_MERCURY).max(0.0);
+  }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::from_f32(*value)
+  }
+}
+
+impl ConvertUnit<UnitInchesOfMercury> for UnitPascals {
+  fn convert_value(value: u32) -> f32 {
+    value as f32 / PASCALS_PER_INCH_OF_MERCURY
+  }
+}
+
+impl ConvertUnit<UnitPascals> for UnitInchesOfMercury {
+  fn convert_value(value: f32) -> u32 {
+    (value * PASCALS_PER_INCH_OF_MERCURY).round().max(0.0) as u32
+  }
+}
+
+/// A system of units in which to present readings; see
+/// [`DynReading::to_unit_system()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum UnitSystem {
+  /// Degrees Celsius and pascals, as reported by the sensor
+  #[default]
+  Metric,
+
+  /// Degrees Fahrenheit and inches of mercury. Units without a customary
+  /// equivalent (e.g. lux and decibels) are unchanged.
+  Imperial,
+}
+
+impl FromStr for UnitSystem {
+  type Err = MetrifulError;
+
+  /// Parses a unit system name, either `metric` or `imperial`.
+  ///
+  /// # Example
+  /// ```
+  /// use metriful::unit::UnitSystem;
+  ///
+  /// assert_eq!("imperial".parse::<UnitSystem>().unwrap(), UnitSystem::Imperial);
+  /// assert!("cubits".parse::<UnitSystem>().is_err());
+  /// ```
+  fn from_str(s: &str) -> Result<Self> {
+    match s.trim().to_ascii_lowercase().as_str() {
+      "metric" => Ok(UnitSystem::Metric),
+      "imperial" => Ok(UnitSystem::Imperial),
+      _ => Err(MetrifulError::InvalidOption {
+        name: "unit system".to_string(),
+        value: s.to_string(),
+      }),
+    }
+  }
 }
 
 #[derive(Default, Debug, Copy, Clone)]
@@ -213,6 +746,14 @@ impl MetrifulUnit for UnitRelativeHumidity {
 
     Ok(read_f32_with_u8_denom(int_part, frac_part))
   }
+
+  fn calibrate(value: &mut Self::Output, calibration: &Calibration) {
+    *value = (*value + calibration.humidity_offset).clamp(0.0, 100.0);
+  }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::from_f32(*value)
+  }
 }
 
 #[derive(Default, Debug, Copy, Clone)]
@@ -236,10 +777,14 @@ impl MetrifulUnit for UnitResistance {
   fn from_bytes(bytes: &mut Bytes) -> Result<Self::Output> {
     Ok(bytes.get_u32_le())
   }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::Number(*value as f64)
+  }
 }
 
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CombinedAirData {
   pub temperature: UnitValue<UnitDegreesCelsius>,
   pub pressure: UnitValue<UnitPascals>,
@@ -289,6 +834,21 @@ impl MetrifulUnit for UnitCombinedAirData {
       gas_sensor_resistance,
     })
   }
+
+  fn calibrate(value: &mut Self::Output, calibration: &Calibration) {
+    UnitDegreesCelsius::calibrate(&mut value.temperature.value, calibration);
+    UnitPascals::calibrate(&mut value.pressure.value, calibration);
+    UnitRelativeHumidity::calibrate(&mut value.humidity.value, calibration);
+  }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::Group(vec![
+      DynReading::from_value("temperature", &value.temperature),
+      DynReading::from_value("pressure", &value.pressure),
+      DynReading::from_value("humidity", &value.humidity),
+      DynReading::from_value("gas_sensor_resistance", &value.gas_sensor_resistance),
+    ])
+  }
 }
 
 #[derive(Default, Debug, Copy, Clone)]
@@ -315,6 +875,10 @@ impl MetrifulUnit for UnitAirQualityIndex {
 
     Ok(read_f32_with_u8_denom(int_part, frac_part))
   }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::from_f32(*value)
+  }
 }
 
 #[derive(Default, Debug, Copy, Clone)]
@@ -336,15 +900,225 @@ impl MetrifulUnit for UnitPartsPerMillion {
   }
 
   fn from_bytes(bytes: &mut Bytes) -> Result<Self::Output> {
-    let int_part = bytes.get_u16_le();
-    let frac_part = bytes.get_u8();
+    Ok(ppm_from_bytes(bytes))
+  }
 
-    Ok(read_f32_with_u8_denom(int_part, frac_part))
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::from_f32(*value)
   }
 }
 
+/// Reads a value in parts per million with a one-digit fractional part.
+fn ppm_from_bytes(bytes: &mut Bytes) -> f32 {
+  let int_part = bytes.get_u16_le();
+  let frac_part = bytes.get_u8();
+
+  read_f32_with_u8_denom(int_part, frac_part)
+}
+
+/// Ventilation level indicated by an estimated CO2 concentration.
+///
+/// Outdoor air is around 400 ppm; levels above 1000 ppm indicate inadequate
+/// ventilation, and levels above 2000 ppm are commonly associated with
+/// drowsiness and poor concentration.
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
+pub enum CO2Level {
+  /// Below 400 ppm
+  Fresh,
+
+  /// 400 to 1000 ppm
+  Ok,
+
+  /// 1000 to 2000 ppm
+  Stale,
+
+  /// 2000 ppm and above
+  Poor,
+}
+
+impl CO2Level {
+  /// Classifies a CO2 concentration in ppm.
+  ///
+  /// # Example
+  /// ```
+  /// use metriful::unit::CO2Level;
+  ///
+  /// assert_eq!(CO2Level::from_ppm(350.0), CO2Level::Fresh);
+  /// assert_eq!(CO2Level::from_ppm(400.0), CO2Level::Ok);
+  /// assert_eq!(CO2Level::from_ppm(1500.0), CO2Level::Stale);
+  /// assert_eq!(CO2Level::from_ppm(2000.0), CO2Level::Poor);
+  /// ```
+  pub fn from_ppm(ppm: f32) -> CO2Level {
+    if ppm < 400.0 {
+      CO2Level::Fresh
+    } else if ppm < 1000.0 {
+      CO2Level::Ok
+    } else if ppm < 2000.0 {
+      CO2Level::Stale
+    } else {
+      CO2Level::Poor
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      CO2Level::Fresh => "fresh",
+      CO2Level::Ok => "ok",
+      CO2Level::Stale => "stale",
+      CO2Level::Poor => "poor",
+    }
+  }
+}
+
+impl fmt::Display for CO2Level {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+/// Estimated CO2 concentration in parts per million.
+#[derive(Default, Debug, Copy, Clone)]
+pub struct UnitEstimatedCO2;
+
+impl MetrifulUnit for UnitEstimatedCO2 {
+  type Output = f32;
+
+  fn name() -> &'static str {
+    UnitPartsPerMillion::name()
+  }
+
+  fn symbol() -> Option<&'static str> {
+    UnitPartsPerMillion::symbol()
+  }
+
+  fn len() -> u8 {
+    3
+  }
+
+  fn from_bytes(bytes: &mut Bytes) -> Result<Self::Output> {
+    Ok(ppm_from_bytes(bytes))
+  }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::from_f32(*value)
+  }
+
+  fn level_name(value: &Self::Output) -> Option<&'static str> {
+    Some(CO2Level::from_ppm(*value).name())
+  }
+}
+
+impl UnitValue<UnitEstimatedCO2> {
+  /// Classifies this concentration; see [`CO2Level`].
+  pub fn level(&self) -> CO2Level {
+    CO2Level::from_ppm(self.value)
+  }
+}
+
+/// Level of breath-equivalent volatile organic compounds (bVOC).
+///
+/// bVOC is itself an estimate based on gas sensor resistance, so these bands
+/// are approximate: clean indoor air is typically below 1 ppm, while levels
+/// above 3 ppm suggest ventilation or a nearby source of VOCs should be
+/// investigated.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
+pub enum VOCLevel {
+  /// Below 1 ppm
+  Low,
+
+  /// 1 to 3 ppm
+  Moderate,
+
+  /// 3 to 10 ppm
+  High,
+
+  /// 10 ppm and above
+  #[cfg_attr(feature = "serde", serde(rename = "very-high"))]
+  VeryHigh,
+}
+
+impl VOCLevel {
+  /// Classifies a bVOC concentration in ppm.
+  ///
+  /// # Example
+  /// ```
+  /// use metriful::unit::VOCLevel;
+  ///
+  /// assert_eq!(VOCLevel::from_ppm(0.5), VOCLevel::Low);
+  /// assert_eq!(VOCLevel::from_ppm(2.0), VOCLevel::Moderate);
+  /// assert_eq!(VOCLevel::from_ppm(10.0), VOCLevel::VeryHigh);
+  /// ```
+  pub fn from_ppm(ppm: f32) -> VOCLevel {
+    if ppm < 1.0 {
+      VOCLevel::Low
+    } else if ppm < 3.0 {
+      VOCLevel::Moderate
+    } else if ppm < 10.0 {
+      VOCLevel::High
+    } else {
+      VOCLevel::VeryHigh
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      VOCLevel::Low => "low",
+      VOCLevel::Moderate => "moderate",
+      VOCLevel::High => "high",
+      VOCLevel::VeryHigh => "very-high",
+    }
+  }
+}
+
+impl fmt::Display for VOCLevel {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+/// Estimated breath-equivalent VOC concentration in parts per million.
+#[derive(Default, Debug, Copy, Clone)]
+pub struct UnitEstimatedVOC;
+
+impl MetrifulUnit for UnitEstimatedVOC {
+  type Output = f32;
+
+  fn name() -> &'static str {
+    UnitPartsPerMillion::name()
+  }
+
+  fn symbol() -> Option<&'static str> {
+    UnitPartsPerMillion::symbol()
+  }
+
+  fn len() -> u8 {
+    3
+  }
+
+  fn from_bytes(bytes: &mut Bytes) -> Result<Self::Output> {
+    Ok(ppm_from_bytes(bytes))
+  }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::from_f32(*value)
+  }
+
+  fn level_name(value: &Self::Output) -> Option<&'static str> {
+    Some(VOCLevel::from_ppm(*value).name())
+  }
+}
+
+impl UnitValue<UnitEstimatedVOC> {
+  /// Classifies this concentration; see [`VOCLevel`].
+  pub fn level(&self) -> VOCLevel {
+    VOCLevel::from_ppm(self.value)
+  }
+}
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
 pub enum AQIAccuracy {
   Invalid,
   Low,
@@ -408,11 +1182,11 @@ impl MetrifulUnit for UnitAQIAccuracy {
 }
 
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CombinedAirQualityData {
   pub aqi: UnitValue<UnitAirQualityIndex>,
-  pub estimated_co2: UnitValue<UnitPartsPerMillion>,
-  pub estimated_voc: UnitValue<UnitPartsPerMillion>,
+  pub estimated_co2: UnitValue<UnitEstimatedCO2>,
+  pub estimated_voc: UnitValue<UnitEstimatedVOC>,
   pub aqi_accuracy: UnitValue<UnitAQIAccuracy>,
 }
 
@@ -447,8 +1221,8 @@ impl MetrifulUnit for UnitCombinedAirQualityData {
 
   fn from_bytes(bytes: &mut Bytes) -> Result<Self::Output> {
     let aqi = UnitValue::<UnitAirQualityIndex>::from_bytes(bytes)?;
-    let estimated_co2 = UnitValue::<UnitPartsPerMillion>::from_bytes(bytes)?;
-    let estimated_voc = UnitValue::<UnitPartsPerMillion>::from_bytes(bytes)?;
+    let estimated_co2 = UnitValue::<UnitEstimatedCO2>::from_bytes(bytes)?;
+    let estimated_voc = UnitValue::<UnitEstimatedVOC>::from_bytes(bytes)?;
     let aqi_accuracy = UnitValue::<UnitAQIAccuracy>::from_bytes(bytes)?;
 
     Ok(CombinedAirQualityData {
@@ -458,6 +1232,15 @@ impl MetrifulUnit for UnitCombinedAirQualityData {
       aqi_accuracy,
     })
   }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::Group(vec![
+      DynReading::from_value("aqi", &value.aqi),
+      DynReading::from_value("estimated_co2", &value.estimated_co2),
+      DynReading::from_value("estimated_voc", &value.estimated_voc),
+      DynReading::from_value("aqi_accuracy", &value.aqi_accuracy),
+    ])
+  }
 }
 
 #[derive(Default, Debug, Copy, Clone)]
@@ -484,6 +1267,10 @@ impl MetrifulUnit for UnitIlluminance {
 
     Ok(read_f32_with_u8_denom(uint_part, frac_part))
   }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::from_f32(*value)
+  }
 }
 
 #[derive(Default, Debug, Copy, Clone)]
@@ -507,10 +1294,14 @@ impl MetrifulUnit for UnitWhiteLevel {
   fn from_bytes(bytes: &mut Bytes) -> Result<Self::Output> {
     Ok(bytes.get_u16_le())
   }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::Number(*value as f64)
+  }
 }
 
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CombinedLightData {
   pub illuminance: UnitValue<UnitIlluminance>,
   pub white_level: UnitValue<UnitWhiteLevel>,
@@ -552,6 +1343,13 @@ impl MetrifulUnit for UnitCombinedLightData {
       white_level,
     })
   }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::Group(vec![
+      DynReading::from_value("illuminance", &value.illuminance),
+      DynReading::from_value("white_level", &value.white_level),
+    ])
+  }
 }
 
 #[derive(Default, Debug, Copy, Clone)]
@@ -578,12 +1376,65 @@ impl MetrifulUnit for UnitAWeightedSPL {
 
     Ok(read_f32_with_u8_denom(uint_part, frac_part))
   }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::from_f32(*value)
+  }
 }
 
+/// The frequency range covered by one of the six SPL bands.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BandInfo {
+  /// The band's center frequency in Hz
+  pub midpoint_hz: u32,
+
+  /// The band's lower frequency limit in Hz
+  pub lower_hz: u32,
+
+  /// The band's upper frequency limit in Hz
+  pub upper_hz: u32,
+}
+
+impl BandInfo {
+  const fn new(midpoint_hz: u32, lower_hz: u32, upper_hz: u32) -> BandInfo {
+    BandInfo { midpoint_hz, lower_hz, upper_hz }
+  }
+}
+
+/// Frequency ranges of the six SPL bands, in the order they are reported in
+/// [`SPLFrequencyBands`].
+pub const SPL_BANDS: [BandInfo; 6] = [
+  BandInfo::new(125, 88, 177),
+  BandInfo::new(250, 177, 354),
+  BandInfo::new(500, 354, 707),
+  BandInfo::new(1000, 707, 1414),
+  BandInfo::new(2000, 1414, 2828),
+  BandInfo::new(4000, 2828, 5657),
+];
+
+/// Sound pressure levels in dB for each of the six frequency bands described
+/// by [`SPL_BANDS`].
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SPLFrequencyBands(pub [f32; 6]);
 
+impl SPLFrequencyBands {
+  /// Returns each band's frequency range along with its level in dB.
+  ///
+  /// # Example
+  /// ```
+  /// use metriful::unit::SPLFrequencyBands;
+  ///
+  /// let bands = SPLFrequencyBands([38.8, 33.1, 35.1, 32.3, 29.4, 26.0]);
+  /// let (info, level) = bands.iter_bands().nth(3).unwrap();
+  /// assert_eq!(info.midpoint_hz, 1000);
+  /// assert_eq!(level, 32.3);
+  /// ```
+  pub fn iter_bands(&self) -> impl Iterator<Item = (BandInfo, f32)> + '_ {
+    SPL_BANDS.iter().copied().zip(self.0.iter().copied())
+  }
+}
+
 impl fmt::Display for SPLFrequencyBands {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     write!(f, "{:?}", self.0)
@@ -622,6 +1473,10 @@ impl MetrifulUnit for UnitSPLFrequencyBands {
 
     Ok(SPLFrequencyBands(bands))
   }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::Numbers(value.0.iter().map(|v| decimal_f64(*v)).collect())
+  }
 }
 
 #[derive(Default, Debug, Copy, Clone)]
@@ -648,10 +1503,14 @@ impl MetrifulUnit for UnitMillipascal {
 
     Ok(read_f32_with_u8_denom(uint_part, frac_part))
   }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::from_f32(*value)
+  }
 }
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
 pub enum SoundMeasurementStability {
   /// Microphone initialization has finished
   Stable,
@@ -707,7 +1566,7 @@ impl MetrifulUnit for UnitSoundMeasurementStability {
 
 
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CombinedSoundData {
   pub weighted_spl: UnitValue<UnitAWeightedSPL>,
   pub spl_bands: UnitValue<UnitSPLFrequencyBands>,
@@ -757,6 +1616,15 @@ impl MetrifulUnit for UnitCombinedSoundData {
       measurement_stability,
     })
   }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::Group(vec![
+      DynReading::from_value("weighted_spl", &value.weighted_spl),
+      DynReading::from_value("spl_bands", &value.spl_bands),
+      DynReading::from_value("peak_amplitude", &value.peak_amplitude),
+      DynReading::from_value("measurement_stability", &value.measurement_stability),
+    ])
+  }
 }
 
 #[derive(Default, Debug, Copy, Clone)]
@@ -783,6 +1651,10 @@ impl MetrifulUnit for UnitPercent {
 
     Ok(read_f32_with_u8_denom(uint_part, frac_part))
   }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::from_f32(*value)
+  }
 }
 
 /// Raw particle concentration from attached particle sensor. Underlying
@@ -790,7 +1662,7 @@ impl MetrifulUnit for UnitPercent {
 ///
 /// Both values are always set and should be approximately equal.
 #[derive(Debug, Copy, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RawParticleConcentration {
   /// 16-bit integer with two-digit fractional part; micrograms per cubic meter
   pub sds011_value: f32,
@@ -852,10 +1724,14 @@ impl MetrifulUnit for UnitRawParticleConcentration {
       ppd42_value: uint_part
     })
   }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::from_f32(value.sds011_value)
+  }
 }
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
 pub enum ParticleDataValidity {
   /// Particle sensor is still initializing (or is not enabled)
   Initializing,
@@ -907,7 +1783,7 @@ impl MetrifulUnit for UnitParticleDataValidity {
 }
 
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CombinedParticleData {
   pub duty_cycle: UnitValue<UnitPercent>,
   pub concentration: UnitValue<UnitRawParticleConcentration>,
@@ -953,20 +1829,48 @@ impl MetrifulUnit for UnitCombinedParticleData {
       validity,
     })
   }
+
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    ReadingValue::Group(vec![
+      DynReading::from_value("duty_cycle", &value.duty_cycle),
+      DynReading::from_value("concentration", &value.concentration),
+      DynReading::from_value("validity", &value.validity),
+    ])
+  }
 }
 
 /// All sensor data, read at once.
 ///
 /// Note that air quality and particle data have additional requirements and may
-/// be invalid; they will be marked as such.
+/// be invalid; they will be marked as such. When read via
+/// [`Metriful::read()`](crate::Metriful::read) while no particle sensor is
+/// enabled, `particle` is None.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "testing")] {
+/// use std::time::Duration;
+/// use metriful::{Metriful, metric::*};
+/// use metriful::testing::*;
+///
+/// // no particle sensor is enabled
+/// let mock = MockDevice::new();
+/// let mut metriful = Metriful::try_new_device_timeout(
+///   mock.ready_line(), mock.clone(), Some(Duration::from_millis(100))
+/// ).unwrap();
+///
+/// assert!(metriful.read(METRIC_PARTICLE_CONCENTRATION).is_err());
+/// assert!(metriful.read(METRIC_COMBINED_ALL).unwrap().value.particle.is_none());
+/// # }
+/// ```
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CombinedData {
   pub air: UnitValue<UnitCombinedAirData>,
   pub air_quality: UnitValue<UnitCombinedAirQualityData>,
   pub light: UnitValue<UnitCombinedLightData>,
   pub sound: UnitValue<UnitCombinedSoundData>,
-  pub particle: UnitValue<UnitCombinedParticleData>,
+  pub particle: Option<UnitValue<UnitCombinedParticleData>>,
 }
 
 impl fmt::Display for CombinedData {
@@ -991,10 +1895,13 @@ impl fmt::Display for CombinedData {
       textwrap::indent(&self.sound.value.to_string(), "  ")
     )?;
 
-    writeln!(
-      f, "particle data:\n{}",
-      textwrap::indent(&self.particle.value.to_string(), "  ")
-    )?;
+    match &self.particle {
+      Some(particle) => writeln!(
+        f, "particle data:\n{}",
+        textwrap::indent(&particle.value.to_string(), "  ")
+      )?,
+      None => writeln!(f, "particle data: not available")?,
+    }
 
     Ok(())
   }
@@ -1022,7 +1929,10 @@ impl MetrifulUnit for UnitCombinedData {
     Err(MetrifulError::InvalidCombinedDataFromBytes)
   }
 
-  fn read(device: &mut LinuxI2CDevice, _register: u8) -> Result<Self::Output> {
+  fn read<D>(device: &mut D, _register: u8) -> Result<Self::Output>
+  where
+    D: MetrifulTransport + ?Sized
+  {
     let air = METRIC_COMBINED_AIR_DATA.read(device)?;
     let air_quality = METRIC_COMBINED_AIR_QUALITY_DATA.read(device)?;
     let light = METRIC_COMBINED_LIGHT_DATA.read(device)?;
@@ -1034,7 +1944,416 @@ impl MetrifulUnit for UnitCombinedData {
       air_quality,
       light,
       sound,
+      particle: Some(particle),
+    })
+  }
+
+  fn calibrate(value: &mut Self::Output, calibration: &Calibration) {
+    UnitCombinedAirData::calibrate(&mut value.air.value, calibration);
+  }
+
+  fn mask_unavailable(value: &mut Self::Output, status: &DeviceStatus) {
+    if !status.particle_sensor_enabled() {
+      value.particle = None;
+    }
+  }
+
+  /// Particle data is omitted if not available.
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    let mut readings = vec![
+      DynReading::from_value("air", &value.air),
+      DynReading::from_value("air_quality", &value.air_quality),
+      DynReading::from_value("light", &value.light),
+      DynReading::from_value("sound", &value.sound),
+    ];
+
+    if let Some(particle) = &value.particle {
+      readings.push(DynReading::from_value("particle", particle));
+    }
+
+    ReadingValue::Group(readings)
+  }
+}
+
+/// The error marking particle data unavailable in combined reads.
+fn particle_sensor_disabled() -> MetrifulError {
+  MetrifulError::ParticleSensorDisabled { metric: "combined_particle_data" }
+}
+
+/// The result of one of the reads making up a [`PartialCombinedData`]. Errors
+/// are shared so that readings may be cloned.
+pub type PartialResult<U> = std::result::Result<UnitValue<U>, Arc<MetrifulError>>;
+
+/// All sensor data, read at once but tolerating failed reads of individual
+/// groups, e.g. a flaky particle sensor. Each field holds either the group's
+/// reading or the error encountered while reading it.
+#[derive(Debug, Clone)]
+pub struct PartialCombinedData {
+  pub air: PartialResult<UnitCombinedAirData>,
+  pub air_quality: PartialResult<UnitCombinedAirQualityData>,
+  pub light: PartialResult<UnitCombinedLightData>,
+  pub sound: PartialResult<UnitCombinedSoundData>,
+  pub particle: PartialResult<UnitCombinedParticleData>,
+}
+
+impl PartialCombinedData {
+  /// Returns true if every group was read successfully.
+  pub fn is_complete(&self) -> bool {
+    self.errors().is_empty()
+  }
+
+  /// Returns the name and error of each group that failed to read.
+  pub fn errors(&self) -> Vec<(&'static str, &MetrifulError)> {
+    fn err<'a, U: MetrifulUnit>(
+      name: &'static str,
+      result: &'a PartialResult<U>,
+    ) -> Option<(&'static str, &'a MetrifulError)> {
+      result.as_ref().err().map(|e| (name, e.as_ref()))
+    }
+
+    vec![
+      err("air", &self.air),
+      err("air_quality", &self.air_quality),
+      err("light", &self.light),
+      err("sound", &self.sound),
+      err("particle", &self.particle),
+    ].into_iter().flatten().collect()
+  }
+
+  /// Converts to a [`CombinedData`] if every group was read successfully,
+  /// otherwise returns the first error. Particle data that is unavailable
+  /// because no particle sensor is enabled doesn't count as an error.
+  pub fn complete(self) -> std::result::Result<CombinedData, Arc<MetrifulError>> {
+    let particle = match self.particle {
+      Ok(particle) => Some(particle),
+      Err(e) if matches!(*e, MetrifulError::ParticleSensorDisabled { .. }) => None,
+      Err(e) => return Err(e),
+    };
+
+    Ok(CombinedData {
+      air: self.air?,
+      air_quality: self.air_quality?,
+      light: self.light?,
+      sound: self.sound?,
       particle,
     })
   }
 }
+
+impl From<CombinedData> for PartialCombinedData {
+  fn from(data: CombinedData) -> Self {
+    PartialCombinedData {
+      air: Ok(data.air),
+      air_quality: Ok(data.air_quality),
+      light: Ok(data.light),
+      sound: Ok(data.sound),
+      particle: data.particle.ok_or_else(|| Arc::new(particle_sensor_disabled())),
+    }
+  }
+}
+
+/// Formats a partial read result as an indented block.
+fn fmt_partial<U: MetrifulUnit>(
+  f: &mut fmt::Formatter<'_>,
+  name: &str,
+  result: &PartialResult<U>,
+) -> fmt::Result {
+  match result {
+    Ok(value) => writeln!(
+      f, "{}:\n{}", name, textwrap::indent(&value.value.to_string(), "  ")
+    ),
+    Err(e) => writeln!(f, "{}: error: {}", name, e),
+  }
+}
+
+impl fmt::Display for PartialCombinedData {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt_partial(f, "air data", &self.air)?;
+    fmt_partial(f, "air quality data", &self.air_quality)?;
+    fmt_partial(f, "light data", &self.light)?;
+    fmt_partial(f, "sound data", &self.sound)?;
+    fmt_partial(f, "particle data", &self.particle)?;
+
+    Ok(())
+  }
+}
+
+/// Serializes a failed read as `{"error": "...", "code": 15, "kind": "..."}`,
+/// per [`MetrifulError::code()`] and [`MetrifulError::kind()`].
+#[cfg(feature = "serde")]
+struct SerializePartial<'a, U: MetrifulUnit>(&'a PartialResult<U>);
+
+#[cfg(feature = "serde")]
+impl<'a, U: MetrifulUnit> Serialize for SerializePartial<'a, U> {
+  fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+  where
+      S: Serializer
+  {
+    match self.0 {
+      Ok(value) => value.serialize(serializer),
+      Err(e) => {
+        let mut state = serializer.serialize_struct("PartialError", 3)?;
+        state.serialize_field("error", &e.to_string())?;
+        state.serialize_field("code", &e.code())?;
+        state.serialize_field("kind", &e.kind())?;
+        state.end()
+      }
+    }
+  }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for PartialCombinedData {
+  fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+  where
+      S: Serializer
+  {
+    let mut state = serializer.serialize_struct("PartialCombinedData", 5)?;
+    state.serialize_field("air", &SerializePartial(&self.air))?;
+    state.serialize_field("air_quality", &SerializePartial(&self.air_quality))?;
+    state.serialize_field("light", &SerializePartial(&self.light))?;
+    state.serialize_field("sound", &SerializePartial(&self.sound))?;
+    state.serialize_field("particle", &SerializePartial(&self.particle))?;
+    state.end()
+  }
+}
+
+/// Reads a failed read as serialized by [`SerializePartial`].
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DeserializePartial<T> {
+  Error { error: String },
+  Value(T),
+}
+
+#[cfg(feature = "serde")]
+impl<U: MetrifulUnit> From<DeserializePartial<UnitValue<U>>> for PartialResult<U> {
+  fn from(partial: DeserializePartial<UnitValue<U>>) -> Self {
+    match partial {
+      DeserializePartial::Error { error } => Err(Arc::new(MetrifulError::RecordedError(error))),
+      DeserializePartial::Value(value) => Ok(value),
+    }
+  }
+}
+
+/// Failed reads are restored as [`MetrifulError::RecordedError`], as the
+/// original error is only serialized as text.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "serde_json")] {
+/// use std::sync::Arc;
+/// use metriful::error::MetrifulError;
+/// use metriful::unit::*;
+///
+/// let data = PartialCombinedData {
+///   particle: Err(Arc::new(MetrifulError::NotReady)),
+///   ..PartialCombinedData::from(CombinedData {
+///     air: UnitValue::new(CombinedAirData {
+///       temperature: UnitValue::new(21.5),
+///       pressure: UnitValue::new(101325),
+///       humidity: UnitValue::new(40.0),
+///       gas_sensor_resistance: UnitValue::new(50000),
+///     }),
+///     air_quality: UnitValue::new(CombinedAirQualityData {
+///       aqi: UnitValue::new(25.0),
+///       estimated_co2: UnitValue::new(500.0),
+///       estimated_voc: UnitValue::new(0.5),
+///       aqi_accuracy: UnitValue::new(AQIAccuracy::High),
+///     }),
+///     light: UnitValue::new(CombinedLightData {
+///       illuminance: UnitValue::new(300.0),
+///       white_level: UnitValue::new(8000),
+///     }),
+///     sound: UnitValue::new(CombinedSoundData {
+///       weighted_spl: UnitValue::new(40.0),
+///       spl_bands: UnitValue::new(SPLFrequencyBands([30.0; 6])),
+///       peak_amplitude: UnitValue::new(10.0),
+///       measurement_stability: UnitValue::new(SoundMeasurementStability::Stable),
+///     }),
+///     particle: None,
+///   })
+/// };
+///
+/// let json = serde_json::to_string(&data).unwrap();
+/// let parsed: PartialCombinedData = serde_json::from_str(&json).unwrap();
+///
+/// assert_eq!(parsed.errors()[0].0, "particle");
+/// assert_eq!(parsed.air.unwrap().value.temperature.value, 21.5);
+/// assert_eq!(parsed.sound.unwrap().value.spl_bands.value.0, [30.0; 6]);
+/// assert_eq!(parsed.particle.unwrap_err().to_string(), "sensor is not in ready state");
+/// # }
+/// ```
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for PartialCombinedData {
+  fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+  where
+      D: Deserializer<'de>
+  {
+    #[derive(Deserialize)]
+    struct Repr {
+      air: DeserializePartial<UnitValue<UnitCombinedAirData>>,
+      air_quality: DeserializePartial<UnitValue<UnitCombinedAirQualityData>>,
+      light: DeserializePartial<UnitValue<UnitCombinedLightData>>,
+      sound: DeserializePartial<UnitValue<UnitCombinedSoundData>>,
+      particle: DeserializePartial<UnitValue<UnitCombinedParticleData>>,
+    }
+
+    let repr = Repr::deserialize(deserializer)?;
+    Ok(PartialCombinedData {
+      air: repr.air.into(),
+      air_quality: repr.air_quality.into(),
+      light: repr.light.into(),
+      sound: repr.sound.into(),
+      particle: repr.particle.into(),
+    })
+  }
+}
+
+#[derive(Default, Debug, Copy, Clone)]
+pub struct UnitPartialCombinedData;
+
+impl MetrifulUnit for UnitPartialCombinedData {
+  type Output = PartialCombinedData;
+
+  fn name() -> &'static str {
+    "all combined data (partial)"
+  }
+
+  fn symbol() -> Option<&'static str> {
+    None
+  }
+
+  fn len() -> u8 {
+    0
+  }
+
+  fn from_bytes(_bytes: &mut Bytes) -> Result<Self::Output> {
+    Err(MetrifulError::InvalidCombinedDataFromBytes)
+  }
+
+  /// Reads each group independently; this never fails as a whole.
+  fn read<D>(device: &mut D, _register: u8) -> Result<Self::Output>
+  where
+    D: MetrifulTransport + ?Sized
+  {
+    Ok(PartialCombinedData {
+      air: METRIC_COMBINED_AIR_DATA.read(device).map_err(Arc::new),
+      air_quality: METRIC_COMBINED_AIR_QUALITY_DATA.read(device).map_err(Arc::new),
+      light: METRIC_COMBINED_LIGHT_DATA.read(device).map_err(Arc::new),
+      sound: METRIC_COMBINED_SOUND_DATA.read(device).map_err(Arc::new),
+      particle: METRIC_COMBINED_PARTICLE_DATA.read(device).map_err(Arc::new),
+    })
+  }
+
+  fn calibrate(value: &mut Self::Output, calibration: &Calibration) {
+    if let Ok(air) = &mut value.air {
+      UnitCombinedAirData::calibrate(&mut air.value, calibration);
+    }
+  }
+
+  fn mask_unavailable(value: &mut Self::Output, status: &DeviceStatus) {
+    if !status.particle_sensor_enabled() {
+      value.particle = Err(Arc::new(particle_sensor_disabled()));
+    }
+  }
+
+  /// Groups that failed to read are included as text describing the error.
+  fn to_reading_value(value: &Self::Output) -> ReadingValue {
+    fn reading<U: MetrifulUnit>(name: &'static str, result: &PartialResult<U>) -> DynReading {
+      match result {
+        Ok(value) => DynReading::from_value(name, value),
+        Err(e) => DynReading {
+          name,
+          unit_name: U::name(),
+          unit_symbol: U::symbol(),
+          value: ReadingValue::Text(e.to_string()),
+          formatted_value: format!("error: {}", e),
+          time: Utc::now(),
+        },
+      }
+    }
+
+    ReadingValue::Group(vec![
+      reading("air", &value.air),
+      reading("air_quality", &value.air_quality),
+      reading("light", &value.light),
+      reading("sound", &value.sound),
+      reading("particle", &value.particle),
+    ])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn enum_bytes_round_trip() {
+    for byte in 0..=3 {
+      assert_eq!(AQIAccuracy::from_byte(byte).unwrap().to_uint(), byte);
+    }
+
+    assert_eq!(ParticleDataValidity::from_byte(0).unwrap(), ParticleDataValidity::Initializing);
+    assert_eq!(ParticleDataValidity::from_byte(1).unwrap(), ParticleDataValidity::Settled);
+  }
+
+  #[test]
+  fn out_of_range_bytes_are_rejected() {
+    for byte in &[4, 0x80, 0xFF] {
+      assert!(matches!(AQIAccuracy::from_byte(*byte), Err(MetrifulError::InvalidAQIAccuracy(b)) if b == *byte));
+      assert!(matches!(
+        UnitAQIAccuracy::from_bytes(&mut Bytes::from(vec![*byte])),
+        Err(MetrifulError::InvalidAQIAccuracy(_))
+      ));
+    }
+
+    assert!(matches!(
+      ParticleDataValidity::from_byte(2),
+      Err(MetrifulError::InvalidParticleDataValidity(2))
+    ));
+  }
+
+  #[test]
+  fn unknown_unit_systems_are_rejected() {
+    assert_eq!(" Metric ".parse::<UnitSystem>().unwrap(), UnitSystem::Metric);
+    assert!(matches!("si".parse::<UnitSystem>(), Err(MetrifulError::InvalidOption { .. })));
+  }
+
+  #[cfg(feature = "serde_json")]
+  #[test]
+  fn invalid_json_is_rejected() {
+    let parse = |json: &str| serde_json::from_str::<UnitValue<UnitAQIAccuracy>>(json);
+
+    assert_eq!(parse(r#"{"timestamp": "2021-03-01T12:00:00Z", "value": "high"}"#).unwrap().value, AQIAccuracy::High);
+    assert!(parse(r#"{"timestamp": "2021-03-01T12:00:00Z", "value": "excellent"}"#).is_err());
+    assert!(parse(r#"{"timestamp": "yesterday", "value": "high"}"#).is_err());
+    assert!(parse(r#"{"value": "high"}"#).is_err());
+  }
+
+  #[cfg(feature = "serde_json")]
+  #[test]
+  fn failed_groups_are_restored_as_recorded_errors() {
+    fn zeroed<U: MetrifulUnit>() -> PartialResult<U> {
+      Ok(UnitValue::new(U::from_bytes(&mut Bytes::from(vec![0; U::len() as usize])).unwrap()))
+    }
+
+    let data = PartialCombinedData {
+      air: zeroed(),
+      air_quality: zeroed(),
+      light: Err(Arc::new(MetrifulError::NotReady)),
+      sound: zeroed(),
+      particle: zeroed(),
+    };
+
+    let json = serde_json::to_string(&data).unwrap();
+    let parsed: PartialCombinedData = serde_json::from_str(&json).unwrap();
+
+    assert!(parsed.air.is_ok());
+    match parsed.light.as_ref().map_err(|e| &**e) {
+      Err(MetrifulError::RecordedError(message)) => assert_eq!(message, &MetrifulError::NotReady.to_string()),
+      other => panic!("expected a recorded error, got {:?}", other),
+    }
+  }
+}